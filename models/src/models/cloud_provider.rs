@@ -1,9 +1,44 @@
+use std::fmt;
+use std::str::FromStr;
+
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum CloudProvider {
 	Vultr,
 	Hetzner,
 	Oracle,
 	HostHatch,
 }
+
+impl CloudProvider {
+	// Lowercase identifier used in the `Providers` table and in API paths.
+	pub fn code(&self) -> &'static str {
+		match self {
+			CloudProvider::Vultr => "vultr",
+			CloudProvider::Hetzner => "hetzner",
+			CloudProvider::Oracle => "oracle",
+			CloudProvider::HostHatch => "hosthatch",
+		}
+	}
+}
+
+impl fmt::Display for CloudProvider {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", self.code())
+	}
+}
+
+impl FromStr for CloudProvider {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.to_lowercase().as_str() {
+			"vultr" => Ok(CloudProvider::Vultr),
+			"hetzner" => Ok(CloudProvider::Hetzner),
+			"oracle" => Ok(CloudProvider::Oracle),
+			"hosthatch" => Ok(CloudProvider::HostHatch),
+			_ => Err(format!("unknown cloud provider: {}", s)),
+		}
+	}
+}
@@ -8,5 +8,6 @@ pub struct Instance {
     pub region: String,
     pub vcpu: u64,
     pub memory: u64,
-    pub boot_volume: Volume,
+    // Unknown until the worker reports it.
+    pub boot_volume: Option<Volume>,
 }
\ No newline at end of file
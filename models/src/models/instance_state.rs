@@ -1,12 +1,41 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 pub enum InstanceState {
 	Starting,
 	Running,
+	// Cordoned: gets no new work while its containers are stopped ahead of removal.
+	Draining,
 	Upgrading,
 	Stopping,
 	Stopped,
 	Terminated,
 	Unknown,
 }
+
+impl InstanceState {
+	// Provider states are polled, so intermediate states may be skipped, e.g. Stopped straight
+	// to Running. Nothing leaves Terminated; anything may become Unknown and leave it again.
+	pub fn can_transition_to(self, next: InstanceState) -> bool {
+		use InstanceState::*;
+
+		match (self, next) {
+			(current, next) if current == next => true,
+			(Terminated, _) => false,
+			(_, Terminated) | (_, Unknown) | (Unknown, _) => true,
+			(Starting, Running | Stopping | Stopped) => true,
+			// Back to Starting on reboots.
+			(Running, Starting | Draining | Upgrading | Stopping | Stopped) => true,
+			(Draining, Running | Stopping | Stopped) => true,
+			(Upgrading, Starting | Running | Stopping | Stopped) => true,
+			(Stopping, Stopped) => true,
+			(Stopped, Starting | Running | Upgrading) => true,
+			_ => false,
+		}
+	}
+
+	// States in which an instance doesn't serve workloads.
+	pub fn is_down(self) -> bool {
+		matches!(self, InstanceState::Stopping | InstanceState::Stopped | InstanceState::Terminated)
+	}
+}
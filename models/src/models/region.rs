@@ -1,45 +1,178 @@
-use std::str::FromStr;
 use std::fmt;
-use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub enum Region {
-    Frankfurt,
-    NewYork,
+use serde::{Deserialize, Serialize, Serializer};
+
+use super::cloud_provider::CloudProvider::{self, Hetzner, HostHatch, Oracle, Vultr};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Continent {
+	Africa,
+	Asia,
+	Europe,
+	NorthAmerica,
+	Oceania,
+	SouthAmerica,
 }
 
-pub struct RegionParseError;
+impl Continent {
+	pub fn code(&self) -> &'static str {
+		match self {
+			Continent::Africa => "africa",
+			Continent::Asia => "asia",
+			Continent::Europe => "europe",
+			Continent::NorthAmerica => "north-america",
+			Continent::Oceania => "oceania",
+			Continent::SouthAmerica => "south-america",
+		}
+	}
+}
 
-impl fmt::Display for RegionParseError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "could not parse region")
-    }
+// A metro area we can run in. `id` is the canonical identifier used by rules, workers and the
+// ping-server; usually the metro's main airport code, which is also what most providers use.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Region {
+	pub id: &'static str,
+	pub metro: &'static str,
+	// ISO 3166-1 alpha-2.
+	pub country: &'static str,
+	pub continent: Continent,
+	// Group of nearby metros, e.g. `eu-central`, for rules that don't care about the exact one.
+	pub area: &'static str,
+	pub latitude: f64,
+	pub longitude: f64,
+	#[serde(serialize_with = "serialize_codes")]
+	pub providers: &'static [(CloudProvider, &'static str)],
 }
 
-impl fmt::Debug for RegionParseError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{{ file: {}, line: {} }}", file!(), line!())
-    }
+// Within an area, metros come in order of preference; `resolve` picks the first one a provider
+// has.
+pub const REGIONS: &[Region] = &[
+	// Europe
+	Region { id: "fra", metro: "Frankfurt", country: "DE", continent: Continent::Europe, area: "eu-central", latitude: 50.11, longitude: 8.68, providers: &[(Vultr, "fra"), (Oracle, "fra")] },
+	Region { id: "fsn", metro: "Falkenstein", country: "DE", continent: Continent::Europe, area: "eu-central", latitude: 50.48, longitude: 12.37, providers: &[(Hetzner, "fsn1")] },
+	Region { id: "nue", metro: "Nuremberg", country: "DE", continent: Continent::Europe, area: "eu-central", latitude: 49.45, longitude: 11.08, providers: &[(Hetzner, "nbg1")] },
+	Region { id: "zrh", metro: "Zurich", country: "CH", continent: Continent::Europe, area: "eu-central", latitude: 47.38, longitude: 8.54, providers: &[(HostHatch, "zrh"), (Oracle, "zrh")] },
+	Region { id: "vie", metro: "Vienna", country: "AT", continent: Continent::Europe, area: "eu-central", latitude: 48.21, longitude: 16.37, providers: &[(HostHatch, "vie")] },
+	Region { id: "waw", metro: "Warsaw", country: "PL", continent: Continent::Europe, area: "eu-central", latitude: 52.23, longitude: 21.01, providers: &[(Vultr, "waw")] },
+	Region { id: "ams", metro: "Amsterdam", country: "NL", continent: Continent::Europe, area: "eu-west", latitude: 52.37, longitude: 4.90, providers: &[(Vultr, "ams"), (HostHatch, "ams"), (Oracle, "ams")] },
+	Region { id: "lhr", metro: "London", country: "GB", continent: Continent::Europe, area: "eu-west", latitude: 51.51, longitude: -0.13, providers: &[(Vultr, "lhr"), (HostHatch, "lon"), (Oracle, "lhr")] },
+	Region { id: "cdg", metro: "Paris", country: "FR", continent: Continent::Europe, area: "eu-west", latitude: 48.86, longitude: 2.35, providers: &[(Vultr, "cdg"), (Oracle, "cdg")] },
+	Region { id: "mad", metro: "Madrid", country: "ES", continent: Continent::Europe, area: "eu-south", latitude: 40.42, longitude: -3.70, providers: &[(Vultr, "mad"), (Oracle, "mad")] },
+	Region { id: "mil", metro: "Milan", country: "IT", continent: Continent::Europe, area: "eu-south", latitude: 45.46, longitude: 9.19, providers: &[(Oracle, "lin")] },
+	Region { id: "mrs", metro: "Marseille", country: "FR", continent: Continent::Europe, area: "eu-south", latitude: 43.30, longitude: 5.37, providers: &[(Oracle, "mrs")] },
+	Region { id: "sto", metro: "Stockholm", country: "SE", continent: Continent::Europe, area: "eu-north", latitude: 59.33, longitude: 18.07, providers: &[(Vultr, "sto"), (HostHatch, "sto"), (Oracle, "arn")] },
+	Region { id: "hel", metro: "Helsinki", country: "FI", continent: Continent::Europe, area: "eu-north", latitude: 60.17, longitude: 24.94, providers: &[(Hetzner, "hel1")] },
+	Region { id: "osl", metro: "Oslo", country: "NO", continent: Continent::Europe, area: "eu-north", latitude: 59.91, longitude: 10.75, providers: &[(HostHatch, "osl")] },
+	// North America
+	Region { id: "ewr", metro: "New York", country: "US", continent: Continent::NorthAmerica, area: "us-east", latitude: 40.71, longitude: -74.01, providers: &[(Vultr, "ewr"), (HostHatch, "nyc")] },
+	Region { id: "iad", metro: "Ashburn", country: "US", continent: Continent::NorthAmerica, area: "us-east", latitude: 39.04, longitude: -77.49, providers: &[(Hetzner, "ash"), (Oracle, "iad")] },
+	Region { id: "atl", metro: "Atlanta", country: "US", continent: Continent::NorthAmerica, area: "us-east", latitude: 33.75, longitude: -84.39, providers: &[(Vultr, "atl")] },
+	Region { id: "mia", metro: "Miami", country: "US", continent: Continent::NorthAmerica, area: "us-east", latitude: 25.76, longitude: -80.19, providers: &[(Vultr, "mia")] },
+	Region { id: "ord", metro: "Chicago", country: "US", continent: Continent::NorthAmerica, area: "us-central", latitude: 41.88, longitude: -87.63, providers: &[(Vultr, "ord"), (HostHatch, "chi"), (Oracle, "ord")] },
+	Region { id: "dfw", metro: "Dallas", country: "US", continent: Continent::NorthAmerica, area: "us-central", latitude: 32.78, longitude: -96.80, providers: &[(Vultr, "dfw")] },
+	Region { id: "sjc", metro: "San Jose", country: "US", continent: Continent::NorthAmerica, area: "us-west", latitude: 37.34, longitude: -121.89, providers: &[(Vultr, "sjc"), (Oracle, "sjc")] },
+	Region { id: "lax", metro: "Los Angeles", country: "US", continent: Continent::NorthAmerica, area: "us-west", latitude: 34.05, longitude: -118.24, providers: &[(Vultr, "lax"), (HostHatch, "lax")] },
+	Region { id: "sea", metro: "Seattle", country: "US", continent: Continent::NorthAmerica, area: "us-west", latitude: 47.61, longitude: -122.33, providers: &[(Vultr, "sea")] },
+	Region { id: "pdx", metro: "Hillsboro", country: "US", continent: Continent::NorthAmerica, area: "us-west", latitude: 45.52, longitude: -122.99, providers: &[(Hetzner, "hil")] },
+	Region { id: "phx", metro: "Phoenix", country: "US", continent: Continent::NorthAmerica, area: "us-west", latitude: 33.45, longitude: -112.07, providers: &[(Oracle, "phx")] },
+	Region { id: "hnl", metro: "Honolulu", country: "US", continent: Continent::NorthAmerica, area: "us-west", latitude: 21.31, longitude: -157.86, providers: &[(Vultr, "hnl")] },
+	Region { id: "yto", metro: "Toronto", country: "CA", continent: Continent::NorthAmerica, area: "ca-central", latitude: 43.65, longitude: -79.38, providers: &[(Vultr, "yto"), (Oracle, "yyz")] },
+	Region { id: "yul", metro: "Montreal", country: "CA", continent: Continent::NorthAmerica, area: "ca-central", latitude: 45.50, longitude: -73.57, providers: &[(Oracle, "yul")] },
+	Region { id: "mex", metro: "Mexico City", country: "MX", continent: Continent::NorthAmerica, area: "mx-central", latitude: 19.43, longitude: -99.13, providers: &[(Vultr, "mex")] },
+	// South America
+	Region { id: "sao", metro: "São Paulo", country: "BR", continent: Continent::SouthAmerica, area: "sa-east", latitude: -23.55, longitude: -46.63, providers: &[(Vultr, "sao"), (Oracle, "gru")] },
+	Region { id: "scl", metro: "Santiago", country: "CL", continent: Continent::SouthAmerica, area: "sa-west", latitude: -33.45, longitude: -70.67, providers: &[(Vultr, "scl")] },
+	// Asia
+	Region { id: "nrt", metro: "Tokyo", country: "JP", continent: Continent::Asia, area: "ap-northeast", latitude: 35.68, longitude: 139.69, providers: &[(Vultr, "nrt"), (Oracle, "nrt")] },
+	Region { id: "itm", metro: "Osaka", country: "JP", continent: Continent::Asia, area: "ap-northeast", latitude: 34.69, longitude: 135.50, providers: &[(Vultr, "itm"), (Oracle, "kix")] },
+	Region { id: "icn", metro: "Seoul", country: "KR", continent: Continent::Asia, area: "ap-northeast", latitude: 37.57, longitude: 126.98, providers: &[(Vultr, "icn"), (Oracle, "icn")] },
+	Region { id: "sgp", metro: "Singapore", country: "SG", continent: Continent::Asia, area: "ap-southeast", latitude: 1.35, longitude: 103.82, providers: &[(Vultr, "sgp"), (HostHatch, "sgp"), (Oracle, "sin")] },
+	Region { id: "hkg", metro: "Hong Kong", country: "HK", continent: Continent::Asia, area: "ap-east", latitude: 22.32, longitude: 114.17, providers: &[(HostHatch, "hkg")] },
+	Region { id: "bom", metro: "Mumbai", country: "IN", continent: Continent::Asia, area: "ap-south", latitude: 19.08, longitude: 72.88, providers: &[(Vultr, "bom"), (Oracle, "bom")] },
+	Region { id: "blr", metro: "Bangalore", country: "IN", continent: Continent::Asia, area: "ap-south", latitude: 12.97, longitude: 77.59, providers: &[(Vultr, "blr")] },
+	Region { id: "del", metro: "Delhi", country: "IN", continent: Continent::Asia, area: "ap-south", latitude: 28.70, longitude: 77.10, providers: &[(Vultr, "del")] },
+	Region { id: "tlv", metro: "Tel Aviv", country: "IL", continent: Continent::Asia, area: "me-central", latitude: 32.09, longitude: 34.78, providers: &[(Vultr, "tlv")] },
+	// Oceania
+	Region { id: "syd", metro: "Sydney", country: "AU", continent: Continent::Oceania, area: "au-east", latitude: -33.87, longitude: 151.21, providers: &[(Vultr, "syd"), (HostHatch, "syd"), (Oracle, "syd")] },
+	Region { id: "mel", metro: "Melbourne", country: "AU", continent: Continent::Oceania, area: "au-east", latitude: -37.81, longitude: 144.96, providers: &[(Vultr, "mel"), (Oracle, "mel")] },
+	// Africa
+	Region { id: "jnb", metro: "Johannesburg", country: "ZA", continent: Continent::Africa, area: "af-south", latitude: -26.20, longitude: 28.05, providers: &[(Vultr, "jnb"), (Oracle, "jnb")] },
+];
+
+impl Region {
+	pub fn list() -> &'static [Region] {
+		REGIONS
+	}
+
+	pub fn get(id: &str) -> Option<&'static Region> {
+		REGIONS.iter().find(|region| region.id.eq_ignore_ascii_case(id))
+	}
+
+	// The region behind a provider's own region code, e.g. `fsn1` on Hetzner.
+	pub fn from_provider_code(provider: CloudProvider, code: &str) -> Option<&'static Region> {
+		REGIONS.iter().find(|region| region.provider_code(provider) == Some(code))
+	}
+
+	// Canonical id or any provider's code, for callers that don't know the provider. No code
+	// is used by two providers for different metros.
+	pub fn lookup(code: &str) -> Option<&'static Region> {
+		Self::get(code).or_else(|| {
+			REGIONS
+				.iter()
+				.find(|region| region.providers.iter().any(|(_, provider_code)| provider_code.eq_ignore_ascii_case(code)))
+		})
+	}
+
+	// Metros matching a canonical id, an area such as `eu-central` or a continent such as
+	// `europe`.
+	pub fn matching(query: &str) -> Vec<&'static Region> {
+		REGIONS
+			.iter()
+			.filter(|region| {
+				region.id.eq_ignore_ascii_case(query)
+					|| region.area.eq_ignore_ascii_case(query)
+					|| region.continent.code().eq_ignore_ascii_case(query)
+			})
+			.collect()
+	}
+
+	// The provider's region code for the first metro matching `query` that it serves.
+	pub fn resolve(provider: CloudProvider, query: &str) -> Option<&'static str> {
+		Self::matching(query).into_iter().find_map(|region| region.provider_code(provider))
+	}
+
+	pub fn provider_code(&self, provider: CloudProvider) -> Option<&'static str> {
+		self.providers.iter().find(|(kind, _)| *kind == provider).map(|(_, code)| *code)
+	}
+
+	// Great-circle distance in kilometres.
+	pub fn distance_km(&self, other: &Region) -> f64 {
+		const EARTH_RADIUS_KM: f64 = 6371.0;
+
+		let (lat1, lat2) = (self.latitude.to_radians(), other.latitude.to_radians());
+		let dlat = lat2 - lat1;
+		let dlon = (other.longitude - self.longitude).to_radians();
+		let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+
+		2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+	}
 }
 
-impl FromStr for Region {
-    type Err = RegionParseError;
+fn serialize_codes<S>(providers: &&'static [(CloudProvider, &'static str)], serializer: S) -> Result<S::Ok, S::Error>
+where
+	S: Serializer,
+{
+	use serde::ser::SerializeMap;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "Frankfurt" => Ok(Region::Frankfurt),
-            "NewYork" => Ok(Region::NewYork),
-            _ => Err(RegionParseError),
-        }
-    }
+	let mut map = serializer.serialize_map(Some(providers.len()))?;
+	for (provider, code) in providers.iter() {
+		map.serialize_entry(provider.code(), code)?;
+	}
+	map.end()
 }
 
 impl fmt::Display for Region {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Region::Frankfurt => write!(f, "Frankfurt"),
-            Region::NewYork => write!(f, "NewYork"),
-           
-        }
-    }
-}
\ No newline at end of file
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", self.id)
+	}
+}
@@ -1,12 +1,37 @@
 use serde::{Deserialize, Serialize};
 
+use super::cloud_provider::CloudProvider;
+
+// A block storage volume, identified by its provider and the provider's volume id.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Volume {
-	pub id: u64,
+	pub id: String,
+	pub provider: CloudProvider,
+	// Provider region code.
+	pub region: String,
+	pub label: String,
+	// Sizes in GB; `used` is only known once a worker reports it.
 	pub used: u64,
 	pub total: u64,
 	pub r#type: VolumeType,
 	pub tier: VolumeTier,
+	pub state: VolumeState,
+	// Provider instance the volume is attached to, and the worker and container using it.
+	pub instance_id: Option<String>,
+	pub worker_id: Option<u64>,
+	pub container: Option<String>,
+	// Block device the volume shows up as on its instance, once the provider reports one.
+	#[serde(default)]
+	pub device: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum VolumeState {
+	Creating,
+	Available,
+	Attached,
+	// Gone from the provider; kept so its last owner is still known.
+	Deleted,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -8,14 +8,20 @@ use super::instance::Instance;
 use super::instance_state::InstanceState;
 use super::metrics::Metrics;
 use super::network::Network;
-use super::region::Region;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Worker {
 	pub id: u64,
+	// Provider instance id, if the worker could find it out.
+	pub instance_id: Option<String>,
 	pub network: Network,
 	pub provider: CloudProvider,
-	pub region: Region,
+	// Named provider account the instance was created in.
+	pub account: String,
+	// Canonical region id, e.g. `ewr`; see `models::region`.
+	pub region: String,
+	// Where the worker's own gRPC services listen.
+	pub grpc_addr: String,
 	pub instance: Instance,
 	pub metrics: Metrics,
 	pub state: InstanceState,
@@ -0,0 +1,660 @@
+use std::collections::{BTreeMap, HashMap};
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use hyper::header::AUTHORIZATION;
+use hyper::http::StatusCode;
+use hyper::{Body, Method, Request, Response};
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+use models::models::instance::Instance;
+use models::models::region::Region;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::api::response::{bad_request, error, internal_error, json, not_found};
+use crate::manager::manager::{Manager, ManagerError};
+use crate::gpu::catalog::GpuFilter;
+use crate::gpu::gpu::GpuInstanceRequest;
+use crate::plans::catalog::CatalogPlan;
+use crate::providers::error::ProviderError;
+use crate::rules::rule::Rule;
+use crate::volumes::autoscale::NewAutoscalePolicy;
+use crate::volumes::snapshots::{CreateSnapshot, NewSnapshotPolicy, RestoreSnapshot};
+use crate::volumes::volumes::{AttachVolume, CreateVolume, VolumeManager};
+use crate::workers::registry::tokens_match;
+
+// Handlers return the error response as `Err` so they can bail out early with `?`; see the
+// `result_large_err` allowance in `main.rs`.
+type HandlerResult = Result<Response<Body>, Response<Body>>;
+
+#[derive(Clone)]
+pub struct ApiState {
+	pub manager: Arc<Manager>,
+	pub volume_manager: Arc<VolumeManager>,
+	// `api.token`; `Config::validate` makes sure it is set.
+	pub token: String,
+}
+
+pub async fn handle_request(req: Request<Body>, state: ApiState) -> Result<Response<Body>, Infallible> {
+	if !authorized(&req, &state.token) {
+		return Ok(error(StatusCode::UNAUTHORIZED, "Missing or invalid API token"));
+	}
+
+	let method = req.method().clone();
+	let path = req.uri().path().to_string();
+
+	let result = match (method, path.as_str()) {
+		(Method::GET, "/instances") => get_instances(&state).await,
+		(Method::GET, "/instances/events") => get_instance_events(&req, &state).await,
+		(Method::GET, "/plan") => get_plan(&state).await,
+		(Method::GET, "/plan/recorded") => Ok(json(StatusCode::OK, &state.manager.recorded_plans())),
+
+		(Method::GET, "/rules") => get_rules(&state).await,
+		(Method::POST, "/rules") => create_rule(req, &state).await,
+		(Method::GET, "/rules/history") => get_rule_history(&req, &state).await,
+		(Method::GET, path) if path.starts_with("/rules/") => get_rule(path, &state).await,
+		(Method::PUT, path) if path.starts_with("/rules/") => update_rule(path, req, &state).await,
+		(Method::DELETE, path) if path.starts_with("/rules/") => delete_rule(path, &state).await,
+
+		(Method::GET, "/gpu/plans") => get_gpu_plans(&req, &state).await,
+		(Method::GET, "/gpu/instances") => get_gpu_instances(&req, &state).await,
+		(Method::POST, "/gpu/instances") => create_gpu_instance(req, &state).await,
+		(Method::DELETE, path) if path.starts_with("/gpu/instances/") => delete_gpu_instance(path, &state).await,
+
+		(Method::GET, "/costs") => get_costs(&req, &state).await,
+
+		(Method::GET, "/catalog/plans") => get_catalog_plans(&req, &state).await,
+		(Method::POST, "/catalog/plans/refresh") => refresh_catalog_plans(&state).await,
+		(Method::POST, "/catalog/plans/cheapest") => cheapest_catalog_plan(req, &state).await,
+
+		(Method::GET, "/regions") => Ok(json(StatusCode::OK, &Region::list())),
+		(Method::GET, "/regions/resolve") => resolve_region(&req),
+
+		(Method::GET, "/workers") => get_workers(&state).await,
+		(Method::GET, path) if path.starts_with("/workers/") => get_worker(path, &state).await,
+
+		(Method::GET, "/volumes") => get_volumes(&req, &state).await,
+		(Method::POST, "/volumes") => create_volume(req, &state).await,
+		(Method::GET, path) if path.starts_with("/volumes/") => get_volume(path, &state).await,
+		(Method::POST, path) if path.starts_with("/volumes/") => volume_action(path, req, &state).await,
+		(Method::DELETE, path) if path.starts_with("/volumes/") => delete_volume(path, &state).await,
+
+		(Method::GET, "/snapshot-policies") => get_snapshot_policies(&state).await,
+		(Method::POST, "/snapshot-policies") => create_snapshot_policy(req, &state).await,
+		(Method::GET, "/snapshot-policies/runs") => get_snapshot_runs(&req, &state).await,
+		(Method::DELETE, path) if path.starts_with("/snapshot-policies/") => delete_snapshot_policy(path, &state).await,
+
+		(Method::GET, "/autoscale-policies") => get_autoscale_policies(&state).await,
+		(Method::POST, "/autoscale-policies") => save_autoscale_policy(req, &state).await,
+		(Method::DELETE, path) if path.starts_with("/autoscale-policies/") => delete_autoscale_policy(path, &state).await,
+
+		_ => Err(not_found()),
+	};
+
+	Ok(result.unwrap_or_else(|response| response))
+}
+
+fn authorized(req: &Request<Body>, token: &str) -> bool {
+	req.headers()
+		.get(AUTHORIZATION)
+		.and_then(|value| value.to_str().ok())
+		.and_then(|value| value.strip_prefix("Bearer "))
+		.is_some_and(|given| !token.is_empty() && tokens_match(given, token))
+}
+
+fn query_param(req: &Request<Body>, key: &str) -> Result<String, Response<Body>> {
+	req.uri()
+		.query()
+		.and_then(|query| {
+			let params: HashMap<String, String> = form_urlencoded::parse(query.as_bytes())
+				.into_owned()
+				.collect();
+			params.get(key).cloned()
+		})
+		.ok_or_else(|| bad_request(format!("Missing {} query parameter", key)))
+}
+
+async fn parse_body<T: DeserializeOwned>(req: Request<Body>) -> Result<T, Response<Body>> {
+	let body = hyper::body::to_bytes(req.into_body())
+		.await
+		.map_err(bad_request)?;
+
+	serde_json::from_slice(&body).map_err(bad_request)
+}
+
+// Parses the trailing id of paths such as `/rules/42`.
+fn path_id(path: &str, prefix: &str) -> Result<i64, Response<Body>> {
+	path.strip_prefix(prefix)
+		.and_then(|id| id.parse().ok())
+		.ok_or_else(not_found)
+}
+
+fn message(message: &str) -> Response<Body> {
+	json(StatusCode::OK, &serde_json::json!({ "message": message }))
+}
+
+async fn get_instances(state: &ApiState) -> HandlerResult {
+	let instances = state.manager.get_instances().await.map_err(manager_error)?;
+
+	Ok(json(StatusCode::OK, &instances))
+}
+
+// `?instance_id=` narrows the events down to a single instance.
+async fn get_instance_events(req: &Request<Body>, state: &ApiState) -> HandlerResult {
+	let instance_id = query_param(req, "instance_id").ok();
+
+	let events = state
+		.manager
+		.event_store()
+		.list(instance_id.as_deref())
+		.await
+		.map_err(internal_error)?;
+
+	Ok(json(StatusCode::OK, &events))
+}
+
+async fn get_plan(state: &ApiState) -> HandlerResult {
+	let plan = state.manager.plan().await.map_err(manager_error)?;
+
+	Ok(json(StatusCode::OK, &plan))
+}
+
+// Provider failures are the upstream's fault, except for rate limits which the client may retry.
+fn manager_error(e: ManagerError) -> Response<Body> {
+	match e {
+		ManagerError::ProviderError(ProviderError::RateLimited { .. }) => error(StatusCode::TOO_MANY_REQUESTS, e.to_string()),
+		ManagerError::ProviderError(ref provider) if provider.is_transient() => {
+			error(StatusCode::SERVICE_UNAVAILABLE, e.to_string())
+		}
+		ManagerError::ProviderError(ProviderError::Invalid(_)) => bad_request(e),
+		ManagerError::ProviderError(ProviderError::NotFound(_)) => error(StatusCode::NOT_FOUND, e.to_string()),
+		ManagerError::ProviderError(ProviderError::Unsupported(_)) => error(StatusCode::NOT_IMPLEMENTED, e.to_string()),
+		ManagerError::ProviderError(_) => error(StatusCode::BAD_GATEWAY, e.to_string()),
+		_ => internal_error(e),
+	}
+}
+
+fn rule_error(e: ManagerError) -> Response<Body> {
+	match e {
+		ManagerError::InvalidRule(_) => bad_request(e),
+		_ => internal_error(e),
+	}
+}
+
+// Unique violations mean another rule already covers the provider, account and region.
+fn rule_store_error(e: sqlx::Error) -> Response<Body> {
+	match e.as_database_error().and_then(|e| e.code()) {
+		Some(code) if code == "23505" => error(
+			StatusCode::CONFLICT,
+			"A rule for this provider, account, region and GPU model already exists",
+		),
+		_ => internal_error(e),
+	}
+}
+
+async fn get_rules(state: &ApiState) -> HandlerResult {
+	let rules = state.manager.rule_store().list().await.map_err(internal_error)?;
+
+	Ok(json(StatusCode::OK, &rules))
+}
+
+async fn get_rule(path: &str, state: &ApiState) -> HandlerResult {
+	let id = path_id(path, "/rules/")?;
+	let rule = state
+		.manager
+		.rule_store()
+		.get(id)
+		.await
+		.map_err(internal_error)?
+		.ok_or_else(not_found)?;
+
+	Ok(json(StatusCode::OK, &rule))
+}
+
+async fn create_rule(req: Request<Body>, state: &ApiState) -> HandlerResult {
+	let rule: Rule = parse_body(req).await?;
+	let rule = state.manager.validate_rule(rule).map_err(rule_error)?;
+	let rule = state
+		.manager
+		.rule_store()
+		.create(&rule)
+		.await
+		.map_err(rule_store_error)?;
+
+	Ok(json(StatusCode::CREATED, &rule))
+}
+
+async fn update_rule(path: &str, req: Request<Body>, state: &ApiState) -> HandlerResult {
+	let id = path_id(path, "/rules/")?;
+	let rule: Rule = parse_body(req).await?;
+	let rule = state.manager.validate_rule(rule).map_err(rule_error)?;
+	let rule = state
+		.manager
+		.rule_store()
+		.update(id, &rule)
+		.await
+		.map_err(rule_store_error)?
+		.ok_or_else(not_found)?;
+
+	Ok(json(StatusCode::OK, &rule))
+}
+
+async fn delete_rule(path: &str, state: &ApiState) -> HandlerResult {
+	let id = path_id(path, "/rules/")?;
+	state
+		.manager
+		.rule_store()
+		.delete(id)
+		.await
+		.map_err(internal_error)?
+		.ok_or_else(not_found)?;
+
+	Ok(message("Rule deleted successfully"))
+}
+
+// `?model=`, `?region=` and `?min_vram_gb=` narrow the catalog down; plans come cheapest first.
+async fn get_gpu_plans(req: &Request<Body>, state: &ApiState) -> HandlerResult {
+	let mut filter = GpuFilter::default();
+	if let Ok(model) = query_param(req, "model") {
+		filter = filter.model(&model);
+	}
+	if let Ok(region) = query_param(req, "region") {
+		filter = filter.region(&region);
+	}
+	if let Ok(min_vram_gb) = query_param(req, "min_vram_gb") {
+		filter = filter.min_vram_gb(min_vram_gb.parse().map_err(|_| bad_request("min_vram_gb must be a number"))?);
+	}
+
+	let plans = state
+		.manager
+		.gpu()
+		.catalog()
+		.find(&filter)
+		.await
+		.map_err(|e| manager_error(e.into()))?;
+
+	Ok(json(StatusCode::OK, &plans))
+}
+
+// `?model=` only lists instances with that GPU model.
+async fn get_gpu_instances(req: &Request<Body>, state: &ApiState) -> HandlerResult {
+	let model = query_param(req, "model").ok();
+	let instances = state
+		.manager
+		.gpu()
+		.list_gpu_instances(model.as_deref())
+		.await
+		.map_err(manager_error)?;
+
+	Ok(json(StatusCode::OK, &instances))
+}
+
+async fn create_gpu_instance(req: Request<Body>, state: &ApiState) -> HandlerResult {
+	let request: GpuInstanceRequest = parse_body(req).await?;
+	let instance = state
+		.manager
+		.gpu()
+		.create_gpu_instance(&request)
+		.await
+		.map_err(manager_error)?;
+
+	Ok(json(StatusCode::CREATED, &instance))
+}
+
+async fn delete_gpu_instance(path: &str, state: &ApiState) -> HandlerResult {
+	let instance_id = path.trim_start_matches("/gpu/instances/");
+	state
+		.manager
+		.gpu()
+		.delete_gpu_instance(instance_id)
+		.await
+		.map_err(manager_error)?;
+
+	Ok(message("GPU instance deleted successfully"))
+}
+
+// The last cost report, built on demand before the first one; `?refresh=true` rebuilds it.
+async fn get_costs(req: &Request<Body>, state: &ApiState) -> HandlerResult {
+	let refresh = query_param(req, "refresh").is_ok_and(|refresh| refresh == "true");
+	let report = match state.manager.costs().report() {
+		Some(report) if !refresh => report,
+		_ => state.manager.cost_report().await.map_err(manager_error)?,
+	};
+
+	Ok(json(StatusCode::OK, &report))
+}
+
+// The shape to fit and where it may run; see `plans::selector::cheapest`.
+#[derive(Deserialize)]
+struct CheapestPlanRequest {
+	shape: Instance,
+	#[serde(default)]
+	regions: Vec<String>,
+}
+
+// `?provider=` and `?region=` (a provider region code) narrow the catalog down; plans come
+// cheapest first.
+async fn get_catalog_plans(req: &Request<Body>, state: &ApiState) -> HandlerResult {
+	let provider = match query_param(req, "provider") {
+		Ok(provider) => Some(provider.parse::<ProviderKind>().map_err(bad_request)?),
+		Err(_) => None,
+	};
+	let region = query_param(req, "region").ok();
+
+	let mut plans: Vec<CatalogPlan> = state
+		.manager
+		.plan_catalog()
+		.plans()
+		.await
+		.map_err(|e| manager_error(e.into()))?
+		.into_iter()
+		.filter(|plan| provider.is_none_or(|provider| plan.provider == provider))
+		.filter(|plan| region.as_ref().is_none_or(|region| plan.regions.contains(region)))
+		.collect();
+	plans.sort_by(|a, b| a.monthly.total_cmp(&b.monthly));
+
+	Ok(json(StatusCode::OK, &plans))
+}
+
+async fn refresh_catalog_plans(state: &ApiState) -> HandlerResult {
+	let plans = state
+		.manager
+		.plan_catalog()
+		.refresh()
+		.await
+		.map_err(|e| manager_error(e.into()))?;
+
+	Ok(message(&format!("Plan catalog refreshed, {} plans", plans.len())))
+}
+
+async fn cheapest_catalog_plan(req: Request<Body>, state: &ApiState) -> HandlerResult {
+	let request: CheapestPlanRequest = parse_body(req).await?;
+	let selection = state
+		.manager
+		.cheapest_plan(&request.shape, &request.regions)
+		.await
+		.map_err(manager_error)?;
+
+	Ok(json(StatusCode::OK, &selection))
+}
+
+// `?region=` takes a canonical region, area or continent and returns each provider's code
+// for it, for providers that have one.
+fn resolve_region(req: &Request<Body>) -> HandlerResult {
+	let region = query_param(req, "region")?;
+	let codes: BTreeMap<&str, &str> = [ProviderKind::Vultr, ProviderKind::Hetzner, ProviderKind::Oracle, ProviderKind::HostHatch]
+		.into_iter()
+		.filter_map(|provider| Region::resolve(provider, &region).map(|code| (provider.code(), code)))
+		.collect();
+
+	if codes.is_empty() {
+		return Err(not_found());
+	}
+
+	Ok(json(StatusCode::OK, &codes))
+}
+
+async fn get_workers(state: &ApiState) -> HandlerResult {
+	let workers = state.manager.worker_store().list().await.map_err(internal_error)?;
+
+	Ok(json(StatusCode::OK, &workers))
+}
+
+async fn get_worker(path: &str, state: &ApiState) -> HandlerResult {
+	let id = path_id(path, "/workers/")?;
+	let id = u64::try_from(id).map_err(|_| bad_request(format!("Invalid worker id {}", id)))?;
+	let worker = state
+		.manager
+		.worker_store()
+		.get(id)
+		.await
+		.map_err(internal_error)?
+		.ok_or_else(not_found)?;
+
+	Ok(json(StatusCode::OK, &worker))
+}
+
+// `?rule_id=` narrows the history down to a single rule.
+async fn get_rule_history(req: &Request<Body>, state: &ApiState) -> HandlerResult {
+	let rule_id = match query_param(req, "rule_id") {
+		Ok(rule_id) => Some(
+			rule_id
+				.parse::<i64>()
+				.map_err(|_| bad_request("rule_id must be an integer"))?,
+		),
+		Err(_) => None,
+	};
+
+	let history = state
+		.manager
+		.rule_store()
+		.history(rule_id)
+		.await
+		.map_err(internal_error)?;
+
+	Ok(json(StatusCode::OK, &history))
+}
+
+// `?provider=` narrows the listing down to one provider.
+async fn get_volumes(req: &Request<Body>, state: &ApiState) -> HandlerResult {
+	let provider = match query_param(req, "provider") {
+		Ok(provider) => Some(provider.parse::<ProviderKind>().map_err(bad_request)?),
+		Err(_) => None,
+	};
+
+	let volumes = state.volume_manager.list(provider).await.map_err(manager_error)?;
+
+	Ok(json(StatusCode::OK, &volumes))
+}
+
+async fn create_volume(req: Request<Body>, state: &ApiState) -> HandlerResult {
+	let request: CreateVolume = parse_body(req).await?;
+	let volume = state.volume_manager.create(&request).await.map_err(manager_error)?;
+
+	Ok(json(StatusCode::CREATED, &volume))
+}
+
+// Splits `/volumes/{provider}/{id}` and an optional trailing action such as `/attach`.
+fn volume_path(path: &str) -> Result<(ProviderKind, &str, Option<&str>), Response<Body>> {
+	let mut parts = path.trim_start_matches("/volumes/").splitn(3, '/');
+	let provider = parts.next().unwrap_or_default().parse::<ProviderKind>().map_err(bad_request)?;
+	let volume_id = parts.next().filter(|id| !id.is_empty()).ok_or_else(not_found)?;
+
+	Ok((provider, volume_id, parts.next()))
+}
+
+#[derive(Deserialize)]
+struct ResizeVolume {
+	size_gb: u64,
+}
+
+async fn get_volume(path: &str, state: &ApiState) -> HandlerResult {
+	let (provider, volume_id, action) = volume_path(path)?;
+	let volumes = &state.volume_manager;
+
+	match action {
+		None => {
+			let volume = volumes.get(provider, volume_id).await.map_err(manager_error)?;
+			Ok(json(StatusCode::OK, &volume))
+		}
+		Some("snapshots") => {
+			let snapshots = volumes.list_snapshots(provider, volume_id).await.map_err(manager_error)?;
+			Ok(json(StatusCode::OK, &snapshots))
+		}
+		_ => Err(not_found()),
+	}
+}
+
+async fn volume_action(path: &str, req: Request<Body>, state: &ApiState) -> HandlerResult {
+	let (provider, volume_id, action) = volume_path(path)?;
+	let volumes = &state.volume_manager;
+	let restore = action
+		.and_then(|action| action.strip_prefix("snapshots/"))
+		.and_then(|action| action.strip_suffix("/restore"))
+		.filter(|snapshot_id| !snapshot_id.contains('/'));
+
+	match (action, restore) {
+		(_, Some(snapshot_id)) => {
+			let request: RestoreSnapshot = parse_body(req).await?;
+			let volume = volumes
+				.restore_snapshot(provider, volume_id, snapshot_id, &request)
+				.await
+				.map_err(manager_error)?;
+			Ok(json(StatusCode::CREATED, &volume))
+		}
+		(Some("attach"), _) => {
+			let request: AttachVolume = parse_body(req).await?;
+			let volume = volumes.attach(provider, volume_id, &request).await.map_err(manager_error)?;
+			Ok(json(StatusCode::OK, &volume))
+		}
+		(Some("detach"), _) => {
+			let volume = volumes.detach(provider, volume_id).await.map_err(manager_error)?;
+			Ok(json(StatusCode::OK, &volume))
+		}
+		(Some("resize"), _) => {
+			let request: ResizeVolume = parse_body(req).await?;
+			let volume = volumes
+				.resize(provider, volume_id, request.size_gb)
+				.await
+				.map_err(manager_error)?;
+			Ok(json(StatusCode::OK, &volume))
+		}
+		(Some("snapshots"), _) => {
+			let request: CreateSnapshot = parse_body(req).await?;
+			let snapshot = volumes
+				.create_snapshot(provider, volume_id, &request.label)
+				.await
+				.map_err(manager_error)?;
+			Ok(json(StatusCode::CREATED, &snapshot))
+		}
+		_ => Err(not_found()),
+	}
+}
+
+async fn delete_volume(path: &str, state: &ApiState) -> HandlerResult {
+	let (provider, volume_id, action) = volume_path(path)?;
+	let volumes = &state.volume_manager;
+
+	match action.map(|action| action.strip_prefix("snapshots/")) {
+		None => {
+			volumes.delete(provider, volume_id).await.map_err(manager_error)?;
+			Ok(message("Volume deleted successfully"))
+		}
+		Some(Some(snapshot_id)) if !snapshot_id.contains('/') => {
+			volumes
+				.delete_snapshot(provider, volume_id, snapshot_id)
+				.await
+				.map_err(manager_error)?;
+			Ok(message("Snapshot deleted successfully"))
+		}
+		_ => Err(not_found()),
+	}
+}
+
+async fn get_snapshot_policies(state: &ApiState) -> HandlerResult {
+	let policies = state.volume_manager.snapshot_policies().await.map_err(manager_error)?;
+
+	Ok(json(StatusCode::OK, &policies))
+}
+
+async fn create_snapshot_policy(req: Request<Body>, state: &ApiState) -> HandlerResult {
+	let policy: NewSnapshotPolicy = parse_body(req).await?;
+	let policy = state
+		.volume_manager
+		.create_snapshot_policy(&policy)
+		.await
+		.map_err(manager_error)?;
+
+	Ok(json(StatusCode::CREATED, &policy))
+}
+
+async fn delete_snapshot_policy(path: &str, state: &ApiState) -> HandlerResult {
+	let id = path_id(path, "/snapshot-policies/")?;
+	let deleted = state
+		.volume_manager
+		.delete_snapshot_policy(id)
+		.await
+		.map_err(manager_error)?;
+	if !deleted {
+		return Err(not_found());
+	}
+
+	Ok(message("Snapshot policy deleted successfully"))
+}
+
+async fn get_autoscale_policies(state: &ApiState) -> HandlerResult {
+	let policies = state.volume_manager.autoscale_policies().await.map_err(manager_error)?;
+
+	Ok(json(StatusCode::OK, &policies))
+}
+
+// A volume has at most one policy; posting another one replaces it.
+async fn save_autoscale_policy(req: Request<Body>, state: &ApiState) -> HandlerResult {
+	let policy: NewAutoscalePolicy = parse_body(req).await?;
+	let policy = state
+		.volume_manager
+		.save_autoscale_policy(&policy)
+		.await
+		.map_err(manager_error)?;
+
+	Ok(json(StatusCode::OK, &policy))
+}
+
+async fn delete_autoscale_policy(path: &str, state: &ApiState) -> HandlerResult {
+	let id = path_id(path, "/autoscale-policies/")?;
+	let deleted = state
+		.volume_manager
+		.delete_autoscale_policy(id)
+		.await
+		.map_err(manager_error)?;
+	if !deleted {
+		return Err(not_found());
+	}
+
+	Ok(message("Autoscale policy deleted successfully"))
+}
+
+// `?policy_id=` narrows the runs down to a single policy.
+async fn get_snapshot_runs(req: &Request<Body>, state: &ApiState) -> HandlerResult {
+	let policy_id = match query_param(req, "policy_id") {
+		Ok(policy_id) => Some(policy_id.parse::<i64>().map_err(bad_request)?),
+		Err(_) => None,
+	};
+
+	let runs = state.volume_manager.snapshot_runs(policy_id).await.map_err(manager_error)?;
+
+	Ok(json(StatusCode::OK, &runs))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn request(authorization: Option<&str>) -> Request<Body> {
+		let mut builder = Request::builder().uri("/instances");
+		if let Some(authorization) = authorization {
+			builder = builder.header(AUTHORIZATION, authorization);
+		}
+
+		builder.body(Body::empty()).unwrap()
+	}
+
+	#[test]
+	fn volume_path_rejects_unknown_providers() {
+		let (provider, volume_id, action) = volume_path("/volumes/hetzner/42/attach").unwrap();
+		assert_eq!((provider, volume_id, action), (ProviderKind::Hetzner, "42", Some("attach")));
+
+		assert_eq!(volume_path("/volumes/acme/42").unwrap_err().status(), StatusCode::BAD_REQUEST);
+		assert_eq!(volume_path("/volumes/vultr/").unwrap_err().status(), StatusCode::NOT_FOUND);
+	}
+
+	#[test]
+	fn authorized_requires_the_bearer_token() {
+		assert!(authorized(&request(Some("Bearer secret")), "secret"));
+
+		assert!(!authorized(&request(None), "secret"));
+		assert!(!authorized(&request(Some("Bearer other")), "secret"));
+		assert!(!authorized(&request(Some("secret")), "secret"));
+		assert!(!authorized(&request(Some("Bearer ")), ""));
+	}
+}
@@ -0,0 +1,39 @@
+use hyper::header::CONTENT_TYPE;
+use hyper::http::StatusCode;
+use hyper::{Body, Response};
+use serde::Serialize;
+use serde_json::json;
+
+pub fn json<T: Serialize>(status: StatusCode, body: &T) -> Response<Body> {
+	match serde_json::to_vec(body) {
+		Ok(body) => Response::builder()
+			.status(status)
+			.header(CONTENT_TYPE, "application/json")
+			.body(Body::from(body))
+			.unwrap(),
+		Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+	}
+}
+
+// Errors are always returned as `{"error": "<message>"}`.
+pub fn error(status: StatusCode, message: impl ToString) -> Response<Body> {
+	Response::builder()
+		.status(status)
+		.header(CONTENT_TYPE, "application/json")
+		.body(Body::from(
+			json!({ "error": message.to_string() }).to_string(),
+		))
+		.unwrap()
+}
+
+pub fn bad_request(message: impl ToString) -> Response<Body> {
+	error(StatusCode::BAD_REQUEST, message)
+}
+
+pub fn not_found() -> Response<Body> {
+	error(StatusCode::NOT_FOUND, "Not found")
+}
+
+pub fn internal_error(message: impl ToString) -> Response<Body> {
+	error(StatusCode::INTERNAL_SERVER_ERROR, message)
+}
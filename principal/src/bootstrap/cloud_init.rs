@@ -0,0 +1,247 @@
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+use serde::Serialize;
+
+use crate::config::config::BootstrapConfig;
+use crate::rules::rule::Rule;
+
+const WORKER_ENV_PATH: &str = "/etc/infralink/worker.env";
+const WORKER_UNIT_PATH: &str = "/etc/systemd/system/infralink-worker.service";
+const WORKER_BINARY_PATH: &str = "/usr/local/bin/worker";
+// Where the worker mounts volumes, see `worker::volumes`.
+const VOLUMES_DIR: &str = "/mnt/volumes";
+
+#[derive(Serialize)]
+struct CloudConfig {
+	package_update: bool,
+	packages: Vec<String>,
+	write_files: Vec<WriteFile>,
+	runcmd: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct WriteFile {
+	path: String,
+	permissions: String,
+	content: String,
+}
+
+// User-data for an instance created for `rule` in `region`. A rule's own template wins,
+// with `{{name}}` placeholders filled in; otherwise the default worker cloud-config is used
+// if bootstrapping is configured and the instance can join as a worker. The join token only
+// ever goes into the default cloud-config, never into templates anyone with API access writes.
+pub fn user_data(config: &BootstrapConfig, rule: &Rule, region: &str) -> Option<String> {
+	let variables = variables(config, rule, region);
+
+	match &rule.user_data {
+		Some(template) => Some(render(
+			template,
+			variables.iter().filter(|(name, _)| *name != "join_token"),
+		)),
+		None if config.is_enabled() && can_join(&rule.provider) => Some(default_cloud_config(config, &variables)),
+		None => None,
+	}
+}
+
+// Workers register with the provider's id of their instance, which they look up on the instance.
+pub fn can_join(provider: &str) -> bool {
+	instance_id_request(provider).is_some()
+}
+
+fn variables(config: &BootstrapConfig, rule: &Rule, region: &str) -> Vec<(&'static str, String)> {
+	vec![
+		("principal_addr", config.principal_addr.clone()),
+		("join_token", config.join_token.clone()),
+		("worker_binary_url", config.worker_binary_url.clone().unwrap_or_default()),
+		("worker_image", config.worker_image.clone().unwrap_or_default()),
+		("provider", rule.provider.clone()),
+		("account", rule.account.clone()),
+		("region", region.to_string()),
+		("rule_id", rule.id.to_string()),
+	]
+}
+
+// Metadata service request answering with the provider's id of the instance it is made from, which
+// the worker registers with so the principal can match it to the instance. HostHatch has no
+// metadata service.
+fn instance_id_request(provider: &str) -> Option<&'static str> {
+	match provider.parse::<ProviderKind>().ok()? {
+		ProviderKind::Vultr => Some("http://169.254.169.254/v1/instance-v2-id"),
+		ProviderKind::Hetzner => Some("http://169.254.169.254/hetzner/v1/metadata/instance-id"),
+		ProviderKind::Oracle => Some("-H 'Authorization: Bearer Oracle' http://169.254.169.254/opc/v2/instance/id"),
+		ProviderKind::HostHatch => None,
+	}
+}
+
+fn render<'a>(template: &str, variables: impl IntoIterator<Item = &'a (&'a str, String)>) -> String {
+	variables
+		.into_iter()
+		.fold(template.to_string(), |rendered, (name, value)| {
+			rendered.replace(&format!("{{{{{}}}}}", name), value)
+		})
+}
+
+// Installs the packages, writes the worker's config and runs it under systemd, either
+// from the downloaded binary or from its container image.
+fn default_cloud_config(config: &BootstrapConfig, variables: &[(&str, String)]) -> String {
+	let worker_env = variables
+		.iter()
+		.filter(|(name, _)| !name.starts_with("worker_"))
+		.map(|(name, value)| format!("{}={}\n", name.to_uppercase(), value))
+		.collect::<String>();
+
+	let mut runcmd = vec!["systemctl enable --now docker".to_string()];
+
+	// The instance id is only known on the instance, so it is added to the worker's env there.
+	let provider = variables.iter().find(|(name, _)| *name == "provider").map_or("", |(_, value)| value.as_str());
+	if let Some(request) = instance_id_request(provider) {
+		runcmd.push(format!("echo \"INSTANCE_ID=$(curl -fsS {})\" >> {}", request, WORKER_ENV_PATH));
+	}
+
+	let exec_start = match (&config.worker_binary_url, &config.worker_image) {
+		(Some(url), _) => {
+			runcmd.push(format!("curl -fsSL -o {} '{}'", WORKER_BINARY_PATH, url));
+			runcmd.push(format!("chmod +x {}", WORKER_BINARY_PATH));
+			WORKER_BINARY_PATH.to_string()
+		}
+		(None, Some(image)) => {
+			runcmd.push(format!("docker pull '{}'", image));
+			// Privileged with the host's devices and a shared volumes directory, so the volumes it
+			// mounts are visible to the host and the containers it runs.
+			runcmd.push(format!("mkdir -p {}", VOLUMES_DIR));
+			format!(
+				"/usr/bin/docker run --rm --name infralink-worker --network host --privileged --env-file {0} -v /var/run/docker.sock:/var/run/docker.sock -v /dev:/dev -v {2}:{2}:rshared {1}",
+				WORKER_ENV_PATH, image, VOLUMES_DIR
+			)
+		}
+		(None, None) => String::new(),
+	};
+
+	runcmd.push("systemctl daemon-reload".to_string());
+	runcmd.push("systemctl enable --now infralink-worker".to_string());
+
+	let unit = format!(
+		"[Unit]\nDescription=Infralink worker\nAfter=docker.service network-online.target\nRequires=docker.service\n\n[Service]\nEnvironmentFile={}\nExecStart={}\nRestart=always\n\n[Install]\nWantedBy=multi-user.target\n",
+		WORKER_ENV_PATH, exec_start
+	);
+
+	let cloud_config = CloudConfig {
+		package_update: true,
+		packages: config.packages.clone(),
+		write_files: vec![
+			WriteFile {
+				path: WORKER_ENV_PATH.to_string(),
+				permissions: "0600".to_string(),
+				content: worker_env,
+			},
+			WriteFile {
+				path: WORKER_UNIT_PATH.to_string(),
+				permissions: "0644".to_string(),
+				content: unit,
+			},
+		],
+		runcmd,
+	};
+
+	// Serializing a handful of strings can't fail.
+	format!(
+		"#cloud-config\n{}",
+		serde_yaml::to_string(&cloud_config).unwrap_or_default()
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::config::config::DEFAULT_ACCOUNT;
+
+	fn config() -> BootstrapConfig {
+		BootstrapConfig {
+			principal_addr: "http://principal.internal:50051".to_string(),
+			join_token: "secret".to_string(),
+			worker_binary_url: Some("https://example.com/worker".to_string()),
+			..BootstrapConfig::default()
+		}
+	}
+
+	fn rule(provider: &str, user_data: Option<&str>) -> Rule {
+		Rule {
+			id: 7,
+			provider: provider.to_string(),
+			account: DEFAULT_ACCOUNT.to_string(),
+			region: vec!["ewr".to_string()],
+			instance_count: 1,
+			min_count: None,
+			max_count: None,
+			plan: None,
+			gpu_model: None,
+			gpu_vram_gb: None,
+			image: None,
+			ssh_keys: Vec::new(),
+			user_data: user_data.map(str::to_string),
+			labels: Vec::new(),
+		}
+	}
+
+	#[test]
+	fn render_fills_in_known_placeholders() {
+		let variables = [("region", "ewr".to_string()), ("rule_id", "7".to_string())];
+
+		assert_eq!(
+			render("{{region}}/{{rule_id}}/{{region}} {{unknown}}", &variables),
+			"ewr/7/ewr {{unknown}}"
+		);
+	}
+
+	#[test]
+	fn rule_templates_never_get_the_join_token() {
+		let user_data = user_data(&config(), &rule("vultr", Some("{{principal_addr}} {{join_token}}")), "ewr");
+
+		assert_eq!(user_data.as_deref(), Some("http://principal.internal:50051 {{join_token}}"));
+	}
+
+	#[test]
+	fn default_cloud_config_runs_the_downloaded_worker() {
+		let user_data = user_data(&config(), &rule("vultr", None), "ewr").unwrap();
+		let cloud_config: serde_yaml::Value =
+			serde_yaml::from_str(user_data.strip_prefix("#cloud-config\n").unwrap()).unwrap();
+
+		let env = cloud_config["write_files"][0]["content"].as_str().unwrap();
+		assert_eq!(
+			env,
+			"PRINCIPAL_ADDR=http://principal.internal:50051\nJOIN_TOKEN=secret\nPROVIDER=vultr\nACCOUNT=default\nREGION=ewr\nRULE_ID=7\n"
+		);
+		assert_eq!(cloud_config["write_files"][0]["permissions"].as_str(), Some("0600"));
+
+		let runcmd: Vec<&str> =
+			cloud_config["runcmd"].as_sequence().unwrap().iter().map(|cmd| cmd.as_str().unwrap()).collect();
+		assert!(runcmd.contains(&"echo \"INSTANCE_ID=$(curl -fsS http://169.254.169.254/v1/instance-v2-id)\" >> /etc/infralink/worker.env"));
+		assert!(runcmd.contains(&"curl -fsSL -o /usr/local/bin/worker 'https://example.com/worker'"));
+		assert_eq!(runcmd.last(), Some(&"systemctl enable --now infralink-worker"));
+	}
+
+	#[test]
+	fn default_cloud_config_runs_the_worker_image() {
+		let config = BootstrapConfig {
+			worker_binary_url: None,
+			worker_image: Some("ghcr.io/example/worker:latest".to_string()),
+			..config()
+		};
+
+		let user_data = user_data(&config, &rule("hetzner", None), "fsn1").unwrap();
+
+		assert!(user_data.contains("docker pull 'ghcr.io/example/worker:latest'"));
+		assert!(user_data.contains("hetzner/v1/metadata/instance-id"));
+	}
+
+	#[test]
+	fn hosthatch_instances_cannot_join() {
+		assert!(!can_join("hosthatch"));
+		assert_eq!(user_data(&config(), &rule("hosthatch", None), "ams"), None);
+		assert!(user_data(&config(), &rule("hosthatch", Some("#!/bin/sh")), "ams").is_some());
+	}
+
+	#[test]
+	fn nothing_without_bootstrapping_or_a_template() {
+		assert_eq!(user_data(&BootstrapConfig::default(), &rule("vultr", None), "ewr"), None);
+	}
+}
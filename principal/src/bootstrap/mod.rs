@@ -0,0 +1 @@
+pub mod cloud_init;
@@ -0,0 +1,681 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+use serde::{Deserialize, Serialize};
+
+// Used when PRINCIPAL_CONFIG is not set; a missing default file is not an error.
+const DEFAULT_CONFIG_PATH: &str = "principal.yaml";
+
+#[derive(Debug)]
+pub enum ConfigError {
+	Io(String, std::io::Error),
+	Parse(String, serde_yaml::Error),
+	Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ConfigError::Io(path, e) => write!(f, "Failed to read {}: {}", path, e),
+			ConfigError::Parse(path, e) => write!(f, "Failed to parse {}: {}", path, e),
+			ConfigError::Invalid(e) => write!(f, "Invalid config: {}", e),
+		}
+	}
+}
+
+impl Error for ConfigError {}
+
+// Runtime configuration: the YAML file first, then environment variables on top.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+	pub database_url: String,
+	pub redis_nodes: Vec<String>,
+	pub providers: ProvidersConfig,
+	pub bootstrap: BootstrapConfig,
+	pub workers: WorkersConfig,
+	pub lifecycle: LifecycleConfig,
+	pub costs: CostsConfig,
+	pub volumes: VolumesConfig,
+	pub api: ApiConfig,
+}
+
+// The HTTP API; every request has to present the token as `Authorization: Bearer <token>`.
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct ApiConfig {
+	pub listen_addr: String,
+	pub token: String,
+}
+
+impl Default for ApiConfig {
+	fn default() -> Self {
+		ApiConfig {
+			listen_addr: "0.0.0.0:8080".to_string(),
+			token: String::new(),
+		}
+	}
+}
+
+// Cost estimates and the budgets checked before anything billable is created.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CostsConfig {
+	// How often the cost report is rebuilt from the provider listings.
+	pub refresh_interval_secs: u64,
+	// Block storage price per GB and month, by provider code.
+	pub volume_gb_monthly: BTreeMap<String, f64>,
+	// Price per GB of outbound traffic past an account's pooled allowance.
+	pub bandwidth_overage_gb: f64,
+	// US dollars per unit of each other currency, by code, e.g. `EUR: 1.08`. Plans priced in
+	// different currencies are only compared when there is a rate.
+	pub exchange_rates: BTreeMap<String, f64>,
+	pub budgets: Vec<BudgetConfig>,
+}
+
+impl Default for CostsConfig {
+	fn default() -> Self {
+		CostsConfig {
+			refresh_interval_secs: 300,
+			volume_gb_monthly: BTreeMap::from([("vultr".to_string(), 0.10), ("hetzner".to_string(), 0.044)]),
+			bandwidth_overage_gb: 0.01,
+			exchange_rates: BTreeMap::new(),
+			budgets: Vec::new(),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BudgetAction {
+	// Creates go ahead and are logged.
+	#[default]
+	Warn,
+	// Creates that would take the month's projected cost past the limit are refused.
+	Block,
+}
+
+// A monthly spending limit. The scope fields narrow down which costs count against it; a
+// budget without any covers everything.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct BudgetConfig {
+	pub name: String,
+	pub monthly_limit: f64,
+	pub action: BudgetAction,
+	pub provider: Option<String>,
+	// Canonical region, area, continent or provider region code.
+	pub region: Option<String>,
+	pub rule_id: Option<i64>,
+	// Configured account, e.g. one per Vultr project.
+	pub project: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LifecycleConfig {
+	// Instances still starting after this long are reported as stuck.
+	pub starting_timeout_secs: u64,
+}
+
+impl Default for LifecycleConfig {
+	fn default() -> Self {
+		LifecycleConfig {
+			starting_timeout_secs: 900,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct VolumesConfig {
+	// How often snapshot policies are checked for a due run.
+	pub snapshot_check_interval_secs: u64,
+	// How often volume usage is checked against the autoscaling policies.
+	pub autoscale_check_interval_secs: u64,
+	// How long a volume is left alone after growing it, so the worker can report usage of the
+	// grown filesystem before it is grown again.
+	pub autoscale_cooldown_secs: u64,
+}
+
+impl Default for VolumesConfig {
+	fn default() -> Self {
+		VolumesConfig {
+			snapshot_check_interval_secs: 60,
+			autoscale_check_interval_secs: 60,
+			autoscale_cooldown_secs: 600,
+		}
+	}
+}
+
+// The gRPC registry workers register with and heartbeat to.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WorkersConfig {
+	pub listen_addr: String,
+	pub heartbeat_interval_secs: u32,
+	// Workers are marked `Unknown` after this many heartbeat intervals without one.
+	pub missed_heartbeats: u32,
+	// How long a drained worker's containers get to stop before its instance is removed.
+	pub drain_grace_period_secs: u64,
+}
+
+impl Default for WorkersConfig {
+	fn default() -> Self {
+		WorkersConfig {
+			listen_addr: "0.0.0.0:50051".to_string(),
+			heartbeat_interval_secs: 15,
+			missed_heartbeats: 3,
+			drain_grace_period_secs: 60,
+		}
+	}
+}
+
+// What new instances need to come up as workers; rendered into their cloud-init user-data.
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct BootstrapConfig {
+	// Address workers register with, e.g. `http://principal.internal:50051`.
+	pub principal_addr: String,
+	// Shared secret workers present when registering.
+	pub join_token: String,
+	// Either a URL to download the worker binary from or a container image to run it from.
+	pub worker_binary_url: Option<String>,
+	pub worker_image: Option<String>,
+	pub packages: Vec<String>,
+}
+
+impl Default for BootstrapConfig {
+	fn default() -> Self {
+		BootstrapConfig {
+			principal_addr: String::new(),
+			join_token: String::new(),
+			worker_binary_url: None,
+			worker_image: None,
+			packages: vec!["docker.io".to_string(), "curl".to_string()],
+		}
+	}
+}
+
+impl BootstrapConfig {
+	// Without a principal address workers would have nowhere to register.
+	pub fn is_enabled(&self) -> bool {
+		!self.principal_addr.is_empty()
+	}
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ProvidersConfig {
+	pub vultr: Option<ProviderConfig>,
+	pub hetzner: Option<ProviderConfig>,
+	pub hosthatch: Option<ProviderConfig>,
+	pub oracle: Option<OracleSettings>,
+}
+
+// Name of the account formed by a provider's top-level credentials.
+pub const DEFAULT_ACCOUNT: &str = "default";
+
+// Credentials and endpoint for one token-authenticated provider.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ProviderConfig {
+	pub api_key: String,
+	// Overrides the public API, e.g. to point at services/fake-cloud.
+	pub base_url: Option<String>,
+	// Defaults for providers that need them to provision anything.
+	pub plan: Option<String>,
+	pub image: Option<String>,
+	// Further named accounts, e.g. one per Vultr project.
+	pub accounts: BTreeMap<String, AccountConfig>,
+}
+
+// A named account; unset fields are taken from the provider entry it sits under.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AccountConfig {
+	pub api_key: String,
+	pub base_url: Option<String>,
+	pub plan: Option<String>,
+	pub image: Option<String>,
+}
+
+// Secrets only show whether they are set, so configs can be logged. The database and Redis
+// URLs may carry passwords too.
+const REDACTED: &str = "<redacted>";
+
+fn redacted(secret: &str) -> &'static str {
+	if secret.is_empty() {
+		""
+	} else {
+		REDACTED
+	}
+}
+
+impl fmt::Debug for Config {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("Config")
+			.field("database_url", &redacted(&self.database_url))
+			.field("redis_nodes", &vec![REDACTED; self.redis_nodes.len()])
+			.field("providers", &self.providers)
+			.field("bootstrap", &self.bootstrap)
+			.field("workers", &self.workers)
+			.field("lifecycle", &self.lifecycle)
+			.field("costs", &self.costs)
+			.field("volumes", &self.volumes)
+			.field("api", &self.api)
+			.finish()
+	}
+}
+
+impl fmt::Debug for ApiConfig {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("ApiConfig")
+			.field("listen_addr", &self.listen_addr)
+			.field("token", &redacted(&self.token))
+			.finish()
+	}
+}
+
+impl fmt::Debug for BootstrapConfig {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("BootstrapConfig")
+			.field("principal_addr", &self.principal_addr)
+			.field("join_token", &redacted(&self.join_token))
+			.field("worker_binary_url", &self.worker_binary_url)
+			.field("worker_image", &self.worker_image)
+			.field("packages", &self.packages)
+			.finish()
+	}
+}
+
+impl fmt::Debug for ProviderConfig {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("ProviderConfig")
+			.field("api_key", &redacted(&self.api_key))
+			.field("base_url", &self.base_url)
+			.field("plan", &self.plan)
+			.field("image", &self.image)
+			.field("accounts", &self.accounts)
+			.finish()
+	}
+}
+
+impl fmt::Debug for AccountConfig {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("AccountConfig")
+			.field("api_key", &redacted(&self.api_key))
+			.field("base_url", &self.base_url)
+			.field("plan", &self.plan)
+			.field("image", &self.image)
+			.finish()
+	}
+}
+
+impl ProviderConfig {
+	pub fn url(&self, default: &str) -> String {
+		self.base_url
+			.as_deref()
+			.unwrap_or(default)
+			.trim_end_matches('/')
+			.to_string()
+	}
+
+	// Every configured account by name, the top-level credentials (if any) as `default`.
+	pub fn accounts(&self) -> Vec<(String, ProviderConfig)> {
+		let mut accounts = Vec::new();
+
+		if !self.api_key.is_empty() {
+			accounts.push((
+				DEFAULT_ACCOUNT.to_string(),
+				ProviderConfig {
+					accounts: BTreeMap::new(),
+					..self.clone()
+				},
+			));
+		}
+
+		for (name, account) in &self.accounts {
+			accounts.push((
+				name.clone(),
+				ProviderConfig {
+					api_key: account.api_key.clone(),
+					base_url: account.base_url.clone().or_else(|| self.base_url.clone()),
+					plan: account.plan.clone().or_else(|| self.plan.clone()),
+					image: account.image.clone().or_else(|| self.image.clone()),
+					accounts: BTreeMap::new(),
+				},
+			));
+		}
+
+		accounts
+	}
+}
+
+// Raw OCI settings; `OracleConfig` validates them and loads the key.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct OracleSettings {
+	pub tenancy_id: String,
+	pub user_id: String,
+	pub fingerprint: String,
+	pub private_key_path: String,
+	pub region: String,
+	pub compartment_id: String,
+	pub availability_domain: String,
+	pub subnet_id: String,
+	pub image_id: String,
+	pub shape: Option<String>,
+	pub ocpus: Option<f32>,
+	pub memory_gb: Option<f32>,
+}
+
+impl Config {
+	// Reads PRINCIPAL_CONFIG (or ./principal.yaml if present) and applies env overrides.
+	pub fn load() -> Result<Self, ConfigError> {
+		let mut config = match var("PRINCIPAL_CONFIG") {
+			Some(path) => Self::from_file(&path)?,
+			None if Path::new(DEFAULT_CONFIG_PATH).exists() => Self::from_file(DEFAULT_CONFIG_PATH)?,
+			None => Config::default(),
+		};
+
+		config.apply_env()?;
+		config.validate()?;
+
+		Ok(config)
+	}
+
+	pub fn from_file(path: &str) -> Result<Self, ConfigError> {
+		let contents = fs::read_to_string(path).map_err(|e| ConfigError::Io(path.to_string(), e))?;
+
+		serde_yaml::from_str(&contents).map_err(|e| ConfigError::Parse(path.to_string(), e))
+	}
+
+	// Environment variables win over the file; empty values are ignored.
+	fn apply_env(&mut self) -> Result<(), ConfigError> {
+		if let Some(database_url) = var("COCKROACH_DB_URL") {
+			self.database_url = database_url;
+		}
+		if let Some(node) = var("NEW_YORK_REDIS_CONNECTION_URL") {
+			self.redis_nodes = vec![node];
+		}
+
+		if let Some(principal_addr) = var("PRINCIPAL_ADDR") {
+			self.bootstrap.principal_addr = principal_addr;
+		}
+		if let Some(join_token) = var("PRINCIPAL_JOIN_TOKEN") {
+			self.bootstrap.join_token = join_token;
+		}
+		if let Some(worker_binary_url) = var("WORKER_BINARY_URL") {
+			self.bootstrap.worker_binary_url = Some(worker_binary_url);
+		}
+		if let Some(worker_image) = var("WORKER_IMAGE") {
+			self.bootstrap.worker_image = Some(worker_image);
+		}
+		if let Some(listen_addr) = var("PRINCIPAL_API_ADDR") {
+			self.api.listen_addr = listen_addr;
+		}
+		if let Some(token) = var("PRINCIPAL_API_TOKEN") {
+			self.api.token = token;
+		}
+		if let Some(listen_addr) = var("PRINCIPAL_GRPC_ADDR") {
+			self.workers.listen_addr = listen_addr;
+		}
+		if let Some(grace_period) = parse_var("WORKER_DRAIN_GRACE_PERIOD_SECS")? {
+			self.workers.drain_grace_period_secs = grace_period;
+		}
+		if let Some(timeout) = parse_var("INSTANCE_STARTING_TIMEOUT_SECS")? {
+			self.lifecycle.starting_timeout_secs = timeout;
+		}
+		if let Some(interval) = parse_var("COSTS_REFRESH_INTERVAL_SECS")? {
+			self.costs.refresh_interval_secs = interval;
+		}
+		if let Some(interval) = parse_var("SNAPSHOT_CHECK_INTERVAL_SECS")? {
+			self.volumes.snapshot_check_interval_secs = interval;
+		}
+		if let Some(interval) = parse_var("AUTOSCALE_CHECK_INTERVAL_SECS")? {
+			self.volumes.autoscale_check_interval_secs = interval;
+		}
+		if let Some(cooldown) = parse_var("AUTOSCALE_COOLDOWN_SECS")? {
+			self.volumes.autoscale_cooldown_secs = cooldown;
+		}
+
+		apply_provider_env(&mut self.providers.vultr, "VULTR");
+		apply_provider_env(&mut self.providers.hetzner, "HETZNER");
+		apply_provider_env(&mut self.providers.hosthatch, "HOSTHATCH");
+		apply_oracle_env(&mut self.providers.oracle)
+	}
+
+	fn validate(&self) -> Result<(), ConfigError> {
+		if self.database_url.is_empty() {
+			return Err(ConfigError::Invalid(
+				"database_url (COCKROACH_DB_URL) is required".to_string(),
+			));
+		}
+
+		if self.api.token.is_empty() {
+			return Err(ConfigError::Invalid("api.token (PRINCIPAL_API_TOKEN) is required".to_string()));
+		}
+		if self.api.listen_addr.parse::<std::net::SocketAddr>().is_err() {
+			return Err(ConfigError::Invalid(format!(
+				"api.listen_addr is not a socket address: {}",
+				self.api.listen_addr
+			)));
+		}
+
+		if self.bootstrap.is_enabled() {
+			if self.bootstrap.join_token.is_empty() {
+				return Err(ConfigError::Invalid(
+					"bootstrap.join_token (PRINCIPAL_JOIN_TOKEN) is required with bootstrap.principal_addr"
+						.to_string(),
+				));
+			}
+			if self.bootstrap.worker_binary_url.is_some() == self.bootstrap.worker_image.is_some() {
+				return Err(ConfigError::Invalid(
+					"bootstrap needs exactly one of worker_binary_url and worker_image".to_string(),
+				));
+			}
+		}
+
+		if self.workers.listen_addr.parse::<std::net::SocketAddr>().is_err() {
+			return Err(ConfigError::Invalid(format!(
+				"workers.listen_addr is not a socket address: {}",
+				self.workers.listen_addr
+			)));
+		}
+		if self.workers.heartbeat_interval_secs == 0 || self.workers.missed_heartbeats == 0 {
+			return Err(ConfigError::Invalid(
+				"workers.heartbeat_interval_secs and workers.missed_heartbeats must be positive".to_string(),
+			));
+		}
+
+		if self.volumes.snapshot_check_interval_secs == 0 || self.volumes.autoscale_check_interval_secs == 0 {
+			return Err(ConfigError::Invalid(
+				"volumes.snapshot_check_interval_secs and volumes.autoscale_check_interval_secs must be positive"
+					.to_string(),
+			));
+		}
+
+		if self.costs.refresh_interval_secs == 0 {
+			return Err(ConfigError::Invalid("costs.refresh_interval_secs must be positive".to_string()));
+		}
+		if let Some((code, _)) = self.costs.exchange_rates.iter().find(|(_, rate)| rate.is_nan() || **rate <= 0.0) {
+			return Err(ConfigError::Invalid(format!("costs.exchange_rates.{} must be positive", code)));
+		}
+		for budget in &self.costs.budgets {
+			if budget.name.is_empty() {
+				return Err(ConfigError::Invalid("costs.budgets need a name".to_string()));
+			}
+			if budget.monthly_limit <= 0.0 {
+				return Err(ConfigError::Invalid(format!(
+					"costs.budgets.{}.monthly_limit must be positive",
+					budget.name
+				)));
+			}
+			if let Some(provider) = &budget.provider {
+				provider
+					.parse::<ProviderKind>()
+					.map_err(|e| ConfigError::Invalid(format!("costs.budgets.{}.provider: {}", budget.name, e)))?;
+			}
+		}
+
+		for (name, provider) in [
+			("vultr", &self.providers.vultr),
+			("hetzner", &self.providers.hetzner),
+			("hosthatch", &self.providers.hosthatch),
+		] {
+			let provider = match provider {
+				Some(provider) => provider,
+				None => continue,
+			};
+
+			if provider.api_key.is_empty() && provider.accounts.is_empty() {
+				return Err(ConfigError::Invalid(format!(
+					"providers.{}.api_key is required",
+					name
+				)));
+			}
+
+			for (account, settings) in &provider.accounts {
+				if account == DEFAULT_ACCOUNT {
+					return Err(ConfigError::Invalid(format!(
+						"providers.{}.accounts.{} is reserved for the top-level api_key",
+						name, account
+					)));
+				}
+				if settings.api_key.is_empty() {
+					return Err(ConfigError::Invalid(format!(
+						"providers.{}.accounts.{}.api_key is required",
+						name, account
+					)));
+				}
+			}
+		}
+
+		Ok(())
+	}
+
+	// Unconfigured providers fall back to an empty key against the public API.
+	pub fn vultr(&self) -> ProviderConfig {
+		self.providers.vultr.clone().unwrap_or_default()
+	}
+
+	pub fn hetzner(&self) -> ProviderConfig {
+		self.providers.hetzner.clone().unwrap_or_default()
+	}
+}
+
+fn var(key: &str) -> Option<String> {
+	env::var(key).ok().filter(|value| !value.is_empty())
+}
+
+// `<PREFIX>_API_KEY`, `<PREFIX>_API_URL`, `<PREFIX>_PLAN` and `<PREFIX>_IMAGE`, all for
+// the default account; named accounts are only read from the file.
+fn apply_provider_env(provider: &mut Option<ProviderConfig>, prefix: &str) {
+	let api_key = var(&format!("{}_API_KEY", prefix));
+	let base_url = var(&format!("{}_API_URL", prefix));
+	let plan = var(&format!("{}_PLAN", prefix));
+	let image = var(&format!("{}_IMAGE", prefix));
+
+	if api_key.is_none() && base_url.is_none() && plan.is_none() && image.is_none() {
+		return;
+	}
+
+	let provider = provider.get_or_insert_with(ProviderConfig::default);
+	if let Some(api_key) = api_key {
+		provider.api_key = api_key;
+	}
+	if base_url.is_some() {
+		provider.base_url = base_url;
+	}
+	if plan.is_some() {
+		provider.plan = plan;
+	}
+	if image.is_some() {
+		provider.image = image;
+	}
+}
+
+// The setting an `ORACLE_*` variable overrides.
+type OracleField = fn(&mut OracleSettings) -> &mut String;
+
+fn apply_oracle_env(oracle: &mut Option<OracleSettings>) -> Result<(), ConfigError> {
+	let fields: [(&str, OracleField); 9] = [
+		("ORACLE_TENANCY_ID", |o| &mut o.tenancy_id),
+		("ORACLE_USER_ID", |o| &mut o.user_id),
+		("ORACLE_FINGERPRINT", |o| &mut o.fingerprint),
+		("ORACLE_PRIVATE_KEY_PATH", |o| &mut o.private_key_path),
+		("ORACLE_REGION", |o| &mut o.region),
+		("ORACLE_COMPARTMENT_ID", |o| &mut o.compartment_id),
+		("ORACLE_AVAILABILITY_DOMAIN", |o| &mut o.availability_domain),
+		("ORACLE_SUBNET_ID", |o| &mut o.subnet_id),
+		("ORACLE_IMAGE_ID", |o| &mut o.image_id),
+	];
+
+	for (key, field) in fields {
+		if let Some(value) = var(key) {
+			*field(oracle.get_or_insert_with(OracleSettings::default)) = value;
+		}
+	}
+
+	if let Some(shape) = var("ORACLE_SHAPE") {
+		oracle.get_or_insert_with(OracleSettings::default).shape = Some(shape);
+	}
+	if let Some(ocpus) = parse_var("ORACLE_OCPUS")? {
+		oracle.get_or_insert_with(OracleSettings::default).ocpus = Some(ocpus);
+	}
+	if let Some(memory_gb) = parse_var("ORACLE_MEMORY_GB")? {
+		oracle.get_or_insert_with(OracleSettings::default).memory_gb = Some(memory_gb);
+	}
+
+	Ok(())
+}
+
+fn parse_var<T: FromStr>(key: &str) -> Result<Option<T>, ConfigError> {
+	var(key)
+		.map(|value| {
+			value
+				.parse()
+				.map_err(|_| ConfigError::Invalid(format!("{} must be a number", key)))
+		})
+		.transpose()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn debug_redacts_secrets() {
+		let mut config = Config {
+			database_url: "postgres://root:hunter2@db/infralink".to_string(),
+			redis_nodes: vec!["redis://:hunter2@redis".to_string()],
+			..Config::default()
+		};
+		config.api.token = "hunter2".to_string();
+		config.bootstrap.join_token = "hunter2".to_string();
+		config.providers.vultr = Some(ProviderConfig {
+			api_key: "hunter2".to_string(),
+			accounts: BTreeMap::from([(
+				"staging".to_string(),
+				AccountConfig {
+					api_key: "hunter2".to_string(),
+					..AccountConfig::default()
+				},
+			)]),
+			..ProviderConfig::default()
+		});
+
+		let debug = format!("{:?}", config);
+
+		assert!(!debug.contains("hunter2"));
+		assert!(debug.contains("api_key: \"<redacted>\""));
+		assert!(debug.contains("listen_addr: \"0.0.0.0:8080\""));
+	}
+}
@@ -0,0 +1,245 @@
+use chrono::{DateTime, Utc};
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+use serde::Serialize;
+
+use crate::config::config::{BudgetAction, BudgetConfig};
+use crate::costs::tracker::{accrue, CostItem, Month};
+use crate::providers::error::ProviderError;
+use crate::regions::resolve::covers;
+
+// Something about to be created, priced so it can be checked against the budgets.
+#[derive(Debug, Clone)]
+pub struct Charge {
+	pub provider: ProviderKind,
+	pub account: String,
+	// Provider region code.
+	pub region: String,
+	pub rule_id: Option<i64>,
+	// E.g. `instance vc2-1c-1gb` or `volume 40 GB`, for messages.
+	pub description: String,
+	pub hourly: f64,
+	pub monthly: f64,
+}
+
+impl Charge {
+	// What it will cost from now until the end of the month.
+	pub fn remaining(&self, now: DateTime<Utc>) -> f64 {
+		let month = Month::of(now);
+
+		accrue(self.hourly, self.monthly, month.hours_between(Some(now), month.end))
+	}
+}
+
+// A budget against the costs of the current month.
+#[derive(Debug, Clone, Serialize)]
+pub struct BudgetStatus {
+	pub name: String,
+	pub action: BudgetAction,
+	pub monthly_limit: f64,
+	pub month_to_date: f64,
+	pub projected: f64,
+	pub exceeded: bool,
+}
+
+// Whether costs with this provider, account, region and rule count against the budget.
+fn applies(budget: &BudgetConfig, provider: ProviderKind, account: &str, region: &str, rule_id: Option<i64>) -> bool {
+	budget.provider.as_ref().is_none_or(|name| name.parse::<ProviderKind>() == Ok(provider))
+		&& budget.project.as_ref().is_none_or(|project| project == account)
+		&& budget.region.as_ref().is_none_or(|query| covers(provider, region, query))
+		&& budget.rule_id.is_none_or(|id| rule_id == Some(id))
+}
+
+pub fn status(budget: &BudgetConfig, items: &[CostItem]) -> BudgetStatus {
+	let (month_to_date, projected) = items
+		.iter()
+		.filter(|item| applies(budget, item.provider, &item.project, &item.region, item.rule_id))
+		.fold((0.0, 0.0), |(month_to_date, projected), item| {
+			(month_to_date + item.month_to_date, projected + item.projected)
+		});
+
+	BudgetStatus {
+		name: budget.name.clone(),
+		action: budget.action,
+		monthly_limit: budget.monthly_limit,
+		month_to_date,
+		projected,
+		exceeded: projected > budget.monthly_limit,
+	}
+}
+
+// The first blocking budget the charge counts against, if any.
+pub fn blocking<'a>(budgets: &'a [BudgetConfig], charge: &Charge) -> Option<&'a BudgetConfig> {
+	budgets.iter().find(|budget| {
+		budget.action == BudgetAction::Block
+			&& applies(budget, charge.provider, &charge.account, &charge.region, charge.rule_id)
+	})
+}
+
+// Refuses the charge if it would take the projected cost of the month past a blocking budget;
+// warning budgets only log. `items` are the costs of the last report and `reserved` the charges
+// approved since, which it doesn't list yet.
+pub fn check(
+	budgets: &[BudgetConfig],
+	items: &[CostItem],
+	reserved: &[Charge],
+	charge: &Charge,
+	now: DateTime<Utc>,
+) -> Result<(), ProviderError> {
+	let added = charge.remaining(now);
+
+	for budget in budgets {
+		if !applies(budget, charge.provider, &charge.account, &charge.region, charge.rule_id) {
+			continue;
+		}
+
+		let pending: f64 = reserved
+			.iter()
+			.filter(|reserved| applies(budget, reserved.provider, &reserved.account, &reserved.region, reserved.rule_id))
+			.map(|reserved| reserved.remaining(now))
+			.sum();
+		let projected = status(budget, items).projected + pending + added;
+		if projected <= budget.monthly_limit {
+			continue;
+		}
+
+		let message = format!(
+			"{} in {} region {} would take budget {} to {:.2} of {:.2} this month",
+			charge.description, charge.provider, charge.region, budget.name, projected, budget.monthly_limit
+		);
+		match budget.action {
+			BudgetAction::Warn => println!("Budget warning: {}", message),
+			BudgetAction::Block => return Err(ProviderError::Invalid(message)),
+		}
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use chrono::TimeZone;
+
+	use super::*;
+	use crate::config::config::DEFAULT_ACCOUNT;
+	use crate::costs::tracker::CostKind;
+
+	// 336 hours before the end of the month, so every charge below costs its monthly price.
+	fn now() -> DateTime<Utc> {
+		Utc.with_ymd_and_hms(2026, 10, 18, 0, 0, 0).unwrap()
+	}
+
+	fn budget(action: BudgetAction) -> BudgetConfig {
+		BudgetConfig {
+			name: "vultr".to_string(),
+			monthly_limit: 100.0,
+			action,
+			provider: Some("vultr".to_string()),
+			..BudgetConfig::default()
+		}
+	}
+
+	fn item(provider: ProviderKind, region: &str, projected: f64) -> CostItem {
+		CostItem {
+			kind: CostKind::Instance,
+			id: "instance".to_string(),
+			provider,
+			project: DEFAULT_ACCOUNT.to_string(),
+			region: region.to_string(),
+			rule_id: None,
+			detail: "vc2-1c-1gb".to_string(),
+			priced: true,
+			month_to_date: projected / 2.0,
+			projected,
+		}
+	}
+
+	fn charge(provider: ProviderKind, region: &str) -> Charge {
+		Charge {
+			provider,
+			account: DEFAULT_ACCOUNT.to_string(),
+			region: region.to_string(),
+			rule_id: None,
+			description: "instance vc2-2c-4gb".to_string(),
+			hourly: 1.0,
+			monthly: 20.0,
+		}
+	}
+
+	#[test]
+	fn remaining_is_capped_at_the_monthly_price() {
+		assert_eq!(charge(ProviderKind::Vultr, "ewr").remaining(now()), 20.0);
+
+		let hourly = Charge {
+			hourly: 0.01,
+			..charge(ProviderKind::Vultr, "ewr")
+		};
+		assert!((hourly.remaining(now()) - 3.36).abs() < 1e-9);
+	}
+
+	#[test]
+	fn check_refuses_charges_past_a_blocking_budget() {
+		let budgets = [budget(BudgetAction::Block)];
+		let charge = charge(ProviderKind::Vultr, "ewr");
+
+		assert!(check(&budgets, &[item(ProviderKind::Vultr, "ewr", 80.0)], &[], &charge, now()).is_ok());
+		assert!(matches!(
+			check(&budgets, &[item(ProviderKind::Vultr, "ewr", 80.5)], &[], &charge, now()),
+			Err(ProviderError::Invalid(_))
+		));
+	}
+
+	#[test]
+	fn check_only_warns_for_warning_budgets() {
+		let budgets = [budget(BudgetAction::Warn)];
+		let items = [item(ProviderKind::Vultr, "ewr", 150.0)];
+
+		assert!(check(&budgets, &items, &[], &charge(ProviderKind::Vultr, "ewr"), now()).is_ok());
+	}
+
+	#[test]
+	fn check_counts_reserved_charges_the_report_does_not_list_yet() {
+		let budgets = [budget(BudgetAction::Block)];
+		let items = [item(ProviderKind::Vultr, "ewr", 50.0)];
+		let charge = charge(ProviderKind::Vultr, "ewr");
+
+		assert!(check(&budgets, &items, std::slice::from_ref(&charge), &charge, now()).is_ok());
+		assert!(check(&budgets, &items, &[charge.clone(), charge.clone()], &charge, now()).is_err());
+
+		// Reservations the budget doesn't cover don't count against it.
+		let elsewhere = [charge.clone(), self::charge(ProviderKind::Hetzner, "fsn1")];
+		assert!(check(&budgets, &items, &elsewhere, &charge, now()).is_ok());
+	}
+
+	#[test]
+	fn check_ignores_budgets_that_do_not_cover_the_charge() {
+		let items = [item(ProviderKind::Vultr, "ewr", 100.0), item(ProviderKind::Vultr, "fra", 100.0)];
+		let charge = charge(ProviderKind::Vultr, "ewr");
+		let scoped = |scope: fn(&mut BudgetConfig)| {
+			let mut budget = budget(BudgetAction::Block);
+			scope(&mut budget);
+			[budget]
+		};
+
+		assert!(check(&scoped(|budget| budget.region = Some("europe".to_string())), &items, &[], &charge, now()).is_ok());
+		assert!(check(&scoped(|budget| budget.region = Some("us-east".to_string())), &items, &[], &charge, now()).is_err());
+		assert!(check(&scoped(|budget| budget.provider = Some("hetzner".to_string())), &items, &[], &charge, now()).is_ok());
+		assert!(check(&scoped(|budget| budget.project = Some("staging".to_string())), &items, &[], &charge, now()).is_ok());
+		assert!(check(&scoped(|budget| budget.rule_id = Some(7)), &items, &[], &charge, now()).is_ok());
+	}
+
+	#[test]
+	fn blocking_finds_the_first_blocking_budget_covering_the_charge() {
+		let budgets = [
+			budget(BudgetAction::Warn),
+			BudgetConfig {
+				name: "hetzner".to_string(),
+				provider: Some("hetzner".to_string()),
+				..budget(BudgetAction::Block)
+			},
+			budget(BudgetAction::Block),
+		];
+
+		assert_eq!(blocking(&budgets, &charge(ProviderKind::Vultr, "ewr")).map(|budget| budget.name.as_str()), Some("vultr"));
+		assert_eq!(blocking(&budgets[..2], &charge(ProviderKind::Vultr, "ewr")).map(|budget| budget.name.as_str()), None);
+	}
+}
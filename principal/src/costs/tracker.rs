@@ -0,0 +1,617 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+
+use chrono::{DateTime, Datelike, TimeZone, Utc};
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+use serde::Serialize;
+
+use crate::config::config::CostsConfig;
+use crate::costs::budget::{self, BudgetStatus, Charge};
+use crate::plans::catalog::CatalogPlan;
+use crate::providers::error::ProviderError;
+use crate::providers::provider::{BandwidthUsage, ProviderInstance, ProviderVolume};
+use crate::regions::resolve::canonical;
+
+// Volumes are only priced per month; their hourly price is the monthly one over the hours of an
+// average month.
+const HOURS_PER_MONTH: f64 = 730.0;
+
+// Providers bill per calendar month (UTC), so costs are reported per month too.
+#[derive(Debug, Clone, Copy)]
+pub struct Month {
+	pub start: DateTime<Utc>,
+	pub end: DateTime<Utc>,
+}
+
+impl Month {
+	pub fn of(now: DateTime<Utc>) -> Self {
+		let (year, month) = (now.year(), now.month());
+		let (next_year, next_month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+
+		Month {
+			start: Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).unwrap(),
+			end: Utc.with_ymd_and_hms(next_year, next_month, 1, 0, 0, 0).unwrap(),
+		}
+	}
+
+	// Hours within this month from `from` (the start of the month if unknown) until `until`.
+	pub fn hours_between(&self, from: Option<DateTime<Utc>>, until: DateTime<Utc>) -> f64 {
+		let from = from.map_or(self.start, |from| from.max(self.start));
+		let until = until.min(self.end);
+
+		(until - from).num_seconds().max(0) as f64 / 3600.0
+	}
+
+	pub fn hours(&self) -> f64 {
+		self.hours_between(None, self.end)
+	}
+}
+
+// Hourly billing up to the monthly price, the way Vultr and Hetzner charge.
+pub fn accrue(hourly: f64, monthly: f64, hours: f64) -> f64 {
+	(hourly * hours).min(monthly)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CostKind {
+	Instance,
+	Volume,
+	// Outbound traffic past the account's allowance.
+	Bandwidth,
+}
+
+// Cost of one resource this month.
+#[derive(Debug, Clone, Serialize)]
+pub struct CostItem {
+	pub kind: CostKind,
+	// Instance or volume id; bandwidth is charged to the instance that sent it.
+	pub id: String,
+	pub provider: ProviderKind,
+	// Configured account, e.g. one per Vultr project.
+	pub project: String,
+	// Provider region code.
+	pub region: String,
+	pub rule_id: Option<i64>,
+	// Plan code, volume size or traffic, e.g. `cx22`, `40 GB` or `12.3 GB over`.
+	pub detail: String,
+	// Items without a known price count as free.
+	pub priced: bool,
+	pub month_to_date: f64,
+	// Month to date plus what the resource costs until the end of the month if it stays.
+	pub projected: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct Cost {
+	pub month_to_date: f64,
+	pub projected: f64,
+}
+
+impl Cost {
+	fn add(&mut self, item: &CostItem) {
+		self.month_to_date += item.month_to_date;
+		self.projected += item.projected;
+	}
+}
+
+// Costs of the current month, estimated from plan prices and how long each resource has
+// existed. Resources deleted earlier in the month are no longer listed and not counted.
+#[derive(Debug, Clone, Serialize)]
+pub struct CostReport {
+	// E.g. `2023-05`.
+	pub month: String,
+	pub generated_at: DateTime<Utc>,
+	pub total: Cost,
+	pub by_provider: BTreeMap<String, Cost>,
+	// Keyed by canonical region where the catalog knows it.
+	pub by_region: BTreeMap<String, Cost>,
+	// Keyed by rule id, `none` for resources no rule accounts for.
+	pub by_rule: BTreeMap<String, Cost>,
+	// Keyed by `provider/account`.
+	pub by_project: BTreeMap<String, Cost>,
+	pub budgets: Vec<BudgetStatus>,
+	pub items: Vec<CostItem>,
+}
+
+// What a report is built from.
+#[derive(Default)]
+pub struct Inventory {
+	pub instances: Vec<ProviderInstance>,
+	pub volumes: Vec<ProviderVolume>,
+	pub bandwidth: Vec<BandwidthUsage>,
+	// Prices, including GPU plans.
+	pub plans: Vec<CatalogPlan>,
+	// Rule id of each instance a rule accounts for, by provider and instance id.
+	pub owners: HashMap<(ProviderKind, String), i64>,
+}
+
+impl Inventory {
+	fn owner(&self, provider: ProviderKind, instance_id: &str) -> Option<i64> {
+		self.owners.get(&(provider, instance_id.to_string())).copied()
+	}
+}
+
+// The plan's price in the region, or anywhere if the catalog doesn't list the region.
+fn price_of<'a>(plans: &'a [CatalogPlan], provider: ProviderKind, plan: &str, region: &str) -> Option<&'a CatalogPlan> {
+	let mut candidates = plans.iter().filter(|candidate| candidate.provider == provider && candidate.id == plan);
+	let first = candidates.clone().next();
+
+	candidates.find(|candidate| candidate.regions.iter().any(|code| code == region)).or(first)
+}
+
+// Builds the report and checks new creates against the budgets it produced.
+#[derive(Debug)]
+pub struct CostTracker {
+	config: CostsConfig,
+	last: RwLock<Option<(CostReport, Vec<CatalogPlan>)>>,
+	// Charges approved since the last report was listed, with when they were approved.
+	reserved: Mutex<Vec<(DateTime<Utc>, Charge)>>,
+}
+
+impl CostTracker {
+	pub fn new(config: CostsConfig) -> Self {
+		CostTracker {
+			config,
+			last: RwLock::new(None),
+			reserved: Mutex::new(Vec::new()),
+		}
+	}
+
+	// US dollars per unit of each other currency plans are priced in, by currency code.
+	pub fn exchange_rates(&self) -> &BTreeMap<String, f64> {
+		&self.config.exchange_rates
+	}
+
+	pub fn refresh_interval(&self) -> Duration {
+		Duration::from_secs(self.config.refresh_interval_secs)
+	}
+
+	pub fn report(&self) -> Option<CostReport> {
+		self.last.read().unwrap().as_ref().map(|(report, _)| report.clone())
+	}
+
+	// Builds a report from the inventory listed at `listed_at` and keeps it, with its prices, for
+	// budget checks. Charges approved before the listing are in it now and no longer reserved.
+	pub fn record(&self, inventory: Inventory, listed_at: DateTime<Utc>) -> CostReport {
+		let report = self.build(&inventory, Utc::now());
+		for status in report.budgets.iter().filter(|status| status.exceeded) {
+			println!(
+				"Budget {} is projected at {:.2} of {:.2} this month",
+				status.name, status.projected, status.monthly_limit
+			);
+		}
+
+		*self.last.write().unwrap() = Some((report.clone(), inventory.plans));
+		self.reserved.lock().unwrap().retain(|(approved_at, _)| *approved_at >= listed_at);
+		report
+	}
+
+	fn build(&self, inventory: &Inventory, now: DateTime<Utc>) -> CostReport {
+		let month = Month::of(now);
+		let mut items = Vec::new();
+
+		for instance in &inventory.instances {
+			let price = price_of(&inventory.plans, instance.provider, &instance.plan, &instance.region);
+			let (month_to_date, projected) = price.map_or((0.0, 0.0), |price| {
+				(
+					accrue(price.hourly, price.monthly, month.hours_between(instance.created_at, now)),
+					accrue(price.hourly, price.monthly, month.hours_between(instance.created_at, month.end)),
+				)
+			});
+
+			items.push(CostItem {
+				kind: CostKind::Instance,
+				id: instance.id.clone(),
+				provider: instance.provider,
+				project: instance.account.clone(),
+				region: instance.region.clone(),
+				rule_id: inventory.owner(instance.provider, &instance.id),
+				detail: instance.plan.clone(),
+				priced: price.is_some(),
+				month_to_date,
+				projected,
+			});
+		}
+
+		for volume in &inventory.volumes {
+			let monthly = self.volume_monthly(volume.provider, volume.size_gb);
+			let (month_to_date, projected) = monthly.map_or((0.0, 0.0), |monthly| {
+				let hourly = monthly / HOURS_PER_MONTH;
+				(
+					accrue(hourly, monthly, month.hours_between(volume.created_at, now)),
+					accrue(hourly, monthly, month.hours_between(volume.created_at, month.end)),
+				)
+			});
+
+			items.push(CostItem {
+				kind: CostKind::Volume,
+				id: volume.id.clone(),
+				provider: volume.provider,
+				project: volume.account.clone(),
+				region: volume.region.clone(),
+				rule_id: volume
+					.attached_to
+					.as_ref()
+					.and_then(|instance_id| inventory.owner(volume.provider, instance_id)),
+				detail: format!("{} GB", volume.size_gb),
+				priced: monthly.is_some(),
+				month_to_date,
+				projected,
+			});
+		}
+
+		items.extend(self.bandwidth_overage(inventory, &month, now));
+
+		let mut report = CostReport {
+			month: now.format("%Y-%m").to_string(),
+			generated_at: now,
+			total: Cost::default(),
+			by_provider: BTreeMap::new(),
+			by_region: BTreeMap::new(),
+			by_rule: BTreeMap::new(),
+			by_project: BTreeMap::new(),
+			budgets: self.config.budgets.iter().map(|budget| budget::status(budget, &items)).collect(),
+			items: Vec::new(),
+		};
+
+		for item in &items {
+			report.total.add(item);
+			report.by_provider.entry(item.provider.code().to_string()).or_default().add(item);
+			report.by_region.entry(canonical(item.provider, &item.region)).or_default().add(item);
+			report
+				.by_rule
+				.entry(item.rule_id.map_or_else(|| "none".to_string(), |id| id.to_string()))
+				.or_default()
+				.add(item);
+			report
+				.by_project
+				.entry(format!("{}/{}", item.provider.code(), item.project))
+				.or_default()
+				.add(item);
+		}
+		report.items = items;
+
+		report
+	}
+
+	// Allowances are pooled per account, so the overage is worked out per account and split
+	// over its instances by their share of the traffic. The projection assumes traffic keeps
+	// its pace for the rest of the month.
+	fn bandwidth_overage(&self, inventory: &Inventory, month: &Month, now: DateTime<Utc>) -> Vec<CostItem> {
+		let mut accounts: BTreeMap<(&str, &str), Vec<&BandwidthUsage>> = BTreeMap::new();
+		for usage in &inventory.bandwidth {
+			accounts.entry((usage.provider.code(), &usage.account)).or_default().push(usage);
+		}
+
+		let elapsed = month.hours_between(None, now) / month.hours();
+		let mut items = Vec::new();
+
+		for usages in accounts.values() {
+			let allowed: f64 = usages.iter().map(|usage| usage.allowed_gb).sum();
+			let outgoing: f64 = usages.iter().map(|usage| usage.outgoing_gb).sum();
+			let projected_outgoing = if elapsed > 0.0 { outgoing / elapsed } else { outgoing };
+
+			let month_to_date = (outgoing - allowed).max(0.0) * self.config.bandwidth_overage_gb;
+			let projected = (projected_outgoing - allowed).max(0.0) * self.config.bandwidth_overage_gb;
+			if projected == 0.0 || outgoing == 0.0 {
+				continue;
+			}
+
+			for usage in usages {
+				let share = usage.outgoing_gb / outgoing;
+				let instance = inventory
+					.instances
+					.iter()
+					.find(|instance| instance.provider == usage.provider && instance.id == usage.instance_id);
+
+				items.push(CostItem {
+					kind: CostKind::Bandwidth,
+					id: usage.instance_id.clone(),
+					provider: usage.provider,
+					project: usage.account.clone(),
+					region: instance.map(|instance| instance.region.clone()).unwrap_or_default(),
+					rule_id: inventory.owner(usage.provider, &usage.instance_id),
+					detail: format!("{:.1} GB over", (outgoing - allowed).max(0.0) * share),
+					priced: true,
+					month_to_date: month_to_date * share,
+					projected: projected * share,
+				});
+			}
+		}
+
+		items
+	}
+
+	fn volume_monthly(&self, provider: ProviderKind, size_gb: u64) -> Option<f64> {
+		self.config
+			.volume_gb_monthly
+			.get(provider.code())
+			.map(|price| price * size_gb as f64)
+	}
+
+	// Prices an instance from the last report's catalog; `None` if the plan isn't in it.
+	pub fn instance_charge(
+		&self,
+		provider: ProviderKind,
+		account: &str,
+		region: &str,
+		plan: &str,
+		rule_id: Option<i64>,
+	) -> Option<Charge> {
+		let last = self.last.read().unwrap();
+		let price = price_of(&last.as_ref()?.1, provider, plan, region)?;
+
+		Some(Charge {
+			provider,
+			account: account.to_string(),
+			region: region.to_string(),
+			rule_id,
+			description: format!("instance {}", plan),
+			hourly: price.hourly,
+			monthly: price.monthly,
+		})
+	}
+
+	pub fn volume_charge(&self, provider: ProviderKind, account: &str, region: &str, size_gb: u64) -> Option<Charge> {
+		let monthly = self.volume_monthly(provider, size_gb)?;
+
+		Some(Charge {
+			provider,
+			account: account.to_string(),
+			region: region.to_string(),
+			rule_id: None,
+			description: format!("volume {} GB", size_gb),
+			hourly: monthly / HOURS_PER_MONTH,
+			monthly,
+		})
+	}
+
+	// Checks a charge against the budgets, the costs of the last report and the charges approved
+	// since, and reserves it if approved. Checks are serialized so two creates can't both fit in
+	// what is left of a budget. Before the first report nothing is known, so a charge a blocking
+	// budget covers is refused until there is one.
+	pub fn check(&self, charge: &Charge) -> Result<(), ProviderError> {
+		let last = self.last.read().unwrap();
+		let mut reserved = self.reserved.lock().unwrap();
+		let now = Utc::now();
+
+		match last.as_ref() {
+			Some((report, _)) => {
+				let pending: Vec<Charge> = reserved.iter().map(|(_, charge)| charge.clone()).collect();
+				budget::check(&self.config.budgets, &report.items, &pending, charge, now)?;
+			}
+			None => {
+				if let Some(budget) = budget::blocking(&self.config.budgets, charge) {
+					return Err(ProviderError::Transient(format!(
+						"no cost report yet to check {} against budget {}",
+						charge.description, budget.name
+					)));
+				}
+			}
+		}
+
+		reserved.push((now, charge.clone()));
+		Ok(())
+	}
+
+	// Unpriced plans can't be checked and go ahead, except before the first report when no plan
+	// is priced yet.
+	pub fn check_instance(
+		&self,
+		provider: ProviderKind,
+		account: &str,
+		region: &str,
+		plan: &str,
+		rule_id: Option<i64>,
+	) -> Result<(), ProviderError> {
+		match self.instance_charge(provider, account, region, plan, rule_id) {
+			Some(charge) => self.check(&charge),
+			None if self.last.read().unwrap().is_none() => self.check(&Charge {
+				provider,
+				account: account.to_string(),
+				region: region.to_string(),
+				rule_id,
+				description: format!("instance {}", plan),
+				hourly: 0.0,
+				monthly: 0.0,
+			}),
+			None => Ok(()),
+		}
+	}
+
+	pub fn check_volume(&self, provider: ProviderKind, account: &str, region: &str, size_gb: u64) -> Result<(), ProviderError> {
+		match self.volume_charge(provider, account, region, size_gb) {
+			Some(charge) => self.check(&charge),
+			None => Ok(()),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use models::models::instance_state::InstanceState;
+
+	use super::*;
+	use crate::config::config::DEFAULT_ACCOUNT;
+	use crate::plans::catalog::{Arch, Currency};
+
+	// 408 hours into October, which has 744.
+	fn now() -> DateTime<Utc> {
+		Utc.with_ymd_and_hms(2026, 10, 18, 0, 0, 0).unwrap()
+	}
+
+	fn assert_close(actual: f64, expected: f64) {
+		assert!((actual - expected).abs() < 1e-6, "{} is not {}", actual, expected);
+	}
+
+	fn plan(id: &str, hourly: f64, monthly: f64, regions: &[&str]) -> CatalogPlan {
+		CatalogPlan {
+			provider: ProviderKind::Vultr,
+			id: id.to_string(),
+			vcpu: 1,
+			ram_mb: 1024,
+			disk_gb: 25,
+			arch: Arch::X86,
+			currency: Currency::Usd,
+			hourly,
+			monthly,
+			regions: regions.iter().map(|region| region.to_string()).collect(),
+		}
+	}
+
+	fn instance(id: &str, plan: &str, region: &str, created_at: Option<DateTime<Utc>>) -> ProviderInstance {
+		ProviderInstance {
+			id: id.to_string(),
+			provider: ProviderKind::Vultr,
+			account: DEFAULT_ACCOUNT.to_string(),
+			region: region.to_string(),
+			plan: plan.to_string(),
+			status: "active".to_string(),
+			state: InstanceState::Running,
+			label: String::new(),
+			main_ip: None,
+			created_at,
+			pending_action: None,
+			labels: BTreeMap::new(),
+		}
+	}
+
+	fn volume(id: &str, provider: ProviderKind, size_gb: u64, attached_to: Option<&str>) -> ProviderVolume {
+		ProviderVolume {
+			id: id.to_string(),
+			provider,
+			account: DEFAULT_ACCOUNT.to_string(),
+			name: id.to_string(),
+			region: "ewr".to_string(),
+			size_gb,
+			attached_to: attached_to.map(str::to_string),
+			created_at: None,
+		}
+	}
+
+	fn item<'a>(report: &'a CostReport, kind: CostKind, id: &str) -> &'a CostItem {
+		report.items.iter().find(|item| item.kind == kind && item.id == id).unwrap()
+	}
+
+	#[test]
+	fn months_are_calendar_months() {
+		let october = Month::of(now());
+		assert_eq!(october.start, Utc.with_ymd_and_hms(2026, 10, 1, 0, 0, 0).unwrap());
+		assert_eq!(october.hours(), 744.0);
+
+		let december = Month::of(Utc.with_ymd_and_hms(2026, 12, 31, 23, 0, 0).unwrap());
+		assert_eq!(december.end, Utc.with_ymd_and_hms(2027, 1, 1, 0, 0, 0).unwrap());
+
+		// Only the part within the month counts.
+		let before = Utc.with_ymd_and_hms(2026, 9, 20, 0, 0, 0).unwrap();
+		assert_eq!(october.hours_between(Some(before), now()), 408.0);
+		assert_eq!(october.hours_between(Some(now()), before), 0.0);
+	}
+
+	#[test]
+	fn accrual_stops_at_the_monthly_price() {
+		assert_close(accrue(0.01, 5.0, 100.0), 1.0);
+		assert_close(accrue(0.01, 5.0, 744.0), 5.0);
+		assert_close(accrue(0.01, 5.0, 0.0), 0.0);
+	}
+
+	#[test]
+	fn instances_accrue_from_creation_or_the_start_of_the_month() {
+		let tracker = CostTracker::new(CostsConfig::default());
+		let inventory = Inventory {
+			instances: vec![
+				instance("new", "vc2-1c-1gb", "ewr", Some(Utc.with_ymd_and_hms(2026, 10, 10, 0, 0, 0).unwrap())),
+				instance("old", "vc2-1c-1gb", "ewr", None),
+				instance("regional", "vc2-1c-1gb", "sgp", None),
+				instance("unknown", "vc2-custom", "ewr", None),
+			],
+			plans: vec![
+				plan("vc2-1c-1gb", 0.007, 5.0, &["ewr"]),
+				plan("vc2-1c-1gb", 0.01, 7.0, &["sgp"]),
+			],
+			owners: HashMap::from([((ProviderKind::Vultr, "new".to_string()), 3)]),
+			..Inventory::default()
+		};
+
+		let report = tracker.build(&inventory, now());
+
+		// Created 192 hours ago and 528 before the end of the month.
+		let new = item(&report, CostKind::Instance, "new");
+		assert_close(new.month_to_date, 192.0 * 0.007);
+		assert_close(new.projected, 528.0 * 0.007);
+		assert_eq!(new.rule_id, Some(3));
+
+		// A whole month costs no more than the monthly price.
+		let old = item(&report, CostKind::Instance, "old");
+		assert_close(old.month_to_date, 408.0 * 0.007);
+		assert_close(old.projected, 5.0);
+
+		assert_close(item(&report, CostKind::Instance, "regional").projected, 7.0);
+
+		let unknown = item(&report, CostKind::Instance, "unknown");
+		assert!(!unknown.priced);
+		assert_eq!((unknown.month_to_date, unknown.projected), (0.0, 0.0));
+
+		assert_close(report.total.projected, 528.0 * 0.007 + 5.0 + 7.0);
+		assert_close(report.by_rule["3"].projected, 528.0 * 0.007);
+		assert_close(report.by_rule["none"].projected, 12.0);
+		assert_eq!(report.month, "2026-10");
+	}
+
+	#[test]
+	fn volumes_accrue_at_the_configured_price_per_gb() {
+		let tracker = CostTracker::new(CostsConfig::default());
+		let inventory = Inventory {
+			volumes: vec![
+				volume("data", ProviderKind::Vultr, 100, Some("new")),
+				volume("hatch", ProviderKind::HostHatch, 100, None),
+			],
+			owners: HashMap::from([((ProviderKind::Vultr, "new".to_string()), 3)]),
+			..Inventory::default()
+		};
+
+		let report = tracker.build(&inventory, now());
+
+		// 100 GB at 0.10 is 10 a month, 10 / 730 an hour.
+		let data = item(&report, CostKind::Volume, "data");
+		assert_close(data.month_to_date, 408.0 * 10.0 / 730.0);
+		assert_close(data.projected, 10.0);
+		assert_eq!((data.rule_id, data.detail.as_str()), (Some(3), "100 GB"));
+
+		assert!(!item(&report, CostKind::Volume, "hatch").priced);
+	}
+
+	#[test]
+	fn bandwidth_past_the_pooled_allowance_is_split_by_traffic() {
+		let tracker = CostTracker::new(CostsConfig::default());
+		let usage = |instance_id: &str, outgoing_gb: f64| BandwidthUsage {
+			instance_id: instance_id.to_string(),
+			provider: ProviderKind::Vultr,
+			account: DEFAULT_ACCOUNT.to_string(),
+			allowed_gb: 500.0,
+			outgoing_gb,
+		};
+		let inventory = Inventory {
+			instances: vec![
+				instance("busy", "vc2-1c-1gb", "ewr", None),
+				instance("quiet", "vc2-1c-1gb", "ewr", None),
+			],
+			bandwidth: vec![usage("busy", 900.0), usage("quiet", 300.0), usage("idle", 0.0)],
+			..Inventory::default()
+		};
+
+		let report = tracker.build(&inventory, now());
+
+		// 1200 of 1500 GB so far is within the allowance; at this pace the month ends at
+		// 1200 / 408 * 744 GB, which is 688.2 GB over.
+		let projected_over = 1200.0 / 408.0 * 744.0 - 1500.0;
+		let busy = item(&report, CostKind::Bandwidth, "busy");
+		assert_close(busy.month_to_date, 0.0);
+		assert_close(busy.projected, projected_over * 0.01 * 0.75);
+		assert_close(item(&report, CostKind::Bandwidth, "quiet").projected, projected_over * 0.01 * 0.25);
+		assert_close(item(&report, CostKind::Bandwidth, "idle").projected, 0.0);
+		assert_eq!(busy.region, "ewr");
+	}
+}
@@ -1,12 +1,11 @@
-use dotenv_codegen::dotenv;
 use redis::cluster::ClusterClient;
 use redis::cluster_async::ClusterConnection;
 use redis::RedisResult;
 
-pub async fn connection() -> RedisResult<ClusterConnection> {
-	let nodes = vec![dotenv!("NEW_YORK_REDIS_CONNECTION_URL")];
+use crate::config::config::Config;
 
-	let client = ClusterClient::new(nodes).unwrap();
+pub async fn connection(config: &Config) -> RedisResult<ClusterConnection> {
+	let client = ClusterClient::new(config.redis_nodes.clone())?;
 
 	let connection = client.get_async_connection().await?;
 
@@ -1,84 +1,142 @@
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
-pub struct StartHealingRequest {}
+pub struct Container {
+    #[prost(string, tag = "1")]
+    pub image: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(string, repeated, tag = "3")]
+    pub commands: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, repeated, tag = "4")]
+    pub ports: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(map = "string, string", tag = "5")]
+    pub env: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
-pub struct StartHealingResponse {
-    #[prost(string, tag = "1")]
-    pub message: ::prost::alloc::string::String,
+pub struct Pod {
+    #[prost(message, repeated, tag = "1")]
+    pub containers: ::prost::alloc::vec::Vec<Container>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
-pub struct StopHealingRequest {}
+pub struct StartContainerRequest {
+    #[prost(string, tag = "1")]
+    pub container_id: ::prost::alloc::string::String,
+}
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
-pub struct StopHealingResponse {
+pub struct StartContainerResponse {
     #[prost(string, tag = "1")]
     pub message: ::prost::alloc::string::String,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
-pub struct HealSelectiveRequest {
-    #[prost(string, repeated, tag = "1")]
-    pub container_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+pub struct StopContainerRequest {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
-pub struct HealSelectiveResponse {
+pub struct StopContainerResponse {
     #[prost(string, tag = "1")]
     pub message: ::prost::alloc::string::String,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
-pub struct GetHealingReportRequest {}
+pub struct CreatePodResponse {
+    #[prost(string, tag = "1")]
+    pub message: ::prost::alloc::string::String,
+}
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
-pub struct GetHealingReportResponse {
-    #[prost(message, repeated, tag = "1")]
-    pub healing_events: ::prost::alloc::vec::Vec<HealingReport>,
+pub struct DeleteContainerRequest {
+    #[prost(string, tag = "1")]
+    pub container_id: ::prost::alloc::string::String,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
-pub struct UpdateRequest {
+pub struct DeleteContainerResponse {
+    #[prost(string, tag = "1")]
+    pub message: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListContainersRequest {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListContainersResponse {
+    /// Names of the running containers.
     #[prost(string, repeated, tag = "1")]
-    pub container_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    pub names: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
-pub struct UpdateResponse {
+pub struct GrowFilesystemRequest {
     #[prost(string, tag = "1")]
-    pub message: ::prost::alloc::string::String,
+    pub volume_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GrowFilesystemResponse {
+    /// Size of the filesystem in bytes once grown.
+    #[prost(uint64, tag = "1")]
+    pub total_bytes: u64,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
-pub struct HealingReport {
+pub struct MountVolumeRequest {
     #[prost(string, tag = "1")]
-    pub container_id: ::prost::alloc::string::String,
-    /// Timestamp as a string in RFC 3339 format
+    pub volume_id: ::prost::alloc::string::String,
+    /// Block device the volume shows up as, formatted as ext4 if it has no filesystem yet.
     #[prost(string, tag = "2")]
-    pub timestamp: ::prost::alloc::string::String,
-    #[prost(string, tag = "3")]
-    pub event: ::prost::alloc::string::String,
+    pub device: ::prost::alloc::string::String,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
-pub struct Empty {}
+pub struct MountVolumeResponse {
+    /// Size of the mounted filesystem in bytes.
+    #[prost(uint64, tag = "1")]
+    pub total_bytes: u64,
+}
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
-pub struct MetricsResponse {
+pub struct UnmountVolumeRequest {
     #[prost(string, tag = "1")]
-    pub metrics: ::prost::alloc::string::String,
+    pub volume_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UnmountVolumeResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CopyVolumeRequest {
+    /// Both volumes must be mounted on the worker.
+    #[prost(string, tag = "1")]
+    pub from_volume_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub to_volume_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CopyVolumeResponse {
+    /// Bytes used on the volume copied to.
+    #[prost(uint64, tag = "1")]
+    pub used_bytes: u64,
 }
 /// Generated client implementations.
-pub mod healer_client {
+pub mod docker_service_client {
     #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
     use tonic::codegen::*;
     use tonic::codegen::http::Uri;
     #[derive(Debug, Clone)]
-    pub struct HealerClient<T> {
+    pub struct DockerServiceClient<T> {
         inner: tonic::client::Grpc<T>,
     }
-    impl HealerClient<tonic::transport::Channel> {
+    impl DockerServiceClient<tonic::transport::Channel> {
         /// Attempt to create a new client by connecting to a given endpoint.
         pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
         where
@@ -89,7 +147,7 @@ pub mod healer_client {
             Ok(Self::new(conn))
         }
     }
-    impl<T> HealerClient<T>
+    impl<T> DockerServiceClient<T>
     where
         T: tonic::client::GrpcService<tonic::body::BoxBody>,
         T::Error: Into<StdError>,
@@ -107,7 +165,7 @@ pub mod healer_client {
         pub fn with_interceptor<F>(
             inner: T,
             interceptor: F,
-        ) -> HealerClient<InterceptedService<T, F>>
+        ) -> DockerServiceClient<InterceptedService<T, F>>
         where
             F: tonic::service::Interceptor,
             T::ResponseBody: Default,
@@ -121,7 +179,7 @@ pub mod healer_client {
                 http::Request<tonic::body::BoxBody>,
             >>::Error: Into<StdError> + Send + Sync,
         {
-            HealerClient::new(InterceptedService::new(inner, interceptor))
+            DockerServiceClient::new(InterceptedService::new(inner, interceptor))
         }
         /// Compress requests with the given encoding.
         ///
@@ -138,10 +196,10 @@ pub mod healer_client {
             self.inner = self.inner.accept_compressed(encoding);
             self
         }
-        pub async fn start_healing(
+        pub async fn create_pod(
             &mut self,
-            request: impl tonic::IntoRequest<super::StartHealingRequest>,
-        ) -> Result<tonic::Response<super::StartHealingResponse>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::Pod>,
+        ) -> Result<tonic::Response<super::CreatePodResponse>, tonic::Status> {
             self.inner
                 .ready()
                 .await
@@ -153,14 +211,14 @@ pub mod healer_client {
                 })?;
             let codec = tonic::codec::ProstCodec::default();
             let path = http::uri::PathAndQuery::from_static(
-                "/healer.Healer/StartHealing",
+                "/docker.DockerService/CreatePod",
             );
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn stop_healing(
+        pub async fn start_container(
             &mut self,
-            request: impl tonic::IntoRequest<super::StopHealingRequest>,
-        ) -> Result<tonic::Response<super::StopHealingResponse>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::StartContainerRequest>,
+        ) -> Result<tonic::Response<super::StartContainerResponse>, tonic::Status> {
             self.inner
                 .ready()
                 .await
@@ -172,14 +230,14 @@ pub mod healer_client {
                 })?;
             let codec = tonic::codec::ProstCodec::default();
             let path = http::uri::PathAndQuery::from_static(
-                "/healer.Healer/StopHealing",
+                "/docker.DockerService/StartContainer",
             );
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn heal_selective(
+        pub async fn stop_container(
             &mut self,
-            request: impl tonic::IntoRequest<super::HealSelectiveRequest>,
-        ) -> Result<tonic::Response<super::HealSelectiveResponse>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::StopContainerRequest>,
+        ) -> Result<tonic::Response<super::StopContainerResponse>, tonic::Status> {
             self.inner
                 .ready()
                 .await
@@ -191,14 +249,14 @@ pub mod healer_client {
                 })?;
             let codec = tonic::codec::ProstCodec::default();
             let path = http::uri::PathAndQuery::from_static(
-                "/healer.Healer/HealSelective",
+                "/docker.DockerService/StopContainer",
             );
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn get_healing_report(
+        pub async fn delete_container(
             &mut self,
-            request: impl tonic::IntoRequest<super::GetHealingReportRequest>,
-        ) -> Result<tonic::Response<super::GetHealingReportResponse>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::DeleteContainerRequest>,
+        ) -> Result<tonic::Response<super::DeleteContainerResponse>, tonic::Status> {
             self.inner
                 .ready()
                 .await
@@ -210,14 +268,15 @@ pub mod healer_client {
                 })?;
             let codec = tonic::codec::ProstCodec::default();
             let path = http::uri::PathAndQuery::from_static(
-                "/healer.Healer/GetHealingReport",
+                "/docker.DockerService/DeleteContainer",
             );
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn perform_rolling_update(
+        /// Used by the principal to find what to stop when draining the worker.
+        pub async fn list_containers(
             &mut self,
-            request: impl tonic::IntoRequest<super::UpdateRequest>,
-        ) -> Result<tonic::Response<super::UpdateResponse>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::ListContainersRequest>,
+        ) -> Result<tonic::Response<super::ListContainersResponse>, tonic::Status> {
             self.inner
                 .ready()
                 .await
@@ -229,14 +288,15 @@ pub mod healer_client {
                 })?;
             let codec = tonic::codec::ProstCodec::default();
             let path = http::uri::PathAndQuery::from_static(
-                "/healer.Healer/PerformRollingUpdate",
+                "/docker.DockerService/ListContainers",
             );
             self.inner.unary(request.into_request(), path, codec).await
         }
-        pub async fn get_metrics(
+        /// Used by the principal after resizing a volume mounted under the worker's volumes directory.
+        pub async fn grow_filesystem(
             &mut self,
-            request: impl tonic::IntoRequest<super::Empty>,
-        ) -> Result<tonic::Response<super::MetricsResponse>, tonic::Status> {
+            request: impl tonic::IntoRequest<super::GrowFilesystemRequest>,
+        ) -> Result<tonic::Response<super::GrowFilesystemResponse>, tonic::Status> {
             self.inner
                 .ready()
                 .await
@@ -247,51 +307,128 @@ pub mod healer_client {
                     )
                 })?;
             let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/healer.Healer/GetMetrics");
+            let path = http::uri::PathAndQuery::from_static(
+                "/docker.DockerService/GrowFilesystem",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// Used by the principal after attaching a volume to the worker's instance, and before detaching it.
+        pub async fn mount_volume(
+            &mut self,
+            request: impl tonic::IntoRequest<super::MountVolumeRequest>,
+        ) -> Result<tonic::Response<super::MountVolumeResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/docker.DockerService/MountVolume",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn unmount_volume(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UnmountVolumeRequest>,
+        ) -> Result<tonic::Response<super::UnmountVolumeResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/docker.DockerService/UnmountVolume",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// Used by the principal to take and restore volume snapshots, which are copies of the volume.
+        pub async fn copy_volume(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CopyVolumeRequest>,
+        ) -> Result<tonic::Response<super::CopyVolumeResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/docker.DockerService/CopyVolume",
+            );
             self.inner.unary(request.into_request(), path, codec).await
         }
     }
 }
 /// Generated server implementations.
-pub mod healer_server {
+pub mod docker_service_server {
     #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
     use tonic::codegen::*;
-    /// Generated trait containing gRPC methods that should be implemented for use with HealerServer.
+    /// Generated trait containing gRPC methods that should be implemented for use with DockerServiceServer.
     #[async_trait]
-    pub trait Healer: Send + Sync + 'static {
-        async fn start_healing(
+    pub trait DockerService: Send + Sync + 'static {
+        async fn create_pod(
+            &self,
+            request: tonic::Request<super::Pod>,
+        ) -> Result<tonic::Response<super::CreatePodResponse>, tonic::Status>;
+        async fn start_container(
+            &self,
+            request: tonic::Request<super::StartContainerRequest>,
+        ) -> Result<tonic::Response<super::StartContainerResponse>, tonic::Status>;
+        async fn stop_container(
             &self,
-            request: tonic::Request<super::StartHealingRequest>,
-        ) -> Result<tonic::Response<super::StartHealingResponse>, tonic::Status>;
-        async fn stop_healing(
+            request: tonic::Request<super::StopContainerRequest>,
+        ) -> Result<tonic::Response<super::StopContainerResponse>, tonic::Status>;
+        async fn delete_container(
             &self,
-            request: tonic::Request<super::StopHealingRequest>,
-        ) -> Result<tonic::Response<super::StopHealingResponse>, tonic::Status>;
-        async fn heal_selective(
+            request: tonic::Request<super::DeleteContainerRequest>,
+        ) -> Result<tonic::Response<super::DeleteContainerResponse>, tonic::Status>;
+        /// Used by the principal to find what to stop when draining the worker.
+        async fn list_containers(
             &self,
-            request: tonic::Request<super::HealSelectiveRequest>,
-        ) -> Result<tonic::Response<super::HealSelectiveResponse>, tonic::Status>;
-        async fn get_healing_report(
+            request: tonic::Request<super::ListContainersRequest>,
+        ) -> Result<tonic::Response<super::ListContainersResponse>, tonic::Status>;
+        /// Used by the principal after resizing a volume mounted under the worker's volumes directory.
+        async fn grow_filesystem(
             &self,
-            request: tonic::Request<super::GetHealingReportRequest>,
-        ) -> Result<tonic::Response<super::GetHealingReportResponse>, tonic::Status>;
-        async fn perform_rolling_update(
+            request: tonic::Request<super::GrowFilesystemRequest>,
+        ) -> Result<tonic::Response<super::GrowFilesystemResponse>, tonic::Status>;
+        /// Used by the principal after attaching a volume to the worker's instance, and before detaching it.
+        async fn mount_volume(
             &self,
-            request: tonic::Request<super::UpdateRequest>,
-        ) -> Result<tonic::Response<super::UpdateResponse>, tonic::Status>;
-        async fn get_metrics(
+            request: tonic::Request<super::MountVolumeRequest>,
+        ) -> Result<tonic::Response<super::MountVolumeResponse>, tonic::Status>;
+        async fn unmount_volume(
             &self,
-            request: tonic::Request<super::Empty>,
-        ) -> Result<tonic::Response<super::MetricsResponse>, tonic::Status>;
+            request: tonic::Request<super::UnmountVolumeRequest>,
+        ) -> Result<tonic::Response<super::UnmountVolumeResponse>, tonic::Status>;
+        /// Used by the principal to take and restore volume snapshots, which are copies of the volume.
+        async fn copy_volume(
+            &self,
+            request: tonic::Request<super::CopyVolumeRequest>,
+        ) -> Result<tonic::Response<super::CopyVolumeResponse>, tonic::Status>;
     }
     #[derive(Debug)]
-    pub struct HealerServer<T: Healer> {
+    pub struct DockerServiceServer<T: DockerService> {
         inner: _Inner<T>,
         accept_compression_encodings: EnabledCompressionEncodings,
         send_compression_encodings: EnabledCompressionEncodings,
     }
     struct _Inner<T>(Arc<T>);
-    impl<T: Healer> HealerServer<T> {
+    impl<T: DockerService> DockerServiceServer<T> {
         pub fn new(inner: T) -> Self {
             Self::from_arc(Arc::new(inner))
         }
@@ -325,9 +462,9 @@ pub mod healer_server {
             self
         }
     }
-    impl<T, B> tonic::codegen::Service<http::Request<B>> for HealerServer<T>
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for DockerServiceServer<T>
     where
-        T: Healer,
+        T: DockerService,
         B: Body + Send + 'static,
         B::Error: Into<StdError> + Send + 'static,
     {
@@ -343,25 +480,61 @@ pub mod healer_server {
         fn call(&mut self, req: http::Request<B>) -> Self::Future {
             let inner = self.inner.clone();
             match req.uri().path() {
-                "/healer.Healer/StartHealing" => {
+                "/docker.DockerService/CreatePod" => {
+                    #[allow(non_camel_case_types)]
+                    struct CreatePodSvc<T: DockerService>(pub Arc<T>);
+                    impl<T: DockerService> tonic::server::UnaryService<super::Pod>
+                    for CreatePodSvc<T> {
+                        type Response = super::CreatePodResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::Pod>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).create_pod(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CreatePodSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/docker.DockerService/StartContainer" => {
                     #[allow(non_camel_case_types)]
-                    struct StartHealingSvc<T: Healer>(pub Arc<T>);
+                    struct StartContainerSvc<T: DockerService>(pub Arc<T>);
                     impl<
-                        T: Healer,
-                    > tonic::server::UnaryService<super::StartHealingRequest>
-                    for StartHealingSvc<T> {
-                        type Response = super::StartHealingResponse;
+                        T: DockerService,
+                    > tonic::server::UnaryService<super::StartContainerRequest>
+                    for StartContainerSvc<T> {
+                        type Response = super::StartContainerResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::StartHealingRequest>,
+                            request: tonic::Request<super::StartContainerRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
                             let fut = async move {
-                                (*inner).start_healing(request).await
+                                (*inner).start_container(request).await
                             };
                             Box::pin(fut)
                         }
@@ -371,7 +544,7 @@ pub mod healer_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = StartHealingSvc(inner);
+                        let method = StartContainerSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -383,25 +556,25 @@ pub mod healer_server {
                     };
                     Box::pin(fut)
                 }
-                "/healer.Healer/StopHealing" => {
+                "/docker.DockerService/StopContainer" => {
                     #[allow(non_camel_case_types)]
-                    struct StopHealingSvc<T: Healer>(pub Arc<T>);
+                    struct StopContainerSvc<T: DockerService>(pub Arc<T>);
                     impl<
-                        T: Healer,
-                    > tonic::server::UnaryService<super::StopHealingRequest>
-                    for StopHealingSvc<T> {
-                        type Response = super::StopHealingResponse;
+                        T: DockerService,
+                    > tonic::server::UnaryService<super::StopContainerRequest>
+                    for StopContainerSvc<T> {
+                        type Response = super::StopContainerResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::StopHealingRequest>,
+                            request: tonic::Request<super::StopContainerRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
                             let fut = async move {
-                                (*inner).stop_healing(request).await
+                                (*inner).stop_container(request).await
                             };
                             Box::pin(fut)
                         }
@@ -411,7 +584,7 @@ pub mod healer_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = StopHealingSvc(inner);
+                        let method = StopContainerSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -423,25 +596,25 @@ pub mod healer_server {
                     };
                     Box::pin(fut)
                 }
-                "/healer.Healer/HealSelective" => {
+                "/docker.DockerService/DeleteContainer" => {
                     #[allow(non_camel_case_types)]
-                    struct HealSelectiveSvc<T: Healer>(pub Arc<T>);
+                    struct DeleteContainerSvc<T: DockerService>(pub Arc<T>);
                     impl<
-                        T: Healer,
-                    > tonic::server::UnaryService<super::HealSelectiveRequest>
-                    for HealSelectiveSvc<T> {
-                        type Response = super::HealSelectiveResponse;
+                        T: DockerService,
+                    > tonic::server::UnaryService<super::DeleteContainerRequest>
+                    for DeleteContainerSvc<T> {
+                        type Response = super::DeleteContainerResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::HealSelectiveRequest>,
+                            request: tonic::Request<super::DeleteContainerRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
                             let fut = async move {
-                                (*inner).heal_selective(request).await
+                                (*inner).delete_container(request).await
                             };
                             Box::pin(fut)
                         }
@@ -451,7 +624,7 @@ pub mod healer_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = HealSelectiveSvc(inner);
+                        let method = DeleteContainerSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -463,25 +636,25 @@ pub mod healer_server {
                     };
                     Box::pin(fut)
                 }
-                "/healer.Healer/GetHealingReport" => {
+                "/docker.DockerService/ListContainers" => {
                     #[allow(non_camel_case_types)]
-                    struct GetHealingReportSvc<T: Healer>(pub Arc<T>);
+                    struct ListContainersSvc<T: DockerService>(pub Arc<T>);
                     impl<
-                        T: Healer,
-                    > tonic::server::UnaryService<super::GetHealingReportRequest>
-                    for GetHealingReportSvc<T> {
-                        type Response = super::GetHealingReportResponse;
+                        T: DockerService,
+                    > tonic::server::UnaryService<super::ListContainersRequest>
+                    for ListContainersSvc<T> {
+                        type Response = super::ListContainersResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::GetHealingReportRequest>,
+                            request: tonic::Request<super::ListContainersRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
                             let fut = async move {
-                                (*inner).get_healing_report(request).await
+                                (*inner).list_containers(request).await
                             };
                             Box::pin(fut)
                         }
@@ -491,7 +664,7 @@ pub mod healer_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = GetHealingReportSvc(inner);
+                        let method = ListContainersSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -503,23 +676,25 @@ pub mod healer_server {
                     };
                     Box::pin(fut)
                 }
-                "/healer.Healer/PerformRollingUpdate" => {
+                "/docker.DockerService/GrowFilesystem" => {
                     #[allow(non_camel_case_types)]
-                    struct PerformRollingUpdateSvc<T: Healer>(pub Arc<T>);
-                    impl<T: Healer> tonic::server::UnaryService<super::UpdateRequest>
-                    for PerformRollingUpdateSvc<T> {
-                        type Response = super::UpdateResponse;
+                    struct GrowFilesystemSvc<T: DockerService>(pub Arc<T>);
+                    impl<
+                        T: DockerService,
+                    > tonic::server::UnaryService<super::GrowFilesystemRequest>
+                    for GrowFilesystemSvc<T> {
+                        type Response = super::GrowFilesystemResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::UpdateRequest>,
+                            request: tonic::Request<super::GrowFilesystemRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
                             let fut = async move {
-                                (*inner).perform_rolling_update(request).await
+                                (*inner).grow_filesystem(request).await
                             };
                             Box::pin(fut)
                         }
@@ -529,7 +704,7 @@ pub mod healer_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = PerformRollingUpdateSvc(inner);
+                        let method = GrowFilesystemSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -541,22 +716,104 @@ pub mod healer_server {
                     };
                     Box::pin(fut)
                 }
-                "/healer.Healer/GetMetrics" => {
+                "/docker.DockerService/MountVolume" => {
                     #[allow(non_camel_case_types)]
-                    struct GetMetricsSvc<T: Healer>(pub Arc<T>);
-                    impl<T: Healer> tonic::server::UnaryService<super::Empty>
-                    for GetMetricsSvc<T> {
-                        type Response = super::MetricsResponse;
+                    struct MountVolumeSvc<T: DockerService>(pub Arc<T>);
+                    impl<
+                        T: DockerService,
+                    > tonic::server::UnaryService<super::MountVolumeRequest>
+                    for MountVolumeSvc<T> {
+                        type Response = super::MountVolumeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::MountVolumeRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).mount_volume(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = MountVolumeSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/docker.DockerService/UnmountVolume" => {
+                    #[allow(non_camel_case_types)]
+                    struct UnmountVolumeSvc<T: DockerService>(pub Arc<T>);
+                    impl<
+                        T: DockerService,
+                    > tonic::server::UnaryService<super::UnmountVolumeRequest>
+                    for UnmountVolumeSvc<T> {
+                        type Response = super::UnmountVolumeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UnmountVolumeRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).unmount_volume(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = UnmountVolumeSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/docker.DockerService/CopyVolume" => {
+                    #[allow(non_camel_case_types)]
+                    struct CopyVolumeSvc<T: DockerService>(pub Arc<T>);
+                    impl<
+                        T: DockerService,
+                    > tonic::server::UnaryService<super::CopyVolumeRequest>
+                    for CopyVolumeSvc<T> {
+                        type Response = super::CopyVolumeResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::Empty>,
+                            request: tonic::Request<super::CopyVolumeRequest>,
                         ) -> Self::Future {
                             let inner = self.0.clone();
-                            let fut = async move { (*inner).get_metrics(request).await };
+                            let fut = async move { (*inner).copy_volume(request).await };
                             Box::pin(fut)
                         }
                     }
@@ -565,7 +822,7 @@ pub mod healer_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = GetMetricsSvc(inner);
+                        let method = CopyVolumeSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -592,7 +849,7 @@ pub mod healer_server {
             }
         }
     }
-    impl<T: Healer> Clone for HealerServer<T> {
+    impl<T: DockerService> Clone for DockerServiceServer<T> {
         fn clone(&self) -> Self {
             let inner = self.inner.clone();
             Self {
@@ -602,7 +859,7 @@ pub mod healer_server {
             }
         }
     }
-    impl<T: Healer> Clone for _Inner<T> {
+    impl<T: DockerService> Clone for _Inner<T> {
         fn clone(&self) -> Self {
             Self(self.0.clone())
         }
@@ -612,7 +869,7 @@ pub mod healer_server {
             write!(f, "{:?}", self.0)
         }
     }
-    impl<T: Healer> tonic::server::NamedService for HealerServer<T> {
-        const NAME: &'static str = "healer.Healer";
+    impl<T: DockerService> tonic::server::NamedService for DockerServiceServer<T> {
+        const NAME: &'static str = "docker.DockerService";
     }
 }
@@ -0,0 +1,321 @@
+use std::sync::RwLock;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+use crate::providers::error::ProviderError;
+use crate::providers::http::HttpClient;
+use crate::providers::vultr::pages::list_all;
+
+// Vultr adds GPU plans rarely, an hourly refresh is plenty.
+const CATALOG_TTL: Duration = Duration::from_secs(3600);
+
+// VRAM of a whole GPU per model; plans with less are a fraction of one, plans with more span
+// several.
+const GPU_VRAM_GB: [(&str, u32); 6] = [
+	("A100", 80),
+	("H100", 80),
+	("A40", 48),
+	("L40S", 48),
+	("A16", 16),
+	("A10", 24),
+];
+
+// A plan as listed by `/plans?type=vcg`.
+#[derive(Deserialize)]
+struct VultrGpuPlan {
+	id: String,
+	vcpu_count: u32,
+	ram: u32,
+	disk: u32,
+	monthly_cost: f64,
+	locations: Vec<String>,
+	gpu_vram_gb: u32,
+	gpu_type: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GpuPlan {
+	pub id: String,
+	// Normalized, e.g. `A100`; see `normalize_model`.
+	pub gpu_model: String,
+	pub vram_gb: u32,
+	pub gpu_count: u32,
+	// Share of a single GPU for fractional plans, e.g. 0.05 for 4 of 80 GB.
+	pub fraction: Option<f64>,
+	pub vcpu: u32,
+	pub ram_mb: u32,
+	pub disk_gb: u32,
+	pub monthly_cost: f64,
+	// Region codes the plan can be deployed in.
+	pub locations: Vec<String>,
+}
+
+impl From<VultrGpuPlan> for GpuPlan {
+	fn from(plan: VultrGpuPlan) -> Self {
+		let gpu_model = normalize_model(&plan.gpu_type);
+		let full_vram = GPU_VRAM_GB
+			.iter()
+			.find(|(model, _)| *model == gpu_model)
+			.map(|(_, vram)| *vram);
+
+		let (gpu_count, fraction) = match full_vram {
+			Some(full) if plan.gpu_vram_gb < full => (1, Some(plan.gpu_vram_gb as f64 / full as f64)),
+			Some(full) => (plan.gpu_vram_gb / full, None),
+			None => (1, None),
+		};
+
+		GpuPlan {
+			id: plan.id,
+			gpu_model,
+			vram_gb: plan.gpu_vram_gb,
+			gpu_count,
+			fraction,
+			vcpu: plan.vcpu_count,
+			ram_mb: plan.ram,
+			disk_gb: plan.disk,
+			monthly_cost: plan.monthly_cost,
+			locations: plan.locations,
+		}
+	}
+}
+
+// Which GPU plans are acceptable; unset fields match anything.
+#[derive(Debug, Clone, Default)]
+pub struct GpuFilter {
+	pub model: Option<String>,
+	pub region: Option<String>,
+	pub min_vram_gb: Option<u32>,
+}
+
+impl GpuFilter {
+	pub fn model(mut self, model: &str) -> Self {
+		self.model = Some(normalize_model(model));
+		self
+	}
+
+	pub fn region(mut self, region: &str) -> Self {
+		self.region = Some(region.to_string());
+		self
+	}
+
+	pub fn min_vram_gb(mut self, min_vram_gb: u32) -> Self {
+		self.min_vram_gb = Some(min_vram_gb);
+		self
+	}
+
+	fn matches(&self, plan: &GpuPlan) -> bool {
+		self.model.as_ref().is_none_or(|model| *model == plan.gpu_model)
+			&& self.region.as_ref().is_none_or(|region| plan.locations.contains(region))
+			&& self.min_vram_gb.is_none_or(|vram| plan.vram_gb >= vram)
+	}
+}
+
+// Vultr's GPU plans, fetched on first use and refreshed once they are older than an hour.
+pub struct GpuCatalog {
+	client: HttpClient,
+	api_key: String,
+	base_url: String,
+	// The plans and when they go stale.
+	cache: RwLock<Option<(Instant, Vec<GpuPlan>)>>,
+}
+
+impl GpuCatalog {
+	pub fn new(client: HttpClient, api_key: String, base_url: String) -> Self {
+		Self {
+			client,
+			api_key,
+			base_url,
+			cache: RwLock::new(None),
+		}
+	}
+
+	// Every GPU plan; a failed refresh falls back to the previous listing if there is one.
+	pub async fn plans(&self) -> Result<Vec<GpuPlan>, ProviderError> {
+		if let Some((stale_at, plans)) = self.cache.read().unwrap().as_ref() {
+			if Instant::now() < *stale_at {
+				return Ok(plans.clone());
+			}
+		}
+
+		let fetched = list_all::<VultrGpuPlan>(
+			&self.client,
+			&format!("{}/plans?type=vcg", self.base_url),
+			&self.api_key,
+			"plans",
+		)
+		.await;
+
+		let mut cache = self.cache.write().unwrap();
+		match fetched {
+			Ok(plans) => {
+				let plans: Vec<GpuPlan> = plans.into_iter().map(GpuPlan::from).collect();
+				*cache = Some((Instant::now() + CATALOG_TTL, plans.clone()));
+				Ok(plans)
+			}
+			Err(e) => match cache.as_ref() {
+				Some((_, plans)) => {
+					println!("Failed to refresh the GPU plan catalog, using the cached one: {}", e);
+					Ok(plans.clone())
+				}
+				None => Err(e),
+			},
+		}
+	}
+
+	// Matching plans, cheapest first.
+	pub async fn find(&self, filter: &GpuFilter) -> Result<Vec<GpuPlan>, ProviderError> {
+		let mut plans: Vec<GpuPlan> = self.plans().await?.into_iter().filter(|plan| filter.matches(plan)).collect();
+		plans.sort_by(|a, b| a.monthly_cost.total_cmp(&b.monthly_cost));
+
+		Ok(plans)
+	}
+
+	pub async fn cheapest(&self, filter: &GpuFilter) -> Result<GpuPlan, ProviderError> {
+		self.find(filter).await?.into_iter().next().ok_or_else(|| {
+			ProviderError::Invalid(format!(
+				"no GPU plan for model {} in region {} with at least {} GB VRAM",
+				filter.model.as_deref().unwrap_or("any"),
+				filter.region.as_deref().unwrap_or("any"),
+				filter.min_vram_gb.unwrap_or(0)
+			))
+		})
+	}
+}
+
+// `NVIDIA_A100`, `nvidia a100` and `a100` all become `A100`.
+pub fn normalize_model(model: &str) -> String {
+	let model: String = model
+		.chars()
+		.filter(|c| c.is_ascii_alphanumeric())
+		.collect::<String>()
+		.to_uppercase();
+
+	model.strip_prefix("NVIDIA").unwrap_or(&model).to_string()
+}
+
+// GPU model of a Vultr plan code, e.g. `A100` for `vcg-a100-1c-6g-4vram`; `None` for plans
+// without a GPU.
+pub fn plan_model(plan: &str) -> Option<String> {
+	plan.strip_prefix("vcg-")?.split('-').next().map(normalize_model)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	use hyper::{Body, Response};
+	use serde_json::{json, Value};
+
+	use super::*;
+	use crate::providers::test_server::{serve, serve_response};
+
+	fn plan(id: &str, gpu_type: &str, vram: u32, monthly_cost: f64, locations: &[&str]) -> Value {
+		json!({
+			"id": id,
+			"vcpu_count": 6,
+			"ram": 61440,
+			"disk": 1400,
+			"monthly_cost": monthly_cost,
+			"locations": locations,
+			"gpu_vram_gb": vram,
+			"gpu_type": gpu_type,
+		})
+	}
+
+	fn plans() -> Value {
+		json!({
+			"plans": [
+				plan("vcg-a100-6c-60g-40vram", "NVIDIA_A100", 40, 875.0, &["ewr", "fra"]),
+				plan("vcg-a100-2c-15g-10vram", "NVIDIA_A100", 10, 250.0, &["ewr"]),
+				plan("vcg-a100-12c-120g-160vram", "NVIDIA_A100", 160, 3500.0, &["ewr", "fra"]),
+				plan("vcg-a40-1c-5g-4vram", "NVIDIA_A40", 4, 90.0, &["fra"]),
+			],
+			"meta": { "links": { "next": "" } },
+		})
+	}
+
+	fn catalog(url: String) -> GpuCatalog {
+		GpuCatalog::new(HttpClient::new(reqwest::Client::new()), "key".to_string(), url)
+	}
+
+	#[test]
+	fn models_are_normalized() {
+		for model in ["NVIDIA_A100", "nvidia a100", "a100", "A100"] {
+			assert_eq!(normalize_model(model), "A100");
+		}
+
+		assert_eq!(plan_model("vcg-l40s-4c-30g-12vram").as_deref(), Some("L40S"));
+		assert_eq!(plan_model("vc2-1c-1gb"), None);
+	}
+
+	#[tokio::test]
+	async fn plans_are_split_into_whole_and_fractional_gpus() {
+		let plans = catalog(serve(|_| plans()).await).plans().await.unwrap();
+		let find = |id: &str| plans.iter().find(|plan| plan.id == id).unwrap();
+
+		let fractional = find("vcg-a100-2c-15g-10vram");
+		assert_eq!((fractional.gpu_model.as_str(), fractional.gpu_count), ("A100", 1));
+		assert_eq!(fractional.fraction, Some(0.125));
+
+		let several = find("vcg-a100-12c-120g-160vram");
+		assert_eq!((several.gpu_count, several.fraction), (2, None));
+	}
+
+	#[tokio::test]
+	async fn find_filters_and_sorts_cheapest_first() {
+		let catalog = catalog(serve(|_| plans()).await);
+
+		let ids = |plans: Vec<GpuPlan>| plans.into_iter().map(|plan| plan.id).collect::<Vec<_>>();
+
+		let a100 = catalog.find(&GpuFilter::default().model("nvidia_a100")).await.unwrap();
+		assert_eq!(
+			ids(a100),
+			vec!["vcg-a100-2c-15g-10vram", "vcg-a100-6c-60g-40vram", "vcg-a100-12c-120g-160vram"]
+		);
+
+		let in_fra = catalog
+			.find(&GpuFilter::default().model("a100").region("fra").min_vram_gb(20))
+			.await
+			.unwrap();
+		assert_eq!(ids(in_fra), vec!["vcg-a100-6c-60g-40vram", "vcg-a100-12c-120g-160vram"]);
+
+		let cheapest = catalog.cheapest(&GpuFilter::default().region("fra")).await.unwrap();
+		assert_eq!(cheapest.id, "vcg-a40-1c-5g-4vram");
+	}
+
+	#[tokio::test]
+	async fn cheapest_fails_without_a_matching_plan() {
+		let catalog = catalog(serve(|_| plans()).await);
+
+		let result = catalog.cheapest(&GpuFilter::default().model("h100").region("ewr")).await;
+
+		assert!(matches!(
+			result,
+			Err(ProviderError::Invalid(e)) if e == "no GPU plan for model H100 in region ewr with at least 0 GB VRAM"
+		));
+	}
+
+	#[tokio::test]
+	async fn plans_are_cached_and_kept_when_a_refresh_fails() {
+		static CALLS: AtomicUsize = AtomicUsize::new(0);
+		let url = serve_response(|_| match CALLS.fetch_add(1, Ordering::SeqCst) {
+			0 => Response::new(Body::from(plans().to_string())),
+			_ => Response::builder().status(400).body(Body::empty()).unwrap(),
+		})
+		.await;
+		let catalog = catalog(url);
+
+		assert_eq!(catalog.plans().await.unwrap().len(), 4);
+		assert_eq!(catalog.plans().await.unwrap().len(), 4);
+		assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+
+		// Once stale, the failed refresh falls back to the listing it has.
+		if let Some((stale_at, _)) = catalog.cache.write().unwrap().as_mut() {
+			*stale_at = Instant::now();
+		}
+		assert_eq!(catalog.plans().await.unwrap().len(), 4);
+		assert_eq!(CALLS.load(Ordering::SeqCst), 2);
+	}
+}
@@ -1,73 +1,135 @@
-use reqwest::{header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE}, Client, Response};
-use serde_json::json;
-use std::error::Error;
+use std::collections::BTreeMap;
+use std::sync::Arc;
 
-struct GpuManager {
-    client: Client,
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+use serde::Deserialize;
+
+use crate::config::config::DEFAULT_ACCOUNT;
+use crate::costs::budget::Charge;
+use crate::costs::tracker::CostTracker;
+use crate::gpu::catalog::{normalize_model, plan_model, GpuCatalog, GpuFilter};
+use crate::manager::manager::ManagerError;
+use crate::plans::catalog::CatalogPlan;
+use crate::providers::error::ProviderError;
+use crate::providers::provider::{CloudProvider, InstanceSpec, ProviderInstance};
+use crate::providers::vultr::provider::{Vultr, VULTR_API_URL};
+use crate::providers::wait::WaitOptions;
+use crate::shared_config::SharedConfig;
+
+// Ubuntu 22.04 x64, used when neither the request nor the Vultr account names an image.
+const DEFAULT_GPU_IMAGE: &str = "1743";
+
+// A GPU instance to create; the plan is either given or the cheapest one matching `model` and
+// `min_vram_gb` that is available in `region`.
+#[derive(Debug, Deserialize)]
+pub struct GpuInstanceRequest {
+    pub region: String,
+    #[serde(default)]
+    pub plan: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub min_vram_gb: Option<u32>,
+    #[serde(default)]
+    pub image: Option<String>,
+    #[serde(default)]
+    pub ssh_keys: Vec<String>,
+    #[serde(default)]
+    pub user_data: Option<String>,
+}
+
+// GPU capacity on Vultr's default account, on top of the regular Vultr provider.
+pub struct GpuManager {
+    vultr: Vultr,
+    catalog: GpuCatalog,
+    costs: Arc<CostTracker>,
 }
 
 impl GpuManager {
-    pub fn new(api_key: &str) -> GpuManager {
-        let mut headers = HeaderMap::new();
-        headers.insert(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", api_key)).unwrap());
-        headers.insert(CONTENT_TYPE, HeaderValue::from_str("application/json").unwrap());
+    pub fn new(shared_config: &mut SharedConfig) -> Self {
+        let vultr = shared_config.config.vultr();
+        let client = shared_config.clients.vultr().clone();
+        let base_url = vultr.url(VULTR_API_URL);
 
         GpuManager {
-            client: Client::builder()
-                .default_headers(headers)
-                .build()
-                .unwrap(),
+            vultr: Vultr::new(client.clone(), vultr.api_key.clone())
+                .base_url(base_url.clone())
+                .image(vultr.image.clone().unwrap_or_else(|| DEFAULT_GPU_IMAGE.to_string())),
+            catalog: GpuCatalog::new(client, vultr.api_key, base_url),
+            costs: Arc::clone(&shared_config.costs),
         }
     }
 
-
-    pub async fn create_gpu_instance(&self, label: &str, hostname: &str,) -> Result<Response, Box<dyn Error + Send + Sync>> {
-        let data = json!({
-            "region": "ewr",
-            "plan": "vcg",
-            "label": label,
-            "os_id": 215,
-            "hostname": hostname,
-            "tags": ["gpu", "instance"]
-        });
-
-        self.client.post("https://api.vultr.com/v2/instances")
-            .json(&data)
-            .send()
-            .await
-            .map_err(|e| Box::new(e) as Box<dyn Error + Send + Sync>)
+    pub fn catalog(&self) -> &GpuCatalog {
+        &self.catalog
     }
 
-    pub async fn delete_gpu_instance(&self, instance_id: &str) -> Result<Response, Box<dyn Error + Send + Sync>> {
-        let url = format!("https://api.vultr.com/v2/instances/{}", instance_id);
+    // Creates the instance and waits for Vultr to finish provisioning it.
+    pub async fn create_gpu_instance(&self, request: &GpuInstanceRequest) -> Result<ProviderInstance, ManagerError> {
+        let region = self.vultr.parse_region(&request.region)?;
+
+        let filter = GpuFilter::default().region(&region);
+        let plan = match &request.plan {
+            Some(code) => self.catalog.find(&filter).await?.into_iter().find(|plan| plan.id == *code).ok_or_else(|| {
+                ProviderError::Invalid(format!("GPU plan {} is not available in region {}", code, region))
+            })?,
+            None => {
+                let mut filter = filter;
+                if let Some(model) = &request.model {
+                    filter = filter.model(model);
+                }
+                if let Some(min_vram_gb) = request.min_vram_gb {
+                    filter = filter.min_vram_gb(min_vram_gb);
+                }
+
+                self.catalog.cheapest(&filter).await?
+            }
+        };
 
-        self.client.delete(&url)
-            .send()
-            .await
-            .map_err(|e| Box::new(e) as Box<dyn Error + Send + Sync>)
+        let price = CatalogPlan::from(&plan);
+        self.costs.check(&Charge {
+            provider: ProviderKind::Vultr,
+            account: DEFAULT_ACCOUNT.to_string(),
+            region: region.clone(),
+            rule_id: None,
+            description: format!("GPU instance {}", plan.id),
+            hourly: price.hourly,
+            monthly: price.monthly,
+        })?;
+
+        let model = plan.gpu_model.to_lowercase();
+        let spec = InstanceSpec {
+            region,
+            plan: Some(plan.id),
+            image: request.image.clone(),
+            ssh_keys: request.ssh_keys.clone(),
+            user_data: request.user_data.clone(),
+            labels: BTreeMap::from([("gpu".to_string(), model)]),
+        };
+
+        let instance = self.vultr.create(&spec).await?;
+
+        Ok(self.vultr.wait_until_created(&instance, &WaitOptions::default()).await?)
     }
 
-    pub async fn list_gpu_instances(&self) -> Result<Vec<serde_json::Value>, Box<dyn Error + Send + Sync>> {
-        let response = self.client 
-            .get("https://api.vultr.com/v2/instances")
-            .send()
-            .await?;
-
-        let data: serde_json::Value = response.json().await?;
-
-        if let Some(instances) = data.get("instances").and_then(|v| v.as_array()) {
-            let gpu_instances: Vec<_> = instances.iter()
-                .filter(|instance| {
-                    instance.get("plan")
-                        .and_then(|v| v.as_str())
-                        .map_or(false, |s| s == "vcg")
-                })
-                .cloned()
-                .collect();
-    
-            Ok(gpu_instances)
-        } else {
-            Ok(Vec::new())
-        }
+    pub async fn delete_gpu_instance(&self, instance_id: &str) -> Result<(), ManagerError> {
+        Ok(self.vultr.delete(instance_id).await?)
     }
-}
\ No newline at end of file
+
+    // Instances on a GPU plan, optionally only those with the given GPU model.
+    pub async fn list_gpu_instances(&self, model: Option<&str>) -> Result<Vec<ProviderInstance>, ManagerError> {
+        let model = model.map(normalize_model);
+
+        Ok(self
+            .vultr
+            .list()
+            .await?
+            .into_iter()
+            .filter(|instance| match (plan_model(&instance.plan), &model) {
+                (Some(plan_model), Some(model)) => plan_model == *model,
+                (Some(_), None) => true,
+                (None, _) => false,
+            })
+            .collect())
+    }
+}
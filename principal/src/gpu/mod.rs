@@ -1 +1,2 @@
+pub mod catalog;
 pub mod gpu;
\ No newline at end of file
@@ -0,0 +1,125 @@
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Serialize;
+use sqlx::postgres::PgPool;
+use sqlx::FromRow;
+
+use crate::lifecycle::tracker::LifecycleEvent;
+
+// Most events returned by `list`.
+const MAX_EVENTS: i64 = 1000;
+
+// A recorded `LifecycleEvent`; states are stored by name.
+#[derive(Debug, Clone, Serialize)]
+pub struct InstanceEvent {
+	pub id: i64,
+	pub instance_id: String,
+	pub provider: String,
+	pub account: String,
+	pub region: String,
+	pub kind: String,
+	pub from_state: Option<String>,
+	pub to_state: String,
+	pub at: DateTime<Utc>,
+}
+
+#[derive(FromRow)]
+struct InstanceEventRow {
+	id: i64,
+	instance_id: String,
+	provider: String,
+	account: String,
+	region: String,
+	kind: String,
+	from_state: Option<String>,
+	to_state: String,
+	at: i64,
+}
+
+impl From<InstanceEventRow> for InstanceEvent {
+	fn from(row: InstanceEventRow) -> Self {
+		InstanceEvent {
+			id: row.id,
+			instance_id: row.instance_id,
+			provider: row.provider,
+			account: row.account,
+			region: row.region,
+			kind: row.kind,
+			from_state: row.from_state,
+			to_state: row.to_state,
+			at: Utc.timestamp_opt(row.at, 0).single().unwrap_or_else(Utc::now),
+		}
+	}
+}
+
+// Lifecycle events are written to `InstanceEvents` so they outlive the principal.
+pub struct LifecycleStore {
+	pool: PgPool,
+}
+
+impl LifecycleStore {
+	pub fn new(pool: PgPool) -> Self {
+		Self { pool }
+	}
+
+	pub async fn record(&self, event: &LifecycleEvent) -> Result<(), sqlx::Error> {
+		sqlx::query(
+			r#"
+			INSERT INTO InstanceEvents (instance_id, provider, account, region, kind, from_state, to_state, at)
+			VALUES ($1, $2, $3, $4, $5, $6, $7, to_timestamp($8))
+			"#,
+		)
+		.bind(&event.instance_id)
+		.bind(event.provider.code())
+		.bind(&event.account)
+		.bind(&event.region)
+		.bind(event.kind.code())
+		.bind(event.from.map(|state| format!("{:?}", state)))
+		.bind(format!("{:?}", event.to))
+		.bind(event.at.timestamp())
+		.execute(&self.pool)
+		.await?;
+
+		Ok(())
+	}
+
+	// The latest event of every instance not terminated since, which the tracker is rebuilt from
+	// on startup.
+	pub async fn current(&self) -> Result<Vec<InstanceEvent>, sqlx::Error> {
+		let rows = sqlx::query_as::<_, InstanceEventRow>(
+			r#"
+			SELECT * FROM (
+				SELECT DISTINCT ON (provider, instance_id)
+					id::BIGINT AS id, instance_id, provider, account, region, kind, from_state, to_state,
+					EXTRACT(EPOCH FROM at)::BIGINT AS at
+				FROM InstanceEvents
+				ORDER BY provider, instance_id, id DESC
+			) latest
+			WHERE to_state <> 'Terminated'
+			"#,
+		)
+		.fetch_all(&self.pool)
+		.await?;
+
+		Ok(rows.into_iter().map(InstanceEvent::from).collect())
+	}
+
+	// Newest first, optionally for a single instance.
+	pub async fn list(&self, instance_id: Option<&str>) -> Result<Vec<InstanceEvent>, sqlx::Error> {
+		let rows = sqlx::query_as::<_, InstanceEventRow>(
+			r#"
+			SELECT id::BIGINT AS id, instance_id, provider, account, region, kind, from_state, to_state,
+				EXTRACT(EPOCH FROM at)::BIGINT AS at
+			FROM InstanceEvents
+			WHERE $1::TEXT IS NULL OR instance_id = $1
+			ORDER BY id DESC
+			LIMIT $2
+			"#,
+		)
+		.bind(instance_id)
+		.bind(MAX_EVENTS)
+		.fetch_all(&self.pool)
+		.await?;
+
+		Ok(rows.into_iter().map(InstanceEvent::from).collect())
+	}
+}
@@ -0,0 +1,473 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Duration, Utc};
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+use models::models::instance_state::InstanceState;
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::lifecycle::store::InstanceEvent;
+use crate::providers::provider::ProviderInstance;
+
+// Events are dropped for subscribers that fall this far behind.
+const EVENT_BUFFER: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+	Transition,
+	// The provider reported a state that can't follow the previous one; it is applied anyway.
+	IllegalTransition,
+	StuckStarting,
+	// Terminated or gone from the listing without the manager removing it.
+	UnexpectedTermination,
+}
+
+impl EventKind {
+	pub fn code(&self) -> &'static str {
+		match self {
+			EventKind::Transition => "transition",
+			EventKind::IllegalTransition => "illegal_transition",
+			EventKind::StuckStarting => "stuck_starting",
+			EventKind::UnexpectedTermination => "unexpected_termination",
+		}
+	}
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LifecycleEvent {
+	pub instance_id: String,
+	pub provider: ProviderKind,
+	pub account: String,
+	pub region: String,
+	pub kind: EventKind,
+	// `None` the first time an instance is seen.
+	pub from: Option<InstanceState>,
+	pub to: InstanceState,
+	pub at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+struct Tracked {
+	account: String,
+	region: String,
+	state: InstanceState,
+	since: DateTime<Utc>,
+	stuck_reported: bool,
+}
+
+// Normalized state of every listed instance, fed with each provider listing. Transitions are
+// checked against `InstanceState::can_transition_to` and published to subscribers.
+pub struct Lifecycle {
+	instances: Mutex<HashMap<(ProviderKind, String), Tracked>>,
+	expected_terminations: Mutex<HashSet<(ProviderKind, String)>>,
+	starting_timeout: Duration,
+	events: broadcast::Sender<LifecycleEvent>,
+}
+
+impl Lifecycle {
+	pub fn new(starting_timeout: Duration) -> Self {
+		let (events, _) = broadcast::channel(EVENT_BUFFER);
+
+		Self {
+			instances: Mutex::new(HashMap::new()),
+			expected_terminations: Mutex::new(HashSet::new()),
+			starting_timeout,
+			events,
+		}
+	}
+
+	// Picks up the states the last run left instances in from their latest recorded events, see
+	// `LifecycleStore::current`, so a restart neither reports them as new nor misses what
+	// happened to them meanwhile. Instances already tracked are left alone.
+	pub fn restore(&self, events: &[InstanceEvent]) {
+		let mut instances = self.instances.lock().unwrap();
+
+		for event in events {
+			let (provider, state) = match (event.provider.parse::<ProviderKind>(), state_from_name(&event.to_state)) {
+				(Ok(provider), Some(state)) => (provider, state),
+				_ => continue,
+			};
+
+			instances
+				.entry((provider, event.instance_id.clone()))
+				.or_insert_with(|| Tracked {
+					account: event.account.clone(),
+					region: event.region.clone(),
+					state,
+					since: event.at,
+					stuck_reported: event.kind == EventKind::StuckStarting.code(),
+				});
+		}
+	}
+
+	pub fn subscribe(&self) -> broadcast::Receiver<LifecycleEvent> {
+		self.events.subscribe()
+	}
+
+	pub fn state(&self, provider: ProviderKind, instance_id: &str) -> Option<InstanceState> {
+		self.instances
+			.lock()
+			.unwrap()
+			.get(&(provider, instance_id.to_string()))
+			.map(|tracked| tracked.state)
+	}
+
+	// Instances the manager removes itself are not reported as unexpected terminations.
+	pub fn expect_termination(&self, provider: ProviderKind, instance_id: &str) {
+		self.expected_terminations
+			.lock()
+			.unwrap()
+			.insert((provider, instance_id.to_string()));
+	}
+
+	// Moves a tracked instance into a state the provider doesn't report, e.g. `Draining`.
+	pub fn set_state(
+		&self,
+		provider: ProviderKind,
+		instance_id: &str,
+		state: InstanceState,
+	) -> Option<LifecycleEvent> {
+		let mut instances = self.instances.lock().unwrap();
+		let tracked = instances.get(&(provider, instance_id.to_string()))?.clone();
+
+		let event = self.transition(
+			&mut instances,
+			provider,
+			instance_id,
+			&tracked.account,
+			&tracked.region,
+			state,
+		);
+		self.publish(event.iter());
+
+		event
+	}
+
+	// Applies a full provider listing and returns the events it caused. Tracked instances
+	// missing from the listing are taken as terminated.
+	pub fn observe(&self, listed: &[ProviderInstance]) -> Vec<LifecycleEvent> {
+		let mut instances = self.instances.lock().unwrap();
+		let mut events = Vec::new();
+
+		for instance in listed {
+			let key = (instance.provider, instance.id.clone());
+
+			// Draining is ours; the provider keeps reporting the instance as running meanwhile.
+			let state = match instances.get(&key) {
+				Some(tracked)
+					if tracked.state == InstanceState::Draining
+						&& instance.state == InstanceState::Running =>
+				{
+					continue
+				}
+				_ => instance.state,
+			};
+
+			events.extend(self.transition(
+				&mut instances,
+				instance.provider,
+				&instance.id,
+				&instance.account,
+				&instance.region,
+				state,
+			));
+		}
+
+		let gone: Vec<(ProviderKind, String)> = instances
+			.keys()
+			.filter(|(provider, id)| {
+				!listed
+					.iter()
+					.any(|instance| instance.provider == *provider && &instance.id == id)
+			})
+			.cloned()
+			.collect();
+
+		for (provider, id) in gone {
+			let tracked = instances[&(provider, id.clone())].clone();
+			events.extend(self.transition(
+				&mut instances,
+				provider,
+				&id,
+				&tracked.account,
+				&tracked.region,
+				InstanceState::Terminated,
+			));
+			instances.remove(&(provider, id.clone()));
+			self.expected_terminations
+				.lock()
+				.unwrap()
+				.remove(&(provider, id));
+		}
+
+		let now = Utc::now();
+		for ((provider, id), tracked) in instances.iter_mut() {
+			if tracked.state != InstanceState::Starting
+				|| tracked.stuck_reported
+				|| now - tracked.since < self.starting_timeout
+			{
+				continue;
+			}
+
+			tracked.stuck_reported = true;
+			events.push(LifecycleEvent {
+				instance_id: id.clone(),
+				provider: *provider,
+				account: tracked.account.clone(),
+				region: tracked.region.clone(),
+				kind: EventKind::StuckStarting,
+				from: Some(InstanceState::Starting),
+				to: InstanceState::Starting,
+				at: now,
+			});
+		}
+
+		self.publish(events.iter());
+
+		events
+	}
+
+	fn transition(
+		&self,
+		instances: &mut HashMap<(ProviderKind, String), Tracked>,
+		provider: ProviderKind,
+		instance_id: &str,
+		account: &str,
+		region: &str,
+		state: InstanceState,
+	) -> Option<LifecycleEvent> {
+		let key = (provider, instance_id.to_string());
+		let from = instances.get(&key).map(|tracked| tracked.state);
+		if from == Some(state) {
+			return None;
+		}
+
+		let now = Utc::now();
+		let kind = match from {
+			None => EventKind::Transition,
+			Some(from) if !from.can_transition_to(state) => EventKind::IllegalTransition,
+			_ if state == InstanceState::Terminated
+				&& !self.expected_terminations.lock().unwrap().contains(&key) =>
+			{
+				EventKind::UnexpectedTermination
+			}
+			_ => EventKind::Transition,
+		};
+
+		instances.insert(
+			key,
+			Tracked {
+				account: account.to_string(),
+				region: region.to_string(),
+				state,
+				since: now,
+				stuck_reported: false,
+			},
+		);
+
+		Some(LifecycleEvent {
+			instance_id: instance_id.to_string(),
+			provider,
+			account: account.to_string(),
+			region: region.to_string(),
+			kind,
+			from,
+			to: state,
+			at: now,
+		})
+	}
+
+	fn publish<'a>(&self, events: impl Iterator<Item = &'a LifecycleEvent>) {
+		for event in events {
+			// Sending only fails without subscribers.
+			let _ = self.events.send(event.clone());
+		}
+	}
+}
+
+// States are recorded by their `Debug` name, which is also their serde name.
+fn state_from_name(name: &str) -> Option<InstanceState> {
+	serde_json::from_value(serde_json::Value::String(name.to_string())).ok()
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::BTreeMap;
+
+	use super::*;
+	use crate::config::config::DEFAULT_ACCOUNT;
+
+	fn instance(id: &str, state: InstanceState) -> ProviderInstance {
+		ProviderInstance {
+			id: id.to_string(),
+			provider: ProviderKind::Vultr,
+			account: DEFAULT_ACCOUNT.to_string(),
+			region: "ewr".to_string(),
+			plan: "vc2-1c-1gb".to_string(),
+			status: String::new(),
+			state,
+			label: String::new(),
+			main_ip: None,
+			created_at: None,
+			pending_action: None,
+			labels: BTreeMap::new(),
+		}
+	}
+
+	fn kinds(events: &[LifecycleEvent]) -> Vec<(EventKind, Option<InstanceState>, InstanceState)> {
+		events.iter().map(|event| (event.kind, event.from, event.to)).collect()
+	}
+
+	#[test]
+	fn transitions_follow_the_lifecycle() {
+		use InstanceState::*;
+
+		for (from, to) in [
+			(Starting, Running),
+			(Running, Draining),
+			(Draining, Stopped),
+			(Stopped, Running),
+			(Running, Starting),
+			(Upgrading, Running),
+			(Running, Terminated),
+			(Terminated, Terminated),
+			(Stopped, Unknown),
+			(Unknown, Running),
+		] {
+			assert!(from.can_transition_to(to), "{:?} -> {:?}", from, to);
+		}
+
+		for (from, to) in [
+			(Terminated, Running),
+			(Terminated, Unknown),
+			(Starting, Draining),
+			(Stopping, Running),
+			(Stopped, Draining),
+			(Draining, Upgrading),
+		] {
+			assert!(!from.can_transition_to(to), "{:?} -> {:?}", from, to);
+		}
+	}
+
+	#[test]
+	fn observing_listings_reports_transitions() {
+		let lifecycle = Lifecycle::new(Duration::minutes(15));
+
+		let events = lifecycle.observe(&[instance("a", InstanceState::Starting)]);
+		assert_eq!(kinds(&events), vec![(EventKind::Transition, None, InstanceState::Starting)]);
+
+		// Nothing changed, nothing to report.
+		assert!(lifecycle.observe(&[instance("a", InstanceState::Starting)]).is_empty());
+
+		let events = lifecycle.observe(&[instance("a", InstanceState::Running)]);
+		assert_eq!(
+			kinds(&events),
+			vec![(EventKind::Transition, Some(InstanceState::Starting), InstanceState::Running)]
+		);
+
+		let events = lifecycle.observe(&[instance("a", InstanceState::Stopping)]);
+		assert_eq!(kinds(&events), vec![(EventKind::Transition, Some(InstanceState::Running), InstanceState::Stopping)]);
+
+		// Polling can't skip back from stopping to running.
+		let events = lifecycle.observe(&[instance("a", InstanceState::Running)]);
+		assert_eq!(
+			kinds(&events),
+			vec![(EventKind::IllegalTransition, Some(InstanceState::Stopping), InstanceState::Running)]
+		);
+		assert_eq!(lifecycle.state(ProviderKind::Vultr, "a"), Some(InstanceState::Running));
+	}
+
+	#[test]
+	fn draining_is_kept_while_the_provider_reports_running() {
+		let lifecycle = Lifecycle::new(Duration::minutes(15));
+		lifecycle.observe(&[instance("a", InstanceState::Running)]);
+
+		let event = lifecycle.set_state(ProviderKind::Vultr, "a", InstanceState::Draining).unwrap();
+		assert_eq!(event.from, Some(InstanceState::Running));
+
+		assert!(lifecycle.observe(&[instance("a", InstanceState::Running)]).is_empty());
+		assert_eq!(lifecycle.state(ProviderKind::Vultr, "a"), Some(InstanceState::Draining));
+
+		// Untracked instances can't be moved.
+		assert!(lifecycle.set_state(ProviderKind::Vultr, "b", InstanceState::Draining).is_none());
+	}
+
+	#[test]
+	fn only_terminations_the_manager_expects_are_not_reported() {
+		let lifecycle = Lifecycle::new(Duration::minutes(15));
+		lifecycle.observe(&[instance("a", InstanceState::Running), instance("b", InstanceState::Running)]);
+
+		lifecycle.expect_termination(ProviderKind::Vultr, "a");
+		let mut events = lifecycle.observe(&[]);
+		events.sort_by(|a, b| a.instance_id.cmp(&b.instance_id));
+
+		assert_eq!(
+			kinds(&events),
+			vec![
+				(EventKind::Transition, Some(InstanceState::Running), InstanceState::Terminated),
+				(EventKind::UnexpectedTermination, Some(InstanceState::Running), InstanceState::Terminated),
+			]
+		);
+		assert_eq!(lifecycle.state(ProviderKind::Vultr, "a"), None);
+	}
+
+	#[test]
+	fn instances_stuck_starting_are_reported_once() {
+		let lifecycle = Lifecycle::new(Duration::zero());
+
+		let events = lifecycle.observe(&[instance("a", InstanceState::Starting)]);
+		assert_eq!(
+			kinds(&events),
+			vec![
+				(EventKind::Transition, None, InstanceState::Starting),
+				(EventKind::StuckStarting, Some(InstanceState::Starting), InstanceState::Starting),
+			]
+		);
+		assert!(lifecycle.observe(&[instance("a", InstanceState::Starting)]).is_empty());
+	}
+
+	#[test]
+	fn restored_instances_are_not_reported_as_new() {
+		let event = |instance_id: &str, kind: EventKind, to_state: &str| InstanceEvent {
+			id: 1,
+			instance_id: instance_id.to_string(),
+			provider: "vultr".to_string(),
+			account: DEFAULT_ACCOUNT.to_string(),
+			region: "ewr".to_string(),
+			kind: kind.code().to_string(),
+			from_state: None,
+			to_state: to_state.to_string(),
+			at: Utc::now() - Duration::hours(1),
+		};
+
+		let lifecycle = Lifecycle::new(Duration::minutes(15));
+		lifecycle.restore(&[
+			event("running", EventKind::Transition, "Running"),
+			event("draining", EventKind::Transition, "Draining"),
+			event("stuck", EventKind::StuckStarting, "Starting"),
+			event("unknown", EventKind::Transition, "Rebooting"),
+		]);
+		assert_eq!(lifecycle.state(ProviderKind::Vultr, "draining"), Some(InstanceState::Draining));
+		assert_eq!(lifecycle.state(ProviderKind::Vultr, "unknown"), None);
+
+		let events = lifecycle.observe(&[
+			instance("running", InstanceState::Running),
+			instance("draining", InstanceState::Running),
+			instance("stuck", InstanceState::Starting),
+		]);
+		assert!(events.is_empty(), "{:?}", events);
+
+		// What happened while the principal was down still shows.
+		let events = lifecycle.observe(&[
+			instance("running", InstanceState::Stopped),
+			instance("draining", InstanceState::Running),
+			instance("stuck", InstanceState::Starting),
+		]);
+		assert_eq!(
+			kinds(&events),
+			vec![(EventKind::Transition, Some(InstanceState::Running), InstanceState::Stopped)]
+		);
+	}
+}
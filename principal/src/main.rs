@@ -1,241 +1,143 @@
+// HTTP handlers return error responses, and gRPC handlers `Status`, as `Err` so they can bail
+// out early with `?`; both are large, and boxing them would only move the allocation.
+#![allow(clippy::result_large_err)]
+
 use dotenv::dotenv;
-use std::collections::HashMap;
-use std::error::Error;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 
+pub mod api;
+pub mod bootstrap;
+pub mod config;
+pub mod costs;
 pub mod providers;
+pub mod regions;
 pub mod shared_config;
 pub mod rules;
 pub mod manager;
 pub mod db;
 pub mod gpu;
+pub mod lifecycle;
+pub mod plans;
 pub mod volumes;
+pub mod workers;
 
-mod healer {
-    pub mod healer;
+pub mod docker {
+    include!("docker.rs");
 }
 
-use volumes::volumes::{VolumeManager, HetznerVolumeConfig, VultrVolumeConfig, HetznerVolumeAttachmentConfig, HetznerVolumeResizeConfig, VultrVolumeDetachConfig, VultrVolumeAttachmentConfig};
-use manager::manager::{Manager, ManagerError, AnyInstance};
+pub mod worker_registry {
+    include!("worker_registry.rs");
+}
 
-use hyper::{Body, Request, Response, Server};
-use hyper::service::{make_service_fn, service_fn};
-use hyper::http::StatusCode;
+#[cfg(test)]
+mod tests;
 
-type ResultResponse = Result<Response<Body>, Box<dyn Error + Send + Sync>>;
+use api::api::{handle_request, ApiState};
+use config::config::Config;
+use manager::manager::Manager;
+use shared_config::SharedConfig;
+use worker_registry::worker_registry_server::WorkerRegistryServer;
+use workers::registry::Registry;
 
-fn handle_error(err: Box<dyn std::error::Error>) -> ResultResponse {
-	Ok(Response::builder().status(StatusCode::INTERNAL_SERVER_ERROR).body(Body::from(err.to_string())).unwrap())
-}
+use hyper::Server;
+use hyper::service::{make_service_fn, service_fn};
 
-async fn await_body_bytes(req: Request<Body>) -> Result<Vec<u8>, hyper::Error> {
-    let full_body = hyper::body::to_bytes(req.into_body()).await?;
-    Ok(full_body.to_vec())
-}
+#[tokio::main]
+async fn main() {
+    dotenv().ok(); // Load .env file
 
-fn get_query_param(req: &Request<Body>, key: &str) -> Option<String> {
-    req.uri().query().and_then(|query| {
-        let params: HashMap<String, String> = form_urlencoded::parse(query.as_bytes()).into_owned().collect();
-        params.get(key).cloned()
-    })
-}
+    // With --dry-run the manager only logs and records what it would change.
+    let dry_run = std::env::args().any(|arg| arg == "--dry-run");
 
-async fn handle_instances_request(
-    req: Request<Body>,
-    manager: Arc<Manager>,
-) -> Result<Response<Body>, Box<dyn Error + Send + Sync>> {
-    match (req.method(), req.uri().path()) {
-        (&hyper::Method::GET, "/instances") => {
-            let instances = match manager.get_instances().await {
-                Ok(instances) => instances,
-                Err(e) => {
-                    return Ok(Response::builder()
-                        .status(StatusCode::INTERNAL_SERVER_ERROR)
-                        .body(Body::from(e.to_string()))
-                        .unwrap())
-                }
-            };
-
-            let response_body = format!("{:?}", instances);
-
-            Ok(Response::builder()
-                .status(StatusCode::OK)
-                .body(Body::from(response_body))
-                .unwrap())
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Failed to load config: {}", e);
+            return;
         }
-        _ => {
-            Ok(Response::builder()
-                .status(StatusCode::NOT_FOUND)
-                .body(Body::from("Not found"))
-                .unwrap())
+    };
+
+    let mut shared_config = SharedConfig::new(config);
+    let manager = match Manager::new(&mut shared_config).await {
+        Ok(manager) => Arc::new(manager.dry_run(dry_run)),
+        Err(e) => {
+            eprintln!("Failed to start manager: {}", e);
+            return;
         }
-    }
-}
+    };
 
-/* 
-async fn handle_request(
-    req: Request<Body>,
-    manager: Arc<Manager>,
-    volume_manager: Arc<Mutex<VolumeManager>>,
-) -> Result<Response<Body>, Box<dyn Error + Send + Sync>> {
-    let vm = volume_manager.lock();
-
-	match (req.method(), req.uri().path()) {
-		(&hyper::Method::GET, "/volumes/hetzner") => {
-            let volume_config: HetznerVolumeConfig = match serde_json::from_slice(&await_body_bytes(req).await?) {
-                Ok(config) => config,
-                Err(e) => return Ok(Response::builder().status(StatusCode::BAD_REQUEST).body(Body::from(e.to_string())).unwrap()),
-            };
-
-            let result = volume_manager.lock().unwrap().create_volume_on_hetzner(volume_config).await;
-            result.map(|volume| Response::new(Body::from(serde_json::to_string(&volume).unwrap())))
-                  .or_else(handle_error)
-        }
-
-        (&hyper::Method::POST, "/volumes/hetzner/attach") => {
-            let attach_config: HetznerVolumeAttachmentConfig = match serde_json::from_slice(&await_body_bytes(req).await?) {
-                Ok(config) => config,
-                Err(e) => return Ok(Response::builder().status(StatusCode::BAD_REQUEST).body(Body::from(e.to_string())).unwrap()),
-            };
-
-            let volume_id = req.uri().query().and_then(|query| {
-                let params: HashMap<String, String> = form_urlencoded::parse(query.as_bytes()).into_owned().collect();
-                params.get("volume_id").cloned()
-            }).ok_or_else(|| "Missing volume_id query parameter".to_string());
-
-            let volume_id = match volume_id {
-                Ok(id) => id,
-                Err(e) => return Ok(Response::builder().status(StatusCode::BAD_REQUEST).body(Body::from(e)).unwrap()),
-            };
-
-            let result = volume_manager.lock().unwrap().attach_volume_on_hetzner(&volume_id, attach_config).await;
-            result.map(|volume| Response::new(Body::from(serde_json::to_string(&volume).unwrap())))
-                  .or_else(handle_error)
-        }
-
-        (&hyper::Method::POST, "/volumes/hetzner/detach") => {
-            let volume_id = req.uri().query().map(|query| {
-                let params: HashMap<String, String> = form_urlencoded::parse(query.as_bytes()).into_owned().collect();
-                params.get("volume_id").map(|id| id.to_string())
-            }).flatten().ok_or_else(|| "Missing volume_id query parameter".to_string());
-
-            let volume_id = match volume_id {
-                Ok(id) => id,
-                Err(e) => return Ok(Response::builder().status(StatusCode::BAD_REQUEST).body(Body::from(e)).unwrap()),
-            };
-
-            let result = volume_manager.lock().unwrap().detach_volume_hetzner(&volume_id).await;
-            result.map(|volume| Response::new(Body::from("Volume detached successfully")))
-                  .or_else(handle_error)
-
-        }
+    // Budgets are checked against the last cost report, so the first one is built before anything
+    // is reconciled. Without it, creates a blocking budget covers are refused until there is one.
+    if let Err(e) = manager.cost_report().await {
+        println!("Failed to build the first cost report: {}", e);
+    }
 
-        (&hyper::Method::POST, "/volumes/hetzner/resize") => {
-            let resize_config: HetznerVolumeResizeConfig = match serde_json::from_slice(&await_body_bytes(req).await?) {
-                Ok(config) => config,
-                Err(e) => return Ok(Response::builder().status(StatusCode::BAD_REQUEST).body(Body::from(e.to_string())).unwrap()),
-            };
-
-            let volume_id = req.uri().query().map(|query| {
-                let params: HashMap<String, String> = form_urlencoded::parse(query.as_bytes()).into_owned().collect();
-                params.get("volume_id").map(|id| id.to_string())
-            }).flatten().ok_or_else(|| "Missing volume_id query parameter".to_string()).unwrap();
-                    
-            let result = volume_manager.lock().unwrap().resize_volume_on_hetzner(&volume_id, resize_config).await;
-            result.map(|_| Response::new(Body::from("Volume resized successfully")))
-                  .or_else(handle_error)
+    let manage_manager = Arc::clone(&manager);
+    tokio::spawn(async move {
+        manage_manager.manage().await;
+    });
+
+    let costs_manager = Arc::clone(&manager);
+    tokio::spawn(async move {
+        costs_manager.track_costs().await;
+    });
+
+    // Workers register and send heartbeats over gRPC; missed heartbeats are swept separately.
+    let registry = Arc::new(Registry::new(
+        Arc::clone(&manager),
+        shared_config.config.workers.clone(),
+        shared_config.config.bootstrap.join_token.clone(),
+    ));
+
+    let sweep_registry = Arc::clone(&registry);
+    tokio::spawn(async move {
+        sweep_registry.sweep().await;
+    });
+
+    // `Config::validate` already checked the address.
+    let grpc_addr = shared_config.config.workers.listen_addr.parse().unwrap();
+    tokio::spawn(async move {
+        println!("Worker registry listening on {}", grpc_addr);
+        if let Err(e) = tonic::transport::Server::builder()
+            .add_service(WorkerRegistryServer::from_arc(registry))
+            .serve(grpc_addr)
+            .await
+        {
+            eprintln!("Worker registry error: {}", e);
         }
-
-        (&hyper::Method::POST, "/volumes/vultr") => {
-            let volume_config: VultrVolumeConfig = match serde_json::from_slice(&await_body_bytes(req).await?) {
-                Ok(config) => config,
-                Err(e) => return Ok(Response::builder().status(StatusCode::BAD_REQUEST).body(Body::from(e.to_string())).unwrap()),
-            };
-
-            let result = volume_manager.lock().unwrap().create_volume_on_vultr(volume_config).await;
-            result.map(|volume| Response::new(Body::from(serde_json::to_string(&volume).unwrap())))
-                  .or_else(handle_error)
-        }   
-
-        (&hyper::Method::POST, "/volumes/vultr/attach") => {
-            let attach_config: VultrVolumeAttachmentConfig = match serde_json::from_slice(&await_body_bytes(req).await?) {
-                Ok(config) => config,
-                Err(e) => return Ok(Response::builder().status(StatusCode::BAD_REQUEST).body(Body::from(e.to_string())).unwrap()),
-            };
-
-            let volume_id = req.uri().query().and_then(|query| {
-                let params: HashMap<String, String> = form_urlencoded::parse(query.as_bytes()).into_owned().collect();
-                params.get("volume_id").cloned()
-            });
-
-            let volume_id = match volume_id {
-                Some(id) => id,
-                None => return Ok(Response::builder().status(StatusCode::BAD_REQUEST).body(Body::from("Missing volume_id query parameter")).unwrap()),
-            };
-
-            let result = volume_manager.lock().unwrap().attach_volume_on_vultr(&volume_id, attach_config).await;
-            result.map(|volume| Response::new(Body::from(serde_json::to_string(&volume).unwrap())))
-                  .or_else(handle_error)
+    });
+
+    // VolumeManager only holds the shared provider clients and its stores, so it is used without a lock.
+    let volume_manager = Arc::new(manager.volumes().clone());
+
+    let snapshot_volumes = Arc::clone(&volume_manager);
+    tokio::spawn(async move {
+        snapshot_volumes.run_snapshot_policies().await;
+    });
+
+    let autoscale_volumes = Arc::clone(&volume_manager);
+    tokio::spawn(async move {
+        autoscale_volumes.run_autoscaling().await;
+    });
+
+    let state = ApiState {
+        manager,
+        volume_manager,
+        token: shared_config.config.api.token.clone(),
+    };
+
+    let make_svc = make_service_fn(move |_conn| {
+        let state = state.clone();
+        async move {
+            Ok::<_, hyper::Error>(service_fn(move |req| handle_request(req, state.clone())))
         }
+    });
 
-        (&hyper::Method::POST, "/volumes/vultr/detach") => {
-            let detach_config: VultrVolumeDetachConfig = match serde_json::from_slice(&await_body_bytes(req).await?) {
-                Ok(config) => config,
-                Err(e) => return Ok(Response::builder().status(StatusCode::BAD_REQUEST).body(Body::from(e.to_string())).unwrap()),
-            };
-
-            let volume_id = get_query_param(&req, "volume_id")
-                .ok_or_else(|| "Missing volume_id query parameter".to_string())?;
-
-            let vm = volume_manager.lock().unwrap();
-            vm.detach_volume_on_vultr(&volume_id, detach_config).await
-                    .map(|volume| Response::new(Body::from(serde_json::to_string(&volume).unwrap())))
-                    .or_else(handle_error)
-        },
-
-        (&hyper::Method::POST, "/volumes/vultr/resize") => {
-            let volume_id = req.uri().query().map(|query| {
-                  let params: HashMap<String, String> = form_urlencoded::parse(query.as_bytes()).into_owned().collect();
-                  params.get("volume_id").map(|id| id.to_string())
-              }).flatten().ok_or_else(|| "Missing volume_id query parameter".to_string()).unwrap();
-          
-              let new_label = req.uri().query().map(|query| {
-                  let params: HashMap<String, String> = form_urlencoded::parse(query.as_bytes()).into_owned().collect();
-                  params.get("new_label").map(|id| id.to_string())
-              }).flatten().ok_or_else(|| "Missing new_label query parameter".to_string()).unwrap();
-          
-              let new_size_gb = req.uri().query().map(|query| {
-                  let params: HashMap<String, String> = form_urlencoded::parse(query.as_bytes()).into_owned().collect();
-                  params.get("new_size_gb").and_then(|size| size.parse::<i32>().ok())
-              }).flatten().ok_or_else(|| "Missing new_size_gb query parameter".to_string()).unwrap();
-              
-              let vm = volume_manager.lock().unwrap();
-              vm.resize_volume_on_vultr(&volume_id, &new_label, new_size_gb).await
-                  .map(|_| Response::new(Body::from("Volume resized successfully")))
-                  .or_else(handle_error)
-          },
-
-          (&hyper::Method::GET, "/instances") => {
-            handle_instances_request(req, manager).await
-          },
-
-		_ => Ok(Response::builder().status(StatusCode::NOT_FOUND).body(Body::from("Not Found")).unwrap()),
-	}
-}
-*/
-
-/* 
-async fn process_request(
-    req: Request<Body>,
-    manager_arc: Arc<Manager>,
-    volume_manager: Arc<Mutex<VolumeManager>>,
-) -> Result<Response<Body>, Box<dyn Error + Send + Sync>> {
-    handle_request(req, manager_arc, volume_manager).await
-}
-*/
+    // `Config::validate` already checked the address.
+    let addr = shared_config.config.api.listen_addr.parse().unwrap();
+    println!("Principal listening on {}", addr);
 
-
-#[tokio::main]
-async fn main() {
-    dotenv().ok(); // Load .env file
+    if let Err(e) = Server::bind(&addr).serve(make_svc).await {
+        eprintln!("Server error: {}", e);
+    }
 }
\ No newline at end of file
@@ -1,38 +1,78 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::error::Error;
 use std::fmt;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+use chrono::Utc;
 
-use reqwest::Client;
 use sqlx::postgres::PgPoolOptions;
-use sqlx::postgres::PgPool;
-use dotenv_codegen::dotenv;
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+use models::models::instance::Instance;
+use models::models::instance_state::InstanceState;
+use crate::bootstrap::cloud_init;
+use crate::config::config::{BootstrapConfig, DEFAULT_ACCOUNT};
+use crate::costs::tracker::{CostReport, CostTracker, Inventory};
 use crate::rules::rule::Rule;
+use crate::rules::store::RuleStore;
+use crate::lifecycle::store::LifecycleStore;
+use crate::lifecycle::tracker::{EventKind, Lifecycle, LifecycleEvent};
+use crate::workers::drain;
+use crate::workers::store::WorkerStore;
+use crate::volumes::volumes::VolumeManager;
+use crate::gpu::catalog::{normalize_model, GpuFilter};
+use crate::gpu::gpu::GpuManager;
+use crate::plans::catalog::{CatalogPlan, PlanCatalog};
+use crate::plans::selector::{cheapest, Selection};
+use crate::regions::resolve::rule_region;
 use crate::shared_config::SharedConfig;
+use crate::providers::provider::{CloudProvider, InstanceSpec, ProviderInstance};
+use crate::manager::reconciler::reconcile;
+use crate::manager::plan::{PlannedInstance, ReconcilePlan};
+use crate::providers::error::ProviderError;
+use crate::providers::wait::{WaitError, WaitOptions};
 
 // Vultr provider
-use crate::providers::vultr::models::request::instance::InstanceBuilder;
-use crate::providers::vultr::models::request::instance::Instance;
-use crate::providers::vultr::models::request::region::Region as VultrRegions;
+use crate::providers::vultr::models::request::plan::Plan as VultrPlan;
+use crate::providers::vultr::provider::Vultr;
 
 // Hetzner
-use crate::providers::hetzner::models::request::instance::InstanceBuilder as HetznerInstanceBuilder;
-use crate::providers::hetzner::models::request::instance::Instance as HetznerInstance;
-use crate::providers::hetzner::models::request::region::Region as HetznerRegions;
-use crate::providers::vultr::models::request::region::NorthAmerica::NewJersey;
+use crate::providers::hetzner::models::request::instance::InstanceType as HetznerInstanceType;
+use crate::providers::hetzner::provider::Hetzner;
+
+// Oracle
+use crate::providers::oracle::provider::{Oracle, OracleConfig};
+
+// HostHatch
+use crate::providers::hosthatch::models::request::plan::Plan as HostHatchPlan;
+use crate::providers::hosthatch::provider::HostHatch;
+
+// How long `manage` waits between reconcile passes.
+const RECONCILE_INTERVAL: Duration = Duration::from_secs(30);
+
+// Wait after failed listings: short for transient errors, long for rejected credentials.
+const TRANSIENT_BACKOFF: Duration = Duration::from_secs(5);
+const UNAUTHORIZED_BACKOFF: Duration = Duration::from_secs(300);
+
+// Number of dry-run plans kept in memory for `GET /plan/recorded`.
+const MAX_RECORDED_PLANS: usize = 50;
 
 #[derive(Debug)]
 pub enum ManagerError {
     DatabaseError(sqlx::Error),
-    ReqwestError(reqwest::Error),
-    ProviderError(String),
+    ProviderError(ProviderError),
+    InvalidRule(String),
 }
 
 impl fmt::Display for ManagerError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             ManagerError::DatabaseError(e) => write!(f, "Database error: {}", e),
-            ManagerError::ReqwestError(e) => write!(f, "Reqwest error: {}", e),
             ManagerError::ProviderError(e) => write!(f, "Provider error: {}", e),
+            ManagerError::InvalidRule(e) => write!(f, "Invalid rule: {}", e),
         }
     }
 }
@@ -45,216 +85,698 @@ impl From<sqlx::Error> for ManagerError {
     }
 }
 
-impl From<reqwest::Error> for ManagerError {
-    fn from(err: reqwest::Error) -> ManagerError {
-        ManagerError::ReqwestError(err)
+impl From<ProviderError> for ManagerError {
+    fn from(e: ProviderError) -> Self {
+        ManagerError::ProviderError(e)
     }
 }
 
-#[derive(Debug)]
-pub struct Manager {
-    client: Client,
-    rules: Vec<Rule>,
-    pool: PgPool,
-    vultr_key: String,
-    hetzner_key: String,
+impl From<WaitError> for ManagerError {
+    fn from(e: WaitError) -> Self {
+        ManagerError::ProviderError(e.into())
+    }
 }
 
-#[derive(Debug)]
-pub enum AnyInstance {
-    Vultr(Box<Instance>),
-    Hetzner(Box<HetznerInstance>),
+impl From<reqwest::Error> for ManagerError {
+    fn from(e: reqwest::Error) -> Self {
+        ManagerError::ProviderError(e.into())
+    }
+}
+
+pub struct Manager {
+    rules: RwLock<Vec<Rule>>,
+    // `RuleStore::version` the rules were loaded at.
+    rules_version: AtomicI64,
+    store: RuleStore,
+    workers: WorkerStore,
+    volumes: VolumeManager,
+    gpu: GpuManager,
+    plans: PlanCatalog,
+    costs: Arc<CostTracker>,
+    drain_grace_period: Duration,
+    // Drains in flight by provider and instance id; each runs as its own task, see `execute`.
+    drains: Mutex<HashMap<(ProviderKind, String), JoinHandle<()>>>,
+    lifecycle: Lifecycle,
+    events: LifecycleStore,
+    bootstrap: BootstrapConfig,
+    providers: HashMap<(ProviderKind, String), Box<dyn CloudProvider>>,
+    dry_run: bool,
+    recorded_plans: Mutex<VecDeque<ReconcilePlan>>,
 }
 
 impl Manager {
-    pub async fn new() -> Result<Self, sqlx::Error> {
-        let database_url = dotenv!("COCKROACH_DB_URL");
-        let pool = PgPoolOptions::new().connect(database_url).await?;
-        let rules = Self::load_rules(&pool).await?;
-
-        let vultr_key = dotenv!("VULTR_API_KEY").to_string();
-        let hetzner_key = dotenv!("HETZNER_API_KEY").to_string();
-
-        Ok(Self {
-            client: Client::new(),
-            rules,
-            pool,
-            vultr_key,
-            hetzner_key,
+    pub async fn new(shared_config: &mut SharedConfig) -> Result<Self, sqlx::Error> {
+        let config = shared_config.config.clone();
+        let pool = PgPoolOptions::new().connect(&config.database_url).await?;
+        let workers = WorkerStore::new(pool.clone());
+        let events = LifecycleStore::new(pool.clone());
+        let volumes = VolumeManager::new(shared_config, pool.clone());
+        let store = RuleStore::new(pool);
+        let rules_version = store.version().await?;
+        let rules = store.list().await?;
+
+        let mut manager = Self {
+            rules: RwLock::new(rules),
+            rules_version: AtomicI64::new(rules_version),
+            store,
+            workers,
+            volumes,
+            gpu: GpuManager::new(shared_config),
+            plans: PlanCatalog::new(shared_config),
+            costs: Arc::clone(&shared_config.costs),
+            drain_grace_period: Duration::from_secs(config.workers.drain_grace_period_secs),
+            drains: Mutex::new(HashMap::new()),
+            lifecycle: Lifecycle::new(chrono::Duration::seconds(config.lifecycle.starting_timeout_secs as i64)),
+            events,
+            bootstrap: config.bootstrap.clone(),
+            providers: HashMap::new(),
+            dry_run: false,
+            recorded_plans: Mutex::new(VecDeque::new()),
+        };
+
+        // Without this every instance would be reported as new after a restart.
+        let current = manager.events.current().await?;
+        manager.lifecycle.restore(&current);
+
+        // Only providers present in the config are registered, once per account.
+        for (account, vultr) in config.providers.vultr.iter().flat_map(|vultr| vultr.accounts()) {
+            let mut provider = Vultr::new(shared_config.clients.vultr().clone(), vultr.api_key.clone()).account(account);
+            if let Some(base_url) = &vultr.base_url {
+                provider = provider.base_url(base_url.clone());
+            }
+            if let Some(plan) = &vultr.plan {
+                match VultrPlan::from_code(plan) {
+                    Ok(plan) => provider = provider.plan(plan),
+                    Err(e) => println!("Ignoring Vultr plan: {}: {}", e, plan),
+                }
+            }
+            if let Some(image) = &vultr.image {
+                provider = provider.image(image.clone());
+            }
+            manager.register(Box::new(provider));
+        }
+
+        for (account, hetzner) in config.providers.hetzner.iter().flat_map(|hetzner| hetzner.accounts()) {
+            let mut provider = Hetzner::new(shared_config.clients.hetzner().clone(), hetzner.api_key.clone()).account(account);
+            if let Some(base_url) = &hetzner.base_url {
+                provider = provider.base_url(base_url.clone());
+            }
+            if let Some(plan) = &hetzner.plan {
+                match HetznerInstanceType::from_code(plan) {
+                    Ok(plan) => provider = provider.plan(plan),
+                    Err(e) => println!("Ignoring Hetzner plan: {}: {}", e, plan),
+                }
+            }
+            if let Some(image) = &hetzner.image {
+                provider = provider.image(image.clone());
+            }
+            manager.register(Box::new(provider));
+        }
+
+        if let Some(oracle) = &config.providers.oracle {
+            match OracleConfig::from_settings(oracle)
+                .and_then(|oracle| Oracle::new(shared_config.clients.oracle().clone(), oracle))
+            {
+                Ok(provider) => manager.register(Box::new(provider)),
+                Err(e) => println!("Oracle provider disabled: {}", e),
+            }
+        }
+
+        // HostHatch only holds cheap pre-warmed capacity, so it is opt-in.
+        for (account, hosthatch) in config.providers.hosthatch.iter().flat_map(|hosthatch| hosthatch.accounts()) {
+            let mut provider = HostHatch::new(shared_config.clients.hosthatch().clone(), hosthatch.api_key.clone()).account(account);
+            if let Some(base_url) = &hosthatch.base_url {
+                provider = provider.base_url(base_url.clone());
+            }
+            if let Some(plan) = &hosthatch.plan {
+                match HostHatchPlan::from_code(plan) {
+                    Ok(plan) => provider = provider.plan(plan),
+                    Err(e) => println!("Ignoring HostHatch plan: {}: {}", e, plan),
+                }
+            }
+            if let Some(image) = &hosthatch.image {
+                provider = provider.image(image.clone());
+            }
+            manager.register(Box::new(provider));
+        }
+
+        Ok(manager)
+    }
+
+    // In dry-run mode `manage` only logs and records its plans instead of changing instances.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    // Adds a provider to the registry, replacing any provider of the same kind and account.
+    pub fn register(&mut self, provider: Box<dyn CloudProvider>) {
+        self.providers.insert((provider.kind(), provider.account().to_string()), provider);
+    }
+
+    pub fn provider(&self, kind: &ProviderKind, account: &str) -> Option<&dyn CloudProvider> {
+        self.providers
+            .get(&(*kind, account.to_string()))
+            .map(|provider| provider.as_ref())
+    }
+
+    pub fn rule_store(&self) -> &RuleStore {
+        &self.store
+    }
+
+    pub fn worker_store(&self) -> &WorkerStore {
+        &self.workers
+    }
+
+    pub fn lifecycle(&self) -> &Lifecycle {
+        &self.lifecycle
+    }
+
+    pub fn event_store(&self) -> &LifecycleStore {
+        &self.events
+    }
+
+    pub fn volumes(&self) -> &VolumeManager {
+        &self.volumes
+    }
+
+    pub fn gpu(&self) -> &GpuManager {
+        &self.gpu
+    }
+
+    pub fn plan_catalog(&self) -> &PlanCatalog {
+        &self.plans
+    }
+
+    pub fn costs(&self) -> &CostTracker {
+        &self.costs
+    }
+
+    // Cheapest catalog plan for `shape` in one of `regions`, among the providers registered
+    // here; see `plans::selector::cheapest`.
+    pub async fn cheapest_plan(&self, shape: &Instance, regions: &[String]) -> Result<Selection, ManagerError> {
+        let plans: Vec<_> = self
+            .plans
+            .plans()
+            .await?
+            .into_iter()
+            .filter(|plan| self.providers.keys().any(|(kind, _)| *kind == plan.provider))
+            .collect();
+
+        cheapest(&plans, shape, regions, self.costs.exchange_rates())?.ok_or_else(|| {
+            let allowed = match (regions.is_empty(), shape.region.is_empty()) {
+                (false, _) => regions.join(", "),
+                (true, false) => shape.region.clone(),
+                (true, true) => "any region".to_string(),
+            };
+
+            ProviderError::Invalid(format!(
+                "no plan with {} vCPUs and {} MB memory in {}",
+                shape.vcpu, shape.memory, allowed
+            ))
+            .into()
         })
     }
 
-    async fn load_rules(pool: &sqlx::Pool<sqlx::Postgres>) -> Result<Vec<Rule>, sqlx::Error> {
-        let mut rules = vec![];
-        let recs = sqlx::query_as::<_, (String, String, i32)>(
-            r#"
-            SELECT provider, region, instance_count
-            FROM Providers
-            "#,
-        )
-        .fetch_all(pool)
-        .await?;
-    
-        
-
-    for rec in recs {
-        let rule = Rule {
-            provider: rec.0,
-            region: vec![rec.1],
-            instance_count: rec.2,
+    // Reloads the rules if they changed since they were last loaded.
+    async fn reload_rules(&self) -> Result<(), ManagerError> {
+        let version = self.store.version().await?;
+        if version == self.rules_version.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        let rules = self.store.list().await?;
+        println!("Reloaded {} rules", rules.len());
+        *self.rules.write().unwrap() = rules;
+        self.rules_version.store(version, Ordering::SeqCst);
+
+        Ok(())
+    }
+
+    // Checks a rule from the API against the registered providers and normalizes its region
+    // to the provider's code. Rules are stored one region per row.
+    pub fn validate_rule(&self, mut rule: Rule) -> Result<Rule, ManagerError> {
+        let kind = rule.provider.parse::<ProviderKind>().map_err(ManagerError::InvalidRule)?;
+        let provider = self.provider(&kind, &rule.account).ok_or_else(|| {
+            ManagerError::InvalidRule(format!("no provider registered for {} account {}", kind, rule.account))
+        })?;
+
+        let region = match rule.region.as_slice() {
+            [region] => rule_region(provider, region).map_err(|e| ManagerError::InvalidRule(e.to_string()))?,
+            _ => return Err(ManagerError::InvalidRule("region must list exactly one region code".to_string())),
         };
-            rules.push(rule);
+
+        if rule.instance_count < 0 {
+            return Err(ManagerError::InvalidRule("instance_count must not be negative".to_string()));
+        }
+        if let (Some(min_count), Some(max_count)) = (rule.min_count, rule.max_count) {
+            if min_count > max_count {
+                return Err(ManagerError::InvalidRule("min_count must not be above max_count".to_string()));
+            }
+        }
+        match (&rule.gpu_model, rule.gpu_vram_gb) {
+            (Some(_), _) if kind != ProviderKind::Vultr => {
+                return Err(ManagerError::InvalidRule("GPU pools are only supported on Vultr".to_string()));
+            }
+            (None, Some(_)) => return Err(ManagerError::InvalidRule("gpu_vram_gb needs a gpu_model".to_string())),
+            (_, Some(vram)) if vram <= 0 => {
+                return Err(ManagerError::InvalidRule("gpu_vram_gb must be positive".to_string()));
+            }
+            _ => {}
+        }
+
+        if rule.user_data.is_none() && self.bootstrap.is_enabled() && !cloud_init::can_join(kind.code()) {
+            return Err(ManagerError::InvalidRule(format!(
+                "{} instances can't look up their id to register as workers; give the rule its own user_data",
+                kind
+            )));
         }
 
-        Ok(rules)
+        rule.gpu_model = rule.gpu_model.as_deref().map(normalize_model);
+
+        rule.provider = kind.code().to_string();
+        rule.region = vec![region];
+
+        Ok(rule)
     }
 
-    pub async fn get_instances(&self) -> Result<Vec<AnyInstance>, ManagerError> {
-        let vultr_future = self.get_vultr_instances();
-        let hetzner_future = self.get_hetzner_instances();
+    pub async fn get_instances(&self) -> Result<Vec<ProviderInstance>, ManagerError> {
+        let results = futures::future::try_join_all(
+            self.providers.values().map(|provider| provider.list()),
+        )
+        .await?;
 
-        let results = futures::try_join!(vultr_future, hetzner_future /*, oracle_future */); 
+        Ok(results.into_iter().flatten().collect())
+    }
 
-        match results {
-            Ok((vultr_instances, hetzner_instances)) => {
-                let mut instances: Vec<AnyInstance>  = Vec::new();
+    pub async fn plan(&self) -> Result<ReconcilePlan, ManagerError> {
+        let instances = self.get_instances().await?;
 
-                for instance in vultr_instances {
-                    instances.push(AnyInstance::Vultr(Box::new(instance)));
-                }
-                for instance in hetzner_instances {
-                    instances.push(AnyInstance::Hetzner(Box::new(instance)));
-                }
+        Ok(self.plan_for(&instances).await)
+    }
 
-                Ok(instances)
-            }
-            Err(e) => Err(ManagerError::from(e))
+    async fn plan_for(&self, instances: &[ProviderInstance]) -> ReconcilePlan {
+        // Without the worker inventory every instance counts as idle.
+        let workers = self.workers.list().await.unwrap_or_else(|e| {
+            println!("Failed to list workers: {}", e);
+            Vec::new()
+        });
+
+        let rules = self.rules.read().unwrap().clone();
+        let (mut states, errors) = reconcile(&rules, instances, |kind, account| self.provider(kind, account));
+        for state in &mut states {
+            state.prefer_idle(|instance| {
+                drain::load(drain::find(&workers, &instance.id, instance.main_ip.as_deref()))
+            });
         }
+
+        let mut plan = ReconcilePlan::new(&states, &errors);
+        self.choose_plans(&mut plan).await;
+
+        plan
     }
 
-    async fn get_vultr_instances(&self) -> Result<Vec<Instance>, reqwest::Error> {
-        let resp = self.client.get("https://api.vultr.com/v2/instances")
-            .bearer_auth(&self.vultr_key)
-            .send()
-            .await?
-            .json::<Vec<Instance>>()
-            .await?;
-        
-        Ok(resp)
+    // Fills in the plan of creates whose rule doesn't name one: the cheapest plan for a GPU
+    // pool's model in the region, otherwise the account's default plan, if it has one.
+    async fn choose_plans(&self, plan: &mut ReconcilePlan) {
+        for planned in plan.create.iter_mut().filter(|planned| planned.plan.is_none()) {
+            planned.plan = match &planned.rule.gpu_model {
+                Some(model) => {
+                    let mut filter = GpuFilter::default().model(model).region(&planned.region);
+                    if let Some(vram) = planned.rule.gpu_vram_gb {
+                        filter = filter.min_vram_gb(vram as u32);
+                    }
+
+                    match self.gpu.catalog().cheapest(&filter).await {
+                        Ok(plan) => Some(plan.id),
+                        Err(e) => {
+                            println!("No GPU plan for region {}: {}", planned.region, e);
+                            None
+                        }
+                    }
+                }
+                None => self
+                    .provider(&planned.provider, &planned.account)
+                    .and_then(|provider| provider.plan()),
+            };
+        }
     }
 
-    /* 
-    async fn get_oracle_instances(&self) -> Result<Vec<Instance>, reqwest::Error> {
-        let resp = self.client.get("api.oracle.com/servers") // TODO: implement actual oracle route.
-            .bearer_auth(&self.oracle_key)
-            .send()
-            .await?
-            .json::<Vec<Instance>>()
-            .await?;
+    // Feeds the lifecycle tracker; every event is stored, the unusual ones are also logged.
+    async fn record_events(&self, events: &[LifecycleEvent]) {
+        for event in events {
+            if event.kind != EventKind::Transition {
+                println!(
+                    "Instance {} in {} region {}: {} ({:?} -> {:?})",
+                    event.instance_id,
+                    event.provider,
+                    event.region,
+                    event.kind.code(),
+                    event.from,
+                    event.to
+                );
+            }
 
-        Ok(resp)
+            if let Err(e) = self.events.record(event).await {
+                println!("Failed to record lifecycle event for instance {}: {}", event.instance_id, e);
+            }
+        }
     }
-    */
-    
-    async fn get_hetzner_instances(&self) -> Result<Vec<HetznerInstance>, reqwest::Error> {
-        let resp = self.client.get("https://api.hetzner.cloud/v1/servers")
-            .bearer_auth(&self.hetzner_key)
-            .send()
-            .await?
-            .json::<Vec<HetznerInstance>>()
-            .await?;
 
-        Ok(resp)
+    // Plans recorded by `manage` while running in dry-run mode, oldest first.
+    pub fn recorded_plans(&self) -> Vec<ReconcilePlan> {
+        self.recorded_plans.lock().unwrap().iter().cloned().collect()
+    }
+
+    fn record_plan(&self, plan: ReconcilePlan) {
+        let mut recorded_plans = self.recorded_plans.lock().unwrap();
+        if recorded_plans.len() == MAX_RECORDED_PLANS {
+            recorded_plans.pop_front();
+        }
+        recorded_plans.push_back(plan);
     }
 
-    pub async fn manage(&self, mut shared_config: SharedConfig) {
+    pub async fn manage(self: Arc<Self>) {
         loop {
-            match self.get_instances().await {
+            if let Err(e) = self.reload_rules().await {
+                println!("Failed to reload rules, keeping the current ones: {}", e);
+            }
+
+            let delay = match self.get_instances().await {
                 Ok(instances) => {
-                    let instance_count = self.count_instances(&instances);
-                    for rule in &self.rules {
-                        match rule.provider.as_str() {
-                            "vultr" => {
-                                for _region_str in &rule.region {
-                                    let region = VultrRegions::NorthAmerica(NewJersey);
-                                    let count = instance_count.get(region.to_string().as_str()).unwrap_or(&0);
-                                    match count {
-                                        c if c < &rule.instance_count => {
-                                            println!("Need to start {} instances in region {}", rule.instance_count - c, region);
-                                            let instance = InstanceBuilder::new()
-                                                .region(region.clone())
-                                                .build(&mut shared_config).await;
-                                            instance.start(&mut shared_config).await;                            
-                                        },
-                                        c if c > &rule.instance_count => {
-                                            println!("Need to stop {} instances in region {}", c - rule.instance_count, region);
-                                            for any_instance in instances.iter().filter(|i| {
-                                                if let AnyInstance::Vultr(instance) = i {
-                                                    instance.region == region && instance.provider == rule.provider
-                                                } else {
-                                                    false
-                                                }
-                                            }) {
-                                                if let AnyInstance::Vultr(instance) = any_instance {
-                                                    instance.halt(&mut shared_config).await;
-                                                }
-                                            }
-                                        }
-                                        _ => (),
-                                    }
-                                }
-                            }
-                            "hetzner" => {
-                                for _region_str in &rule.region {
-                                    let region = HetznerRegions::Helsinki;
-                                    let count = instance_count.get(region.to_string().as_str()).unwrap_or(&0);
-                                    match count {
-                                        c if c < &rule.instance_count => {
-                                            println!("Need to start {} instances in region {:?}", rule.instance_count - c, region);
-                                            let instance = HetznerInstanceBuilder::new()
-                                                .region(region.clone())
-                                                .build(&mut shared_config).await;
-                                            instance.start(&mut shared_config).await;
-                                        }
-                                       c if c > &rule.instance_count => {
-                                            println!("Need to stop {} instances in region {:?}", rule.instance_count - c, region);
-                                            for any_instance in instances.iter().filter(|i| {
-                                                if let AnyInstance::Hetzner(instance) = i {
-                                                    instance.region == region && instance.provider == rule.provider
-                                                } else {
-                                                    false
-                                                }
-                                            }) {
-                                                if let AnyInstance::Vultr(instance) = any_instance {
-                                                    instance.halt(&mut shared_config).await;
-                                                }
-                                            }
-                                       }
-                                        _ => (),
-                                    }
-                                }
-                            }
-                            _ => {
-                                println!("Unsupported provider: {}", rule.provider);
-                            }
+                    let events = self.lifecycle.observe(&instances);
+                    self.record_events(&events).await;
+
+                    let plan = self.plan_for(&instances).await;
+                    for error in &plan.errors {
+                        println!(
+                            "Skipping rule for {} in region {}: {}",
+                            error.rule.provider,
+                            error.region.as_deref().unwrap_or("*"),
+                            error.error
+                        );
+                    }
+
+                    if self.dry_run {
+                        for planned in &plan.create {
+                            println!(
+                                "[dry-run] Would create an instance in {} account {} region {}",
+                                planned.provider, planned.account, planned.region
+                            );
+                        }
+                        for planned in &plan.halt {
+                            println!(
+                                "[dry-run] Would drain and remove instance {} in {} account {} region {}",
+                                planned.instance_id.as_deref().unwrap_or_default(),
+                                planned.provider,
+                                planned.account,
+                                planned.region
+                            );
                         }
+                        self.record_plan(plan);
+                    } else {
+                        self.execute(&plan).await;
                     }
+
+                    RECONCILE_INTERVAL
                 }
-                Err(_) => todo!()
+                Err(e) => self.backoff(&e),
+            };
+
+            sleep(delay).await;
+        }
+    }
+
+    // Logs why listing the instances failed and returns how long to wait before the next pass.
+    // Nothing is created or drained without a complete listing.
+    fn backoff(&self, e: &ManagerError) -> Duration {
+        match e {
+            ManagerError::ProviderError(ProviderError::RateLimited { retry_after }) => {
+                let delay = retry_after.unwrap_or(RECONCILE_INTERVAL).max(RECONCILE_INTERVAL);
+                println!("Rate limited while listing instances, next pass in {}s", delay.as_secs());
+                delay
+            }
+            // Credentials don't fix themselves, so there is no point in hammering the provider.
+            ManagerError::ProviderError(ProviderError::Unauthorized(e)) => {
+                println!("Provider rejected the credentials, check the configured API keys: {}", e);
+                UNAUTHORIZED_BACKOFF
+            }
+            ManagerError::ProviderError(e) if e.is_transient() => {
+                println!("Transient error while listing instances, retrying soon: {}", e);
+                TRANSIENT_BACKOFF
+            }
+            e => {
+                println!("Failed to list instances, skipping this pass: {}", e);
+                RECONCILE_INTERVAL
             }
         }
     }
 
-    fn count_instances(&self, instances: &Vec<AnyInstance>) -> HashMap<String, i32> {
-        let mut instance_count: HashMap<String, i32> = HashMap::new();
+    // Lists everything billable and rebuilds the cost report the budgets are checked against.
+    pub async fn cost_report(&self) -> Result<CostReport, ManagerError> {
+        let listed_at = Utc::now();
+        let instances = self.get_instances().await?;
+        let volumes = futures::future::try_join_all(self.providers.values().map(|provider| provider.volumes()))
+            .await?
+            .into_iter()
+            .flatten()
+            .collect();
+        let bandwidth = futures::future::try_join_all(self.providers.values().map(|provider| provider.bandwidth()))
+            .await?
+            .into_iter()
+            .flatten()
+            .collect();
+
+        let mut plans = self.plans.plans().await?;
+        match self.gpu.catalog().plans().await {
+            Ok(gpu_plans) => plans.extend(gpu_plans.iter().map(CatalogPlan::from)),
+            Err(e) => println!("Failed to list GPU plans, GPU instances are left unpriced: {}", e),
+        }
 
-        for any_instance in instances {
-            let region = match any_instance {
-                AnyInstance::Vultr(instance) => instance.region.to_string(),
-                AnyInstance::Hetzner(instance) => instance.region.to_string()
+        // Instances belong to the rule they were created for, stopped ones included.
+        let owners = instances
+            .iter()
+            .filter_map(|instance| Some(((instance.provider, instance.id.clone()), instance.rule_id()?)))
+            .collect();
+
+        Ok(self.costs.record(
+            Inventory {
+                instances,
+                volumes,
+                bandwidth,
+                plans,
+                owners,
+            },
+            listed_at,
+        ))
+    }
+
+    // Keeps the cost report, and with it the budget checks, up to date. The first report is
+    // built before reconciling starts, see `main`.
+    pub async fn track_costs(&self) {
+        loop {
+            sleep(self.costs.refresh_interval()).await;
+
+            if let Err(e) = self.cost_report().await {
+                println!("Failed to update the cost report: {}", e);
+            }
+        }
+    }
+
+    // Creates the instances listed in the plan and starts draining the surplus ones.
+    async fn execute(self: &Arc<Self>, plan: &ReconcilePlan) {
+        // Budgets are checked one create at a time, each approved one reserved before the next is
+        // checked, so a pass can't overrun a budget with creates that each fit on their own.
+        let mut approved = Vec::new();
+        for planned in &plan.create {
+            if let Some((provider, spec)) = self.prepare(planned).await {
+                approved.push((planned, provider, spec));
+            }
+        }
+
+        // Creates wait for provisioning to finish, so they run side by side.
+        futures::future::join_all(approved.iter().map(|(planned, provider, spec)| self.create(planned, *provider, spec))).await;
+
+        // Drains wait out a grace period, so they run as their own tasks instead of holding up
+        // the next pass. An instance still being drained is planned for halting again until it is
+        // gone, and is skipped until its drain has finished.
+        let mut drains = self.drains.lock().unwrap();
+        drains.retain(|_, drain| !drain.is_finished());
+        for planned in &plan.halt {
+            let instance_id = match &planned.instance_id {
+                Some(instance_id) => instance_id.clone(),
+                None => continue,
             };
 
-            let count = instance_count.entry(region).or_insert(0);
+            drains.entry((planned.provider, instance_id)).or_insert_with(|| {
+                let manager = Arc::clone(self);
+                let planned = planned.clone();
+                tokio::spawn(async move { manager.drain(&planned).await })
+            });
+        }
+    }
+
+    // Picks the plan of an instance to create and checks it against the budgets; `None` if it
+    // can't or mustn't be created.
+    async fn prepare(&self, planned: &PlannedInstance) -> Option<(&dyn CloudProvider, InstanceSpec)> {
+        let provider = self.provider(&planned.provider, &planned.account)?;
+
+        let user_data = cloud_init::user_data(&self.bootstrap, &planned.rule, &planned.region);
+        let mut spec = planned.rule.spec(&planned.region, user_data);
+        spec.plan = planned.plan.clone();
+        // `choose_plans` already logged why a GPU pool has none.
+        if planned.rule.gpu_model.is_some() && spec.plan.is_none() {
+            return None;
+        }
+
+        if let Some(plan) = &spec.plan {
+            let checked = self.costs.check_instance(
+                planned.provider,
+                &planned.account,
+                &planned.region,
+                plan,
+                Some(planned.rule.id),
+            );
+            if let Err(e) = checked {
+                println!("Not creating an instance in region {}: {}", planned.region, e);
+                return None;
+            }
+        }
+
+        Some((provider, spec))
+    }
+
+    // Creates an instance and starts it once the provider has finished provisioning it.
+    async fn create(&self, planned: &PlannedInstance, provider: &dyn CloudProvider, spec: &InstanceSpec) {
+        println!(
+            "Creating an instance in {} account {} region {}",
+            planned.provider, planned.account, planned.region
+        );
+
+        let instance = match provider.create(spec).await {
+            Ok(instance) => instance,
+            Err(e) => {
+                println!("Failed to create instance in region {}: {}", planned.region, e);
+                return;
+            }
+        };
+
+        let instance = match provider.wait_until_created(&instance, &WaitOptions::default()).await {
+            Ok(instance) => instance,
+            Err(e) => {
+                println!("Instance {} did not finish provisioning: {}", instance.id, e);
+                return;
+            }
+        };
+
+        if instance.state != InstanceState::Running {
+            if let Err(e) = provider.start(&instance.id).await {
+                println!("Failed to start instance {}: {}", instance.id, e);
+            }
+        }
+    }
+
+    // Cordons the instance's worker and stops its containers, waits out the grace period and
+    // detaches its volumes, and only then halts and deletes the instance.
+    async fn drain(&self, planned: &PlannedInstance) {
+        let (provider, instance_id) = match (self.provider(&planned.provider, &planned.account), &planned.instance_id) {
+            (Some(provider), Some(instance_id)) => (provider, instance_id),
+            _ => return,
+        };
+
+        println!(
+            "Draining instance {} in {} account {} region {}",
+            instance_id, planned.provider, planned.account, planned.region
+        );
+
+        let worker = match self.workers.list().await {
+            Ok(workers) => drain::find(&workers, instance_id, planned.main_ip.as_deref()).cloned(),
+            Err(e) => {
+                println!("Failed to list workers: {}", e);
+                None
+            }
+        };
+
+        let events: Vec<LifecycleEvent> = self
+            .lifecycle
+            .set_state(planned.provider, instance_id, InstanceState::Draining)
+            .into_iter()
+            .collect();
+        self.record_events(&events).await;
+
+        // An instance without a registered worker runs nothing we know of.
+        if let Some(mut worker) = worker.clone() {
+            worker.state = InstanceState::Draining;
+            if let Err(e) = self.workers.save(&worker).await {
+                println!("Failed to cordon worker {}: {}", worker.id, e);
+            }
+
+            match drain::stop_containers(&self.workers, &worker).await {
+                Ok(stopped) => println!("Stopped {} containers on worker {}", stopped, worker.id),
+                Err(e) => println!("Failed to stop containers on worker {}: {}", worker.id, e),
+            }
+
+            sleep(self.drain_grace_period).await;
+        }
+
+        // Left cordoned, the instance is picked again on the next tick.
+        if let Err(e) = self.detach_volumes(provider, instance_id).await {
+            println!("Failed to detach volumes from instance {}: {}", instance_id, e);
+            return;
+        }
+
+        println!("Halting instance {}", instance_id);
+        self.lifecycle.expect_termination(planned.provider, instance_id);
+        if let Err(e) = provider.halt(instance_id).await {
+            println!("Failed to halt instance {}: {}", instance_id, e);
+            return;
+        }
+        if let Err(e) = provider.delete(instance_id).await {
+            println!("Failed to delete instance {}: {}", instance_id, e);
+            return;
+        }
+
+        if let Some(mut worker) = worker {
+            worker.state = InstanceState::Terminated;
+            if let Err(e) = self.workers.save(&worker).await {
+                println!("Failed to update worker {}: {}", worker.id, e);
+            }
+        }
+    }
+
+    // Volumes of the default account go through `VolumeManager`, which has the worker unmount
+    // them and updates its records; those of other accounts are detached with the account's own
+    // client. Providers that can't detach volumes do so when the instance is deleted.
+    async fn detach_volumes(&self, provider: &dyn CloudProvider, instance_id: &str) -> Result<(), ManagerError> {
+        let volumes = provider.volumes().await?;
+        let attached = volumes.iter().filter(|volume| volume.attached_to.as_deref() == Some(instance_id));
+        for volume in attached {
+            println!("Detaching volume {} from instance {}", volume.id, instance_id);
+
+            if provider.account() == DEFAULT_ACCOUNT && self.volumes.supports(volume.provider) {
+                self.volumes.detach(volume.provider, &volume.id).await?;
+                continue;
+            }
+
+            match provider.detach_volume(&volume.id).await {
+                Err(ProviderError::Unsupported(_)) => {
+                    println!("Leaving volume {} to be detached when instance {} is deleted", volume.id, instance_id)
+                }
+                result => result?,
+            }
+        }
+
+        Ok(())
+    }
+
+    // Number of active instances per provider, account and region code.
+    pub fn count_instances(&self, instances: &[ProviderInstance]) -> HashMap<(ProviderKind, String, String), i32> {
+        let mut instance_count: HashMap<(ProviderKind, String, String), i32> = HashMap::new();
+
+        for instance in instances.iter().filter(|i| i.is_active()) {
+            let count = instance_count
+                .entry((instance.provider, instance.account.clone(), instance.region.clone()))
+                .or_insert(0);
             *count += 1;
         }
 
@@ -1 +1,3 @@
 pub mod manager;
+pub mod plan;
+pub mod reconciler;
@@ -0,0 +1,87 @@
+use chrono::{DateTime, Utc};
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+use serde::Serialize;
+
+use crate::manager::reconciler::{RegionState, RuleError};
+use crate::rules::rule::Rule;
+
+// A single instance the reconciler wants to create, halt or leave alone.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedInstance {
+	pub rule: Rule,
+	pub provider: ProviderKind,
+	pub account: String,
+	pub region: String,
+	pub plan: Option<String>,
+	pub instance_id: Option<String>,
+	pub main_ip: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanError {
+	pub rule: Rule,
+	pub region: Option<String>,
+	pub error: String,
+}
+
+// Typed diff between the rules and what is currently running at the providers.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReconcilePlan {
+	pub created_at: DateTime<Utc>,
+	pub create: Vec<PlannedInstance>,
+	pub halt: Vec<PlannedInstance>,
+	pub keep: Vec<PlannedInstance>,
+	pub errors: Vec<PlanError>,
+}
+
+impl ReconcilePlan {
+	pub fn new(states: &[RegionState], errors: &[RuleError]) -> Self {
+		let mut plan = ReconcilePlan {
+			created_at: Utc::now(),
+			create: Vec::new(),
+			halt: Vec::new(),
+			keep: Vec::new(),
+			errors: errors
+				.iter()
+				.map(|e| PlanError {
+					rule: e.rule.clone(),
+					region: e.region.clone(),
+					error: e.error.to_string(),
+				})
+				.collect(),
+		};
+
+		for state in states {
+			for _ in 0..state.missing() {
+				plan.create.push(PlannedInstance {
+					rule: state.rule.clone(),
+					provider: state.provider,
+					account: state.account.clone(),
+					region: state.region.clone(),
+					// The manager picks one for rules that don't name a plan, see `Manager::choose_plans`.
+					plan: state.rule.plan.clone(),
+					instance_id: None,
+					main_ip: None,
+				});
+			}
+
+			let surplus = state.surplus().len();
+			let (keep, surplus) = state.active.split_at(state.active.len() - surplus);
+			let halt: Vec<_> = surplus.iter().chain(&state.stale).copied().collect();
+
+			for (instances, target) in [(keep, &mut plan.keep), (&halt[..], &mut plan.halt)] {
+				target.extend(instances.iter().map(|instance| PlannedInstance {
+					rule: state.rule.clone(),
+					provider: state.provider,
+					account: state.account.clone(),
+					region: state.region.clone(),
+					plan: Some(instance.plan.clone()),
+					instance_id: Some(instance.id.clone()),
+					main_ip: instance.main_ip.clone(),
+				}));
+			}
+		}
+
+		plan
+	}
+}
@@ -0,0 +1,402 @@
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+
+use crate::gpu::catalog::plan_model;
+use crate::manager::manager::ManagerError;
+use crate::providers::provider::{parse_label_tags, CloudProvider, ProviderInstance};
+use crate::regions::resolve::provider_region;
+use crate::rules::rule::Rule;
+
+// Desired versus observed state for a single (provider, account, region) of a rule.
+#[derive(Debug)]
+pub struct RegionState<'a> {
+	pub rule: &'a Rule,
+	pub provider: ProviderKind,
+	pub account: String,
+	pub region: String,
+	pub desired: usize,
+	pub active: Vec<&'a ProviderInstance>,
+	// Active instances of the rule that no longer fit it, e.g. after its plan or labels changed.
+	// They don't count towards it and are halted like the surplus.
+	pub stale: Vec<&'a ProviderInstance>,
+}
+
+impl<'a> RegionState<'a> {
+	// Number of instances that must be created to reach the rule's count.
+	pub fn missing(&self) -> usize {
+		self.desired.saturating_sub(self.active.len())
+	}
+
+	// Instances that must be halted to get back down to the rule's count.
+	pub fn surplus(&self) -> &[&'a ProviderInstance] {
+		&self.active[self.desired.min(self.active.len())..]
+	}
+
+	// Orders the active instances busiest first, so `surplus` picks the idle or least-loaded ones.
+	pub fn prefer_idle<F>(&mut self, load: F)
+	where
+		F: Fn(&ProviderInstance) -> f64,
+	{
+		self.active.sort_by(|a, b| load(b).total_cmp(&load(a)));
+	}
+}
+
+// A rule region that could not be reconciled, e.g. an unknown provider or region code.
+#[derive(Debug)]
+pub struct RuleError<'a> {
+	pub rule: &'a Rule,
+	pub region: Option<String>,
+	pub error: ManagerError,
+}
+
+// Matches every rule region against the listed instances. `provider` resolves a
+// provider kind and account to its registered implementation, which is used to
+// parse the rule's region strings into that provider's region codes.
+pub fn reconcile<'a, F>(
+	rules: &'a [Rule],
+	instances: &'a [ProviderInstance],
+	provider: F,
+) -> (Vec<RegionState<'a>>, Vec<RuleError<'a>>)
+where
+	F: Fn(&ProviderKind, &str) -> Option<&'a dyn CloudProvider>,
+{
+	let mut states = Vec::new();
+	let mut errors = Vec::new();
+
+	for rule in rules {
+		let provider = match rule.provider.parse::<ProviderKind>() {
+			Ok(kind) => match provider(&kind, &rule.account) {
+				Some(provider) => provider,
+				None => {
+					errors.push(RuleError {
+						rule,
+						region: None,
+						error: ManagerError::InvalidRule(format!(
+							"no provider registered for {} account {}",
+							kind, rule.account
+						)),
+					});
+					continue;
+				}
+			},
+			Err(e) => {
+				errors.push(RuleError {
+					rule,
+					region: None,
+					error: ManagerError::InvalidRule(e),
+				});
+				continue;
+			}
+		};
+
+		let mut codes = Vec::new();
+		for region in &rule.region {
+			let code = match provider_region(provider, region) {
+				Ok(code) => code,
+				Err(error) => {
+					errors.push(RuleError {
+						rule,
+						region: Some(region.clone()),
+						error,
+					});
+					continue;
+				}
+			};
+
+			// An area and a metro in it can resolve to the same code, which only counts once.
+			if codes.contains(&code) {
+				continue;
+			}
+			codes.push(code.clone());
+
+			let (active, stale) = instances
+				.iter()
+				.filter(|i| {
+					i.provider == provider.kind()
+						&& i.account == provider.account()
+						&& i.region == code
+						&& i.rule_id() == Some(rule.id)
+						&& i.is_active()
+				})
+				.partition(|i| fits(rule, i));
+
+			states.push(RegionState {
+				rule,
+				provider: provider.kind(),
+				account: provider.account().to_string(),
+				region: code,
+				desired: rule.desired(),
+				active,
+				stale,
+			});
+		}
+	}
+
+	(states, errors)
+}
+
+// Whether an instance created for the rule still has its plan, GPU model and labels.
+fn fits(rule: &Rule, instance: &ProviderInstance) -> bool {
+	rule.plan.as_ref().is_none_or(|plan| plan.eq_ignore_ascii_case(&instance.plan))
+		&& plan_model(&instance.plan) == rule.gpu_model
+		&& parse_label_tags(rule.labels.iter().map(String::as_str))
+			.iter()
+			.all(|(key, value)| instance.labels.get(key) == Some(value))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::BTreeMap;
+
+	use models::models::instance_state::InstanceState;
+
+	use super::*;
+	use crate::config::config::DEFAULT_ACCOUNT;
+	use crate::manager::plan::ReconcilePlan;
+	use crate::providers::http::HttpClient;
+	use crate::providers::provider::RULE_LABEL;
+	use crate::providers::vultr::provider::Vultr;
+
+	fn rule(region: &[&str], instance_count: i32) -> Rule {
+		Rule {
+			id: 1,
+			provider: "vultr".to_string(),
+			account: DEFAULT_ACCOUNT.to_string(),
+			region: region.iter().map(|region| region.to_string()).collect(),
+			instance_count,
+			min_count: None,
+			max_count: None,
+			plan: None,
+			gpu_model: None,
+			gpu_vram_gb: None,
+			image: None,
+			ssh_keys: Vec::new(),
+			user_data: None,
+			labels: Vec::new(),
+		}
+	}
+
+	fn instance(id: &str, region: &str, state: InstanceState) -> ProviderInstance {
+		ProviderInstance {
+			id: id.to_string(),
+			provider: ProviderKind::Vultr,
+			account: DEFAULT_ACCOUNT.to_string(),
+			region: region.to_string(),
+			plan: "vc2-1c-1gb".to_string(),
+			status: String::new(),
+			state,
+			label: String::new(),
+			main_ip: None,
+			created_at: None,
+			pending_action: None,
+			labels: BTreeMap::from([(RULE_LABEL.to_string(), "1".to_string())]),
+		}
+	}
+
+	fn state<'a>(rule: &'a Rule, active: &'a [ProviderInstance]) -> RegionState<'a> {
+		RegionState {
+			rule,
+			provider: ProviderKind::Vultr,
+			account: DEFAULT_ACCOUNT.to_string(),
+			region: "ewr".to_string(),
+			desired: rule.desired(),
+			active: active.iter().collect(),
+			stale: Vec::new(),
+		}
+	}
+
+	// Region parsing doesn't call the API, so the client is never used.
+	fn vultr() -> Vultr {
+		Vultr::new(HttpClient::new(reqwest::Client::new()), String::new())
+	}
+
+	fn ids<'a>(instances: impl IntoIterator<Item = &'a &'a ProviderInstance>) -> Vec<&'a str> {
+		instances.into_iter().map(|instance| instance.id.as_str()).collect()
+	}
+
+	#[test]
+	fn reconcile_matches_active_instances_of_each_rule_region() {
+		let vultr = vultr();
+		// `fra` is the Vultr metro of `eu-central` and only counts once.
+		let rules = [rule(&["ewr", "eu-central", "fra"], 2)];
+		let instances = [
+			instance("a", "ewr", InstanceState::Running),
+			instance("b", "ewr", InstanceState::Starting),
+			instance("c", "ewr", InstanceState::Stopped),
+			instance("d", "fra", InstanceState::Running),
+			instance("e", "lax", InstanceState::Running),
+			instance("f", "ewr", InstanceState::Running).with_account("staging"),
+			// Servers infralink didn't create for the rule are never counted, let alone halted.
+			ProviderInstance {
+				labels: BTreeMap::new(),
+				..instance("g", "ewr", InstanceState::Running)
+			},
+			ProviderInstance {
+				labels: BTreeMap::from([(RULE_LABEL.to_string(), "2".to_string())]),
+				..instance("h", "ewr", InstanceState::Running)
+			},
+		];
+
+		let (states, errors) = reconcile(&rules, &instances, |_, account| {
+			(account == DEFAULT_ACCOUNT).then_some(&vultr as &dyn CloudProvider)
+		});
+
+		assert!(errors.is_empty());
+		assert_eq!(states.len(), 2);
+		assert_eq!((states[0].region.as_str(), ids(&states[0].active)), ("ewr", vec!["a", "b"]));
+		assert_eq!((states[1].region.as_str(), ids(&states[1].active)), ("fra", vec!["d"]));
+		assert!(states.iter().all(|state| state.desired == 2 && state.stale.is_empty()));
+	}
+
+	#[test]
+	fn reconcile_sets_apart_instances_that_no_longer_fit_the_rule() {
+		let vultr = vultr();
+		let rules = [Rule {
+			plan: Some("vc2-1c-1gb".to_string()),
+			labels: vec!["role=worker".to_string()],
+			..rule(&["ewr"], 2)
+		}];
+		let labelled = |id| {
+			let mut instance = instance(id, "ewr", InstanceState::Running);
+			instance.labels.insert("role".to_string(), "worker".to_string());
+			instance
+		};
+		let instances = [
+			labelled("a"),
+			// Created before the rule's plan, its labels or its GPU model changed.
+			ProviderInstance {
+				plan: "vc2-2c-4gb".to_string(),
+				..labelled("b")
+			},
+			instance("c", "ewr", InstanceState::Running),
+			ProviderInstance {
+				plan: "vcg-a100-1c-6g-4vram".to_string(),
+				..labelled("d")
+			},
+		];
+
+		let (states, _) = reconcile(&rules, &instances, |_, _| Some(&vultr as &dyn CloudProvider));
+		let plan = ReconcilePlan::new(&states, &[]);
+
+		assert_eq!(ids(&states[0].active), vec!["a"]);
+		assert_eq!(ids(&states[0].stale), vec!["b", "c", "d"]);
+		assert_eq!(
+			plan.create.iter().map(|planned| planned.plan.as_deref()).collect::<Vec<_>>(),
+			vec![Some("vc2-1c-1gb")]
+		);
+		assert_eq!(
+			plan.halt.iter().map(|planned| planned.instance_id.as_deref().unwrap()).collect::<Vec<_>>(),
+			vec!["b", "c", "d"]
+		);
+	}
+
+	#[test]
+	fn reconcile_reports_rules_it_cannot_resolve() {
+		let vultr = vultr();
+		let rules = [
+			rule(&["ewr", "atlantis"], 1),
+			Rule {
+				provider: "hetzner".to_string(),
+				..rule(&["fsn1"], 1)
+			},
+			Rule {
+				provider: "digitalocean".to_string(),
+				..rule(&["ams"], 1)
+			},
+		];
+
+		let (states, errors) = reconcile(&rules, &[], |kind, _| {
+			(*kind == ProviderKind::Vultr).then_some(&vultr as &dyn CloudProvider)
+		});
+
+		assert_eq!(states.len(), 1);
+		assert_eq!(
+			errors.iter().map(|e| (e.rule.provider.as_str(), e.region.as_deref())).collect::<Vec<_>>(),
+			vec![("vultr", Some("atlantis")), ("hetzner", None), ("digitalocean", None)]
+		);
+	}
+
+	#[test]
+	fn missing_and_surplus_follow_the_clamped_count() {
+		let instances = [
+			instance("a", "ewr", InstanceState::Running),
+			instance("b", "ewr", InstanceState::Running),
+			instance("c", "ewr", InstanceState::Running),
+		];
+		let region = |rule, active| state(rule, &instances[..active]);
+
+		let grow = rule(&["ewr"], 3);
+		assert_eq!((region(&grow, 1).missing(), region(&grow, 1).surplus().len()), (2, 0));
+		assert_eq!((region(&grow, 3).missing(), region(&grow, 3).surplus().len()), (0, 0));
+
+		let shrink = rule(&["ewr"], 1);
+		assert_eq!(region(&shrink, 3).missing(), 0);
+		assert_eq!(ids(region(&shrink, 3).surplus()), vec!["b", "c"]);
+
+		let capped = Rule {
+			max_count: Some(2),
+			..rule(&["ewr"], 5)
+		};
+		assert_eq!((region(&capped, 1).missing(), region(&capped, 3).surplus().len()), (1, 1));
+
+		let floored = Rule {
+			min_count: Some(1),
+			..rule(&["ewr"], -2)
+		};
+		assert_eq!((region(&floored, 0).missing(), region(&floored, 3).surplus().len()), (1, 2));
+	}
+
+	#[test]
+	fn prefer_idle_leaves_the_least_loaded_instances_as_surplus() {
+		let rule = rule(&["ewr"], 1);
+		let instances = [
+			instance("idle", "ewr", InstanceState::Running),
+			instance("busy", "ewr", InstanceState::Running),
+			instance("quiet", "ewr", InstanceState::Running),
+		];
+		let mut state = state(&rule, &instances);
+
+		state.prefer_idle(|instance| match instance.id.as_str() {
+			"busy" => 0.9,
+			"quiet" => 0.2,
+			_ => 0.0,
+		});
+
+		assert_eq!(ids(state.surplus()), vec!["quiet", "idle"]);
+	}
+
+	#[test]
+	fn plan_creates_the_missing_instances_and_halts_the_surplus() {
+		let vultr = vultr();
+		let rules = [rule(&["ewr"], 1), rule(&["fra"], 3), Rule {
+			provider: "hetzner".to_string(),
+			..rule(&["fsn1"], 1)
+		}];
+		let instances = [
+			instance("a", "ewr", InstanceState::Running),
+			instance("b", "ewr", InstanceState::Running),
+			instance("c", "fra", InstanceState::Running),
+		];
+
+		let (states, errors) = reconcile(&rules, &instances, |kind, _| {
+			(*kind == ProviderKind::Vultr).then_some(&vultr as &dyn CloudProvider)
+		});
+		let plan = ReconcilePlan::new(&states, &errors);
+
+		let planned = |instances: &[crate::manager::plan::PlannedInstance]| {
+			instances
+				.iter()
+				.map(|planned| (planned.region.clone(), planned.instance_id.clone()))
+				.collect::<Vec<_>>()
+		};
+		assert_eq!(planned(&plan.create), vec![("fra".to_string(), None), ("fra".to_string(), None)]);
+		assert_eq!(planned(&plan.halt), vec![("ewr".to_string(), Some("b".to_string()))]);
+		assert_eq!(
+			planned(&plan.keep),
+			vec![("ewr".to_string(), Some("a".to_string())), ("fra".to_string(), Some("c".to_string()))]
+		);
+		assert_eq!(plan.errors.len(), 1);
+		// Left for the manager to pick, the rule doesn't name a plan.
+		assert!(plan.create.iter().all(|planned| planned.plan.is_none()));
+		assert!(plan.halt.iter().all(|planned| planned.plan.as_deref() == Some("vc2-1c-1gb")));
+	}
+}
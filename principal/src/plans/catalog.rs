@@ -0,0 +1,314 @@
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+use std::time::Duration;
+
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+use crate::gpu::catalog::GpuPlan;
+use crate::providers::error::ProviderError;
+use crate::providers::hetzner::models::request::instance::Pricing;
+use crate::providers::hetzner::pages as hetzner_pages;
+use crate::providers::hetzner::provider::HETZNER_API_URL;
+use crate::providers::http::HttpClient;
+use crate::providers::vultr::pages as vultr_pages;
+use crate::providers::vultr::provider::VULTR_API_URL;
+use crate::shared_config::SharedConfig;
+
+// Prices change a few times a year, an hourly refresh is plenty.
+const CATALOG_TTL: Duration = Duration::from_secs(3600);
+
+// Vultr bills by the hour up to 672 hours (28 days), then the monthly price, so its hourly price
+// is the monthly one over 672 where the listing leaves it out. Hetzner lists both prices, with
+// its own cap at the monthly price; `costs::tracker::accrue` caps both the same way.
+const VULTR_HOURS_PER_MONTH: f64 = 672.0;
+
+// Vultr bills in US dollars; Hetzner in euros, the default currency of its accounts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Currency {
+	Usd,
+	Eur,
+}
+
+impl Currency {
+	pub fn code(&self) -> &'static str {
+		match self {
+			Currency::Usd => "USD",
+			Currency::Eur => "EUR",
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Arch {
+	X86,
+	Arm,
+}
+
+// A plan as listed by Vultr's `/plans`.
+#[derive(Deserialize)]
+struct VultrCatalogPlan {
+	id: String,
+	vcpu_count: u32,
+	ram: u64,
+	disk: u64,
+	monthly_cost: f64,
+	#[serde(default)]
+	hourly_cost: Option<f64>,
+	#[serde(rename = "type")]
+	kind: String,
+	locations: Vec<String>,
+}
+
+// A server type as listed by Hetzner's `/server_types`; memory is in GB.
+#[derive(Deserialize)]
+struct HetznerServerType {
+	name: String,
+	cores: u32,
+	memory: f64,
+	disk: u64,
+	architecture: Arch,
+	#[serde(default)]
+	deprecated: Option<bool>,
+	prices: Vec<Pricing>,
+}
+
+// One compute plan of one provider at one price.
+#[derive(Debug, Clone, Serialize)]
+pub struct CatalogPlan {
+	pub provider: ProviderKind,
+	// Provider plan code, e.g. `vc2-1c-1gb` or `cx22`.
+	pub id: String,
+	pub vcpu: u32,
+	pub ram_mb: u64,
+	pub disk_gb: u64,
+	pub arch: Arch,
+	// Net prices in `currency`.
+	pub currency: Currency,
+	pub hourly: f64,
+	pub monthly: f64,
+	// Provider region codes the plan is offered in at this price.
+	pub regions: Vec<String>,
+}
+
+impl From<VultrCatalogPlan> for CatalogPlan {
+	fn from(plan: VultrCatalogPlan) -> Self {
+		CatalogPlan {
+			provider: ProviderKind::Vultr,
+			hourly: plan.hourly_cost.unwrap_or(plan.monthly_cost / VULTR_HOURS_PER_MONTH),
+			id: plan.id,
+			vcpu: plan.vcpu_count,
+			ram_mb: plan.ram,
+			disk_gb: plan.disk,
+			// Vultr has no ARM cloud compute.
+			arch: Arch::X86,
+			currency: Currency::Usd,
+			monthly: plan.monthly_cost,
+			regions: plan.locations,
+		}
+	}
+}
+
+// GPU plans live in `gpu::catalog`; this lets costs price GPU instances too.
+impl From<&GpuPlan> for CatalogPlan {
+	fn from(plan: &GpuPlan) -> Self {
+		CatalogPlan {
+			provider: ProviderKind::Vultr,
+			id: plan.id.clone(),
+			vcpu: plan.vcpu,
+			ram_mb: plan.ram_mb as u64,
+			disk_gb: plan.disk_gb as u64,
+			arch: Arch::X86,
+			currency: Currency::Usd,
+			hourly: plan.monthly_cost / VULTR_HOURS_PER_MONTH,
+			monthly: plan.monthly_cost,
+			regions: plan.locations.clone(),
+		}
+	}
+}
+
+impl HetznerServerType {
+	// Hetzner prices each location separately; locations sharing a price become one plan.
+	fn into_plans(self) -> Result<Vec<CatalogPlan>, ProviderError> {
+		let mut by_price: BTreeMap<(String, String), Vec<String>> = BTreeMap::new();
+		for price in self.prices {
+			by_price
+				.entry((price.price_hourly.net, price.price_monthly.net))
+				.or_default()
+				.push(price.location);
+		}
+
+		by_price
+			.into_iter()
+			.map(|((hourly, monthly), regions)| {
+				Ok(CatalogPlan {
+					provider: ProviderKind::Hetzner,
+					id: self.name.clone(),
+					vcpu: self.cores,
+					ram_mb: (self.memory * 1024.0).round() as u64,
+					disk_gb: self.disk,
+					arch: self.architecture,
+					currency: Currency::Eur,
+					hourly: parse_price(&self.name, &hourly)?,
+					monthly: parse_price(&self.name, &monthly)?,
+					regions,
+				})
+			})
+			.collect()
+	}
+}
+
+fn parse_price(server_type: &str, amount: &str) -> Result<f64, ProviderError> {
+	amount
+		.parse()
+		.map_err(|_| ProviderError::Decode(format!("price {:?} of server type {}", amount, server_type)))
+}
+
+// Where one provider's plans come from, and the last listing of them.
+struct Source {
+	provider: ProviderKind,
+	client: HttpClient,
+	api_key: String,
+	base_url: String,
+	cache: RwLock<Option<(Instant, Vec<CatalogPlan>)>>,
+}
+
+impl Source {
+	fn new(provider: ProviderKind, client: HttpClient, api_key: String, base_url: String) -> Self {
+		Source {
+			provider,
+			client,
+			api_key,
+			base_url,
+			cache: RwLock::new(None),
+		}
+	}
+
+	async fn fetch(&self) -> Result<Vec<CatalogPlan>, ProviderError> {
+		match self.provider {
+			ProviderKind::Hetzner => {
+				let server_types = hetzner_pages::list_all::<HetznerServerType>(
+					&self.client,
+					&format!("{}/server_types", self.base_url),
+					&self.api_key,
+					"server_types",
+				)
+				.await?;
+
+				let mut plans = Vec::new();
+				for server_type in server_types.into_iter().filter(|server_type| server_type.deprecated != Some(true)) {
+					plans.extend(server_type.into_plans()?);
+				}
+
+				Ok(plans)
+			}
+			_ => {
+				let plans = vultr_pages::list_all::<VultrCatalogPlan>(
+					&self.client,
+					&format!("{}/plans", self.base_url),
+					&self.api_key,
+					"plans",
+				)
+				.await?;
+
+				// GPU plans are sold by GPU, not by shape; see `gpu::catalog`.
+				Ok(plans
+					.into_iter()
+					.filter(|plan| plan.kind != "vcg" && !plan.locations.is_empty())
+					.map(CatalogPlan::from)
+					.collect())
+			}
+		}
+	}
+
+	// Cached plans while fresh; a failed refresh falls back to the previous listing if there is
+	// one.
+	async fn plans(&self, force: bool) -> Result<Vec<CatalogPlan>, ProviderError> {
+		if let Some((fetched_at, plans)) = self.cache.read().unwrap().as_ref() {
+			if !force && fetched_at.elapsed() < CATALOG_TTL {
+				return Ok(plans.clone());
+			}
+		}
+
+		let fetched = self.fetch().await;
+
+		let mut cache = self.cache.write().unwrap();
+		match fetched {
+			Ok(plans) => {
+				*cache = Some((Instant::now(), plans.clone()));
+				Ok(plans)
+			}
+			Err(e) => match cache.as_ref() {
+				Some((_, plans)) => {
+					println!("Failed to refresh the {} plan catalog, using the cached one: {}", self.provider, e);
+					Ok(plans.clone())
+				}
+				None => Err(e),
+			},
+		}
+	}
+}
+
+// Compute plans and prices of the providers that publish them, fetched on first use and
+// refreshed once they are older than an hour.
+pub struct PlanCatalog {
+	sources: Vec<Source>,
+}
+
+impl PlanCatalog {
+	// Vultr lists its plans publicly; Hetzner only with a token, so it needs a configured account.
+	pub fn new(shared_config: &mut SharedConfig) -> Self {
+		let vultr = shared_config.config.vultr();
+		let mut sources = vec![Source::new(
+			ProviderKind::Vultr,
+			shared_config.clients.vultr().clone(),
+			vultr.api_key.clone(),
+			vultr.url(VULTR_API_URL),
+		)];
+
+		let hetzner = shared_config.config.providers.hetzner.iter().flat_map(|hetzner| hetzner.accounts()).next();
+		if let Some((_, hetzner)) = hetzner {
+			sources.push(Source::new(
+				ProviderKind::Hetzner,
+				shared_config.clients.hetzner().clone(),
+				hetzner.api_key.clone(),
+				hetzner.url(HETZNER_API_URL),
+			));
+		}
+
+		PlanCatalog { sources }
+	}
+
+	// Every provider's plans. A provider that can't be listed is left out, unless none can.
+	pub async fn plans(&self) -> Result<Vec<CatalogPlan>, ProviderError> {
+		self.collect(false).await
+	}
+
+	// Lists every provider again regardless of the cache age.
+	pub async fn refresh(&self) -> Result<Vec<CatalogPlan>, ProviderError> {
+		self.collect(true).await
+	}
+
+	async fn collect(&self, force: bool) -> Result<Vec<CatalogPlan>, ProviderError> {
+		let mut plans = Vec::new();
+		let mut last_error = None;
+
+		for source in &self.sources {
+			match source.plans(force).await {
+				Ok(listed) => plans.extend(listed),
+				Err(e) => {
+					println!("Failed to list {} plans: {}", source.provider, e);
+					last_error = Some(e);
+				}
+			}
+		}
+
+		match last_error {
+			Some(e) if plans.is_empty() => Err(e),
+			_ => Ok(plans),
+		}
+	}
+}
@@ -0,0 +1,240 @@
+use std::collections::BTreeMap;
+
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+use models::models::instance::Instance;
+use models::models::region::Region;
+use serde::Serialize;
+
+use crate::plans::catalog::{CatalogPlan, Currency};
+use crate::providers::error::ProviderError;
+use crate::regions::resolve::covers;
+
+// The cheapest plan for a shape and the region to deploy it in.
+#[derive(Debug, Clone, Serialize)]
+pub struct Selection {
+	pub provider: ProviderKind,
+	pub plan: String,
+	// Provider region code, and the canonical region it belongs to if the catalog knows it.
+	pub region: String,
+	pub canonical_region: Option<&'static str>,
+	pub vcpu: u32,
+	pub ram_mb: u64,
+	pub disk_gb: u64,
+	// Prices in the plan's own currency.
+	pub currency: Currency,
+	pub hourly: f64,
+	pub monthly: f64,
+}
+
+// Whether a plan is big enough for `shape`: at least its vCPUs, memory (MB) and boot volume
+// (GB, once known). A non-empty `shape.provider` only allows that provider's plans.
+pub fn fits(plan: &CatalogPlan, shape: &Instance) -> bool {
+	(shape.provider.is_empty() || shape.provider.eq_ignore_ascii_case(plan.provider.code()))
+		&& u64::from(plan.vcpu) >= shape.vcpu
+		&& plan.ram_mb >= shape.memory
+		&& shape.boot_volume.as_ref().is_none_or(|volume| plan.disk_gb >= volume.total)
+}
+
+// The first of the plan's regions covered by `regions`, trying the allowed regions in order.
+// Each allowed region may be a canonical region, an area, a continent or a provider code.
+fn allowed_region(plan: &CatalogPlan, regions: &[String]) -> Option<String> {
+	if regions.is_empty() {
+		return plan.regions.first().cloned();
+	}
+
+	regions
+		.iter()
+		.find_map(|allowed| plan.regions.iter().find(|code| covers(plan.provider, code, allowed)).cloned())
+}
+
+// US dollars per unit of `currency`, from `rates` keyed by currency code.
+fn usd_rate(currency: Currency, rates: &BTreeMap<String, f64>) -> Result<f64, ProviderError> {
+	match currency {
+		Currency::Usd => Ok(1.0),
+		_ => rates.get(currency.code()).copied().ok_or_else(|| {
+			ProviderError::Invalid(format!(
+				"plans are priced in USD and {0}, set costs.exchange_rates.{0} to compare them",
+				currency.code()
+			))
+		}),
+	}
+}
+
+// Cheapest plan across all providers that fits `shape` in one of `regions`; without allowed
+// regions `shape.region` is the only one, and if that is empty too any region will do. Ties go
+// to the smaller plan. Plans in different currencies are compared in US dollars at `rates`, and
+// not at all without a rate.
+pub fn cheapest(
+	plans: &[CatalogPlan],
+	shape: &Instance,
+	regions: &[String],
+	rates: &BTreeMap<String, f64>,
+) -> Result<Option<Selection>, ProviderError> {
+	let regions = match (regions.is_empty(), shape.region.is_empty()) {
+		(true, false) => vec![shape.region.clone()],
+		_ => regions.to_vec(),
+	};
+
+	let candidates: Vec<(&CatalogPlan, String)> = plans
+		.iter()
+		.filter(|plan| fits(plan, shape))
+		.filter_map(|plan| allowed_region(plan, &regions).map(|region| (plan, region)))
+		.collect();
+	let mixed = candidates.windows(2).any(|pair| pair[0].0.currency != pair[1].0.currency);
+
+	let mut priced = Vec::with_capacity(candidates.len());
+	for (plan, region) in candidates {
+		let rate = if mixed { usd_rate(plan.currency, rates)? } else { 1.0 };
+		priced.push((plan, region, rate));
+	}
+
+	Ok(priced
+		.into_iter()
+		.min_by(|(a, _, a_rate), (b, _, b_rate)| {
+			(a.monthly * a_rate)
+				.total_cmp(&(b.monthly * b_rate))
+				.then((a.hourly * a_rate).total_cmp(&(b.hourly * b_rate)))
+				.then(a.vcpu.cmp(&b.vcpu))
+				.then(a.ram_mb.cmp(&b.ram_mb))
+		})
+		.map(|(plan, region, _)| Selection {
+			provider: plan.provider,
+			plan: plan.id.clone(),
+			canonical_region: Region::from_provider_code(plan.provider, &region).map(|region| region.id),
+			region,
+			vcpu: plan.vcpu,
+			ram_mb: plan.ram_mb,
+			disk_gb: plan.disk_gb,
+			currency: plan.currency,
+			hourly: plan.hourly,
+			monthly: plan.monthly,
+		}))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::plans::catalog::Arch;
+
+	fn plan(
+		provider: ProviderKind,
+		id: &str,
+		vcpu: u32,
+		ram_mb: u64,
+		currency: Currency,
+		monthly: f64,
+		regions: &[&str],
+	) -> CatalogPlan {
+		CatalogPlan {
+			provider,
+			id: id.to_string(),
+			vcpu,
+			ram_mb,
+			disk_gb: 40,
+			arch: Arch::X86,
+			currency,
+			hourly: monthly / 730.0,
+			monthly,
+			regions: regions.iter().map(|region| region.to_string()).collect(),
+		}
+	}
+
+	fn plans() -> Vec<CatalogPlan> {
+		vec![
+			plan(ProviderKind::Vultr, "vc2-1c-1gb", 1, 1024, Currency::Usd, 5.0, &["ewr", "fra"]),
+			plan(ProviderKind::Vultr, "vc2-2c-4gb", 2, 4096, Currency::Usd, 20.0, &["ewr", "fra"]),
+			plan(ProviderKind::Hetzner, "cx22", 2, 4096, Currency::Eur, 4.5, &["fsn1", "nbg1"]),
+			plan(ProviderKind::Hetzner, "cx32", 4, 8192, Currency::Eur, 7.5, &["fsn1", "nbg1"]),
+		]
+	}
+
+	fn shape(provider: &str, region: &str, vcpu: u64, memory: u64) -> Instance {
+		Instance {
+			provider: provider.to_string(),
+			region: region.to_string(),
+			vcpu,
+			memory,
+			boot_volume: None,
+		}
+	}
+
+	fn rates() -> BTreeMap<String, f64> {
+		BTreeMap::from([("EUR".to_string(), 1.08)])
+	}
+
+	#[test]
+	fn picks_the_cheapest_plan_that_fits() {
+		let selection = cheapest(&plans(), &shape("vultr", "", 1, 2048), &[], &rates()).unwrap().unwrap();
+		assert_eq!((selection.provider, selection.plan.as_str()), (ProviderKind::Vultr, "vc2-2c-4gb"));
+		assert_eq!((selection.region.as_str(), selection.canonical_region), ("ewr", Some("ewr")));
+
+		let selection = cheapest(&plans(), &shape("", "", 3, 4096), &[], &rates()).unwrap().unwrap();
+		assert_eq!(selection.plan, "cx32");
+
+		assert!(cheapest(&plans(), &shape("", "", 8, 1024), &[], &rates()).unwrap().is_none());
+	}
+
+	#[test]
+	fn ties_go_to_the_smaller_plan() {
+		let plans = vec![
+			plan(ProviderKind::Vultr, "vc2-2c-2gb", 2, 2048, Currency::Usd, 10.0, &["ewr"]),
+			plan(ProviderKind::Vultr, "vc2-1c-2gb", 1, 2048, Currency::Usd, 10.0, &["ewr"]),
+		];
+
+		let selection = cheapest(&plans, &shape("", "", 1, 1024), &[], &rates()).unwrap().unwrap();
+		assert_eq!(selection.plan, "vc2-1c-2gb");
+	}
+
+	#[test]
+	fn only_allowed_regions_are_considered_in_order() {
+		let plans = plans();
+		let small = shape("", "", 1, 1024);
+
+		// An area covers both providers' regions there; 4.50 EUR is 4.86 USD at 1.08.
+		let regions = ["eu-central".to_string()];
+		let selection = cheapest(&plans, &small, &regions, &rates()).unwrap().unwrap();
+		assert_eq!((selection.plan.as_str(), selection.region.as_str()), ("cx22", "fsn1"));
+
+		let selection = cheapest(&plans, &shape("vultr", "", 1, 1024), &regions, &rates()).unwrap().unwrap();
+		assert_eq!((selection.plan.as_str(), selection.region.as_str()), ("vc2-1c-1gb", "fra"));
+
+		// The first allowed region a plan is offered in is the one it is deployed to.
+
+		let regions = ["nue".to_string(), "fsn".to_string()];
+		let selection = cheapest(&plans, &shape("hetzner", "", 1, 1024), &regions, &rates()).unwrap().unwrap();
+		assert_eq!((selection.region.as_str(), selection.canonical_region), ("nbg1", Some("nue")));
+
+		// Without allowed regions the shape's own region is the only one.
+		let selection = cheapest(&plans, &shape("", "fsn1", 1, 1024), &[], &rates()).unwrap().unwrap();
+		assert_eq!(selection.plan, "cx22");
+
+		assert!(cheapest(&plans, &small, &["sgp".to_string()], &rates()).unwrap().is_none());
+	}
+
+	#[test]
+	fn mixed_currencies_are_compared_in_us_dollars() {
+		let plans = vec![
+			plan(ProviderKind::Vultr, "vc2-2c-4gb", 2, 4096, Currency::Usd, 5.0, &["ewr"]),
+			plan(ProviderKind::Hetzner, "cx22", 2, 4096, Currency::Eur, 4.8, &["fsn1"]),
+		];
+		let small = shape("", "", 1, 1024);
+
+		// 4.80 EUR is 5.18 USD at 1.08.
+		let selection = cheapest(&plans, &small, &[], &rates()).unwrap().unwrap();
+		assert_eq!((selection.plan.as_str(), selection.currency), ("vc2-2c-4gb", Currency::Usd));
+
+		let cheaper_euro = BTreeMap::from([("EUR".to_string(), 1.0)]);
+		let selection = cheapest(&plans, &small, &[], &cheaper_euro).unwrap().unwrap();
+		assert_eq!((selection.plan.as_str(), selection.currency, selection.monthly), ("cx22", Currency::Eur, 4.8));
+
+		let result = cheapest(&plans, &small, &[], &BTreeMap::new());
+		assert!(matches!(
+			result,
+			Err(ProviderError::Invalid(e)) if e == "plans are priced in USD and EUR, set costs.exchange_rates.EUR to compare them"
+		));
+
+		// A single currency needs no rate.
+		let selection = cheapest(&plans[1..], &small, &[], &BTreeMap::new()).unwrap().unwrap();
+		assert_eq!(selection.plan, "cx22");
+	}
+}
@@ -0,0 +1,72 @@
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+use reqwest::StatusCode;
+
+// Why a provider call failed, classified so callers can decide whether to retry, back off or
+// give up.
+#[derive(Debug)]
+pub enum ProviderError {
+	// 429; `retry_after` is set when the provider said how long to wait.
+	RateLimited { retry_after: Option<Duration> },
+	// 401 or 403: the API key is missing, wrong or lacks the permission.
+	Unauthorized(String),
+	NotFound(String),
+	// Other 4xx, or a request we refuse to send, e.g. an unknown region or plan.
+	Invalid(String),
+	// 5xx, timeouts, dropped connections and operations the provider failed on its side.
+	Transient(String),
+	// The provider answered with something we could not decode.
+	Decode(String),
+	// The provider has no API for the operation at all.
+	Unsupported(String),
+}
+
+impl ProviderError {
+	// Maps an HTTP error status, with the response body as message.
+	pub fn from_status(status: StatusCode, message: String) -> Self {
+		match status {
+			StatusCode::TOO_MANY_REQUESTS => ProviderError::RateLimited { retry_after: None },
+			StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ProviderError::Unauthorized(message),
+			StatusCode::NOT_FOUND => ProviderError::NotFound(message),
+			status if status.is_server_error() => ProviderError::Transient(message),
+			_ => ProviderError::Invalid(message),
+		}
+	}
+
+	// Whether sending the same request again later may succeed.
+	pub fn is_transient(&self) -> bool {
+		matches!(self, ProviderError::RateLimited { .. } | ProviderError::Transient(_))
+	}
+}
+
+impl fmt::Display for ProviderError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ProviderError::RateLimited { retry_after: Some(retry_after) } => {
+				write!(f, "Rate limited, retry after {}s", retry_after.as_secs())
+			}
+			ProviderError::RateLimited { retry_after: None } => write!(f, "Rate limited"),
+			ProviderError::Unauthorized(e) => write!(f, "Unauthorized: {}", e),
+			ProviderError::NotFound(e) => write!(f, "Not found: {}", e),
+			ProviderError::Invalid(e) => write!(f, "Invalid request: {}", e),
+			ProviderError::Transient(e) => write!(f, "Transient error: {}", e),
+			ProviderError::Decode(e) => write!(f, "Unexpected response: {}", e),
+			ProviderError::Unsupported(e) => write!(f, "Unsupported: {}", e),
+		}
+	}
+}
+
+impl Error for ProviderError {}
+
+impl From<reqwest::Error> for ProviderError {
+	fn from(e: reqwest::Error) -> Self {
+		match e.status() {
+			Some(status) => ProviderError::from_status(status, e.to_string()),
+			None if e.is_decode() => ProviderError::Decode(e.to_string()),
+			None if e.is_builder() => ProviderError::Invalid(e.to_string()),
+			None => ProviderError::Transient(e.to_string()),
+		}
+	}
+}
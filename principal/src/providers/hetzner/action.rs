@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+use crate::providers::http::HttpClient;
+use crate::providers::wait::{wait_until, Progress, WaitError, WaitOptions};
+
+// Server and volume calls return an action that tracks the change asynchronously.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Action {
+	pub id: u64,
+	pub command: String,
+	pub status: ActionStatus,
+	pub progress: u8,
+	pub error: Option<ActionError>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ActionStatus {
+	Running,
+	Success,
+	Error,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ActionError {
+	pub code: String,
+	pub message: String,
+}
+
+#[derive(Deserialize)]
+pub struct ActionResponse {
+	pub action: Action,
+}
+
+// Polls `/actions/{id}` until the action succeeded or failed.
+pub async fn wait_for_action(
+	client: &HttpClient,
+	base_url: &str,
+	api_key: &str,
+	action_id: u64,
+	options: &WaitOptions,
+) -> Result<Action, WaitError> {
+	wait_until(options, || async {
+		let action = client
+			.get(format!("{}/actions/{}", base_url, action_id))
+			.bearer_auth(api_key)
+			.send()
+			.await?
+			.json::<ActionResponse>()
+			.await?
+			.action;
+
+		Ok(match action.status {
+			ActionStatus::Running => Progress::Pending,
+			ActionStatus::Success => Progress::Done(action),
+			ActionStatus::Error => Progress::Failed(match action.error {
+				Some(error) => format!("{} {}: {}", action.command, error.code, error.message),
+				None => format!("{} failed", action.command),
+			}),
+		})
+	})
+	.await
+}
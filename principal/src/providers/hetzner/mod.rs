@@ -1 +1,4 @@
+pub mod action;
 pub mod models;
+pub mod pages;
+pub mod provider;
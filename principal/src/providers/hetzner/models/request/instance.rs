@@ -1,33 +1,44 @@
 use std::collections::HashMap;
 
-use serde::{Deserialize, Serialize};
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+use models::models::instance_state::InstanceState;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+use crate::config::config::DEFAULT_ACCOUNT;
+use crate::providers::error::ProviderError;
+use crate::providers::hetzner::provider::HETZNER_API_URL;
 use crate::shared_config::SharedConfig;
 
 use super::region::Region;
-use dotenv_codegen::dotenv;
 
-const HETZNER_API_KEY: &str = dotenv!("HETZNER_API_KEY");
+#[derive(Deserialize)]
+struct ServerResponse {
+	server: Instance,
+}
 
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "lowercase")]
 pub enum Architecture {
 	X86,
 	Arm,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "lowercase")]
 pub enum CpuType {
 	Shared,
 	Dedicated,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "lowercase")]
 pub enum FirewallStatus {
 	Applied,
 	Pending,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "lowercase")]
 pub enum ImageStatus {
 	Available,
 	Creating,
@@ -35,6 +46,7 @@ pub enum ImageStatus {
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "lowercase")]
 pub enum ImageType {
 	System,
 	App,
@@ -44,6 +56,7 @@ pub enum ImageType {
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "lowercase")]
 pub enum InstanceStatus {
 	Running,
 	Initializing,
@@ -57,17 +70,20 @@ pub enum InstanceStatus {
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "lowercase")]
 pub enum IsoType {
 	Public,
 	Private,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "lowercase")]
 pub enum PlacementGroupType {
 	Spread,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "lowercase")]
 pub enum StorageType {
 	Local,
 	Network,
@@ -84,7 +100,7 @@ pub struct DataCenter {
 	id: u64,
 	name: String,
 	description: String,
-	location: LocationObject,
+	pub location: LocationObject,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -102,15 +118,16 @@ pub struct FirewallInstance {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ImageObject {
 	id: u32,
-	name: String,
+	name: Option<String>,
 	bound_to: Option<u32>,
 	created: String,
-	created_from: CreatedFromObject,
+	created_from: Option<CreatedFromObject>,
 	deleted: Option<String>,
 	deprecated: Option<String>,
 	description: String,
-	disk_size: u64,
-	image_size: u64,
+	// Sizes in GB; `image_size` is only known for snapshots and backups.
+	disk_size: f64,
+	image_size: Option<f64>,
 	os_flavor: String,
 	os_version: Option<String>,
 	protection: ProtectionObject,
@@ -121,18 +138,26 @@ pub struct ImageObject {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct IPAddress {
-	pub id: u64,
+	pub id: Option<u64>,
 	pub blocked: bool,
-	pub dns_ptr: Option<Vec<DnsPTR>>,
+	// A hostname for IPv4 and a list of them for IPv6.
+	pub dns_ptr: Option<DnsPtrs>,
 	pub ip: String,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum DnsPtrs {
+	Ipv4(String),
+	Ipv6(Vec<DnsPTR>),
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Iso {
 	id: u64,
 	name: String,
 	architecture: Option<Architecture>,
-	deprecated: String,
+	deprecated: Option<String>,
 	description: String,
 	r#type: IsoType,
 }
@@ -140,7 +165,7 @@ pub struct Iso {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct LocationObject {
 	id: u64,
-	name: String,
+	pub name: String,
 	city: String,
 	country: String,
 	description: String,
@@ -159,17 +184,19 @@ pub struct PlacementGroup {
 	servers: Vec<u64>,
 }
 
+// Price of a server type in one location; see `plans::catalog`.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Pricing {
-	location: String,
-	price_hourly: String,
-	price_monthly: String,
+	pub location: String,
+	pub price_hourly: PricingModel,
+	pub price_monthly: PricingModel,
 }
 
+// Hetzner sends amounts as decimal strings, e.g. `"0.0060000000"`.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct PricingModel {
-	gross: String,
-	net: String,
+	pub gross: String,
+	pub net: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -193,23 +220,25 @@ pub struct ProtectionObjectInstance {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct PublicNetInstance {
-	firewalls: Vec<FirewallInstance>,
-	floating_ips: Vec<u64>,
-	ipv4: IPAddress,
-	ipv6: IPAddress,
+	pub firewalls: Vec<FirewallInstance>,
+	pub floating_ips: Vec<u64>,
+	// Unset when the server was created without one.
+	pub ipv4: Option<IPAddress>,
+	pub ipv6: Option<IPAddress>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ServerType {
-	id: u64,
-	name: String,
-	cores: u64,
-	cpu_type: CpuType,
-	deprecated: bool,
-	disk: u64,
-	memory: u64,
-	storage_type: StorageType,
-	prices: Vec<Pricing>,
+	pub id: u64,
+	pub name: String,
+	pub cores: u64,
+	pub cpu_type: CpuType,
+	pub deprecated: bool,
+	pub disk: u64,
+	// In GB.
+	pub memory: f64,
+	pub storage_type: StorageType,
+	pub prices: Vec<Pricing>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -219,9 +248,9 @@ pub struct Instance {
 	pub backup_window: Option<String>,
 	pub created: String,
 	pub datacenter: DataCenter,
-	pub image: ImageObject,
+	// Unset when the server was booted from an ISO or its image was deleted.
+	pub image: Option<ImageObject>,
 	pub included_traffic: u64,
-	pub region: Region,
 	pub ingoing_traffic: u64,
 	pub outgoing_traffic: Option<u64>,
 	pub iso: Option<Iso>,
@@ -237,7 +266,6 @@ pub struct Instance {
 	pub server_type: ServerType,
 	pub status: InstanceStatus,
 	pub volumes: Vec<u64>,
-	pub provider: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -248,7 +276,7 @@ pub struct PublicNet {
 	pub ipv6: Option<u64>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum InstanceType {
 	Sharedx86(SharedX86),       // Shared x86 Instances
 	DedicatedX86(DedicatedX86), // Dedicated x86 Instances
@@ -256,7 +284,65 @@ pub enum InstanceType {
 	Unknown,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl InstanceType {
+	pub fn list() -> Vec<Self> {
+		use DedicatedX86::*;
+		use SharedArm::*;
+		use SharedX86::*;
+
+		let shared_x86 = [CX11, CPX11, CX21, CPX21, CX31, CPX31, CX41, CPX41, CX51, CPX51];
+		let shared_arm = [CAX11, CAX21, CAX31, CAX41];
+		let dedicated_x86 = [
+			CCX11, CCX12, CCX21, CCX22, CCX31, CCX32, CCX41, CCX42, CCX51, CCX52, CCX62,
+		];
+
+		shared_x86
+			.into_iter()
+			.map(InstanceType::Sharedx86)
+			.chain(shared_arm.into_iter().map(InstanceType::SharedArm))
+			.chain(dedicated_x86.into_iter().map(InstanceType::DedicatedX86))
+			.collect()
+	}
+
+	// Server type names as the API uses them, e.g. `cx21`.
+	pub fn code(&self) -> String {
+		match self {
+			InstanceType::Sharedx86(server_type) => format!("{:?}", server_type).to_lowercase(),
+			InstanceType::DedicatedX86(server_type) => format!("{:?}", server_type).to_lowercase(),
+			InstanceType::SharedArm(server_type) => format!("{:?}", server_type).to_lowercase(),
+			InstanceType::Unknown => String::new(),
+		}
+	}
+
+	pub fn from_code(code: &str) -> Result<Self, &'static str> {
+		Self::list()
+			.into_iter()
+			.find(|server_type| server_type.code() == code)
+			.ok_or("Unknown server type")
+	}
+}
+
+impl Serialize for InstanceType {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		serializer.serialize_str(&self.code())
+	}
+}
+
+impl<'de> Deserialize<'de> for InstanceType {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		let code = String::deserialize(deserializer)?;
+
+		InstanceType::from_code(&code).map_err(serde::de::Error::custom)
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 pub enum SharedX86 {
 	CX11,  // 1vCPU 2GB RAM (Intel)
 	CPX11, // 2vCPU 2GB RAM (AMD)
@@ -270,7 +356,7 @@ pub enum SharedX86 {
 	CPX51, // 16vCPU 32GB RAM (AMD)
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 pub enum SharedArm {
 	CAX11, // 2vCPU 4GB RAM
 	CAX21, // 4vCPU 8GB RAM
@@ -278,7 +364,7 @@ pub enum SharedArm {
 	CAX41, // 16vCPU 32GB RAM
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 pub enum DedicatedX86 {
 	CCX11, // 2vCPU 8GB RAM (Intel)
 	CCX12, // 2vCPU 8GB RAM (AMD)
@@ -295,6 +381,8 @@ pub enum DedicatedX86 {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct InstanceBuilder {
+	// Only used to pick `location`; not part of the API request.
+	#[serde(skip_serializing)]
 	pub region: Region,
 	pub name: String,
 	pub automount: Option<bool>,
@@ -318,16 +406,36 @@ pub struct Firewall {
 	id: u64,
 }
 
+impl From<&InstanceStatus> for InstanceState {
+	fn from(status: &InstanceStatus) -> Self {
+		match status {
+			InstanceStatus::Initializing | InstanceStatus::Starting => InstanceState::Starting,
+			InstanceStatus::Running => InstanceState::Running,
+			InstanceStatus::Migrating | InstanceStatus::Rebuilding => InstanceState::Upgrading,
+			InstanceStatus::Stopping | InstanceStatus::Deleting => InstanceState::Stopping,
+			InstanceStatus::Off => InstanceState::Stopped,
+			InstanceStatus::Unknown => InstanceState::Unknown,
+		}
+	}
+}
+
 impl Instance {
-	pub async fn start(&self, shared_config: &mut SharedConfig) {
+	pub async fn start(&self, shared_config: &mut SharedConfig) -> Result<(), ProviderError> {
+		let hetzner = shared_config.config.hetzner();
+
 		shared_config
 			.clients
 			.hetzner()
-			.post("https://api.hetzner.cloud/v1/servers/{}/actions/poweron")
-			.bearer_auth(dotenv!("HETZNER_API_KEY"))
+			.post(format!(
+				"{}/servers/{}/actions/poweron",
+				hetzner.url(HETZNER_API_URL),
+				self.id
+			))
+			.bearer_auth(&hetzner.api_key)
 			.send()
-			.await
-			.unwrap();
+			.await?;
+
+		Ok(())
 	}
 }
 
@@ -433,17 +541,28 @@ impl InstanceBuilder {
 		self
 	}	
 	
-	pub async fn build(self, shared_config: &mut SharedConfig) -> Instance {
+	pub async fn build(self, shared_config: &mut SharedConfig) -> Result<Instance, ProviderError> {
+		let hetzner = shared_config.config.hetzner();
+		let location = self.location.as_ref().unwrap_or(&self.region);
+		shared_config.costs.check_instance(
+			ProviderKind::Hetzner,
+			DEFAULT_ACCOUNT,
+			&location.code(),
+			&self.server_type.code(),
+			None,
+		)?;
+
 		shared_config
 			.clients
 			.hetzner()
-			.post("https://api.hetzner.cloud/v1/servers")
+			.post(format!("{}/servers", hetzner.url(HETZNER_API_URL)))
+			.bearer_auth(&hetzner.api_key)
 			.json(&self)
 			.send()
+			.await?
+			.json::<ServerResponse>()
 			.await
-			.unwrap()
-			.json::<Instance>()
-			.await
-			.unwrap()
+			.map(|response| response.server)
+			.map_err(ProviderError::from)
 	}
 }
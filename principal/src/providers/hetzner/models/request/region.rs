@@ -1,6 +1,8 @@
-use serde::{Deserialize, Serialize};
+use std::fmt;
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Debug, PartialEq, Clone)]
 pub enum Region {
 	Falkenstein,
 	Nuremberg,
@@ -10,21 +12,33 @@ pub enum Region {
 	Unknown,
 }
 
-impl ToString for Region {
-	fn to_string(&self) -> String {
-		match self {
-			Region::Falkenstein => "Falkestein".to_string(),
-			Region::Nuremberg => "Nuremberg".to_string(),
-			Region::Helsinki => "Helsinki".to_string(),
-			Region::Ashburn => "Ashburn".to_string(),
-			Region::Hillsboro => "Hillsboro".to_string(),
-			Region::Unknown => "Unknown".to_string(),
-		}
+impl fmt::Display for Region {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let name = match self {
+			Region::Falkenstein => "Falkestein",
+			Region::Nuremberg => "Nuremberg",
+			Region::Helsinki => "Helsinki",
+			Region::Ashburn => "Ashburn",
+			Region::Hillsboro => "Hillsboro",
+			Region::Unknown => "Unknown",
+		};
+
+		write!(f, "{}", name)
 	}
 }
 
 impl Region {
-	fn code(self) -> String {
+	pub fn list() -> Vec<Self> {
+		vec![
+			Region::Falkenstein,
+			Region::Nuremberg,
+			Region::Helsinki,
+			Region::Ashburn,
+			Region::Hillsboro,
+		]
+	}
+
+	pub fn code(&self) -> String {
 		match self {
 			Region::Falkenstein => "fsn1".to_string(),
 			Region::Nuremberg => "nbg1".to_string(),
@@ -34,4 +48,36 @@ impl Region {
 			Region::Unknown => "Unknown".to_string(),
 		}
 	}
+
+	pub fn from_code(code: &str) -> Result<Self, &'static str> {
+		match code {
+			"fsn1" => Ok(Region::Falkenstein),
+			"nbg1" => Ok(Region::Nuremberg),
+			"hel1" => Ok(Region::Helsinki),
+			"ash" => Ok(Region::Ashburn),
+			"hil" => Ok(Region::Hillsboro),
+			_ => Err("Unknown region code"),
+		}
+	}
+}
+
+// The API names locations by code, e.g. `hel1`.
+impl Serialize for Region {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		serializer.serialize_str(&self.code())
+	}
+}
+
+impl<'de> Deserialize<'de> for Region {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		let code = String::deserialize(deserializer)?;
+
+		Region::from_code(&code).map_err(serde::de::Error::custom)
+	}
 }
@@ -0,0 +1,107 @@
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::providers::error::ProviderError;
+use crate::providers::http::HttpClient;
+
+// Largest page Hetzner serves.
+const PER_PAGE: u32 = 50;
+
+#[derive(Deserialize)]
+struct Pagination {
+	next_page: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct Meta {
+	pagination: Pagination,
+}
+
+// Fetches every page of a list endpoint. Hetzner wraps the items in `{ "<key>": [..], "meta": .. }`
+// with the number of the next page in `meta.pagination`, null on the last one.
+pub async fn list_all<T: DeserializeOwned>(
+	client: &HttpClient,
+	url: &str,
+	api_key: &str,
+	key: &str,
+) -> Result<Vec<T>, ProviderError> {
+	let mut items = Vec::new();
+	let mut page_number = 1;
+
+	loop {
+		let mut page = client
+			.get(url)
+			.bearer_auth(api_key)
+			.query(&[("page", page_number), ("per_page", PER_PAGE)])
+			.send()
+			.await?
+			.json::<Value>()
+			.await?;
+
+		let page_items = page.get_mut(key).map(Value::take).unwrap_or(Value::Null);
+		items.extend(serde_json::from_value::<Vec<T>>(page_items).map_err(|e| {
+			ProviderError::Decode(format!("{} in {}: {}", key, url, e))
+		})?);
+
+		let meta = page.get_mut("meta").map(Value::take).unwrap_or(Value::Null);
+		page_number = match serde_json::from_value::<Meta>(meta) {
+			Ok(Meta { pagination: Pagination { next_page: Some(next) } }) if next > page_number => next,
+			_ => return Ok(items),
+		};
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::json;
+
+	use super::*;
+	use crate::providers::test_server::serve;
+
+	fn page(items: &[u32], next_page: Option<u32>) -> Value {
+		json!({ "servers": items, "meta": { "pagination": { "next_page": next_page } } })
+	}
+
+	#[tokio::test]
+	async fn list_all_reads_pages_until_there_is_no_next_one() {
+		let url = serve(|query| {
+			assert_eq!(query["per_page"], PER_PAGE.to_string());
+
+			match query["page"].as_str() {
+				"1" => page(&[1, 2], Some(2)),
+				"2" => page(&[3, 4], Some(3)),
+				"3" => page(&[5], None),
+				page => panic!("unexpected page {}", page),
+			}
+		})
+		.await;
+
+		let items = list_all::<u32>(&HttpClient::new(reqwest::Client::new()), &url, "key", "servers").await.unwrap();
+
+		assert_eq!(items, vec![1, 2, 3, 4, 5]);
+	}
+
+	// A next page that doesn't move forward would otherwise be fetched forever.
+	#[tokio::test]
+	async fn list_all_stops_when_the_next_page_does_not_advance() {
+		let url = serve(|query| match query["page"].as_str() {
+			"1" => page(&[1], Some(2)),
+			_ => page(&[2], Some(1)),
+		})
+		.await;
+
+		let items = list_all::<u32>(&HttpClient::new(reqwest::Client::new()), &url, "key", "servers").await.unwrap();
+
+		assert_eq!(items, vec![1, 2]);
+	}
+
+	#[tokio::test]
+	async fn list_all_fails_on_items_it_cannot_decode() {
+		let url = serve(|_| json!({ "servers": [{ "id": 1 }], "meta": { "pagination": { "next_page": null } } })).await;
+
+		let result = list_all::<u32>(&HttpClient::new(reqwest::Client::new()), &url, "key", "servers").await;
+
+		assert!(matches!(result, Err(ProviderError::Decode(_))));
+	}
+}
@@ -0,0 +1,286 @@
+use async_trait::async_trait;
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::config::config::DEFAULT_ACCOUNT;
+use crate::providers::error::ProviderError;
+use crate::providers::http::HttpClient;
+use crate::providers::provider::{parse_timestamp, CloudProvider, InstanceSpec, ProviderInstance, ProviderVolume};
+use crate::providers::wait::{WaitError, WaitOptions};
+
+use super::action::{wait_for_action, Action, ActionResponse};
+use super::models::request::instance::{Instance, InstanceBuilder, InstanceType};
+use super::models::request::region::Region;
+use super::pages::list_all;
+
+pub const HETZNER_API_URL: &str = "https://api.hetzner.cloud/v1";
+
+#[derive(Deserialize)]
+struct ServerResponse {
+	server: Instance,
+	// Only set when the server is created.
+	action: Option<Action>,
+}
+
+#[derive(Deserialize)]
+struct Volume {
+	id: u64,
+	name: String,
+	size: u64,
+	server: Option<u64>,
+	location: VolumeLocation,
+	created: String,
+}
+
+#[derive(Deserialize)]
+struct VolumeLocation {
+	name: String,
+}
+
+pub struct Hetzner {
+	client: HttpClient,
+	api_key: String,
+	base_url: String,
+	account: String,
+	plan: Option<InstanceType>,
+	image: Option<String>,
+}
+
+impl Hetzner {
+	pub fn new(client: HttpClient, api_key: String) -> Self {
+		Self {
+			client,
+			api_key,
+			base_url: HETZNER_API_URL.to_string(),
+			account: DEFAULT_ACCOUNT.to_string(),
+			plan: None,
+			image: None,
+		}
+	}
+
+	// Points the client at another API, e.g. the fake cloud used for local testing.
+	pub fn base_url(mut self, base_url: String) -> Self {
+		self.base_url = base_url.trim_end_matches('/').to_string();
+		self
+	}
+
+	pub fn account(mut self, account: String) -> Self {
+		self.account = account;
+		self
+	}
+
+	// Used for rules that don't name a server type or image themselves.
+	pub fn plan(mut self, plan: InstanceType) -> Self {
+		self.plan = Some(plan);
+		self
+	}
+
+	pub fn image(mut self, image: String) -> Self {
+		self.image = Some(image);
+		self
+	}
+
+	// Runs a server or volume action and waits for it to finish.
+	async fn action(&self, resource: &str, id: &str, action: &str) -> Result<(), ProviderError> {
+		let response = self
+			.client
+			.post(format!(
+				"{}/{}/{}/actions/{}",
+				self.base_url, resource, id, action
+			))
+			.bearer_auth(&self.api_key)
+			.send()
+			.await?
+			.json::<ActionResponse>()
+			.await?;
+
+		wait_for_action(
+			&self.client,
+			&self.base_url,
+			&self.api_key,
+			response.action.id,
+			&WaitOptions::default(),
+		)
+		.await?;
+
+		Ok(())
+	}
+
+	async fn server(&self, instance_id: &str) -> Result<Instance, ProviderError> {
+		let response = self
+			.client
+			.get(format!("{}/servers/{}", self.base_url, instance_id))
+			.bearer_auth(&self.api_key)
+			.send()
+			.await?
+			.json::<ServerResponse>()
+			.await?;
+
+		Ok(response.server)
+	}
+}
+
+impl From<&Instance> for ProviderInstance {
+	fn from(instance: &Instance) -> Self {
+		ProviderInstance {
+			id: instance.id.to_string(),
+			provider: ProviderKind::Hetzner,
+			account: DEFAULT_ACCOUNT.to_string(),
+			region: instance.datacenter.location.name.clone(),
+			plan: instance.server_type.name.clone(),
+			status: format!("{:?}", instance.status).to_lowercase(),
+			state: (&instance.status).into(),
+			label: instance.name.clone(),
+			main_ip: instance.public_net.ipv4.as_ref().map(|ipv4| ipv4.ip.clone()),
+			created_at: parse_timestamp(&instance.created),
+			pending_action: None,
+			labels: instance.labels.clone().into_iter().collect(),
+		}
+	}
+}
+
+#[async_trait]
+impl CloudProvider for Hetzner {
+	fn kind(&self) -> ProviderKind {
+		ProviderKind::Hetzner
+	}
+
+	fn account(&self) -> &str {
+		&self.account
+	}
+
+	fn plan(&self) -> Option<String> {
+		self.plan.as_ref().map(InstanceType::code)
+	}
+
+	fn regions(&self) -> Vec<String> {
+		Region::list().iter().map(Region::code).collect()
+	}
+
+	fn parse_region(&self, region: &str) -> Result<String, ProviderError> {
+		Region::from_code(region)
+			.map(|region| region.code())
+			.map_err(|e| ProviderError::Invalid(format!("{}: {}", e, region)))
+	}
+
+	async fn list(&self) -> Result<Vec<ProviderInstance>, ProviderError> {
+		let instances =
+			list_all::<Instance>(&self.client, &format!("{}/servers", self.base_url), &self.api_key, "servers")
+				.await?;
+
+		Ok(instances
+			.iter()
+			.map(|instance| ProviderInstance::from(instance).with_account(&self.account))
+			.collect())
+	}
+
+	async fn create(&self, spec: &InstanceSpec) -> Result<ProviderInstance, ProviderError> {
+		let region = Region::from_code(&spec.region)
+			.map_err(|e| ProviderError::Invalid(format!("{}: {}", e, spec.region)))?;
+
+		let server_type = match &spec.plan {
+			Some(code) => InstanceType::from_code(code)
+				.map_err(|e| ProviderError::Invalid(format!("{}: {}", e, code)))?,
+			None => self.plan.clone().ok_or_else(|| {
+				ProviderError::Invalid("no Hetzner server type set on the rule or account".to_string())
+			})?,
+		};
+		let image = spec.image.clone().or_else(|| self.image.clone()).ok_or_else(|| {
+			ProviderError::Invalid("no Hetzner image set on the rule or account".to_string())
+		})?;
+
+		// Server names must be unique per project.
+		let name = format!("infralink-{}-{:08x}", region.code(), rand::thread_rng().gen::<u32>());
+
+		let mut builder = InstanceBuilder::new()
+			.region(region.clone())
+			.location(region)
+			.name(name)
+			.server_type(server_type)
+			.image(image)
+			.labels(spec.labels.clone().into_iter().collect());
+
+		if !spec.ssh_keys.is_empty() {
+			builder = builder.ssh_keys(spec.ssh_keys.clone());
+		}
+		if let Some(user_data) = &spec.user_data {
+			builder = builder.user_data(user_data.clone());
+		}
+
+		let response = self
+			.client
+			.post(format!("{}/servers", self.base_url))
+			.bearer_auth(&self.api_key)
+			.json(&builder)
+			.send()
+			.await?
+			.json::<ServerResponse>()
+			.await?;
+
+		let mut instance = ProviderInstance::from(&response.server).with_account(&self.account);
+		instance.pending_action = response.action.map(|action| action.id.to_string());
+
+		Ok(instance)
+	}
+
+	async fn wait_until_created(
+		&self,
+		instance: &ProviderInstance,
+		options: &WaitOptions,
+	) -> Result<ProviderInstance, WaitError> {
+		if let Some(action_id) = instance.pending_action.as_ref().and_then(|id| id.parse().ok()) {
+			wait_for_action(&self.client, &self.base_url, &self.api_key, action_id, options).await?;
+		}
+
+		let server = self.server(&instance.id).await.map_err(WaitError::Poll)?;
+
+		Ok(ProviderInstance::from(&server).with_account(&self.account))
+	}
+
+	async fn start(&self, instance_id: &str) -> Result<(), ProviderError> {
+		self.action("servers", instance_id, "poweron").await
+	}
+
+	async fn halt(&self, instance_id: &str) -> Result<(), ProviderError> {
+		self.action("servers", instance_id, "shutdown").await
+	}
+
+	async fn delete(&self, instance_id: &str) -> Result<(), ProviderError> {
+		self.client
+			.delete(format!("{}/servers/{}", self.base_url, instance_id))
+			.bearer_auth(&self.api_key)
+			.send()
+			.await?;
+
+		Ok(())
+	}
+
+	async fn reboot(&self, instance_id: &str) -> Result<(), ProviderError> {
+		self.action("servers", instance_id, "reboot").await
+	}
+
+	async fn volumes(&self) -> Result<Vec<ProviderVolume>, ProviderError> {
+		let volumes =
+			list_all::<Volume>(&self.client, &format!("{}/volumes", self.base_url), &self.api_key, "volumes")
+				.await?;
+
+		Ok(volumes
+			.into_iter()
+			.map(|volume| ProviderVolume {
+				id: volume.id.to_string(),
+				provider: ProviderKind::Hetzner,
+				account: self.account.clone(),
+				name: volume.name,
+				region: volume.location.name,
+				size_gb: volume.size,
+				attached_to: volume.server.map(|id| id.to_string()),
+				created_at: parse_timestamp(&volume.created),
+			})
+			.collect())
+	}
+
+	async fn detach_volume(&self, volume_id: &str) -> Result<(), ProviderError> {
+		self.action("volumes", volume_id, "detach").await
+	}
+}
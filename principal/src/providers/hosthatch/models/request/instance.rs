@@ -0,0 +1,132 @@
+use models::models::instance_state::InstanceState;
+use serde::{Deserialize, Serialize};
+
+use super::plan::Plan;
+use super::region::Region;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum InstanceStatus {
+	Pending,
+	Active,
+	Suspended,
+	Cancelled,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum PowerStatus {
+	Running,
+	Stopped,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Instance {
+	pub id: u64,
+	pub hostname: String,
+	pub label: Option<String>,
+	pub product: Plan,
+	pub location: Region,
+	pub image: Option<String>,
+	pub status: InstanceStatus,
+	pub power_status: PowerStatus,
+	pub ipv4: Option<String>,
+	pub ipv6: Option<String>,
+	pub created_at: String,
+}
+
+impl Instance {
+	// Servers only report a usable power state once provisioning has finished.
+	pub fn state(&self) -> String {
+		match (&self.status, &self.power_status) {
+			(InstanceStatus::Active, PowerStatus::Running) => "running".to_string(),
+			(InstanceStatus::Active, PowerStatus::Stopped) => "stopped".to_string(),
+			(InstanceStatus::Cancelled, _) => "terminated".to_string(),
+			(status, _) => format!("{:?}", status).to_lowercase(),
+		}
+	}
+
+	pub fn instance_state(&self) -> InstanceState {
+		match (&self.status, &self.power_status) {
+			(InstanceStatus::Pending, _) => InstanceState::Starting,
+			(InstanceStatus::Active, PowerStatus::Running) => InstanceState::Running,
+			(InstanceStatus::Active, PowerStatus::Stopped) => InstanceState::Stopped,
+			(InstanceStatus::Suspended, _) => InstanceState::Stopped,
+			(InstanceStatus::Cancelled, _) => InstanceState::Terminated,
+		}
+	}
+}
+
+// Body of `POST /servers`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct InstanceBuilder {
+	pub product: Plan,
+	pub location: Region,
+	pub image: Option<String>,
+	pub hostname: Option<String>,
+	pub label: Option<String>,
+	pub ssh_keys: Option<Vec<String>>,
+	pub user_data: Option<String>,
+	pub backups: Option<bool>,
+}
+
+impl Default for InstanceBuilder {
+	fn default() -> Self {
+		InstanceBuilder {
+			product: Plan::Unknown,
+			location: Region::Unknown,
+			image: None,
+			hostname: None,
+			label: None,
+			ssh_keys: None,
+			user_data: None,
+			backups: None,
+		}
+	}
+}
+
+impl InstanceBuilder {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn product(mut self, product: Plan) -> Self {
+		self.product = product;
+		self
+	}
+
+	pub fn location(mut self, location: Region) -> Self {
+		self.location = location;
+		self
+	}
+
+	pub fn image(mut self, image: String) -> Self {
+		self.image = Some(image);
+		self
+	}
+
+	pub fn hostname(mut self, hostname: String) -> Self {
+		self.hostname = Some(hostname);
+		self
+	}
+
+	pub fn label(mut self, label: String) -> Self {
+		self.label = Some(label);
+		self
+	}
+
+	pub fn ssh_keys(mut self, ssh_keys: Vec<String>) -> Self {
+		self.ssh_keys = Some(ssh_keys);
+		self
+	}
+
+	pub fn user_data(mut self, user_data: String) -> Self {
+		self.user_data = Some(user_data);
+		self
+	}
+
+	pub fn backups(mut self, backups: bool) -> Self {
+		self.backups = Some(backups);
+		self
+	}
+}
@@ -0,0 +1,3 @@
+pub mod instance;
+pub mod plan;
+pub mod region;
@@ -0,0 +1,131 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+// HostHatch sells NVMe compute plans and large-disk storage plans.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Plan {
+	Nvme(Compute),
+	Storage(Compute),
+	Unknown,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Compute {
+	pub vcpu: u16,
+	pub ram: u32,
+	pub disk: u32,
+}
+
+impl Plan {
+	fn nvme_plans() -> Vec<Self> {
+		vec![
+			Plan::Nvme(Compute {
+				vcpu: 1,
+				ram: 2048,
+				disk: 20,
+			}),
+			Plan::Nvme(Compute {
+				vcpu: 2,
+				ram: 4096,
+				disk: 40,
+			}),
+			Plan::Nvme(Compute {
+				vcpu: 4,
+				ram: 8192,
+				disk: 80,
+			}),
+			Plan::Nvme(Compute {
+				vcpu: 4,
+				ram: 16384,
+				disk: 160,
+			}),
+			Plan::Nvme(Compute {
+				vcpu: 8,
+				ram: 32768,
+				disk: 320,
+			}),
+			Plan::Nvme(Compute {
+				vcpu: 12,
+				ram: 49152,
+				disk: 480,
+			}),
+			Plan::Nvme(Compute {
+				vcpu: 16,
+				ram: 65536,
+				disk: 640,
+			}),
+		]
+	}
+
+	fn storage_plans() -> Vec<Self> {
+		vec![
+			Plan::Storage(Compute {
+				vcpu: 1,
+				ram: 1024,
+				disk: 250,
+			}),
+			Plan::Storage(Compute {
+				vcpu: 1,
+				ram: 2048,
+				disk: 1024,
+			}),
+			Plan::Storage(Compute {
+				vcpu: 2,
+				ram: 4096,
+				disk: 2048,
+			}),
+			Plan::Storage(Compute {
+				vcpu: 3,
+				ram: 8192,
+				disk: 4096,
+			}),
+		]
+	}
+
+	pub fn list() -> Vec<Self> {
+		let mut plans = Self::nvme_plans();
+		plans.extend(Self::storage_plans());
+		plans
+	}
+
+	// Plans are named after their RAM (NVMe) or disk (storage) size, e.g. `nvme-4gb`.
+	pub fn code(&self) -> String {
+		match self {
+			Plan::Nvme(compute) => format!("nvme-{}gb", compute.ram / 1024),
+			Plan::Storage(compute) => {
+				if compute.disk >= 1024 {
+					format!("storage-{}tb", compute.disk / 1024)
+				} else {
+					format!("storage-{}gb", compute.disk)
+				}
+			}
+			Plan::Unknown => String::new(),
+		}
+	}
+
+	pub fn from_code(code: &str) -> Result<Self, &'static str> {
+		Self::list()
+			.into_iter()
+			.find(|plan| plan.code() == code)
+			.ok_or("Unknown plan")
+	}
+}
+
+impl Serialize for Plan {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		serializer.serialize_str(&self.code())
+	}
+}
+
+impl<'de> Deserialize<'de> for Plan {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		let code = String::deserialize(deserializer)?;
+
+		Plan::from_code(&code).map_err(serde::de::Error::custom)
+	}
+}
@@ -0,0 +1,114 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Region {
+	Amsterdam,
+	Chicago,
+	HongKong,
+	London,
+	LosAngeles,
+	NewYork,
+	Oslo,
+	Singapore,
+	Stockholm,
+	Sydney,
+	Vienna,
+	Zurich,
+	Unknown,
+}
+
+impl fmt::Display for Region {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Region::Amsterdam => write!(f, "Amsterdam"),
+			Region::Chicago => write!(f, "Chicago"),
+			Region::HongKong => write!(f, "Hong Kong"),
+			Region::London => write!(f, "London"),
+			Region::LosAngeles => write!(f, "Los Angeles"),
+			Region::NewYork => write!(f, "New York"),
+			Region::Oslo => write!(f, "Oslo"),
+			Region::Singapore => write!(f, "Singapore"),
+			Region::Stockholm => write!(f, "Stockholm"),
+			Region::Sydney => write!(f, "Sydney"),
+			Region::Vienna => write!(f, "Vienna"),
+			Region::Zurich => write!(f, "Zurich"),
+			Region::Unknown => write!(f, "Unknown"),
+		}
+	}
+}
+
+impl Region {
+	pub fn list() -> Vec<Self> {
+		vec![
+			Region::Amsterdam,
+			Region::Chicago,
+			Region::HongKong,
+			Region::London,
+			Region::LosAngeles,
+			Region::NewYork,
+			Region::Oslo,
+			Region::Singapore,
+			Region::Stockholm,
+			Region::Sydney,
+			Region::Vienna,
+			Region::Zurich,
+		]
+	}
+
+	pub fn code(&self) -> String {
+		match self {
+			Region::Amsterdam => "ams".to_string(),
+			Region::Chicago => "chi".to_string(),
+			Region::HongKong => "hkg".to_string(),
+			Region::London => "lon".to_string(),
+			Region::LosAngeles => "lax".to_string(),
+			Region::NewYork => "nyc".to_string(),
+			Region::Oslo => "osl".to_string(),
+			Region::Singapore => "sgp".to_string(),
+			Region::Stockholm => "sto".to_string(),
+			Region::Sydney => "syd".to_string(),
+			Region::Vienna => "vie".to_string(),
+			Region::Zurich => "zrh".to_string(),
+			Region::Unknown => "Unknown".to_string(),
+		}
+	}
+
+	pub fn from_code(code: &str) -> Result<Self, &'static str> {
+		match code {
+			"ams" => Ok(Region::Amsterdam),
+			"chi" => Ok(Region::Chicago),
+			"hkg" => Ok(Region::HongKong),
+			"lon" => Ok(Region::London),
+			"lax" => Ok(Region::LosAngeles),
+			"nyc" => Ok(Region::NewYork),
+			"osl" => Ok(Region::Oslo),
+			"sgp" => Ok(Region::Singapore),
+			"sto" => Ok(Region::Stockholm),
+			"syd" => Ok(Region::Sydney),
+			"vie" => Ok(Region::Vienna),
+			"zrh" => Ok(Region::Zurich),
+			_ => Err("Unknown region code"),
+		}
+	}
+}
+
+impl Serialize for Region {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		serializer.serialize_str(&self.code())
+	}
+}
+
+impl<'de> Deserialize<'de> for Region {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		let code = String::deserialize(deserializer)?;
+
+		Region::from_code(&code).map_err(serde::de::Error::custom)
+	}
+}
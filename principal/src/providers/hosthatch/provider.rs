@@ -0,0 +1,341 @@
+use async_trait::async_trait;
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+use models::models::instance_state::InstanceState;
+use serde::Deserialize;
+
+use crate::config::config::DEFAULT_ACCOUNT;
+use crate::providers::error::ProviderError;
+use crate::providers::http::HttpClient;
+use crate::providers::provider::{
+	label_tags, parse_label_tags, parse_timestamp, CloudProvider, InstanceSpec, ProviderInstance, ProviderVolume,
+};
+use crate::providers::wait::{wait_until, Progress, WaitError, WaitOptions};
+
+use super::models::request::instance::{Instance, InstanceBuilder};
+use super::models::request::plan::{Compute, Plan};
+use super::models::request::region::Region;
+
+const HOSTHATCH_API_URL: &str = "https://cloud.hosthatch.com/api/v1";
+
+#[derive(Deserialize)]
+struct ServersResponse {
+	servers: Vec<Instance>,
+}
+
+#[derive(Deserialize)]
+struct ServerResponse {
+	server: Instance,
+}
+
+pub struct HostHatch {
+	client: HttpClient,
+	api_key: String,
+	base_url: String,
+	plan: Plan,
+	image: String,
+	account: String,
+}
+
+impl HostHatch {
+	// Defaults to the smallest NVMe plan, which is what we keep pre-warmed.
+	pub fn new(client: HttpClient, api_key: String) -> Self {
+		Self {
+			client,
+			api_key,
+			base_url: HOSTHATCH_API_URL.to_string(),
+			plan: Plan::Nvme(Compute {
+				vcpu: 1,
+				ram: 2048,
+				disk: 20,
+			}),
+			image: "ubuntu-22.04".to_string(),
+			account: DEFAULT_ACCOUNT.to_string(),
+		}
+	}
+
+	pub fn base_url(mut self, base_url: String) -> Self {
+		self.base_url = base_url.trim_end_matches('/').to_string();
+		self
+	}
+
+	pub fn plan(mut self, plan: Plan) -> Self {
+		self.plan = plan;
+		self
+	}
+
+	pub fn image(mut self, image: String) -> Self {
+		self.image = image;
+		self
+	}
+
+	pub fn account(mut self, account: String) -> Self {
+		self.account = account;
+		self
+	}
+
+	async fn server(&self, instance_id: &str) -> Result<Instance, ProviderError> {
+		let response = self
+			.client
+			.get(format!("{}/servers/{}", self.base_url, instance_id))
+			.bearer_auth(&self.api_key)
+			.send()
+			.await?
+			.json::<ServerResponse>()
+			.await?;
+
+		Ok(response.server)
+	}
+
+	async fn server_action(&self, action: &str, instance_id: &str) -> Result<(), ProviderError> {
+		self.client
+			.post(format!(
+				"{}/servers/{}/{}",
+				self.base_url, instance_id, action
+			))
+			.bearer_auth(&self.api_key)
+			.send()
+			.await?;
+
+		Ok(())
+	}
+}
+
+impl From<&Instance> for ProviderInstance {
+	fn from(instance: &Instance) -> Self {
+		ProviderInstance {
+			id: instance.id.to_string(),
+			provider: ProviderKind::HostHatch,
+			account: DEFAULT_ACCOUNT.to_string(),
+			region: instance.location.code(),
+			plan: instance.product.code(),
+			status: instance.state(),
+			state: instance.instance_state(),
+			label: instance
+				.label
+				.clone()
+				.unwrap_or_else(|| instance.hostname.clone()),
+			main_ip: instance.ipv4.clone(),
+			created_at: parse_timestamp(&instance.created_at),
+			pending_action: None,
+			labels: parse_label_tags(instance.label.iter().flat_map(|label| label.split(','))),
+		}
+	}
+}
+
+#[async_trait]
+impl CloudProvider for HostHatch {
+	fn kind(&self) -> ProviderKind {
+		ProviderKind::HostHatch
+	}
+
+	fn account(&self) -> &str {
+		&self.account
+	}
+
+	fn regions(&self) -> Vec<String> {
+		Region::list().iter().map(Region::code).collect()
+	}
+
+	fn parse_region(&self, region: &str) -> Result<String, ProviderError> {
+		Region::from_code(region)
+			.map(|region| region.code())
+			.map_err(|e| ProviderError::Invalid(format!("{}: {}", e, region)))
+	}
+
+	async fn list(&self) -> Result<Vec<ProviderInstance>, ProviderError> {
+		let response = self
+			.client
+			.get(format!("{}/servers", self.base_url))
+			.bearer_auth(&self.api_key)
+			.send()
+			.await?
+			.json::<ServersResponse>()
+			.await?;
+
+		Ok(response
+			.servers
+			.iter()
+			.map(|instance| ProviderInstance::from(instance).with_account(&self.account))
+			.collect())
+	}
+
+	// HostHatch has no labels, so they go in the server's label as comma separated tags.
+	async fn create(&self, spec: &InstanceSpec) -> Result<ProviderInstance, ProviderError> {
+		let region = Region::from_code(&spec.region)
+			.map_err(|e| ProviderError::Invalid(format!("{}: {}", e, spec.region)))?;
+
+		let plan = match &spec.plan {
+			Some(code) => Plan::from_code(code)
+				.map_err(|e| ProviderError::Invalid(format!("{}: {}", e, code)))?,
+			None => self.plan.clone(),
+		};
+
+		let mut builder = InstanceBuilder::new()
+			.product(plan)
+			.location(region)
+			.image(spec.image.clone().unwrap_or_else(|| self.image.clone()));
+
+		if !spec.ssh_keys.is_empty() {
+			builder = builder.ssh_keys(spec.ssh_keys.clone());
+		}
+		if let Some(user_data) = &spec.user_data {
+			builder = builder.user_data(user_data.clone());
+		}
+		if !spec.labels.is_empty() {
+			builder = builder.label(label_tags(&spec.labels).join(","));
+		}
+
+		let response = self
+			.client
+			.post(format!("{}/servers", self.base_url))
+			.bearer_auth(&self.api_key)
+			.json(&builder)
+			.send()
+			.await?
+			.json::<ServerResponse>()
+			.await?;
+
+		Ok(ProviderInstance::from(&response.server).with_account(&self.account))
+	}
+
+	// Servers come back `pending` until HostHatch has provisioned them.
+	async fn wait_until_created(
+		&self,
+		instance: &ProviderInstance,
+		options: &WaitOptions,
+	) -> Result<ProviderInstance, WaitError> {
+		let server = wait_until(options, || async {
+			let server = self.server(&instance.id).await?;
+
+			Ok(match server.instance_state() {
+				InstanceState::Running | InstanceState::Stopped => Progress::Done(server),
+				InstanceState::Terminated => Progress::Failed(format!("server {} was cancelled", instance.id)),
+				_ => Progress::Pending,
+			})
+		})
+		.await?;
+
+		Ok(ProviderInstance::from(&server).with_account(&self.account))
+	}
+
+	async fn start(&self, instance_id: &str) -> Result<(), ProviderError> {
+		self.server_action("boot", instance_id).await
+	}
+
+	async fn halt(&self, instance_id: &str) -> Result<(), ProviderError> {
+		self.server_action("shutdown", instance_id).await
+	}
+
+	// Cancels the server immediately rather than at the end of the billing period.
+	async fn delete(&self, instance_id: &str) -> Result<(), ProviderError> {
+		self.client
+			.delete(format!("{}/servers/{}", self.base_url, instance_id))
+			.bearer_auth(&self.api_key)
+			.query(&[("immediate", "true")])
+			.send()
+			.await?;
+
+		Ok(())
+	}
+
+	async fn reboot(&self, instance_id: &str) -> Result<(), ProviderError> {
+		self.server_action("reboot", instance_id).await
+	}
+
+	// HostHatch has no block storage API; disk space comes with the plan.
+	async fn volumes(&self) -> Result<Vec<ProviderVolume>, ProviderError> {
+		Ok(Vec::new())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use std::time::Duration;
+
+	use serde_json::{json, Value};
+
+	use super::*;
+	use crate::providers::test_server::serve;
+
+	fn hosthatch(url: String) -> HostHatch {
+		HostHatch::new(HttpClient::new(reqwest::Client::new()), "key".to_string()).base_url(url)
+	}
+
+	fn server(status: &str) -> Value {
+		json!({
+			"id": 42,
+			"hostname": "worker-1",
+			"label": "infralink-rule=7,canary",
+			"product": "nvme-2gb",
+			"location": "ams",
+			"image": "ubuntu-22.04",
+			"status": status,
+			"power_status": "running",
+			"ipv4": "192.0.2.10",
+			"ipv6": null,
+			"created_at": "2023-05-01T12:00:00Z"
+		})
+	}
+
+	fn options() -> WaitOptions {
+		WaitOptions {
+			timeout: Duration::from_secs(5),
+			initial_delay: Duration::from_millis(1),
+			max_delay: Duration::from_millis(1),
+		}
+	}
+
+	#[tokio::test]
+	async fn list_reads_labels_back_from_the_server_label() {
+		let url = serve(|_| json!({ "servers": [server("active")] })).await;
+
+		let instances = hosthatch(url).account("eu".to_string()).list().await.unwrap();
+
+		assert_eq!(instances.len(), 1);
+		let instance = &instances[0];
+		assert_eq!((instance.id.as_str(), instance.account.as_str()), ("42", "eu"));
+		assert_eq!((instance.region.as_str(), instance.plan.as_str()), ("ams", "nvme-2gb"));
+		assert_eq!(instance.state, InstanceState::Running);
+		assert_eq!(instance.main_ip.as_deref(), Some("192.0.2.10"));
+		assert_eq!(instance.rule_id(), Some(7));
+		assert_eq!(instance.labels.get("canary").map(String::as_str), Some(""));
+	}
+
+	#[tokio::test]
+	async fn wait_until_created_polls_until_the_server_is_provisioned() {
+		static POLLS: AtomicUsize = AtomicUsize::new(0);
+		let url = serve(|_| match POLLS.fetch_add(1, Ordering::SeqCst) {
+			0 | 1 => json!({ "server": server("pending") }),
+			_ => json!({ "server": server("active") }),
+		})
+		.await;
+		let hosthatch = hosthatch(url);
+		let pending = ProviderInstance::from(&serde_json::from_value::<Instance>(server("pending")).unwrap());
+		assert_eq!(pending.state, InstanceState::Starting);
+
+		let created = hosthatch.wait_until_created(&pending, &options()).await.unwrap();
+
+		assert_eq!(created.state, InstanceState::Running);
+		assert_eq!(POLLS.load(Ordering::SeqCst), 3);
+	}
+
+	#[tokio::test]
+	async fn wait_until_created_fails_on_cancelled_servers() {
+		let url = serve(|_| json!({ "server": server("cancelled") })).await;
+		let hosthatch = hosthatch(url);
+		let pending = ProviderInstance::from(&serde_json::from_value::<Instance>(server("pending")).unwrap());
+
+		let result = hosthatch.wait_until_created(&pending, &options()).await;
+
+		assert!(matches!(result, Err(WaitError::Failed(_))));
+	}
+
+	#[test]
+	fn parse_region_only_takes_hosthatch_locations() {
+		let hosthatch = hosthatch(String::new());
+
+		assert_eq!(hosthatch.parse_region("ams").unwrap(), "ams");
+		assert!(matches!(hosthatch.parse_region("fra"), Err(ProviderError::Invalid(_))));
+	}
+}
@@ -0,0 +1,374 @@
+use std::fmt::Display;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rand::Rng;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Client, IntoUrl, Method, Request, RequestBuilder, Response, StatusCode};
+use serde::Serialize;
+use tokio::time::{sleep, Instant};
+
+use crate::providers::error::ProviderError;
+
+// Hetzner sends the unix time at which its bucket is full again, other APIs seconds to wait.
+const RATE_LIMIT_REMAINING: [&str; 2] = ["ratelimit-remaining", "x-ratelimit-remaining"];
+const RATE_LIMIT_RESET: [&str; 2] = ["ratelimit-reset", "x-ratelimit-reset"];
+
+// How often a failed request is sent again, and how long to wait in between.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+	pub max_attempts: u32,
+	pub initial_delay: Duration,
+	pub max_delay: Duration,
+	// Rate limits asking for a longer wait are returned to the caller instead.
+	pub max_rate_limit_wait: Duration,
+}
+
+impl Default for RetryPolicy {
+	fn default() -> Self {
+		RetryPolicy {
+			max_attempts: 4,
+			initial_delay: Duration::from_millis(500),
+			max_delay: Duration::from_secs(10),
+			max_rate_limit_wait: Duration::from_secs(60),
+		}
+	}
+}
+
+// HTTP client for a provider API. Every request goes through `execute`, which waits out rate
+// limits and retries idempotent calls; clones share the connection pool and the rate limit.
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+	client: Client,
+	retry: RetryPolicy,
+	// Set when the provider said the rate limit is used up; requests wait until then.
+	paused_until: Arc<Mutex<Option<Instant>>>,
+}
+
+impl HttpClient {
+	pub fn new(client: Client) -> Self {
+		HttpClient {
+			client,
+			retry: RetryPolicy::default(),
+			paused_until: Arc::new(Mutex::new(None)),
+		}
+	}
+
+	pub fn retry(mut self, retry: RetryPolicy) -> Self {
+		self.retry = retry;
+		self
+	}
+
+	pub fn get(&self, url: impl IntoUrl) -> HttpRequest<'_> {
+		self.request(Method::GET, url)
+	}
+
+	pub fn post(&self, url: impl IntoUrl) -> HttpRequest<'_> {
+		self.request(Method::POST, url)
+	}
+
+	pub fn put(&self, url: impl IntoUrl) -> HttpRequest<'_> {
+		self.request(Method::PUT, url)
+	}
+
+	pub fn patch(&self, url: impl IntoUrl) -> HttpRequest<'_> {
+		self.request(Method::PATCH, url)
+	}
+
+	pub fn delete(&self, url: impl IntoUrl) -> HttpRequest<'_> {
+		self.request(Method::DELETE, url)
+	}
+
+	pub fn request(&self, method: Method, url: impl IntoUrl) -> HttpRequest<'_> {
+		HttpRequest {
+			client: self,
+			builder: self.client.request(method, url),
+		}
+	}
+
+	// Sends the request and turns error statuses into `ProviderError`s. Rate limited requests
+	// were not processed, so they are resent whatever the method; other failures only for
+	// idempotent methods.
+	pub async fn execute(&self, mut request: Request) -> Result<Response, ProviderError> {
+		let idempotent = is_idempotent(request.method());
+		let mut delay = self.retry.initial_delay;
+		let mut attempt = 1;
+
+		loop {
+			self.wait_for_rate_limit().await?;
+
+			// Streaming bodies can't be cloned, so those requests are only sent once.
+			let next = request.try_clone();
+			let error = match self.client.execute(request).await {
+				Ok(response) if response.status().is_success() => {
+					self.observe_rate_limit(response.headers());
+					return Ok(response);
+				}
+				Ok(response) => self.error_for_response(response).await,
+				Err(e) => ProviderError::from(e),
+			};
+
+			let wait = match &error {
+				ProviderError::RateLimited { retry_after: Some(retry_after) } => *retry_after,
+				ProviderError::RateLimited { retry_after: None } => jitter(delay),
+				ProviderError::Transient(_) if idempotent => jitter(delay),
+				_ => return Err(error),
+			};
+
+			request = match next {
+				Some(next) if attempt < self.retry.max_attempts && wait <= self.retry.max_rate_limit_wait => next,
+				_ => return Err(error),
+			};
+
+			sleep(wait).await;
+			delay = (delay * 2).min(self.retry.max_delay);
+			attempt += 1;
+		}
+	}
+
+	// Waits out a pause the provider asked for. Requests paused for longer than
+	// `max_rate_limit_wait` fail right away with the time left, without being sent.
+	async fn wait_for_rate_limit(&self) -> Result<(), ProviderError> {
+		let paused_until = *self.paused_until.lock().unwrap();
+
+		if let Some(paused_until) = paused_until {
+			let left = paused_until.saturating_duration_since(Instant::now());
+			if left > self.retry.max_rate_limit_wait {
+				return Err(ProviderError::RateLimited { retry_after: Some(left) });
+			}
+			sleep(left).await;
+		}
+
+		Ok(())
+	}
+
+	// Pauses all requests when the provider reports the rate limit as used up.
+	fn observe_rate_limit(&self, headers: &HeaderMap) {
+		let exhausted = header_u64(headers, &RATE_LIMIT_REMAINING) == Some(0);
+
+		if let (true, Some(reset)) = (exhausted, rate_limit_reset(headers)) {
+			*self.paused_until.lock().unwrap() = Some(Instant::now() + reset);
+		}
+	}
+
+	// Rate limits keep the wait the provider asked for; only `execute` weighs it against
+	// `max_rate_limit_wait`.
+	async fn error_for_response(&self, response: Response) -> ProviderError {
+		let status = response.status();
+
+		if status == StatusCode::TOO_MANY_REQUESTS {
+			let retry_after = retry_after(response.headers()).or_else(|| rate_limit_reset(response.headers()));
+			if let Some(retry_after) = retry_after {
+				*self.paused_until.lock().unwrap() = Some(Instant::now() + retry_after);
+			}
+
+			return ProviderError::RateLimited { retry_after };
+		}
+
+		let url = response.url().to_string();
+		let body = response.text().await.unwrap_or_default();
+
+		ProviderError::from_status(status, format!("{} {}: {}", status, url, body))
+	}
+}
+
+// A request being built against an `HttpClient`; mirrors the `RequestBuilder` methods the
+// providers use.
+pub struct HttpRequest<'a> {
+	client: &'a HttpClient,
+	builder: RequestBuilder,
+}
+
+impl<'a> HttpRequest<'a> {
+	pub fn bearer_auth(mut self, token: impl Display) -> Self {
+		self.builder = self.builder.bearer_auth(token);
+		self
+	}
+
+	pub fn headers(mut self, headers: HeaderMap) -> Self {
+		self.builder = self.builder.headers(headers);
+		self
+	}
+
+	pub fn json<T: Serialize + ?Sized>(mut self, json: &T) -> Self {
+		self.builder = self.builder.json(json);
+		self
+	}
+
+	pub fn query<T: Serialize + ?Sized>(mut self, query: &T) -> Self {
+		self.builder = self.builder.query(query);
+		self
+	}
+
+	// For requests that have to be altered once built, e.g. signed, before `execute`.
+	pub fn build(self) -> Result<Request, ProviderError> {
+		Ok(self.builder.build()?)
+	}
+
+	pub async fn send(self) -> Result<Response, ProviderError> {
+		self.client.execute(self.builder.build()?).await
+	}
+}
+
+fn is_idempotent(method: &Method) -> bool {
+	matches!(*method, Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS)
+}
+
+// Scales the delay by a random factor in [0.5, 1.5) so concurrent callers don't retry in step.
+fn jitter(delay: Duration) -> Duration {
+	delay.mul_f64(rand::thread_rng().gen_range(0.5..1.5))
+}
+
+fn header_u64(headers: &HeaderMap, names: &[&str]) -> Option<u64> {
+	names
+		.iter()
+		.filter_map(|name| headers.get(*name))
+		.find_map(|value| value.to_str().ok()?.trim().parse().ok())
+}
+
+// Only the delay-seconds form; providers don't send HTTP dates.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+	header_u64(headers, &[RETRY_AFTER.as_str()]).map(Duration::from_secs)
+}
+
+fn rate_limit_reset(headers: &HeaderMap) -> Option<Duration> {
+	let reset = header_u64(headers, &RATE_LIMIT_RESET)?;
+	let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+
+	// Anything past 2001 is a timestamp rather than a number of seconds.
+	if reset > 1_000_000_000 {
+		Some(Duration::from_secs(reset.saturating_sub(now)))
+	} else {
+		Some(Duration::from_secs(reset))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	use hyper::{Body, Response as ServerResponse};
+
+	use super::*;
+	use crate::providers::test_server::serve_response;
+
+	fn client() -> HttpClient {
+		HttpClient::new(Client::new()).retry(RetryPolicy {
+			max_attempts: 3,
+			initial_delay: Duration::from_millis(1),
+			max_delay: Duration::from_millis(4),
+			max_rate_limit_wait: Duration::from_secs(60),
+		})
+	}
+
+	fn respond(status: u16, headers: &[(&str, &str)]) -> ServerResponse<Body> {
+		let mut response = ServerResponse::builder().status(status);
+		for (name, value) in headers {
+			response = response.header(*name, *value);
+		}
+
+		response.body(Body::empty()).unwrap()
+	}
+
+	#[tokio::test]
+	async fn idempotent_requests_are_retried_until_they_succeed() {
+		static CALLS: AtomicUsize = AtomicUsize::new(0);
+		let url = serve_response(|_| match CALLS.fetch_add(1, Ordering::SeqCst) {
+			0 | 1 => respond(500, &[]),
+			_ => respond(200, &[]),
+		})
+		.await;
+
+		assert!(client().get(&url).send().await.is_ok());
+		assert_eq!(CALLS.load(Ordering::SeqCst), 3);
+	}
+
+	#[tokio::test]
+	async fn retries_stop_after_max_attempts() {
+		static CALLS: AtomicUsize = AtomicUsize::new(0);
+		let url = serve_response(|_| {
+			CALLS.fetch_add(1, Ordering::SeqCst);
+			respond(503, &[])
+		})
+		.await;
+
+		let result = client().get(&url).send().await;
+		assert!(matches!(result, Err(ProviderError::Transient(_))));
+		assert_eq!(CALLS.load(Ordering::SeqCst), 3);
+	}
+
+	#[tokio::test]
+	async fn other_requests_are_not_retried_on_server_errors() {
+		static CALLS: AtomicUsize = AtomicUsize::new(0);
+		let url = serve_response(|_| {
+			CALLS.fetch_add(1, Ordering::SeqCst);
+			respond(500, &[])
+		})
+		.await;
+
+		let result = client().post(&url).send().await;
+		assert!(matches!(result, Err(ProviderError::Transient(_))));
+		assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+	}
+
+	#[tokio::test]
+	async fn rate_limited_requests_are_retried_whatever_the_method() {
+		static CALLS: AtomicUsize = AtomicUsize::new(0);
+		let url = serve_response(|_| match CALLS.fetch_add(1, Ordering::SeqCst) {
+			0 => respond(429, &[("retry-after", "0")]),
+			1 => respond(429, &[]),
+			_ => respond(201, &[]),
+		})
+		.await;
+
+		assert!(client().post(&url).send().await.is_ok());
+		assert_eq!(CALLS.load(Ordering::SeqCst), 3);
+	}
+
+	#[tokio::test]
+	async fn long_rate_limits_are_returned_with_the_wait_the_provider_asked_for() {
+		static CALLS: AtomicUsize = AtomicUsize::new(0);
+		let url = serve_response(|_| {
+			CALLS.fetch_add(1, Ordering::SeqCst);
+			respond(429, &[("retry-after", "120")])
+		})
+		.await;
+		let client = client();
+
+		let result = client.get(&url).send().await;
+		assert!(matches!(
+			result,
+			Err(ProviderError::RateLimited { retry_after: Some(wait) }) if wait == Duration::from_secs(120)
+		));
+		assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+
+		// Requests in the meantime aren't sent at all.
+		let result = client.get(&url).send().await;
+		assert!(matches!(
+			result,
+			Err(ProviderError::RateLimited { retry_after: Some(wait) }) if wait > Duration::from_secs(60)
+		));
+		assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+	}
+
+	#[tokio::test]
+	async fn an_exhausted_rate_limit_pauses_requests_until_it_resets() {
+		let url = serve_response(|_| respond(200, &[("ratelimit-remaining", "0"), ("ratelimit-reset", "1")])).await;
+		let client = client();
+
+		client.get(&url).send().await.unwrap();
+		let start = Instant::now();
+		client.get(&url).send().await.unwrap();
+
+		assert!(start.elapsed() >= Duration::from_millis(900));
+	}
+
+	#[test]
+	fn jitter_spreads_delays_around_the_delay() {
+		let delay = Duration::from_millis(100);
+		let delays: Vec<Duration> = (0..1000).map(|_| jitter(delay)).collect();
+
+		assert!(delays.iter().all(|jittered| *jittered >= delay / 2 && *jittered < delay * 3 / 2));
+		assert!(delays.iter().any(|jittered| *jittered != delays[0]));
+	}
+}
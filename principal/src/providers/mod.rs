@@ -1,3 +1,11 @@
+pub mod error;
 pub mod hetzner;
 pub mod hosthatch;
+pub mod http;
+pub mod oracle;
+pub mod provider;
 pub mod vultr;
+pub mod wait;
+
+#[cfg(test)]
+pub mod test_server;
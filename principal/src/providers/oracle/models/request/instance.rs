@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+
+use models::models::instance_state::InstanceState;
+use serde::{Deserialize, Serialize};
+
+use super::region::Region;
+use super::shape::{Compute, Shape};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum LifecycleState {
+	Moving,
+	Provisioning,
+	Running,
+	Starting,
+	Stopping,
+	Stopped,
+	CreatingImage,
+	Terminating,
+	Terminated,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Instance {
+	pub id: String,
+	pub display_name: Option<String>,
+	pub availability_domain: String,
+	pub compartment_id: String,
+	pub region: Region,
+	pub shape: String,
+	pub shape_config: Option<Compute>,
+	pub lifecycle_state: LifecycleState,
+	pub time_created: String,
+	pub image_id: Option<String>,
+	#[serde(default)]
+	pub metadata: HashMap<String, String>,
+	#[serde(default)]
+	pub freeform_tags: HashMap<String, String>,
+}
+
+impl Instance {
+	pub fn shape(&self) -> Shape {
+		Shape::from_code(&self.shape, self.shape_config.clone()).unwrap_or(Shape::Unknown)
+	}
+
+	pub fn instance_state(&self) -> InstanceState {
+		match self.lifecycle_state {
+			LifecycleState::Provisioning | LifecycleState::Starting => InstanceState::Starting,
+			LifecycleState::Running => InstanceState::Running,
+			LifecycleState::Moving | LifecycleState::CreatingImage => InstanceState::Upgrading,
+			LifecycleState::Stopping | LifecycleState::Terminating => InstanceState::Stopping,
+			LifecycleState::Stopped => InstanceState::Stopped,
+			LifecycleState::Terminated => InstanceState::Terminated,
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceDetails {
+	pub source_type: String,
+	pub image_id: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct VnicDetails {
+	pub subnet_id: String,
+	pub assign_public_ip: Option<bool>,
+}
+
+// Body of `POST /instances` (LaunchInstanceDetails).
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceBuilder {
+	pub availability_domain: String,
+	pub compartment_id: String,
+	pub shape: String,
+	pub shape_config: Option<Compute>,
+	pub display_name: Option<String>,
+	pub source_details: SourceDetails,
+	pub create_vnic_details: VnicDetails,
+	pub metadata: HashMap<String, String>,
+	pub freeform_tags: HashMap<String, String>,
+}
+
+impl Default for InstanceBuilder {
+	fn default() -> Self {
+		InstanceBuilder {
+			availability_domain: String::new(),
+			compartment_id: String::new(),
+			shape: Shape::Unknown.code(),
+			shape_config: None,
+			display_name: None,
+			source_details: SourceDetails {
+				source_type: "image".to_string(),
+				image_id: String::new(),
+			},
+			create_vnic_details: VnicDetails {
+				subnet_id: String::new(),
+				assign_public_ip: None,
+			},
+			metadata: HashMap::new(),
+			freeform_tags: HashMap::new(),
+		}
+	}
+}
+
+impl InstanceBuilder {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn availability_domain(mut self, availability_domain: String) -> Self {
+		self.availability_domain = availability_domain;
+		self
+	}
+
+	pub fn compartment_id(mut self, compartment_id: String) -> Self {
+		self.compartment_id = compartment_id;
+		self
+	}
+
+	pub fn shape(mut self, shape: Shape) -> Self {
+		self.shape = shape.code();
+		self.shape_config = shape.compute().cloned();
+		self
+	}
+
+	pub fn display_name(mut self, display_name: String) -> Self {
+		self.display_name = Some(display_name);
+		self
+	}
+
+	pub fn image_id(mut self, image_id: String) -> Self {
+		self.source_details.image_id = image_id;
+		self
+	}
+
+	pub fn subnet_id(mut self, subnet_id: String) -> Self {
+		self.create_vnic_details.subnet_id = subnet_id;
+		self
+	}
+
+	pub fn assign_public_ip(mut self, assign_public_ip: bool) -> Self {
+		self.create_vnic_details.assign_public_ip = Some(assign_public_ip);
+		self
+	}
+
+	pub fn ssh_authorized_keys(mut self, ssh_authorized_keys: Vec<String>) -> Self {
+		self.metadata
+			.insert("ssh_authorized_keys".to_string(), ssh_authorized_keys.join("\n"));
+		self
+	}
+
+	// OCI expects base64 encoded user data.
+	pub fn user_data(mut self, user_data: String) -> Self {
+		self.metadata.insert("user_data".to_string(), user_data);
+		self
+	}
+
+	pub fn freeform_tags(mut self, freeform_tags: HashMap<String, String>) -> Self {
+		self.freeform_tags = freeform_tags;
+		self
+	}
+}
@@ -0,0 +1,3 @@
+pub mod instance;
+pub mod region;
+pub mod shape;
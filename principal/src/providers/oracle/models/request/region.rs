@@ -0,0 +1,207 @@
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+use std::fmt;
+
+// OCI regions, identified by their region identifier (e.g. `eu-frankfurt-1`).
+#[derive(Debug, PartialEq, Clone)]
+pub enum Region {
+	Ashburn,
+	Phoenix,
+	SanJose,
+	Chicago,
+	Toronto,
+	Montreal,
+	SaoPaulo,
+	Frankfurt,
+	Amsterdam,
+	London,
+	Paris,
+	Marseille,
+	Milan,
+	Madrid,
+	Stockholm,
+	Zurich,
+	Tokyo,
+	Osaka,
+	Seoul,
+	Singapore,
+	Mumbai,
+	Sydney,
+	Melbourne,
+	Johannesburg,
+	Unknown,
+}
+
+impl fmt::Display for Region {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Region::Ashburn => write!(f, "Ashburn"),
+			Region::Phoenix => write!(f, "Phoenix"),
+			Region::SanJose => write!(f, "San Jose"),
+			Region::Chicago => write!(f, "Chicago"),
+			Region::Toronto => write!(f, "Toronto"),
+			Region::Montreal => write!(f, "Montreal"),
+			Region::SaoPaulo => write!(f, "Sao Paulo"),
+			Region::Frankfurt => write!(f, "Frankfurt"),
+			Region::Amsterdam => write!(f, "Amsterdam"),
+			Region::London => write!(f, "London"),
+			Region::Paris => write!(f, "Paris"),
+			Region::Marseille => write!(f, "Marseille"),
+			Region::Milan => write!(f, "Milan"),
+			Region::Madrid => write!(f, "Madrid"),
+			Region::Stockholm => write!(f, "Stockholm"),
+			Region::Zurich => write!(f, "Zurich"),
+			Region::Tokyo => write!(f, "Tokyo"),
+			Region::Osaka => write!(f, "Osaka"),
+			Region::Seoul => write!(f, "Seoul"),
+			Region::Singapore => write!(f, "Singapore"),
+			Region::Mumbai => write!(f, "Mumbai"),
+			Region::Sydney => write!(f, "Sydney"),
+			Region::Melbourne => write!(f, "Melbourne"),
+			Region::Johannesburg => write!(f, "Johannesburg"),
+			Region::Unknown => write!(f, "Unknown"),
+		}
+	}
+}
+
+impl Region {
+	pub fn list() -> Vec<Self> {
+		vec![
+			Region::Ashburn,
+			Region::Phoenix,
+			Region::SanJose,
+			Region::Chicago,
+			Region::Toronto,
+			Region::Montreal,
+			Region::SaoPaulo,
+			Region::Frankfurt,
+			Region::Amsterdam,
+			Region::London,
+			Region::Paris,
+			Region::Marseille,
+			Region::Milan,
+			Region::Madrid,
+			Region::Stockholm,
+			Region::Zurich,
+			Region::Tokyo,
+			Region::Osaka,
+			Region::Seoul,
+			Region::Singapore,
+			Region::Mumbai,
+			Region::Sydney,
+			Region::Melbourne,
+			Region::Johannesburg,
+		]
+	}
+
+	pub fn code(&self) -> String {
+		match self {
+			Region::Ashburn => "us-ashburn-1".to_string(),
+			Region::Phoenix => "us-phoenix-1".to_string(),
+			Region::SanJose => "us-sanjose-1".to_string(),
+			Region::Chicago => "us-chicago-1".to_string(),
+			Region::Toronto => "ca-toronto-1".to_string(),
+			Region::Montreal => "ca-montreal-1".to_string(),
+			Region::SaoPaulo => "sa-saopaulo-1".to_string(),
+			Region::Frankfurt => "eu-frankfurt-1".to_string(),
+			Region::Amsterdam => "eu-amsterdam-1".to_string(),
+			Region::London => "uk-london-1".to_string(),
+			Region::Paris => "eu-paris-1".to_string(),
+			Region::Marseille => "eu-marseille-1".to_string(),
+			Region::Milan => "eu-milan-1".to_string(),
+			Region::Madrid => "eu-madrid-1".to_string(),
+			Region::Stockholm => "eu-stockholm-1".to_string(),
+			Region::Zurich => "eu-zurich-1".to_string(),
+			Region::Tokyo => "ap-tokyo-1".to_string(),
+			Region::Osaka => "ap-osaka-1".to_string(),
+			Region::Seoul => "ap-seoul-1".to_string(),
+			Region::Singapore => "ap-singapore-1".to_string(),
+			Region::Mumbai => "ap-mumbai-1".to_string(),
+			Region::Sydney => "ap-sydney-1".to_string(),
+			Region::Melbourne => "ap-melbourne-1".to_string(),
+			Region::Johannesburg => "af-johannesburg-1".to_string(),
+			Region::Unknown => "Unknown".to_string(),
+		}
+	}
+
+	pub fn from_code(code: &str) -> Result<Self, &'static str> {
+		Region::list()
+			.into_iter()
+			.find(|region| region.code() == code)
+			.ok_or("Unknown region code")
+	}
+
+	// Three letter region keys used in instance responses.
+	pub fn from_key(key: &str) -> Result<Self, &'static str> {
+		match key.to_lowercase().as_str() {
+			"iad" => Ok(Region::Ashburn),
+			"phx" => Ok(Region::Phoenix),
+			"sjc" => Ok(Region::SanJose),
+			"ord" => Ok(Region::Chicago),
+			"yyz" => Ok(Region::Toronto),
+			"yul" => Ok(Region::Montreal),
+			"gru" => Ok(Region::SaoPaulo),
+			"fra" => Ok(Region::Frankfurt),
+			"ams" => Ok(Region::Amsterdam),
+			"lhr" => Ok(Region::London),
+			"cdg" => Ok(Region::Paris),
+			"mrs" => Ok(Region::Marseille),
+			"lin" => Ok(Region::Milan),
+			"mad" => Ok(Region::Madrid),
+			"arn" => Ok(Region::Stockholm),
+			"zrh" => Ok(Region::Zurich),
+			"nrt" => Ok(Region::Tokyo),
+			"kix" => Ok(Region::Osaka),
+			"icn" => Ok(Region::Seoul),
+			"sin" => Ok(Region::Singapore),
+			"bom" => Ok(Region::Mumbai),
+			"syd" => Ok(Region::Sydney),
+			"mel" => Ok(Region::Melbourne),
+			"jnb" => Ok(Region::Johannesburg),
+			_ => Err("Unknown region key"),
+		}
+	}
+
+	// Base URL of the core services (compute, block storage) API in this region.
+	pub fn iaas_url(&self) -> String {
+		format!("https://iaas.{}.oraclecloud.com/20160918", self.code())
+	}
+}
+
+impl Serialize for Region {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		serializer.serialize_str(&self.code())
+	}
+}
+
+struct RegionVisitor;
+
+impl<'de> Visitor<'de> for RegionVisitor {
+	type Value = Region;
+
+	fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		formatter.write_str("an OCI region identifier")
+	}
+
+	fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+	where
+		E: de::Error,
+	{
+		// Instances report their region by key (e.g. `fra`) or by identifier.
+		Region::from_code(v)
+			.or_else(|_| Region::from_key(v))
+			.map_err(de::Error::custom)
+	}
+}
+
+impl<'de> Deserialize<'de> for Region {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		deserializer.deserialize_str(RegionVisitor)
+	}
+}
@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+// Compute shapes we launch on OCI. Flex shapes take an explicit OCPU and memory
+// size, fixed shapes come with theirs.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape {
+	StandardE4Flex(Compute), // AMD EPYC (Milan)
+	StandardE3Flex(Compute), // AMD EPYC (Rome)
+	Standard3Flex(Compute),  // Intel Xeon (Ice Lake)
+	StandardA1Flex(Compute), // Ampere Altra (ARM)
+	StandardE2Micro,         // 1/8 OCPU 1GB RAM (Always Free)
+	Unknown,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Compute {
+	pub ocpus: f32,
+	#[serde(rename = "memoryInGBs")]
+	pub memory_in_gbs: f32,
+}
+
+impl Shape {
+	pub fn code(&self) -> String {
+		match self {
+			Shape::StandardE4Flex(_) => "VM.Standard.E4.Flex".to_string(),
+			Shape::StandardE3Flex(_) => "VM.Standard.E3.Flex".to_string(),
+			Shape::Standard3Flex(_) => "VM.Standard3.Flex".to_string(),
+			Shape::StandardA1Flex(_) => "VM.Standard.A1.Flex".to_string(),
+			Shape::StandardE2Micro => "VM.Standard.E2.1.Micro".to_string(),
+			Shape::Unknown => String::new(),
+		}
+	}
+
+	// The `shapeConfig` sent when launching a flex shape.
+	pub fn compute(&self) -> Option<&Compute> {
+		match self {
+			Shape::StandardE4Flex(compute)
+			| Shape::StandardE3Flex(compute)
+			| Shape::Standard3Flex(compute)
+			| Shape::StandardA1Flex(compute) => Some(compute),
+			_ => None,
+		}
+	}
+
+	pub fn from_code(code: &str, compute: Option<Compute>) -> Result<Self, &'static str> {
+		let flex = |shape: fn(Compute) -> Shape| {
+			compute
+				.clone()
+				.map(shape)
+				.ok_or("Flex shapes need an OCPU and memory size")
+		};
+
+		match code {
+			"VM.Standard.E4.Flex" => flex(Shape::StandardE4Flex),
+			"VM.Standard.E3.Flex" => flex(Shape::StandardE3Flex),
+			"VM.Standard3.Flex" => flex(Shape::Standard3Flex),
+			"VM.Standard.A1.Flex" => flex(Shape::StandardA1Flex),
+			"VM.Standard.E2.1.Micro" => Ok(Shape::StandardE2Micro),
+			_ => Err("Unknown shape"),
+		}
+	}
+}
@@ -0,0 +1,421 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+use models::models::instance_state::InstanceState;
+use reqwest::Response;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::config::config::{OracleSettings, DEFAULT_ACCOUNT};
+use crate::providers::error::ProviderError;
+use crate::providers::http::{HttpClient, HttpRequest};
+use crate::providers::provider::{parse_timestamp, CloudProvider, InstanceSpec, ProviderInstance, ProviderVolume};
+use crate::providers::wait::{wait_until, Progress, WaitError, WaitOptions};
+
+use super::models::request::instance::{Instance, InstanceBuilder};
+use super::models::request::region::Region;
+use super::models::request::shape::{Compute, Shape};
+use super::signer::RequestSigner;
+
+// Everything needed to sign requests and launch instances in one compartment.
+pub struct OracleConfig {
+	pub tenancy_id: String,
+	pub user_id: String,
+	pub fingerprint: String,
+	pub private_key_pem: String,
+	pub region: Region,
+	pub compartment_id: String,
+	pub availability_domain: String,
+	pub subnet_id: String,
+	pub image_id: String,
+	pub shape: Shape,
+}
+
+impl OracleConfig {
+	pub fn from_settings(settings: &OracleSettings) -> Result<Self, String> {
+		let required = |key: &str, value: &str| {
+			if value.is_empty() {
+				Err(format!("providers.oracle.{} is not set", key))
+			} else {
+				Ok(value.to_string())
+			}
+		};
+
+		let private_key_path = required("private_key_path", &settings.private_key_path)?;
+		let private_key_pem = fs::read_to_string(&private_key_path)
+			.map_err(|e| format!("failed to read {}: {}", private_key_path, e))?;
+
+		let region = Region::from_code(&required("region", &settings.region)?).map_err(|e| e.to_string())?;
+
+		let shape = Shape::from_code(
+			&settings
+				.shape
+				.clone()
+				.unwrap_or_else(|| Shape::StandardE4Flex(default_compute()).code()),
+			Some(Compute {
+				ocpus: settings.ocpus.unwrap_or(default_compute().ocpus),
+				memory_in_gbs: settings.memory_gb.unwrap_or(default_compute().memory_in_gbs),
+			}),
+		)?;
+
+		Ok(Self {
+			tenancy_id: required("tenancy_id", &settings.tenancy_id)?,
+			user_id: required("user_id", &settings.user_id)?,
+			fingerprint: required("fingerprint", &settings.fingerprint)?,
+			private_key_pem,
+			region,
+			compartment_id: required("compartment_id", &settings.compartment_id)?,
+			availability_domain: required("availability_domain", &settings.availability_domain)?,
+			subnet_id: required("subnet_id", &settings.subnet_id)?,
+			image_id: required("image_id", &settings.image_id)?,
+			shape,
+		})
+	}
+}
+
+fn default_compute() -> Compute {
+	Compute {
+		ocpus: 1.0,
+		memory_in_gbs: 8.0,
+	}
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Volume {
+	id: String,
+	display_name: String,
+	size_in_gbs: u64,
+	time_created: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VolumeAttachment {
+	instance_id: String,
+	volume_id: String,
+	lifecycle_state: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VnicAttachment {
+	instance_id: String,
+	vnic_id: Option<String>,
+	lifecycle_state: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Vnic {
+	#[serde(default)]
+	is_primary: bool,
+	public_ip: Option<String>,
+	private_ip: Option<String>,
+}
+
+pub struct Oracle {
+	client: HttpClient,
+	signer: RequestSigner,
+	config: OracleConfig,
+	// Main address of each attached VNIC by id, `None` for secondary ones. A VNIC keeps its
+	// addresses for as long as it exists, so each is only fetched once.
+	vnic_ips: Mutex<HashMap<String, Option<String>>>,
+}
+
+impl Oracle {
+	pub fn new(client: HttpClient, config: OracleConfig) -> Result<Self, String> {
+		let signer = RequestSigner::new(
+			&config.tenancy_id,
+			&config.user_id,
+			&config.fingerprint,
+			&config.private_key_pem,
+		)?;
+
+		Ok(Self {
+			client,
+			signer,
+			config,
+			vnic_ips: Mutex::new(HashMap::new()),
+		})
+	}
+
+	async fn send(&self, request: HttpRequest<'_>) -> Result<Response, ProviderError> {
+		let mut request = request.build()?;
+		self.signer
+			.sign(&mut request)
+			.map_err(ProviderError::Invalid)?;
+
+		self.client.execute(request).await
+	}
+
+	async fn instance_action(&self, action: &str, instance_id: &str) -> Result<(), ProviderError> {
+		self.send(self.client.post(format!(
+			"{}/instances/{}?action={}",
+			self.config.region.iaas_url(),
+			instance_id,
+			action
+		)))
+		.await?;
+
+		Ok(())
+	}
+
+	// Fetches every page of a list endpoint in the compartment. Oracle returns a bare array and
+	// hands out the token for the next page in the `opc-next-page` header, absent on the last one.
+	async fn list_all<T: DeserializeOwned>(&self, path: &str) -> Result<Vec<T>, ProviderError> {
+		let url = format!("{}/{}", self.config.region.iaas_url(), path);
+		let mut items = Vec::new();
+		let mut next_page: Option<String> = None;
+
+		loop {
+			let mut query = vec![("compartmentId", self.config.compartment_id.as_str())];
+			if let Some(page) = &next_page {
+				query.push(("page", page.as_str()));
+			}
+
+			let response = self.send(self.client.get(&url).query(&query)).await?;
+			next_page = response
+				.headers()
+				.get("opc-next-page")
+				.and_then(|value| value.to_str().ok())
+				.filter(|value| !value.is_empty())
+				.map(str::to_string);
+
+			items.extend(response.json::<Vec<T>>().await?);
+			if next_page.is_none() {
+				return Ok(items);
+			}
+		}
+	}
+
+	// Instances don't carry their addresses, those live on the primary VNIC attached to them.
+	// Prefers the public address, which is the one workers register with.
+	async fn main_ips(&self) -> Result<HashMap<String, String>, ProviderError> {
+		let attachments = self.list_all::<VnicAttachment>("vnicAttachments").await?;
+		let mut main_ips = HashMap::new();
+		let mut attached = HashSet::new();
+
+		for attachment in attachments {
+			let vnic_id = match attachment.vnic_id {
+				Some(vnic_id) if attachment.lifecycle_state == "ATTACHED" => vnic_id,
+				_ => continue,
+			};
+			attached.insert(vnic_id.clone());
+			if main_ips.contains_key(&attachment.instance_id) {
+				continue;
+			}
+
+			let cached = self.vnic_ips.lock().unwrap().get(&vnic_id).cloned();
+			let main_ip = match cached {
+				Some(main_ip) => main_ip,
+				None => {
+					let vnic = self
+						.send(self.client.get(format!("{}/vnics/{}", self.config.region.iaas_url(), vnic_id)))
+						.await?
+						.json::<Vnic>()
+						.await?;
+					let main_ip = vnic.is_primary.then(|| vnic.public_ip.or(vnic.private_ip)).flatten();
+					self.vnic_ips.lock().unwrap().insert(vnic_id, main_ip.clone());
+					main_ip
+				}
+			};
+			if let Some(ip) = main_ip {
+				main_ips.insert(attachment.instance_id, ip);
+			}
+		}
+
+		// Detached VNICs are gone for good.
+		self.vnic_ips.lock().unwrap().retain(|vnic_id, _| attached.contains(vnic_id));
+
+		Ok(main_ips)
+	}
+}
+
+impl From<&Instance> for ProviderInstance {
+	fn from(instance: &Instance) -> Self {
+		ProviderInstance {
+			id: instance.id.clone(),
+			provider: ProviderKind::Oracle,
+			account: DEFAULT_ACCOUNT.to_string(),
+			region: instance.region.code(),
+			plan: instance.shape.clone(),
+			status: format!("{:?}", instance.lifecycle_state).to_lowercase(),
+			state: instance.instance_state(),
+			label: instance.display_name.clone().unwrap_or_default(),
+			main_ip: None,
+			created_at: parse_timestamp(&instance.time_created),
+			pending_action: None,
+			labels: instance.freeform_tags.clone().into_iter().collect(),
+		}
+	}
+}
+
+#[async_trait]
+impl CloudProvider for Oracle {
+	fn kind(&self) -> ProviderKind {
+		ProviderKind::Oracle
+	}
+
+	// Only a single tenancy is supported.
+	fn account(&self) -> &str {
+		DEFAULT_ACCOUNT
+	}
+
+	// Subnets and availability domains are regional, so we only launch in the configured region.
+	fn regions(&self) -> Vec<String> {
+		vec![self.config.region.code()]
+	}
+
+	// Takes region identifiers (`eu-frankfurt-1`) and keys (`fra`), the codes the region catalog uses.
+	fn parse_region(&self, region: &str) -> Result<String, ProviderError> {
+		Region::from_code(region)
+			.or_else(|_| Region::from_key(region))
+			.map(|region| region.code())
+			.map_err(|e| ProviderError::Invalid(format!("{}: {}", e, region)))
+	}
+
+	async fn list(&self) -> Result<Vec<ProviderInstance>, ProviderError> {
+		let instances = self.list_all::<Instance>("instances").await?;
+		let mut main_ips = self.main_ips().await?;
+
+		Ok(instances
+			.iter()
+			.map(|instance| ProviderInstance {
+				main_ip: main_ips.remove(&instance.id),
+				..ProviderInstance::from(instance)
+			})
+			.collect())
+	}
+
+	async fn create(&self, spec: &InstanceSpec) -> Result<ProviderInstance, ProviderError> {
+		let region = Region::from_code(&spec.region)
+			.map_err(|e| ProviderError::Invalid(format!("{}: {}", e, spec.region)))?;
+
+		if region != self.config.region {
+			return Err(ProviderError::Invalid(format!(
+				"Oracle is only configured for region {}",
+				self.config.region.code()
+			)));
+		}
+
+		// Flex shapes named by a rule get the configured OCPU and memory size.
+		let shape = match &spec.plan {
+			Some(code) => Shape::from_code(
+				code,
+				Some(self.config.shape.compute().cloned().unwrap_or_else(default_compute)),
+			)
+			.map_err(|e| ProviderError::Invalid(format!("{}: {}", e, code)))?,
+			None => self.config.shape.clone(),
+		};
+
+		let mut builder = InstanceBuilder::new()
+			.availability_domain(self.config.availability_domain.clone())
+			.compartment_id(self.config.compartment_id.clone())
+			.shape(shape)
+			.image_id(spec.image.clone().unwrap_or_else(|| self.config.image_id.clone()))
+			.subnet_id(self.config.subnet_id.clone())
+			.assign_public_ip(true)
+			.freeform_tags(spec.labels.clone().into_iter().collect());
+
+		if !spec.ssh_keys.is_empty() {
+			builder = builder.ssh_authorized_keys(spec.ssh_keys.clone());
+		}
+		if let Some(user_data) = &spec.user_data {
+			builder = builder.user_data(BASE64.encode(user_data));
+		}
+
+		let instance = self
+			.send(
+				self.client
+					.post(format!("{}/instances", region.iaas_url()))
+					.json(&builder),
+			)
+			.await?
+			.json::<Instance>()
+			.await?;
+
+		Ok(ProviderInstance::from(&instance))
+	}
+
+	// Launches return the instance `PROVISIONING`; polls it until it is running.
+	async fn wait_until_created(
+		&self,
+		instance: &ProviderInstance,
+		options: &WaitOptions,
+	) -> Result<ProviderInstance, WaitError> {
+		let url = format!("{}/instances/{}", self.config.region.iaas_url(), instance.id);
+		let launched = wait_until(options, || async {
+			let launched = self.send(self.client.get(&url)).await?.json::<Instance>().await?;
+
+			Ok(match launched.instance_state() {
+				InstanceState::Running | InstanceState::Stopped => Progress::Done(launched),
+				InstanceState::Stopping | InstanceState::Terminated => {
+					Progress::Failed(format!("instance {} was terminated while launching", instance.id))
+				}
+				_ => Progress::Pending,
+			})
+		})
+		.await?;
+
+		Ok(ProviderInstance::from(&launched))
+	}
+
+	async fn start(&self, instance_id: &str) -> Result<(), ProviderError> {
+		self.instance_action("START", instance_id).await
+	}
+
+	async fn halt(&self, instance_id: &str) -> Result<(), ProviderError> {
+		self.instance_action("SOFTSTOP", instance_id).await
+	}
+
+	// Terminates the instance and its boot volume.
+	async fn delete(&self, instance_id: &str) -> Result<(), ProviderError> {
+		self.send(
+			self.client
+				.delete(format!(
+					"{}/instances/{}",
+					self.config.region.iaas_url(),
+					instance_id
+				))
+				.query(&[("preserveBootVolume", "false")]),
+		)
+		.await?;
+
+		Ok(())
+	}
+
+	async fn reboot(&self, instance_id: &str) -> Result<(), ProviderError> {
+		self.instance_action("SOFTRESET", instance_id).await
+	}
+
+	async fn volumes(&self) -> Result<Vec<ProviderVolume>, ProviderError> {
+		let volumes = self.list_all::<Volume>("volumes").await?;
+		let mut attached_to: HashMap<String, String> = self
+			.list_all::<VolumeAttachment>("volumeAttachments")
+			.await?
+			.into_iter()
+			.filter(|attachment| attachment.lifecycle_state == "ATTACHED")
+			.map(|attachment| (attachment.volume_id, attachment.instance_id))
+			.collect();
+
+		Ok(volumes
+			.into_iter()
+			.map(|volume| ProviderVolume {
+				attached_to: attached_to.remove(&volume.id),
+				id: volume.id,
+				provider: ProviderKind::Oracle,
+				account: DEFAULT_ACCOUNT.to_string(),
+				name: volume.display_name,
+				region: self.config.region.code(),
+				size_gb: volume.size_in_gbs,
+				created_at: parse_timestamp(&volume.time_created),
+			})
+			.collect())
+	}
+}
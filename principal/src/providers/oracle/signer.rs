@@ -0,0 +1,215 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::Utc;
+use reqwest::header::{HeaderValue, AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, DATE, HOST};
+use reqwest::{Method, Request};
+use rsa::pkcs1::DecodeRsaPrivateKey;
+use rsa::pkcs1v15::SigningKey;
+use rsa::pkcs8::DecodePrivateKey;
+use rsa::signature::{SignatureEncoding, Signer};
+use rsa::RsaPrivateKey;
+use sha2::{Digest, Sha256};
+
+// Signs requests with the OCI API key scheme (draft-cavage HTTP signatures with
+// rsa-sha256). See https://docs.oracle.com/iaas/Content/API/Concepts/signingrequests.htm
+pub struct RequestSigner {
+	key_id: String,
+	signing_key: SigningKey<Sha256>,
+}
+
+impl RequestSigner {
+	pub fn new(
+		tenancy_id: &str,
+		user_id: &str,
+		fingerprint: &str,
+		private_key_pem: &str,
+	) -> Result<Self, String> {
+		let private_key = RsaPrivateKey::from_pkcs8_pem(private_key_pem)
+			.or_else(|_| RsaPrivateKey::from_pkcs1_pem(private_key_pem))
+			.map_err(|e| format!("invalid OCI private key: {}", e))?;
+
+		Ok(Self {
+			key_id: format!("{}/{}/{}", tenancy_id, user_id, fingerprint),
+			signing_key: SigningKey::<Sha256>::new(private_key),
+		})
+	}
+
+	// Adds the `date`, body headers and `authorization` header to a built request.
+	pub fn sign(&self, request: &mut Request) -> Result<(), String> {
+		let url = request.url().clone();
+		let host = url.host_str().ok_or("request URL has no host")?.to_string();
+		let date = Utc::now().format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+
+		let mut target = url.path().to_string();
+		if let Some(query) = url.query() {
+			target = format!("{}?{}", target, query);
+		}
+
+		let mut headers = vec![
+			("date", date.clone()),
+			(
+				"(request-target)",
+				format!("{} {}", request.method().as_str().to_lowercase(), target),
+			),
+			("host", host.clone()),
+		];
+
+		// PUT and POST also sign the body, even when it is empty.
+		let signs_body = matches!(*request.method(), Method::POST | Method::PUT | Method::PATCH);
+		if signs_body {
+			let body = request
+				.body()
+				.and_then(|body| body.as_bytes())
+				.unwrap_or_default()
+				.to_vec();
+			let content_sha256 = BASE64.encode(Sha256::digest(&body));
+
+			headers.push(("content-length", body.len().to_string()));
+			headers.push(("content-type", "application/json".to_string()));
+			headers.push(("x-content-sha256", content_sha256.clone()));
+
+			let request_headers = request.headers_mut();
+			request_headers.insert(CONTENT_LENGTH, header_value(&body.len().to_string())?);
+			request_headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+			request_headers.insert("x-content-sha256", header_value(&content_sha256)?);
+		}
+
+		let signing_string = headers
+			.iter()
+			.map(|(name, value)| format!("{}: {}", name, value))
+			.collect::<Vec<_>>()
+			.join("\n");
+		let signature = BASE64.encode(self.signing_key.sign(signing_string.as_bytes()).to_bytes());
+
+		let authorization = format!(
+			"Signature version=\"1\",keyId=\"{}\",algorithm=\"rsa-sha256\",headers=\"{}\",signature=\"{}\"",
+			self.key_id,
+			headers
+				.iter()
+				.map(|(name, _)| *name)
+				.collect::<Vec<_>>()
+				.join(" "),
+			signature
+		);
+
+		let request_headers = request.headers_mut();
+		request_headers.insert(DATE, header_value(&date)?);
+		request_headers.insert(HOST, header_value(&host)?);
+		request_headers.insert(AUTHORIZATION, header_value(&authorization)?);
+
+		Ok(())
+	}
+}
+
+fn header_value(value: &str) -> Result<HeaderValue, String> {
+	HeaderValue::from_str(value).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+	use rsa::pkcs1v15::{Signature, VerifyingKey};
+	use rsa::pkcs8::{EncodePrivateKey, LineEnding};
+	use rsa::signature::Verifier;
+
+	use super::*;
+
+	fn key() -> RsaPrivateKey {
+		RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap()
+	}
+
+	fn signer(key: &RsaPrivateKey) -> RequestSigner {
+		let pem = key.to_pkcs8_pem(LineEnding::LF).unwrap();
+
+		RequestSigner::new("ocid1.tenancy", "ocid1.user", "aa:bb", &pem).unwrap()
+	}
+
+	// The `authorization` header's parameters, e.g. `headers` and `signature`.
+	fn parameter(request: &Request, name: &str) -> String {
+		let authorization = request.headers()[AUTHORIZATION].to_str().unwrap();
+		let prefix = format!("{}=\"", name);
+		let start = authorization.find(&prefix).unwrap() + prefix.len();
+
+		authorization[start..].split('"').next().unwrap().to_string()
+	}
+
+	// Rebuilds the signing string from the signed request, the way OCI does, and checks the
+	// signature against it.
+	fn verify(key: &RsaPrivateKey, request: &Request) {
+		let signing_string = parameter(request, "headers")
+			.split(' ')
+			.map(|name| match name {
+				"(request-target)" => {
+					let url = request.url();
+					let query = url.query().map(|query| format!("?{}", query)).unwrap_or_default();
+					format!("{}: {} {}{}", name, request.method().as_str().to_lowercase(), url.path(), query)
+				}
+				_ => format!("{}: {}", name, request.headers()[name].to_str().unwrap()),
+			})
+			.collect::<Vec<_>>()
+			.join("\n");
+		let signature = Signature::try_from(BASE64.decode(parameter(request, "signature")).unwrap().as_slice()).unwrap();
+
+		VerifyingKey::<Sha256>::new(key.to_public_key())
+			.verify(signing_string.as_bytes(), &signature)
+			.unwrap();
+	}
+
+	#[test]
+	fn signs_the_target_host_and_date_of_requests_without_a_body() {
+		let key = key();
+		let mut request = reqwest::Client::new()
+			.get("https://iaas.eu-frankfurt-1.oraclecloud.com/20160918/instances?compartmentId=ocid1.compartment")
+			.build()
+			.unwrap();
+
+		signer(&key).sign(&mut request).unwrap();
+
+		assert_eq!(parameter(&request, "keyId"), "ocid1.tenancy/ocid1.user/aa:bb");
+		assert_eq!(parameter(&request, "algorithm"), "rsa-sha256");
+		assert_eq!(parameter(&request, "headers"), "date (request-target) host");
+		assert_eq!(request.headers()[HOST], "iaas.eu-frankfurt-1.oraclecloud.com");
+		assert!(request.headers().get("x-content-sha256").is_none());
+		verify(&key, &request);
+	}
+
+	#[test]
+	fn signs_the_body_of_posts() {
+		let key = key();
+		let body = r#"{"displayName":"worker"}"#;
+		let mut request = reqwest::Client::new()
+			.post("https://iaas.eu-frankfurt-1.oraclecloud.com/20160918/instances")
+			.body(body)
+			.build()
+			.unwrap();
+
+		signer(&key).sign(&mut request).unwrap();
+
+		assert_eq!(
+			parameter(&request, "headers"),
+			"date (request-target) host content-length content-type x-content-sha256"
+		);
+		assert_eq!(request.headers()[CONTENT_LENGTH], body.len().to_string().as_str());
+		assert_eq!(request.headers()["x-content-sha256"], BASE64.encode(Sha256::digest(body)).as_str());
+		verify(&key, &request);
+	}
+
+	#[test]
+	fn signs_empty_post_bodies() {
+		let key = key();
+		let mut request = reqwest::Client::new()
+			.post("https://iaas.eu-frankfurt-1.oraclecloud.com/20160918/instances/ocid1.instance?action=START")
+			.build()
+			.unwrap();
+
+		signer(&key).sign(&mut request).unwrap();
+
+		assert_eq!(request.headers()[CONTENT_LENGTH], "0");
+		assert_eq!(request.headers()["x-content-sha256"], BASE64.encode(Sha256::digest(b"")).as_str());
+		verify(&key, &request);
+	}
+
+	#[test]
+	fn rejects_invalid_keys() {
+		assert!(RequestSigner::new("ocid1.tenancy", "ocid1.user", "aa:bb", "not a key").is_err());
+	}
+}
@@ -0,0 +1,174 @@
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+use models::models::instance_state::InstanceState;
+
+use crate::providers::error::ProviderError;
+use crate::providers::wait::{WaitError, WaitOptions};
+
+// Provider-agnostic view of an instance, built from each provider's own response model.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderInstance {
+	pub id: String,
+	pub provider: ProviderKind,
+	// Name of the configured account the instance was listed from.
+	pub account: String,
+	pub region: String,
+	pub plan: String,
+	// Raw provider status, `state` is its normalized form.
+	pub status: String,
+	pub state: InstanceState,
+	pub label: String,
+	pub main_ip: Option<String>,
+	// When the provider created the instance, which is when billing starts.
+	pub created_at: Option<DateTime<Utc>>,
+	// Provider operation still working on the instance, e.g. Hetzner's `create_server` action.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub pending_action: Option<String>,
+	// Labels, tags or freeform tags, whichever the provider has.
+	pub labels: BTreeMap<String, String>,
+}
+
+// Label every instance created for a rule carries, with the rule id as value. Only instances
+// with it count towards a rule, so servers made outside infralink are never halted or deleted.
+pub const RULE_LABEL: &str = "infralink-rule";
+
+impl ProviderInstance {
+	pub fn with_account(mut self, account: &str) -> Self {
+		self.account = account.to_string();
+		self
+	}
+
+	// Rule the instance was created for, see `RULE_LABEL`.
+	pub fn rule_id(&self) -> Option<i64> {
+		self.labels.get(RULE_LABEL)?.parse().ok()
+	}
+
+	// Stopped and terminated instances stay listed by the provider but don't serve workloads.
+	pub fn is_active(&self) -> bool {
+		!self.state.is_down()
+	}
+}
+
+// Provider-agnostic view of a block storage volume.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderVolume {
+	pub id: String,
+	pub provider: ProviderKind,
+	pub account: String,
+	pub name: String,
+	// Provider region code.
+	pub region: String,
+	pub size_gb: u64,
+	pub attached_to: Option<String>,
+	pub created_at: Option<DateTime<Utc>>,
+}
+
+// Outbound traffic of an instance this month against its monthly allowance.
+#[derive(Debug, Clone, Serialize)]
+pub struct BandwidthUsage {
+	pub instance_id: String,
+	pub provider: ProviderKind,
+	pub account: String,
+	pub allowed_gb: f64,
+	pub outgoing_gb: f64,
+}
+
+// What to boot, built from a rule. Unset fields fall back to the provider's configured defaults.
+#[derive(Debug, Clone, Default)]
+pub struct InstanceSpec {
+	pub region: String,
+	// Provider plan code, e.g. `vhf-1c-1gb` for Vultr or `cx21` for Hetzner.
+	pub plan: Option<String>,
+	// Vultr OS id or image id, Hetzner image name, HostHatch image slug or OCI image OCID.
+	pub image: Option<String>,
+	pub ssh_keys: Vec<String>,
+	pub user_data: Option<String>,
+	pub labels: BTreeMap<String, String>,
+}
+
+// Operations the manager needs from a cloud provider. Region arguments are the
+// provider's own region codes (e.g. `ewr` for Vultr, `hel1` for Hetzner).
+#[async_trait]
+pub trait CloudProvider: Send + Sync {
+	fn kind(&self) -> ProviderKind;
+
+	// Configured account this client authenticates as; `default` unless named.
+	fn account(&self) -> &str;
+
+	// Plan used for specs that don't name one, if the account has a default.
+	fn plan(&self) -> Option<String> {
+		None
+	}
+
+	// Region codes this provider can create instances in.
+	fn regions(&self) -> Vec<String>;
+
+	// Validates a region string from a rule and returns the provider's region code.
+	fn parse_region(&self, region: &str) -> Result<String, ProviderError>;
+
+	async fn list(&self) -> Result<Vec<ProviderInstance>, ProviderError>;
+
+	async fn create(&self, spec: &InstanceSpec) -> Result<ProviderInstance, ProviderError>;
+
+	// Waits for an instance returned by `create` to finish provisioning and returns it as it is
+	// then.
+	async fn wait_until_created(
+		&self,
+		instance: &ProviderInstance,
+		options: &WaitOptions,
+	) -> Result<ProviderInstance, WaitError>;
+
+	async fn start(&self, instance_id: &str) -> Result<(), ProviderError>;
+
+	async fn halt(&self, instance_id: &str) -> Result<(), ProviderError>;
+
+	async fn delete(&self, instance_id: &str) -> Result<(), ProviderError>;
+
+	async fn reboot(&self, instance_id: &str) -> Result<(), ProviderError>;
+
+	async fn volumes(&self) -> Result<Vec<ProviderVolume>, ProviderError>;
+
+	// Detaches a volume from the instance it is attached to. Providers without it detach volumes
+	// when the instance is deleted.
+	async fn detach_volume(&self, _volume_id: &str) -> Result<(), ProviderError> {
+		Err(ProviderError::Unsupported(format!("{} can't detach volumes", self.kind())))
+	}
+
+	// Traffic this month per instance, for providers that charge for traffic past an allowance.
+	async fn bandwidth(&self) -> Result<Vec<BandwidthUsage>, ProviderError> {
+		Ok(Vec::new())
+	}
+}
+
+// Labels as `key=value` tags, or just `key` when the value is empty, for providers that only
+// have tags.
+pub fn label_tags(labels: &BTreeMap<String, String>) -> Vec<String> {
+	labels
+		.iter()
+		.map(|(key, value)| {
+			if value.is_empty() {
+				key.clone()
+			} else {
+				format!("{}={}", key, value)
+			}
+		})
+		.collect()
+}
+
+pub fn parse_label_tags<'a>(tags: impl IntoIterator<Item = &'a str>) -> BTreeMap<String, String> {
+	tags.into_iter()
+		.map(|tag| match tag.split_once('=') {
+			Some((key, value)) => (key.to_string(), value.to_string()),
+			None => (tag.to_string(), String::new()),
+		})
+		.collect()
+}
+
+// Provider timestamps are RFC 3339, e.g. `2023-05-02T10:21:09+00:00`.
+pub fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+	DateTime::parse_from_rfc3339(value).ok().map(|at| at.with_timezone(&Utc))
+}
@@ -0,0 +1,34 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Response, Server};
+use serde_json::Value;
+
+// Serves `respond(query)` as JSON on a local port for the rest of the test and returns its URL.
+pub async fn serve(respond: fn(&HashMap<String, String>) -> Value) -> String {
+	serve_response(move |query| Response::new(Body::from(respond(query).to_string()))).await
+}
+
+// Like `serve`, for tests that need to set the status or headers.
+pub async fn serve_response(
+	respond: impl Fn(&HashMap<String, String>) -> Response<Body> + Copy + Send + Sync + 'static,
+) -> String {
+	let make_svc = make_service_fn(move |_conn| async move {
+		Ok::<_, Infallible>(service_fn(move |req| async move {
+			let query: HashMap<String, String> = req
+				.uri()
+				.query()
+				.map(|query| form_urlencoded::parse(query.as_bytes()).into_owned().collect())
+				.unwrap_or_default();
+
+			Ok::<_, Infallible>(respond(&query))
+		}))
+	});
+
+	let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_svc);
+	let url = format!("http://{}", server.local_addr());
+	tokio::spawn(server);
+
+	url
+}
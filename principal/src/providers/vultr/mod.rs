@@ -1 +1,4 @@
 pub mod models;
+pub mod pages;
+pub mod provider;
+pub mod status;
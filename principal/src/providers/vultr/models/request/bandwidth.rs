@@ -2,6 +2,6 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Bandwidth {
-	incoming_bytes: u64,
-	outgoing_bytes: u64,
+	pub incoming_bytes: u64,
+	pub outgoing_bytes: u64,
 }
@@ -1,10 +1,15 @@
 use std::str::FromStr;
 
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+use models::models::instance_state::InstanceState;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
+use crate::config::config::DEFAULT_ACCOUNT;
+use crate::providers::error::ProviderError;
+use crate::providers::http::HttpClient;
+use crate::providers::vultr::provider::VULTR_API_URL;
 use crate::shared_config::SharedConfig;
-use dotenv_codegen::dotenv;
 
 use super::bandwidth::Bandwidth;
 use super::plan::Plan;
@@ -12,6 +17,16 @@ use super::region::Region;
 
 use std::collections::HashMap;
 
+#[derive(Deserialize)]
+struct InstanceResponse {
+	instance: Instance,
+}
+
+#[derive(Deserialize)]
+struct BandwidthResponse {
+	bandwidth: HashMap<String, Bandwidth>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Instance {
 	pub id: String,
@@ -21,6 +36,8 @@ pub struct Instance {
 	pub main_ip: String,
 	pub vcpu_count: u32,
 	pub region: Region,
+	// Only returned when the instance is created.
+	#[serde(default)]
 	pub default_password: String,
 	pub date_created: String,
 	pub status: String,
@@ -29,7 +46,9 @@ pub struct Instance {
 	pub allowed_bandwidth: u32,
 	pub netmask_v4: String,
 	pub gateway_v4: String,
-	pub v6_networks: Vec<HashMap<String, String>>,
+	pub v6_network: String,
+	pub v6_main_ip: String,
+	pub v6_network_size: u32,
 	pub hostname: String,
 	pub label: String,
 	pub tag: Option<String>,
@@ -42,7 +61,6 @@ pub struct Instance {
 	pub features: Vec<String>,
 	pub plan: Plan,
 	pub tags: Vec<String>,
-	pub provider: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -267,102 +285,147 @@ impl InstanceBuilder {
 		self
 	}
 
-	pub async fn build(self, shared_config: &mut SharedConfig) -> Instance {
+	pub async fn build(self, shared_config: &mut SharedConfig) -> Result<Instance, ProviderError> {
+		let vultr = shared_config.config.vultr();
+		shared_config.costs.check_instance(
+			ProviderKind::Vultr,
+			DEFAULT_ACCOUNT,
+			&self.region.code(),
+			&self.plan.code(),
+			None,
+		)?;
+
 		shared_config
 			.clients
 			.vultr()
-			.post("https://api.vultr.com/v2/instances")
+			.post(format!("{}/instances", vultr.url(VULTR_API_URL)))
+			.bearer_auth(&vultr.api_key)
 			.json(&self)
 			.send()
+			.await?
+			.json::<InstanceResponse>()
 			.await
-			.unwrap()
-			.json::<Instance>()
-			.await
-			.unwrap()
+			.map(|response| response.instance)
+			.map_err(ProviderError::from)
 	}
 }
 
 impl Instance {
-	pub async fn start(&self, shared_config: &mut SharedConfig) {
+	// `status` covers provisioning and billing, `power_status` the VM and `server_status` its boot.
+	pub fn instance_state(&self) -> InstanceState {
+		match (
+			self.status.as_str(),
+			self.power_status.as_str(),
+			self.server_status.as_str(),
+		) {
+			("pending", _, _) => InstanceState::Starting,
+			("resizing", _, _) => InstanceState::Upgrading,
+			("suspended", _, _) | ("active", "stopped", _) => InstanceState::Stopped,
+			("active", "running", "ok") => InstanceState::Running,
+			("active", "running", _) => InstanceState::Starting,
+			_ => InstanceState::Unknown,
+		}
+	}
+
+	pub async fn start(&self, shared_config: &mut SharedConfig) -> Result<(), ProviderError> {
+		let vultr = shared_config.config.vultr();
+
 		shared_config
 			.clients
 			.vultr()
-			.post("https://api.vultr.com/v2/instances/start")
+			.post(format!("{}/instances/start", vultr.url(VULTR_API_URL)))
 			.json(&json!({ "instance_ids": vec![self.id.clone()] }))
-			.bearer_auth(dotenv!("VULTR_API_KEY"))
+			.bearer_auth(&vultr.api_key)
 			.send()
-			.await
-			.unwrap();
+			.await?;
+
+		Ok(())
 	}
 
-	pub async fn halt(&self, shared_config: &mut SharedConfig) {
+	pub async fn halt(&self, shared_config: &mut SharedConfig) -> Result<(), ProviderError> {
+		let vultr = shared_config.config.vultr();
+
 		shared_config
 			.clients
 			.vultr()
-			.post("https://api.vultr.com/v2/instances/halt")
+			.post(format!("{}/instances/halt", vultr.url(VULTR_API_URL)))
 			.json(&json!({ "instance_ids": vec![self.id.clone()] }))
-			.bearer_auth(dotenv!("VULTR_API_KEY"))
+			.bearer_auth(&vultr.api_key)
 			.send()
-			.await
-			.unwrap();
+			.await?;
+
+		Ok(())
 	}
 
-	pub async fn reboot(&self, shared_config: &mut SharedConfig) {
+	pub async fn reboot(&self, shared_config: &mut SharedConfig) -> Result<(), ProviderError> {
+		let vultr = shared_config.config.vultr();
+
 		shared_config
 			.clients
 			.vultr()
-			.post(format!(
-				"https://api.vultr.com/v2/instances/{}/reboot",
-				self.id
-			))
-			.bearer_auth(dotenv!("VULTR_API_KEY"))
+			.post(format!("{}/instances/{}/reboot", vultr.url(VULTR_API_URL), self.id))
+			.bearer_auth(&vultr.api_key)
 			.send()
-			.await
-			.unwrap();
+			.await?;
+
+		Ok(())
 	}
 
-	pub async fn delete(&self, shared_config: &mut SharedConfig) {
+	pub async fn delete(&self, shared_config: &mut SharedConfig) -> Result<(), ProviderError> {
+		let vultr = shared_config.config.vultr();
+
 		shared_config
 			.clients
 			.vultr()
-			.delete(format!("https://api.vultr.com/v2/instances/{}", self.id))
-			.bearer_auth(dotenv!("VULTR_API_KEY"))
+			.delete(format!("{}/instances/{}", vultr.url(VULTR_API_URL), self.id))
+			.bearer_auth(&vultr.api_key)
 			.send()
-			.await
-			.unwrap();
+			.await?;
+
+		Ok(())
 	}
 
-	pub async fn reinstall(&self, hostname: String, shared_config: &mut SharedConfig) {
+	pub async fn reinstall(&self, hostname: String, shared_config: &mut SharedConfig) -> Result<(), ProviderError> {
+		let vultr = shared_config.config.vultr();
+
 		shared_config
 			.clients
 			.vultr()
-			.post(format!(
-				"https://api.vultr.com/v2/instances/{}/reinstall",
-				self.id
-			))
+			.post(format!("{}/instances/{}/reinstall", vultr.url(VULTR_API_URL), self.id))
 			.json(&json!({
 				"hostname": hostname,
 			}))
-			.bearer_auth(dotenv!("VULTR_API_KEY"))
+			.bearer_auth(&vultr.api_key)
 			.send()
-			.await
-			.unwrap();
+			.await?;
+
+		Ok(())
 	}
 
-	pub async fn bandwidth(&self, shared_config: &mut SharedConfig) -> HashMap<String, Bandwidth> {
-		shared_config
-			.clients
-			.vultr()
-			.get(format!(
-				"https://api.vultr.com/v2/instances/{}/bandwidth",
-				self.id
-			))
-			.bearer_auth(dotenv!("VULTR_API_KEY"))
-			.send()
-			.await
-			.unwrap()
-			.json::<HashMap<String, Bandwidth>>()
-			.await
-			.unwrap()
+	pub async fn bandwidth(
+		&self,
+		shared_config: &mut SharedConfig,
+	) -> Result<HashMap<String, Bandwidth>, ProviderError> {
+		let vultr = shared_config.config.vultr();
+
+		fetch_bandwidth(shared_config.clients.vultr(), &vultr.url(VULTR_API_URL), &vultr.api_key, &self.id).await
 	}
 }
+
+// Daily traffic of an instance over the last month, keyed by date (`2023-05-02`).
+pub async fn fetch_bandwidth(
+	client: &HttpClient,
+	base_url: &str,
+	api_key: &str,
+	instance_id: &str,
+) -> Result<HashMap<String, Bandwidth>, ProviderError> {
+	client
+		.get(format!("{}/instances/{}/bandwidth", base_url, instance_id))
+		.bearer_auth(api_key)
+		.send()
+		.await?
+		.json::<BandwidthResponse>()
+		.await
+		.map(|response| response.bandwidth)
+		.map_err(ProviderError::from)
+}
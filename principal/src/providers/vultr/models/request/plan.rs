@@ -10,6 +10,8 @@ pub enum Plan {
 	HighPerformance(Compute),
 	GeneralPurpose(Compute),
 	CPUOptimized(Compute),
+	// Cloud GPU codes, e.g. `vcg-a100-1c-6g-4vram`, are kept as is; see `gpu::catalog`.
+	CloudGpu(String),
 	Unknown,
 }
 
@@ -164,7 +166,7 @@ impl Plan {
 					"{}-{}c-{}gb",
 					InstanceType::HighFrequency.to_string(),
 					compute.vcpu,
-					compute.ram
+					compute.ram / 1024
 				)
 			}
 			Plan::HighPerformance(compute) => {
@@ -172,7 +174,7 @@ impl Plan {
 					"{}-{}c-{}gb-intel",
 					InstanceType::HighPerformance.to_string(),
 					compute.vcpu,
-					compute.ram
+					compute.ram / 1024
 				)
 			}
 			Plan::GeneralPurpose(compute) => {
@@ -189,7 +191,7 @@ impl Plan {
 					"{}-{}c-{}gb-{}s-amd",
 					InstanceType::GeneralPurpose.to_string(),
 					compute.vcpu,
-					compute.ram,
+					compute.ram / 1024,
 					disk_size
 				)
 			}
@@ -205,38 +207,55 @@ impl Plan {
 					"{}-{}c-{}gb-{}s-amd",
 					InstanceType::CPUOptimized.to_string(),
 					compute.vcpu,
-					compute.ram,
+					compute.ram / 1024,
 					disk_size
 				)
 			}
+			Plan::CloudGpu(code) => code.clone(),
 			_ => String::new(),
 		}
 	}
 
+	// Inverse of `code`. Codes give RAM in GB, `Compute` keeps it in MB.
 	pub fn from_code(code: &str) -> Result<Self, &'static str> {
-		let parts: Vec<&str> = code.split('-').collect();
-
-		if parts.len() != 4 {
-			return Err("Invalid code format");
+		if code.starts_with("vcg-") {
+			return Ok(Self::CloudGpu(code.to_string()));
 		}
 
+		let (instance_type, rest) = ["voc-g", "voc-c", "vhp", "vhf"]
+			.into_iter()
+			.find_map(|prefix| {
+				code.strip_prefix(prefix)
+					.and_then(|rest| rest.strip_prefix('-'))
+					.map(|rest| (prefix, rest))
+			})
+			.ok_or("Invalid instance type")?;
+
 		let instance_type =
-			InstanceType::from_str(parts[0]).map_err(|_| "Invalid instance type")?;
+			InstanceType::from_str(instance_type).map_err(|_| "Invalid instance type")?;
+
+		let mut parts = rest.split('-');
 
-		let vcpu = parts[1][..parts[1].len() - 1]
-			.parse::<u16>()
-			.map_err(|_| "Invalid vCPU count")?;
-		let ram = parts[2][..parts[2].len() - 2]
-			.parse::<u32>()
-			.map_err(|_| "Invalid RAM size")?;
-		let disk = parts[3][..parts[3].len() - 1]
-			.parse::<u32>()
-			.map_err(|_| "Invalid disk size")?;
+		let vcpu = parts
+			.next()
+			.and_then(|part| part.strip_suffix('c'))
+			.and_then(|part| part.parse::<u16>().ok())
+			.ok_or("Invalid vCPU count")?;
+		let ram = parts
+			.next()
+			.and_then(|part| part.strip_suffix("gb"))
+			.and_then(|part| part.parse::<u32>().ok())
+			.ok_or("Invalid RAM size")?;
+		// Only general purpose and CPU optimized codes carry a disk size, e.g. `30s`.
+		let disk = parts
+			.next()
+			.and_then(|part| part.strip_suffix('s'))
+			.and_then(|part| part.parse::<u32>().ok());
 
 		let compute = Compute {
 			vcpu,
-			ram,
-			disk: Some(disk),
+			ram: ram * 1024,
+			disk,
 		};
 
 		let plan = match instance_type {
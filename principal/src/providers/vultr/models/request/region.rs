@@ -174,7 +174,43 @@ impl fmt::Display for Region {
 }
 
 impl Region {
-	fn code(&self) -> String {
+	pub fn list() -> Vec<Self> {
+		vec![
+			Region::Asia(Asia::Tokyo),
+			Region::Asia(Asia::Osaka),
+			Region::Asia(Asia::Seoul),
+			Region::Asia(Asia::Singapore),
+			Region::Asia(Asia::Mumbai),
+			Region::Asia(Asia::TelAviv),
+			Region::Asia(Asia::Bangalore),
+			Region::Asia(Asia::Delhi),
+			Region::Australia(Australia::Sydney),
+			Region::Australia(Australia::Melbourne),
+			Region::Europe(Europe::Amsterdam),
+			Region::Europe(Europe::London),
+			Region::Europe(Europe::Frankfurt),
+			Region::Europe(Europe::Paris),
+			Region::Europe(Europe::Warsaw),
+			Region::Europe(Europe::Madrid),
+			Region::Europe(Europe::Stockholm),
+			Region::NorthAmerica(NorthAmerica::NewJersey),
+			Region::NorthAmerica(NorthAmerica::Chicago),
+			Region::NorthAmerica(NorthAmerica::Dallas),
+			Region::NorthAmerica(NorthAmerica::Seattle),
+			Region::NorthAmerica(NorthAmerica::LosAngeles),
+			Region::NorthAmerica(NorthAmerica::Atlanta),
+			Region::NorthAmerica(NorthAmerica::SiliconValley),
+			Region::NorthAmerica(NorthAmerica::Toronto),
+			Region::NorthAmerica(NorthAmerica::Miami),
+			Region::NorthAmerica(NorthAmerica::MexicoCity),
+			Region::NorthAmerica(NorthAmerica::Honolulu),
+			Region::SouthAmerica(SouthAmerica::SaoPaulo),
+			Region::SouthAmerica(SouthAmerica::Santiago),
+			Region::Africa(Africa::Johannesburg),
+		]
+	}
+
+	pub fn code(&self) -> String {
 		match self {
 			Region::Asia(city) => match city {
 				Asia::Tokyo => "nrt".to_string(),
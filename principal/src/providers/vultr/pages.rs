@@ -0,0 +1,102 @@
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::providers::error::ProviderError;
+use crate::providers::http::HttpClient;
+
+// Largest page Vultr serves.
+const PER_PAGE: u32 = 500;
+
+#[derive(Deserialize)]
+struct Links {
+	next: String,
+}
+
+#[derive(Deserialize)]
+struct Meta {
+	links: Links,
+}
+
+// Fetches every page of a list endpoint. Vultr wraps the items in `{ "<key>": [..], "meta": .. }`
+// and hands out an opaque cursor for the next page, empty on the last one.
+pub async fn list_all<T: DeserializeOwned>(
+	client: &HttpClient,
+	url: &str,
+	api_key: &str,
+	key: &str,
+) -> Result<Vec<T>, ProviderError> {
+	let mut items = Vec::new();
+	let mut cursor = String::new();
+
+	loop {
+		let mut query = vec![("per_page", PER_PAGE.to_string())];
+		if !cursor.is_empty() {
+			query.push(("cursor", cursor));
+		}
+
+		// Public endpoints such as `/plans` are also listed without a key.
+		let mut request = client.get(url).query(&query);
+		if !api_key.is_empty() {
+			request = request.bearer_auth(api_key);
+		}
+
+		let mut page = request.send().await?.json::<Value>().await?;
+
+		let page_items = page.get_mut(key).map(Value::take).unwrap_or(Value::Null);
+		items.extend(serde_json::from_value::<Vec<T>>(page_items).map_err(|e| {
+			ProviderError::Decode(format!("{} in {}: {}", key, url, e))
+		})?);
+
+		let meta = page.get_mut("meta").map(Value::take).unwrap_or(Value::Null);
+		cursor = match serde_json::from_value::<Meta>(meta) {
+			Ok(meta) if !meta.links.next.is_empty() => meta.links.next,
+			_ => return Ok(items),
+		};
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::json;
+
+	use super::*;
+	use crate::providers::test_server::serve;
+
+	#[tokio::test]
+	async fn list_all_follows_the_cursor_until_it_is_empty() {
+		let url = serve(|query| {
+			assert_eq!(query["per_page"], PER_PAGE.to_string());
+
+			match query.get("cursor").map(String::as_str) {
+				None => json!({ "plans": [1, 2], "meta": { "total": 5, "links": { "next": "abc", "prev": "" } } }),
+				Some("abc") => json!({ "plans": [3, 4], "meta": { "total": 5, "links": { "next": "def", "prev": "" } } }),
+				Some("def") => json!({ "plans": [5], "meta": { "total": 5, "links": { "next": "", "prev": "abc" } } }),
+				Some(cursor) => panic!("unexpected cursor {}", cursor),
+			}
+		})
+		.await;
+
+		let items = list_all::<u32>(&HttpClient::new(reqwest::Client::new()), &url, "key", "plans").await.unwrap();
+
+		assert_eq!(items, vec![1, 2, 3, 4, 5]);
+	}
+
+	#[tokio::test]
+	async fn list_all_stops_without_meta() {
+		let url = serve(|_| json!({ "plans": [1] })).await;
+
+		let items = list_all::<u32>(&HttpClient::new(reqwest::Client::new()), &url, "", "plans").await.unwrap();
+
+		assert_eq!(items, vec![1]);
+	}
+
+	#[tokio::test]
+	async fn list_all_fails_on_items_it_cannot_decode() {
+		let url = serve(|_| json!({ "plans": ["one"], "meta": { "links": { "next": "" } } })).await;
+
+		let result = list_all::<u32>(&HttpClient::new(reqwest::Client::new()), &url, "key", "plans").await;
+
+		assert!(matches!(result, Err(ProviderError::Decode(_))));
+	}
+}
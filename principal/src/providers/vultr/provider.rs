@@ -0,0 +1,298 @@
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::config::config::DEFAULT_ACCOUNT;
+use crate::providers::error::ProviderError;
+use crate::providers::http::HttpClient;
+use crate::providers::provider::{
+	label_tags, parse_label_tags, parse_timestamp, BandwidthUsage, CloudProvider, InstanceSpec, ProviderInstance,
+	ProviderVolume,
+};
+use crate::providers::wait::{WaitError, WaitOptions};
+
+use super::models::request::instance::{fetch_bandwidth, Instance, InstanceBuilder};
+use super::models::request::plan::Plan;
+use super::models::request::region::Region;
+use super::pages::list_all;
+use super::status::wait_for_instance;
+
+pub const VULTR_API_URL: &str = "https://api.vultr.com/v2";
+
+#[derive(Deserialize)]
+struct InstanceResponse {
+	instance: Instance,
+}
+
+#[derive(Deserialize)]
+struct Block {
+	id: String,
+	label: String,
+	region: String,
+	size_gb: u64,
+	attached_to_instance: Option<String>,
+	date_created: String,
+}
+
+pub struct Vultr {
+	client: HttpClient,
+	api_key: String,
+	base_url: String,
+	account: String,
+	plan: Option<Plan>,
+	image: Option<String>,
+}
+
+impl Vultr {
+	pub fn new(client: HttpClient, api_key: String) -> Self {
+		Self {
+			client,
+			api_key,
+			base_url: VULTR_API_URL.to_string(),
+			account: DEFAULT_ACCOUNT.to_string(),
+			plan: None,
+			image: None,
+		}
+	}
+
+	// Points the client at another API, e.g. the fake cloud used for local testing.
+	pub fn base_url(mut self, base_url: String) -> Self {
+		self.base_url = base_url.trim_end_matches('/').to_string();
+		self
+	}
+
+	// Name of the configured account this client belongs to; listings are tagged with it.
+	pub fn account(mut self, account: String) -> Self {
+		self.account = account;
+		self
+	}
+
+	// Used for rules that don't name a plan or image themselves.
+	pub fn plan(mut self, plan: Plan) -> Self {
+		self.plan = Some(plan);
+		self
+	}
+
+	pub fn image(mut self, image: String) -> Self {
+		self.image = Some(image);
+		self
+	}
+
+	async fn instance_action(&self, action: &str, instance_id: &str) -> Result<(), ProviderError> {
+		self.client
+			.post(format!("{}/instances/{}", self.base_url, action))
+			.bearer_auth(&self.api_key)
+			.json(&json!({ "instance_ids": vec![instance_id] }))
+			.send()
+			.await?;
+
+		Ok(())
+	}
+}
+
+impl From<&Instance> for ProviderInstance {
+	fn from(instance: &Instance) -> Self {
+		ProviderInstance {
+			id: instance.id.clone(),
+			provider: ProviderKind::Vultr,
+			account: DEFAULT_ACCOUNT.to_string(),
+			region: instance.region.code(),
+			plan: instance.plan.code(),
+			status: instance.power_status.clone(),
+			state: instance.instance_state(),
+			label: instance.label.clone(),
+			main_ip: Some(instance.main_ip.clone()),
+			created_at: parse_timestamp(&instance.date_created),
+			pending_action: None,
+			labels: parse_label_tags(instance.tags.iter().map(String::as_str)),
+		}
+	}
+}
+
+#[async_trait]
+impl CloudProvider for Vultr {
+	fn kind(&self) -> ProviderKind {
+		ProviderKind::Vultr
+	}
+
+	fn account(&self) -> &str {
+		&self.account
+	}
+
+	fn plan(&self) -> Option<String> {
+		self.plan.as_ref().map(Plan::code)
+	}
+
+	fn regions(&self) -> Vec<String> {
+		Region::list().iter().map(Region::code).collect()
+	}
+
+	fn parse_region(&self, region: &str) -> Result<String, ProviderError> {
+		Region::from_code(region)
+			.map(|region| region.code())
+			.map_err(|e| ProviderError::Invalid(format!("{}: {}", e, region)))
+	}
+
+	async fn list(&self) -> Result<Vec<ProviderInstance>, ProviderError> {
+		let instances = list_all::<Instance>(
+			&self.client,
+			&format!("{}/instances", self.base_url),
+			&self.api_key,
+			"instances",
+		)
+		.await?;
+
+		Ok(instances
+			.iter()
+			.map(|instance| ProviderInstance::from(instance).with_account(&self.account))
+			.collect())
+	}
+
+	async fn create(&self, spec: &InstanceSpec) -> Result<ProviderInstance, ProviderError> {
+		let region = Region::from_code(&spec.region)
+			.map_err(|e| ProviderError::Invalid(format!("{}: {}", e, spec.region)))?;
+
+		let plan = match &spec.plan {
+			Some(code) => Plan::from_code(code)
+				.map_err(|e| ProviderError::Invalid(format!("{}: {}", e, code)))?,
+			None => self.plan.clone().ok_or_else(|| {
+				ProviderError::Invalid("no Vultr plan set on the rule or account".to_string())
+			})?,
+		};
+
+		let mut builder = InstanceBuilder::new().region(region).plan(plan);
+
+		// Numeric images are OS ids, anything else is an application image id.
+		match spec.image.as_ref().or(self.image.as_ref()) {
+			Some(image) => match image.parse::<u32>() {
+				Ok(os_id) => builder = builder.os_id(os_id),
+				Err(_) => builder = builder.image_id(image.clone()),
+			},
+			None => {
+				return Err(ProviderError::Invalid(
+					"no Vultr image set on the rule or account".to_string(),
+				))
+			}
+		}
+
+		if !spec.ssh_keys.is_empty() {
+			builder = builder.sshkey_id(spec.ssh_keys.clone());
+		}
+		if let Some(user_data) = &spec.user_data {
+			builder = builder.user_data(BASE64.encode(user_data));
+		}
+		if !spec.labels.is_empty() {
+			builder = builder.tags(label_tags(&spec.labels));
+		}
+
+		let response = self
+			.client
+			.post(format!("{}/instances", self.base_url))
+			.bearer_auth(&self.api_key)
+			.json(&builder)
+			.send()
+			.await?
+			.json::<InstanceResponse>()
+			.await?;
+
+		Ok(ProviderInstance::from(&response.instance).with_account(&self.account))
+	}
+
+	async fn wait_until_created(
+		&self,
+		instance: &ProviderInstance,
+		options: &WaitOptions,
+	) -> Result<ProviderInstance, WaitError> {
+		let instance = wait_for_instance(&self.client, &self.base_url, &self.api_key, &instance.id, options).await?;
+
+		Ok(ProviderInstance::from(&instance).with_account(&self.account))
+	}
+
+	async fn start(&self, instance_id: &str) -> Result<(), ProviderError> {
+		self.instance_action("start", instance_id).await
+	}
+
+	async fn halt(&self, instance_id: &str) -> Result<(), ProviderError> {
+		self.instance_action("halt", instance_id).await
+	}
+
+	async fn delete(&self, instance_id: &str) -> Result<(), ProviderError> {
+		self.client
+			.delete(format!("{}/instances/{}", self.base_url, instance_id))
+			.bearer_auth(&self.api_key)
+			.send()
+			.await?;
+
+		Ok(())
+	}
+
+	async fn reboot(&self, instance_id: &str) -> Result<(), ProviderError> {
+		self.instance_action("reboot", instance_id).await
+	}
+
+	async fn volumes(&self) -> Result<Vec<ProviderVolume>, ProviderError> {
+		let blocks =
+			list_all::<Block>(&self.client, &format!("{}/blocks", self.base_url), &self.api_key, "blocks").await?;
+
+		Ok(blocks
+			.into_iter()
+			.map(|block| ProviderVolume {
+				id: block.id,
+				provider: ProviderKind::Vultr,
+				account: self.account.clone(),
+				name: block.label,
+				region: block.region,
+				size_gb: block.size_gb,
+				attached_to: block.attached_to_instance.filter(|id| !id.is_empty()),
+				created_at: parse_timestamp(&block.date_created),
+			})
+			.collect())
+	}
+
+	async fn detach_volume(&self, volume_id: &str) -> Result<(), ProviderError> {
+		self.client
+			.post(format!("{}/blocks/{}/detach", self.base_url, volume_id))
+			.bearer_auth(&self.api_key)
+			.json(&json!({ "live": false }))
+			.send()
+			.await?;
+
+		Ok(())
+	}
+
+	// Vultr pools the allowances of an account's instances and bills outbound traffic past the
+	// pool. Daily figures are keyed by date, so this month's are the ones with its prefix.
+	async fn bandwidth(&self) -> Result<Vec<BandwidthUsage>, ProviderError> {
+		let instances = list_all::<Instance>(
+			&self.client,
+			&format!("{}/instances", self.base_url),
+			&self.api_key,
+			"instances",
+		)
+		.await?;
+
+		let month = chrono::Utc::now().format("%Y-%m").to_string();
+		let mut usage = Vec::new();
+		for instance in instances {
+			let days = fetch_bandwidth(&self.client, &self.base_url, &self.api_key, &instance.id).await?;
+			let outgoing_bytes: u64 = days
+				.iter()
+				.filter(|(day, _)| day.starts_with(&month))
+				.map(|(_, bandwidth)| bandwidth.outgoing_bytes)
+				.sum();
+
+			usage.push(BandwidthUsage {
+				instance_id: instance.id,
+				provider: ProviderKind::Vultr,
+				account: self.account.clone(),
+				allowed_gb: instance.allowed_bandwidth as f64,
+				outgoing_gb: outgoing_bytes as f64 / 1e9,
+			});
+		}
+
+		Ok(usage)
+	}
+}
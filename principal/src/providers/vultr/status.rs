@@ -0,0 +1,75 @@
+use models::models::instance_state::InstanceState;
+use serde::Deserialize;
+
+use crate::providers::http::HttpClient;
+use crate::providers::wait::{wait_until, Progress, WaitError, WaitOptions};
+
+use super::models::request::instance::Instance;
+
+#[derive(Deserialize)]
+struct InstanceResponse {
+	instance: Instance,
+}
+
+#[derive(Deserialize)]
+struct BlockStatus {
+	status: String,
+}
+
+#[derive(Deserialize)]
+struct BlockResponse {
+	block: BlockStatus,
+}
+
+// Instance creation returns straight away with a `pending` instance; polls `/instances/{id}`
+// until it has booted or come up stopped.
+pub async fn wait_for_instance(
+	client: &HttpClient,
+	base_url: &str,
+	api_key: &str,
+	instance_id: &str,
+	options: &WaitOptions,
+) -> Result<Instance, WaitError> {
+	wait_until(options, || async {
+		let instance = client
+			.get(format!("{}/instances/{}", base_url, instance_id))
+			.bearer_auth(api_key)
+			.send()
+			.await?
+			.json::<InstanceResponse>()
+			.await?
+			.instance;
+
+		Ok(match instance.instance_state() {
+			InstanceState::Running | InstanceState::Stopped => Progress::Done(instance),
+			_ => Progress::Pending,
+		})
+	})
+	.await
+}
+
+// Polls `/blocks/{id}` until a new block storage volume is `active`.
+pub async fn wait_for_block(
+	client: &HttpClient,
+	base_url: &str,
+	api_key: &str,
+	block_id: &str,
+	options: &WaitOptions,
+) -> Result<(), WaitError> {
+	wait_until(options, || async {
+		let block = client
+			.get(format!("{}/blocks/{}", base_url, block_id))
+			.bearer_auth(api_key)
+			.send()
+			.await?
+			.json::<BlockResponse>()
+			.await?
+			.block;
+
+		Ok(match block.status.as_str() {
+			"active" => Progress::Done(()),
+			_ => Progress::Pending,
+		})
+	})
+	.await
+}
@@ -0,0 +1,170 @@
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+
+use tokio::time::{sleep, Instant};
+
+use crate::providers::error::ProviderError;
+
+// How long to poll an asynchronous provider operation, and how often.
+#[derive(Debug, Clone)]
+pub struct WaitOptions {
+	pub timeout: Duration,
+	pub initial_delay: Duration,
+	pub max_delay: Duration,
+}
+
+impl Default for WaitOptions {
+	fn default() -> Self {
+		WaitOptions {
+			timeout: Duration::from_secs(300),
+			initial_delay: Duration::from_secs(1),
+			max_delay: Duration::from_secs(15),
+		}
+	}
+}
+
+impl WaitOptions {
+	pub fn timeout(mut self, timeout: Duration) -> Self {
+		self.timeout = timeout;
+		self
+	}
+}
+
+// What a single poll found out about the operation.
+pub enum Progress<T> {
+	Done(T),
+	Pending,
+	Failed(String),
+}
+
+#[derive(Debug)]
+pub enum WaitError {
+	// The provider reported the operation as failed.
+	Failed(String),
+	TimedOut(Duration),
+	// Polling itself failed, e.g. the API could not be reached.
+	Poll(ProviderError),
+}
+
+impl fmt::Display for WaitError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			WaitError::Failed(e) => write!(f, "Operation failed: {}", e),
+			WaitError::TimedOut(timeout) => write!(f, "Operation did not finish within {}s", timeout.as_secs()),
+			WaitError::Poll(e) => write!(f, "Failed to poll operation: {}", e),
+		}
+	}
+}
+
+impl Error for WaitError {}
+
+impl From<WaitError> for ProviderError {
+	fn from(e: WaitError) -> Self {
+		match e {
+			WaitError::Poll(e) => e,
+			e => ProviderError::Transient(e.to_string()),
+		}
+	}
+}
+
+// Polls until the operation is done or failed, doubling the delay between polls up to
+// `max_delay`.
+pub async fn wait_until<T, F, Fut>(options: &WaitOptions, mut poll: F) -> Result<T, WaitError>
+where
+	F: FnMut() -> Fut,
+	Fut: Future<Output = Result<Progress<T>, ProviderError>>,
+{
+	let deadline = Instant::now() + options.timeout;
+	let mut delay = options.initial_delay;
+
+	loop {
+		match poll().await.map_err(WaitError::Poll)? {
+			Progress::Done(value) => return Ok(value),
+			Progress::Failed(e) => return Err(WaitError::Failed(e)),
+			Progress::Pending => {}
+		}
+
+		let now = Instant::now();
+		if now >= deadline {
+			return Err(WaitError::TimedOut(options.timeout));
+		}
+
+		sleep(delay.min(deadline - now)).await;
+		delay = (delay * 2).min(options.max_delay);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::cell::RefCell;
+
+	use super::*;
+
+	fn options(timeout_secs: u64) -> WaitOptions {
+		WaitOptions {
+			timeout: Duration::from_secs(timeout_secs),
+			initial_delay: Duration::from_secs(1),
+			max_delay: Duration::from_secs(4),
+		}
+	}
+
+	// Seconds since `start` at which each poll happened; time is paused, so they are exact.
+	fn offsets(polls: &RefCell<Vec<Instant>>, start: Instant) -> Vec<u64> {
+		polls.borrow().iter().map(|poll| (*poll - start).as_secs()).collect()
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn polls_with_a_doubling_delay_until_done() {
+		let start = Instant::now();
+		let polls = RefCell::new(Vec::new());
+
+		let value = wait_until(&options(300), || async {
+			polls.borrow_mut().push(Instant::now());
+			Ok(if polls.borrow().len() < 6 { Progress::Pending } else { Progress::Done("done") })
+		})
+		.await
+		.unwrap();
+
+		assert_eq!(value, "done");
+		// 1, 2 and 4 seconds apart, then capped at `max_delay`.
+		assert_eq!(offsets(&polls, start), vec![0, 1, 3, 7, 11, 15]);
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn times_out_with_a_last_poll_at_the_deadline() {
+		let start = Instant::now();
+		let polls = RefCell::new(Vec::new());
+
+		let result = wait_until(&options(10), || async {
+			polls.borrow_mut().push(Instant::now());
+			Ok::<Progress<()>, ProviderError>(Progress::Pending)
+		})
+		.await;
+
+		assert!(matches!(result, Err(WaitError::TimedOut(timeout)) if timeout == Duration::from_secs(10)));
+		assert_eq!(offsets(&polls, start), vec![0, 1, 3, 7, 10]);
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn a_failed_operation_or_poll_ends_the_wait() {
+		let failed = wait_until(&options(10), || async {
+			Ok::<Progress<()>, _>(Progress::Failed("error".to_string()))
+		})
+		.await;
+		assert!(matches!(failed, Err(WaitError::Failed(e)) if e == "error"));
+
+		let unreachable = wait_until(&options(10), || async {
+			Err::<Progress<()>, _>(ProviderError::Transient("unreachable".to_string()))
+		})
+		.await;
+		assert!(matches!(unreachable, Err(WaitError::Poll(ProviderError::Transient(_)))));
+	}
+
+	#[test]
+	fn timeouts_become_transient_provider_errors() {
+		let error: ProviderError = WaitError::TimedOut(Duration::from_secs(300)).into();
+		assert!(matches!(error, ProviderError::Transient(e) if e == "Operation did not finish within 300s"));
+	}
+}
@@ -0,0 +1,173 @@
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+use models::models::region::Region;
+
+use crate::manager::manager::ManagerError;
+use crate::providers::provider::CloudProvider;
+
+// Rules may name a canonical region (`fra`), an area (`eu-central`), a continent (`europe`)
+// or the provider's own region code; all of them resolve to the provider's code here. Only
+// regions the account can launch in are accepted, e.g. the configured one for Oracle.
+pub fn provider_region(provider: &dyn CloudProvider, region: &str) -> Result<String, ManagerError> {
+	let served = provider.regions();
+	let metros = Region::matching(region);
+
+	let code = if metros.is_empty() {
+		provider.parse_region(region)?
+	} else {
+		// The first metro of an area the account serves.
+		metros
+			.iter()
+			.filter_map(|metro| metro.provider_code(provider.kind()))
+			.filter_map(|code| provider.parse_region(code).ok())
+			.find(|code| served.contains(code))
+			.ok_or_else(|| ManagerError::InvalidRule(format!("{} has no region in {}", provider.kind(), region)))?
+	};
+
+	if !served.contains(&code) {
+		return Err(ManagerError::InvalidRule(format!(
+			"{} account {} can't launch in {}",
+			provider.kind(),
+			provider.account(),
+			code
+		)));
+	}
+
+	Ok(code)
+}
+
+// What a rule stores as its region: areas and continents as given, so they are resolved
+// again on every pass, and metros by their canonical id, whichever code they were given as.
+pub fn rule_region(provider: &dyn CloudProvider, region: &str) -> Result<String, ManagerError> {
+	let code = provider_region(provider, region)?;
+	let region = region.to_lowercase();
+
+	if !Region::matching(&region).is_empty() {
+		return Ok(region);
+	}
+
+	Ok(Region::from_provider_code(provider.kind(), &code)
+		.map(|region| region.id.to_string())
+		.unwrap_or(code))
+}
+
+// Canonical id for a provider's region code, or the code itself for regions the catalog
+// doesn't know yet.
+pub fn canonical(provider: ProviderKind, code: &str) -> String {
+	Region::from_provider_code(provider, code)
+		.map(|region| region.id.to_string())
+		.unwrap_or_else(|| code.to_string())
+}
+
+// Whether a provider's region code falls under `query`: the code itself, or a canonical region,
+// area or continent containing it.
+pub fn covers(provider: ProviderKind, code: &str, query: &str) -> bool {
+	code.eq_ignore_ascii_case(query)
+		|| Region::from_provider_code(provider, code)
+			.is_some_and(|region| Region::matching(query).iter().any(|metro| metro.id == region.id))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use rsa::pkcs8::{EncodePrivateKey, LineEnding};
+	use rsa::RsaPrivateKey;
+
+	use crate::providers::hetzner::provider::Hetzner;
+	use crate::providers::http::HttpClient;
+	use crate::providers::oracle::models::request::region::Region as OracleRegion;
+	use crate::providers::oracle::models::request::shape::{Compute, Shape};
+	use crate::providers::oracle::provider::{Oracle, OracleConfig};
+	use crate::providers::vultr::provider::Vultr;
+
+	// Region parsing doesn't call the APIs, so the clients are never used.
+	fn vultr() -> Vultr {
+		Vultr::new(HttpClient::new(reqwest::Client::new()), String::new())
+	}
+
+	fn hetzner() -> Hetzner {
+		Hetzner::new(HttpClient::new(reqwest::Client::new()), String::new())
+	}
+
+	// Only launches in its configured region, Frankfurt.
+	fn oracle() -> Oracle {
+		let key = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+		let config = OracleConfig {
+			tenancy_id: "ocid1.tenancy".to_string(),
+			user_id: "ocid1.user".to_string(),
+			fingerprint: "aa:bb".to_string(),
+			private_key_pem: key.to_pkcs8_pem(LineEnding::LF).unwrap().to_string(),
+			region: OracleRegion::Frankfurt,
+			compartment_id: "ocid1.compartment".to_string(),
+			availability_domain: "AD-1".to_string(),
+			subnet_id: "ocid1.subnet".to_string(),
+			image_id: "ocid1.image".to_string(),
+			shape: Shape::StandardE4Flex(Compute {
+				ocpus: 1.0,
+				memory_in_gbs: 8.0,
+			}),
+		};
+
+		Oracle::new(HttpClient::new(reqwest::Client::new()), config).unwrap()
+	}
+
+	#[test]
+	fn provider_region_takes_provider_codes_and_canonical_ids() {
+		assert_eq!(provider_region(&vultr(), "fra").unwrap(), "fra");
+		assert_eq!(provider_region(&hetzner(), "hel1").unwrap(), "hel1");
+		assert_eq!(provider_region(&hetzner(), "iad").unwrap(), "ash");
+		assert_eq!(provider_region(&hetzner(), "FSN").unwrap(), "fsn1");
+	}
+
+	#[test]
+	fn provider_region_picks_the_first_metro_of_an_area_the_provider_serves() {
+		assert_eq!(provider_region(&vultr(), "eu-central").unwrap(), "fra");
+		assert_eq!(provider_region(&hetzner(), "eu-central").unwrap(), "fsn1");
+		assert_eq!(provider_region(&hetzner(), "north-america").unwrap(), "ash");
+	}
+
+	#[test]
+	fn provider_region_rejects_unknown_and_unserved_regions() {
+		assert!(matches!(provider_region(&hetzner(), "sa-east"), Err(ManagerError::InvalidRule(_))));
+		assert!(matches!(provider_region(&hetzner(), "asia"), Err(ManagerError::InvalidRule(_))));
+		assert!(matches!(provider_region(&vultr(), "atlantis"), Err(ManagerError::ProviderError(_))));
+	}
+
+	#[test]
+	fn provider_region_only_takes_regions_the_account_launches_in() {
+		let oracle = oracle();
+
+		assert_eq!(provider_region(&oracle, "eu-frankfurt-1").unwrap(), "eu-frankfurt-1");
+		assert_eq!(provider_region(&oracle, "fra").unwrap(), "eu-frankfurt-1");
+		assert_eq!(provider_region(&oracle, "eu-central").unwrap(), "eu-frankfurt-1");
+		assert!(matches!(provider_region(&oracle, "eu-amsterdam-1"), Err(ManagerError::InvalidRule(_))));
+		assert!(matches!(provider_region(&oracle, "eu-west"), Err(ManagerError::InvalidRule(_))));
+	}
+
+	#[test]
+	fn rule_region_keeps_areas_and_stores_metros_by_canonical_id() {
+		assert_eq!(rule_region(&vultr(), "EU-Central").unwrap(), "eu-central");
+		assert_eq!(rule_region(&hetzner(), "europe").unwrap(), "europe");
+		assert_eq!(rule_region(&hetzner(), "fsn1").unwrap(), "fsn");
+		assert_eq!(rule_region(&hetzner(), "ash").unwrap(), "iad");
+		assert_eq!(rule_region(&vultr(), "ewr").unwrap(), "ewr");
+		assert!(rule_region(&hetzner(), "sa-east").is_err());
+	}
+
+	#[test]
+	fn canonical_falls_back_to_the_code() {
+		assert_eq!(canonical(ProviderKind::Hetzner, "nbg1"), "nue");
+		assert_eq!(canonical(ProviderKind::Vultr, "lhr"), "lhr");
+		assert_eq!(canonical(ProviderKind::Vultr, "new1"), "new1");
+	}
+
+	#[test]
+	fn covers_matches_the_code_and_every_region_containing_it() {
+		assert!(covers(ProviderKind::Hetzner, "fsn1", "FSN1"));
+		assert!(covers(ProviderKind::Hetzner, "fsn1", "fsn"));
+		assert!(covers(ProviderKind::Hetzner, "fsn1", "eu-central"));
+		assert!(covers(ProviderKind::Hetzner, "fsn1", "europe"));
+		assert!(!covers(ProviderKind::Hetzner, "fsn1", "eu-north"));
+		assert!(!covers(ProviderKind::Hetzner, "fsn1", "nbg1"));
+		assert!(!covers(ProviderKind::Vultr, "new1", "europe"));
+	}
+}
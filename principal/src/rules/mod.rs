@@ -1 +1,2 @@
-pub mod rule;
\ No newline at end of file
+pub mod rule;
+pub mod store;
@@ -1,8 +1,80 @@
+use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
-#[derive(Debug, Clone, FromRow)]
+use crate::config::config::DEFAULT_ACCOUNT;
+use crate::providers::provider::{parse_label_tags, InstanceSpec, RULE_LABEL};
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 pub struct Rule {
+    // Row id in `Providers`; ignored when creating or updating a rule through the API.
+    #[serde(default)]
+    pub id: i64,
     pub provider: String,
+    // Named provider account the rule's instances live in.
+    #[serde(default = "default_account")]
+    pub account: String,
+    // Canonical region, area such as `eu-central`, continent or provider region code; see
+    // `regions::resolve`.
     pub region: Vec<String>,
     pub instance_count: i32,
-}
\ No newline at end of file
+    // Bounds on `instance_count`, which scaling may move within.
+    #[serde(default)]
+    pub min_count: Option<i32>,
+    #[serde(default)]
+    pub max_count: Option<i32>,
+    // Provider plan code; the account's configured plan when unset.
+    #[serde(default)]
+    pub plan: Option<String>,
+    // Makes the rule a GPU pool: only instances with this GPU model count towards it, and new
+    // ones get the cheapest plan of that model in the region unless `plan` is set. Vultr only.
+    #[serde(default)]
+    pub gpu_model: Option<String>,
+    // Least VRAM a GPU pool plan must have, which rules out the smaller fractional plans.
+    #[serde(default)]
+    pub gpu_vram_gb: Option<i32>,
+    // OS or image, in whatever form the provider takes it; see `InstanceSpec::image`.
+    #[serde(default)]
+    pub image: Option<String>,
+    #[serde(default)]
+    pub ssh_keys: Vec<String>,
+    // cloud-init user-data template, see `bootstrap::cloud_init::user_data`.
+    #[serde(default)]
+    pub user_data: Option<String>,
+    // `key=value` labels; Vultr, which only has tags, gets them as is.
+    #[serde(default)]
+    pub labels: Vec<String>,
+}
+
+impl Rule {
+    // Number of instances to keep running, clamped to the rule's bounds.
+    pub fn desired(&self) -> usize {
+        let mut count = self.instance_count;
+        if let Some(max_count) = self.max_count {
+            count = count.min(max_count);
+        }
+        if let Some(min_count) = self.min_count {
+            count = count.max(min_count);
+        }
+
+        count.max(0) as usize
+    }
+
+    // `user_data` is the rendered user-data, not the rule's template.
+    pub fn spec(&self, region: &str, user_data: Option<String>) -> InstanceSpec {
+        let mut labels = parse_label_tags(self.labels.iter().map(String::as_str));
+        labels.insert(RULE_LABEL.to_string(), self.id.to_string());
+
+        InstanceSpec {
+            region: region.to_string(),
+            plan: self.plan.clone(),
+            image: self.image.clone(),
+            ssh_keys: self.ssh_keys.clone(),
+            user_data,
+            labels,
+        }
+    }
+}
+
+fn default_account() -> String {
+    DEFAULT_ACCOUNT.to_string()
+}
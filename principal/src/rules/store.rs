@@ -0,0 +1,230 @@
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Serialize;
+use sqlx::postgres::PgPool;
+use sqlx::{FromRow, Postgres, Transaction};
+
+use crate::rules::rule::Rule;
+
+// Each `Providers` row holds a single region; it is wrapped in an array to fit `Rule`.
+const SELECT_RULES: &str = r#"
+	SELECT id::BIGINT AS id, provider, account, ARRAY[region] AS region, instance_count,
+		min_count, max_count, plan, gpu_model, gpu_vram_gb, image, ssh_keys, user_data, labels
+	FROM Providers
+"#;
+
+#[derive(Debug, Clone, Copy)]
+pub enum RuleAction {
+	Create,
+	Update,
+	Delete,
+}
+
+impl RuleAction {
+	fn code(&self) -> &'static str {
+		match self {
+			RuleAction::Create => "create",
+			RuleAction::Update => "update",
+			RuleAction::Delete => "delete",
+		}
+	}
+}
+
+// One change to a rule; `rule` is the rule as it was after the change (before it, for deletes).
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleChange {
+	pub id: i64,
+	pub rule_id: i64,
+	pub action: String,
+	pub rule: serde_json::Value,
+	pub changed_at: DateTime<Utc>,
+}
+
+#[derive(FromRow)]
+struct RuleChangeRow {
+	id: i64,
+	rule_id: i64,
+	action: String,
+	rule: String,
+	changed_at: i64,
+}
+
+impl From<RuleChangeRow> for RuleChange {
+	fn from(row: RuleChangeRow) -> Self {
+		RuleChange {
+			id: row.id,
+			rule_id: row.rule_id,
+			action: row.action,
+			rule: serde_json::from_str(&row.rule).unwrap_or(serde_json::Value::Null),
+			changed_at: Utc
+				.timestamp_opt(row.changed_at, 0)
+				.single()
+				.unwrap_or_else(Utc::now),
+		}
+	}
+}
+
+// Rules live in the `Providers` table, every change is also written to `RuleHistory`.
+pub struct RuleStore {
+	pool: PgPool,
+}
+
+impl RuleStore {
+	pub fn new(pool: PgPool) -> Self {
+		Self { pool }
+	}
+
+	pub async fn list(&self) -> Result<Vec<Rule>, sqlx::Error> {
+		sqlx::query_as::<_, Rule>(&format!("{} ORDER BY id", SELECT_RULES))
+			.fetch_all(&self.pool)
+			.await
+	}
+
+	pub async fn get(&self, id: i64) -> Result<Option<Rule>, sqlx::Error> {
+		sqlx::query_as::<_, Rule>(&format!("{} WHERE id = $1", SELECT_RULES))
+			.bind(id)
+			.fetch_optional(&self.pool)
+			.await
+	}
+
+	// Expects a validated rule with exactly one region.
+	pub async fn create(&self, rule: &Rule) -> Result<Rule, sqlx::Error> {
+		let mut tx = self.pool.begin().await?;
+
+		let id = sqlx::query_scalar::<_, i64>(
+			r#"
+			INSERT INTO Providers (provider, account, region, instance_count,
+				min_count, max_count, plan, gpu_model, gpu_vram_gb, image, ssh_keys, user_data, labels)
+			VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+			RETURNING id::BIGINT
+			"#,
+		)
+		.bind(&rule.provider)
+		.bind(&rule.account)
+		.bind(&rule.region[0])
+		.bind(rule.instance_count)
+		.bind(rule.min_count)
+		.bind(rule.max_count)
+		.bind(&rule.plan)
+		.bind(&rule.gpu_model)
+		.bind(rule.gpu_vram_gb)
+		.bind(&rule.image)
+		.bind(&rule.ssh_keys)
+		.bind(&rule.user_data)
+		.bind(&rule.labels)
+		.fetch_one(&mut tx)
+		.await?;
+
+		let rule = Rule { id, ..rule.clone() };
+		record(&mut tx, RuleAction::Create, &rule).await?;
+		tx.commit().await?;
+
+		Ok(rule)
+	}
+
+	// Returns `None` if there is no rule with that id.
+	pub async fn update(&self, id: i64, rule: &Rule) -> Result<Option<Rule>, sqlx::Error> {
+		let mut tx = self.pool.begin().await?;
+
+		let updated = sqlx::query(
+			r#"
+			UPDATE Providers
+			SET provider = $2, account = $3, region = $4, instance_count = $5,
+				min_count = $6, max_count = $7, plan = $8, gpu_model = $9, gpu_vram_gb = $10,
+				image = $11, ssh_keys = $12, user_data = $13, labels = $14
+			WHERE id = $1
+			"#,
+		)
+		.bind(id)
+		.bind(&rule.provider)
+		.bind(&rule.account)
+		.bind(&rule.region[0])
+		.bind(rule.instance_count)
+		.bind(rule.min_count)
+		.bind(rule.max_count)
+		.bind(&rule.plan)
+		.bind(&rule.gpu_model)
+		.bind(rule.gpu_vram_gb)
+		.bind(&rule.image)
+		.bind(&rule.ssh_keys)
+		.bind(&rule.user_data)
+		.bind(&rule.labels)
+		.execute(&mut tx)
+		.await?
+		.rows_affected();
+
+		if updated == 0 {
+			return Ok(None);
+		}
+
+		let rule = Rule { id, ..rule.clone() };
+		record(&mut tx, RuleAction::Update, &rule).await?;
+		tx.commit().await?;
+
+		Ok(Some(rule))
+	}
+
+	// Returns the deleted rule, or `None` if there was none with that id.
+	pub async fn delete(&self, id: i64) -> Result<Option<Rule>, sqlx::Error> {
+		let mut tx = self.pool.begin().await?;
+
+		let rule = sqlx::query_as::<_, Rule>(&format!("{} WHERE id = $1 FOR UPDATE", SELECT_RULES))
+			.bind(id)
+			.fetch_optional(&mut tx)
+			.await?;
+
+		let rule = match rule {
+			Some(rule) => rule,
+			None => return Ok(None),
+		};
+
+		sqlx::query("DELETE FROM Providers WHERE id = $1")
+			.bind(id)
+			.execute(&mut tx)
+			.await?;
+
+		record(&mut tx, RuleAction::Delete, &rule).await?;
+		tx.commit().await?;
+
+		Ok(Some(rule))
+	}
+
+	// Newest first, optionally for a single rule.
+	pub async fn history(&self, rule_id: Option<i64>) -> Result<Vec<RuleChange>, sqlx::Error> {
+		let rows = sqlx::query_as::<_, RuleChangeRow>(
+			r#"
+			SELECT id::BIGINT AS id, rule_id, action, rule, EXTRACT(EPOCH FROM changed_at)::BIGINT AS changed_at
+			FROM RuleHistory
+			WHERE $1::BIGINT IS NULL OR rule_id = $1
+			ORDER BY id DESC
+			"#,
+		)
+		.bind(rule_id)
+		.fetch_all(&self.pool)
+		.await?;
+
+		Ok(rows.into_iter().map(RuleChange::from).collect())
+	}
+
+	// Id of the latest change; the manager reloads its rules when this moves. Edits made
+	// straight to `Providers` without going through the store are not picked up.
+	pub async fn version(&self) -> Result<i64, sqlx::Error> {
+		sqlx::query_scalar::<_, i64>("SELECT COALESCE(MAX(id), 0)::BIGINT FROM RuleHistory")
+			.fetch_one(&self.pool)
+			.await
+	}
+}
+
+async fn record(
+	tx: &mut Transaction<'_, Postgres>,
+	action: RuleAction,
+	rule: &Rule,
+) -> Result<(), sqlx::Error> {
+	sqlx::query("INSERT INTO RuleHistory (rule_id, action, rule) VALUES ($1, $2, $3)")
+		.bind(rule.id)
+		.bind(action.code())
+		.bind(serde_json::to_string(rule).unwrap_or_default())
+		.execute(tx)
+		.await?;
+
+	Ok(())
+}
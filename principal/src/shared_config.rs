@@ -1,38 +1,84 @@
+use std::sync::Arc;
+use std::time::Duration;
+
 use reqwest::Client;
 
+use crate::config::config::Config;
+use crate::costs::tracker::CostTracker;
+use crate::providers::http::HttpClient;
+
 pub struct SharedConfig {
+	pub config: Config,
 	pub clients: ProviderClients,
+	// Shared so every create path checks the same budgets.
+	pub costs: Arc<CostTracker>,
+}
+
+impl SharedConfig {
+	pub fn new(config: Config) -> Self {
+		SharedConfig {
+			costs: Arc::new(CostTracker::new(config.costs.clone())),
+			config,
+			clients: ProviderClients {
+				vultr: None,
+				hetzner: None,
+				oracle: None,
+				hosthatch: None,
+			},
+		}
+	}
 }
 
 pub struct ProviderClients {
-	pub vultr: Option<Client>,
-	pub hetzner: Option<Client>,
-	pub oracle: Option<Client>,
+	pub vultr: Option<HttpClient>,
+	pub hetzner: Option<HttpClient>,
+	pub oracle: Option<HttpClient>,
+	pub hosthatch: Option<HttpClient>,
 }
 
 impl ProviderClients {
-	pub fn vultr(&mut self) -> &Client {
+	pub fn vultr(&mut self) -> &HttpClient {
 		if self.vultr.is_none() {
-			self.vultr = Some(Client::builder().use_rustls_tls().build().unwrap());
-		} 
+			self.vultr = Some(http_client());
+		}
 
 		self.vultr.as_ref().unwrap()
 	}
 
-	pub fn hetzner(&mut self) -> &Client {
+	pub fn hetzner(&mut self) -> &HttpClient {
 		if self.hetzner.is_none() {
-			self.hetzner = Some(Client::builder().use_rustls_tls().build().unwrap());
+			self.hetzner = Some(http_client());
 		}
 
 		self.hetzner.as_ref().unwrap()
 	}
 
 
-	pub fn oracle(&mut self) -> &Client {
+	pub fn oracle(&mut self) -> &HttpClient {
 		if self.oracle.is_none() {
-			self.oracle = Some(Client::builder().use_rustls_tls().build().unwrap());
+			self.oracle = Some(http_client());
 		}
 
 		self.oracle.as_ref().unwrap()
 	}
+
+	pub fn hosthatch(&mut self) -> &HttpClient {
+		if self.hosthatch.is_none() {
+			self.hosthatch = Some(http_client());
+		}
+
+		self.hosthatch.as_ref().unwrap()
+	}
+}
+
+// Each provider gets its own client so a rate limit on one doesn't pause the others.
+fn http_client() -> HttpClient {
+	HttpClient::new(
+		Client::builder()
+			.use_rustls_tls()
+			.connect_timeout(Duration::from_secs(10))
+			.timeout(Duration::from_secs(60))
+			.build()
+			.unwrap(),
+	)
 }
@@ -0,0 +1,39 @@
+use chrono::Utc;
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+use models::models::instance_state::InstanceState;
+
+use super::database;
+use crate::lifecycle::store::LifecycleStore;
+use crate::lifecycle::tracker::{EventKind, Lifecycle, LifecycleEvent};
+
+fn event(instance_id: &str, from: Option<InstanceState>, to: InstanceState) -> LifecycleEvent {
+	LifecycleEvent {
+		instance_id: instance_id.to_string(),
+		provider: ProviderKind::Hetzner,
+		account: "default".to_string(),
+		region: "fsn1".to_string(),
+		kind: EventKind::Transition,
+		from,
+		to,
+		at: Utc::now(),
+	}
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres database in TEST_DATABASE_URL"]
+async fn the_tracker_is_rebuilt_from_the_latest_events() {
+	let store = LifecycleStore::new(database().await);
+	let suffix = Utc::now().timestamp_nanos_opt().unwrap();
+	let (kept, gone) = (format!("kept-{}", suffix), format!("gone-{}", suffix));
+
+	store.record(&event(&kept, None, InstanceState::Starting)).await.unwrap();
+	store.record(&event(&kept, Some(InstanceState::Starting), InstanceState::Running)).await.unwrap();
+	store.record(&event(&gone, None, InstanceState::Running)).await.unwrap();
+	store.record(&event(&gone, Some(InstanceState::Running), InstanceState::Terminated)).await.unwrap();
+
+	let lifecycle = Lifecycle::new(chrono::Duration::minutes(15));
+	lifecycle.restore(&store.current().await.unwrap());
+
+	assert_eq!(lifecycle.state(ProviderKind::Hetzner, &kept), Some(InstanceState::Running));
+	assert_eq!(lifecycle.state(ProviderKind::Hetzner, &gone), None);
+}
@@ -0,0 +1,57 @@
+// End-to-end tests against an in-process services/fake-cloud. Tests that also need the stores
+// are ignored by default; run them with `cargo test -- --ignored` and TEST_DATABASE_URL set to a
+// scratch Postgres database.
+
+use std::env;
+use std::net::TcpListener;
+use std::time::Duration;
+
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use sqlx::Executor;
+
+use crate::config::config::{Config, ProviderConfig};
+use crate::shared_config::SharedConfig;
+
+mod lifecycle;
+mod plans;
+mod reconcile;
+mod snapshots;
+mod volumes;
+mod workers;
+
+// The principal's tables, the same file scripts/manager_data.sh applies.
+const SCHEMA: &str = include_str!("../../../scripts/manager_schema.sql");
+
+// Starts a fake cloud with no provisioning delay for the rest of the test and returns its URL.
+pub fn fake_cloud() -> String {
+	let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+	let url = format!("http://{}", listener.local_addr().unwrap());
+	tokio::spawn(::fake_cloud::serve(listener, Duration::ZERO, None));
+
+	url
+}
+
+// Config with Vultr and Hetzner pointed at the fake cloud at `url`.
+pub fn shared_config(url: &str) -> SharedConfig {
+	let mut config = Config::default();
+	config.providers.vultr = Some(ProviderConfig {
+		api_key: "vultr-token".to_string(),
+		base_url: Some(format!("{}/v2", url)),
+		..ProviderConfig::default()
+	});
+	config.providers.hetzner = Some(ProviderConfig {
+		api_key: "hetzner-token".to_string(),
+		base_url: Some(format!("{}/v1", url)),
+		..ProviderConfig::default()
+	});
+
+	SharedConfig::new(config)
+}
+
+pub async fn database() -> PgPool {
+	let url = env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL must point at a scratch database");
+	let pool = PgPoolOptions::new().connect(&url).await.unwrap();
+	pool.execute(SCHEMA).await.unwrap();
+
+	pool
+}
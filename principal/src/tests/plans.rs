@@ -0,0 +1,31 @@
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+
+use super::{fake_cloud, shared_config};
+use crate::plans::catalog::{Arch, Currency, PlanCatalog};
+
+#[tokio::test]
+async fn catalog_lists_the_compute_plans_of_both_providers() {
+	let catalog = PlanCatalog::new(&mut shared_config(&fake_cloud()));
+	let plans = catalog.plans().await.unwrap();
+	let plan = |provider: ProviderKind, id: &str| {
+		plans
+			.iter()
+			.find(|plan| plan.provider == provider && plan.id == id)
+			.unwrap_or_else(|| panic!("{} plan {} is listed", provider, id))
+	};
+
+	// GPU plans are left to `gpu::catalog`.
+	assert!(plans.iter().all(|plan| !plan.id.starts_with("vcg-")));
+
+	let vultr = plan(ProviderKind::Vultr, "vc2-1c-1gb");
+	assert_eq!((vultr.vcpu, vultr.ram_mb, vultr.currency), (1, 1024, Currency::Usd));
+	assert_eq!(vultr.monthly, 5.0);
+	assert!((vultr.hourly - 5.0 / 672.0).abs() < 1e-9);
+
+	// Hetzner prices every location the same, which makes one plan.
+	let hetzner = plan(ProviderKind::Hetzner, "cax11");
+	assert_eq!((hetzner.vcpu, hetzner.ram_mb, hetzner.arch, hetzner.currency), (2, 4096, Arch::Arm, Currency::Eur));
+	assert_eq!(hetzner.monthly, 7.0);
+	assert_eq!(hetzner.regions, ["fsn1", "nbg1", "hel1", "ash", "hil"]);
+	assert_eq!(plans.iter().filter(|plan| plan.provider == ProviderKind::Hetzner && plan.id == "cax11").count(), 1);
+}
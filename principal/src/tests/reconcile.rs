@@ -0,0 +1,171 @@
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+use models::models::instance_state::InstanceState;
+
+use super::{fake_cloud, shared_config};
+use crate::config::config::DEFAULT_ACCOUNT;
+use crate::manager::plan::ReconcilePlan;
+use crate::manager::reconciler::reconcile;
+use crate::providers::hetzner::provider::Hetzner;
+use crate::providers::provider::{CloudProvider, InstanceSpec, ProviderInstance};
+use crate::providers::vultr::provider::Vultr;
+use crate::providers::wait::WaitOptions;
+use crate::rules::rule::Rule;
+
+fn providers(url: &str) -> Vec<Box<dyn CloudProvider>> {
+	let mut shared_config = shared_config(url);
+	let (vultr, hetzner) = (shared_config.config.vultr(), shared_config.config.hetzner());
+
+	vec![
+		Box::new(Vultr::new(shared_config.clients.vultr().clone(), vultr.api_key).base_url(vultr.base_url.unwrap())),
+		Box::new(
+			Hetzner::new(shared_config.clients.hetzner().clone(), hetzner.api_key).base_url(hetzner.base_url.unwrap()),
+		),
+	]
+}
+
+fn rule(provider: &str, region: &str, plan: &str, image: &str, instance_count: i32) -> Rule {
+	Rule {
+		id: 1,
+		provider: provider.to_string(),
+		account: DEFAULT_ACCOUNT.to_string(),
+		region: vec![region.to_string()],
+		instance_count,
+		min_count: None,
+		max_count: None,
+		plan: Some(plan.to_string()),
+		gpu_model: None,
+		gpu_vram_gb: None,
+		image: Some(image.to_string()),
+		ssh_keys: Vec::new(),
+		user_data: None,
+		labels: vec!["role=worker".to_string()],
+	}
+}
+
+async fn list(providers: &[Box<dyn CloudProvider>]) -> Vec<ProviderInstance> {
+	let mut instances = Vec::new();
+	for provider in providers {
+		instances.extend(provider.list().await.unwrap());
+	}
+
+	instances
+}
+
+// One pass the way `Manager::manage` makes it: list, diff against the rules, create what is
+// missing and halt the surplus. Returns the plan it carried out.
+async fn reconcile_once(providers: &[Box<dyn CloudProvider>], rules: &[Rule]) -> ReconcilePlan {
+	let find = |kind: &ProviderKind, account: &str| {
+		providers
+			.iter()
+			.find(|provider| provider.kind() == *kind && provider.account() == account)
+			.map(|provider| provider.as_ref())
+	};
+
+	let instances = list(providers).await;
+	let (states, errors) = reconcile(rules, &instances, find);
+	let plan = ReconcilePlan::new(&states, &errors);
+
+	for planned in &plan.create {
+		let provider = find(&planned.provider, &planned.account).unwrap();
+		let instance = provider.create(&planned.rule.spec(&planned.region, None)).await.unwrap();
+		provider.wait_until_created(&instance, &WaitOptions::default()).await.unwrap();
+	}
+	for planned in &plan.halt {
+		let provider = find(&planned.provider, &planned.account).unwrap();
+		provider.halt(planned.instance_id.as_deref().unwrap()).await.unwrap();
+	}
+
+	plan
+}
+
+fn count(plan: &ReconcilePlan) -> (usize, usize, usize) {
+	(plan.create.len(), plan.halt.len(), plan.keep.len())
+}
+
+#[tokio::test]
+async fn reconcile_converges_on_the_rules() {
+	let providers = providers(&fake_cloud());
+	let mut rules = vec![
+		rule("vultr", "ewr", "vhf-1c-1gb", "1743", 2),
+		rule("hetzner", "eu-central", "cx21", "ubuntu-22.04", 1),
+	];
+
+	assert_eq!(count(&reconcile_once(&providers, &rules).await), (3, 0, 0));
+
+	let instances = list(&providers).await;
+	assert_eq!(instances.len(), 3);
+	assert!(instances.iter().all(|instance| instance.state == InstanceState::Running));
+	assert_eq!(
+		instances.iter().filter(|instance| instance.provider == ProviderKind::Hetzner && instance.region == "fsn1").count(),
+		1
+	);
+
+	// Nothing changes once the rules are met.
+	assert_eq!(count(&reconcile_once(&providers, &rules).await), (0, 0, 3));
+
+	// Scaling the Vultr rule down halts one instance, which then no longer counts.
+	rules[0].instance_count = 1;
+	assert_eq!(count(&reconcile_once(&providers, &rules).await), (0, 1, 2));
+	assert_eq!(count(&reconcile_once(&providers, &rules).await), (0, 0, 2));
+
+	let stopped: Vec<_> = list(&providers)
+		.await
+		.into_iter()
+		.filter(|instance| instance.state == InstanceState::Stopped)
+		.collect();
+	assert_eq!(stopped.len(), 1);
+	assert_eq!(stopped[0].provider, ProviderKind::Vultr);
+
+	// Scaling back up creates a new instance rather than starting the halted one.
+	rules[0].instance_count = 2;
+	assert_eq!(count(&reconcile_once(&providers, &rules).await), (1, 0, 2));
+	assert_eq!(list(&providers).await.len(), 4);
+}
+
+#[tokio::test]
+async fn reconcile_reports_rules_the_provider_cannot_serve() {
+	let providers = providers(&fake_cloud());
+	let rules = [
+		rule("hetzner", "sa-east", "cx21", "ubuntu-22.04", 1),
+		rule("hosthatch", "ams", "", "", 1),
+	];
+
+	let plan = reconcile_once(&providers, &rules).await;
+
+	assert_eq!(count(&plan), (0, 0, 0));
+	assert_eq!(plan.errors.len(), 2);
+	assert!(list(&providers).await.is_empty());
+}
+
+#[tokio::test]
+async fn reconcile_leaves_instances_it_did_not_create_alone() {
+	let providers = providers(&fake_cloud());
+	let unmanaged = InstanceSpec {
+		region: "ewr".to_string(),
+		plan: Some("vhf-1c-1gb".to_string()),
+		image: Some("1743".to_string()),
+		..InstanceSpec::default()
+	};
+	for _ in 0..2 {
+		let instance = providers[0].create(&unmanaged).await.unwrap();
+		providers[0].wait_until_created(&instance, &WaitOptions::default()).await.unwrap();
+	}
+	let mut rules = vec![rule("vultr", "ewr", "vhf-1c-1gb", "1743", 1)];
+
+	assert_eq!(count(&reconcile_once(&providers, &rules).await), (1, 0, 0));
+
+	rules[0].instance_count = 0;
+	let plan = reconcile_once(&providers, &rules).await;
+	assert_eq!(count(&plan), (0, 1, 0));
+
+	let instances = list(&providers).await;
+	let halted = plan.halt[0].instance_id.as_deref().unwrap();
+	assert_eq!(instances.iter().find(|instance| instance.id == halted).unwrap().rule_id(), Some(1));
+	assert_eq!(
+		instances
+			.iter()
+			.filter(|instance| instance.rule_id().is_none() && instance.state == InstanceState::Running)
+			.count(),
+		2
+	);
+}
@@ -0,0 +1,270 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use chrono::{Duration, Utc};
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+use models::models::volume::{Volume, VolumeState};
+use tokio::net::TcpListener;
+use tonic::transport::Server;
+use tonic::{Request, Response, Status};
+
+use super::volumes::{create_instance, provider};
+use super::workers::worker;
+use super::{database, fake_cloud, shared_config};
+use crate::docker::docker_service_server::{DockerService, DockerServiceServer};
+use crate::docker::{
+	CopyVolumeRequest, CopyVolumeResponse, CreatePodResponse, DeleteContainerRequest, DeleteContainerResponse,
+	GrowFilesystemRequest, GrowFilesystemResponse, ListContainersRequest, ListContainersResponse, MountVolumeRequest,
+	MountVolumeResponse, Pod, StartContainerRequest, StartContainerResponse, StopContainerRequest,
+	StopContainerResponse, UnmountVolumeRequest, UnmountVolumeResponse,
+};
+use crate::volumes::snapshots::{NewSnapshotPolicy, RestoreSnapshot, SnapshotState};
+use crate::volumes::volumes::{AttachVolume, CreateVolume, VolumeManager};
+use crate::workers::store::WorkerStore;
+
+// Stands in for a worker's `DockerService`. A volume's filesystem is a string, which copying
+// carries over from one mounted volume to another.
+#[derive(Clone, Default)]
+struct FakeWorker {
+	disks: Arc<Mutex<HashMap<String, String>>>,
+	mounted: Arc<Mutex<HashSet<String>>>,
+}
+
+impl FakeWorker {
+	fn write(&self, volume_id: &str, data: &str) {
+		self.disks.lock().unwrap().insert(volume_id.to_string(), data.to_string());
+	}
+
+	fn read(&self, volume_id: &str) -> String {
+		self.disks.lock().unwrap().get(volume_id).cloned().unwrap_or_default()
+	}
+
+	fn is_mounted(&self, volume_id: &str) -> bool {
+		self.mounted.lock().unwrap().contains(volume_id)
+	}
+
+	// Serves the worker on a local port for the rest of the test and returns its address.
+	async fn serve(&self) -> String {
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let addr = listener.local_addr().unwrap().to_string();
+		let incoming = futures::stream::unfold(listener, |listener| async move {
+			let stream = listener.accept().await.map(|(stream, _)| stream);
+			Some((stream, listener))
+		});
+		tokio::spawn(Server::builder().add_service(DockerServiceServer::new(self.clone())).serve_with_incoming(incoming));
+
+		addr
+	}
+}
+
+#[tonic::async_trait]
+impl DockerService for FakeWorker {
+	async fn create_pod(&self, _: Request<Pod>) -> Result<Response<CreatePodResponse>, Status> {
+		Err(Status::unimplemented("create_pod"))
+	}
+
+	async fn start_container(&self, _: Request<StartContainerRequest>) -> Result<Response<StartContainerResponse>, Status> {
+		Err(Status::unimplemented("start_container"))
+	}
+
+	async fn stop_container(&self, _: Request<StopContainerRequest>) -> Result<Response<StopContainerResponse>, Status> {
+		Err(Status::unimplemented("stop_container"))
+	}
+
+	async fn delete_container(&self, _: Request<DeleteContainerRequest>) -> Result<Response<DeleteContainerResponse>, Status> {
+		Err(Status::unimplemented("delete_container"))
+	}
+
+	async fn list_containers(&self, _: Request<ListContainersRequest>) -> Result<Response<ListContainersResponse>, Status> {
+		Ok(Response::new(ListContainersResponse { names: Vec::new() }))
+	}
+
+	async fn grow_filesystem(&self, _: Request<GrowFilesystemRequest>) -> Result<Response<GrowFilesystemResponse>, Status> {
+		Err(Status::unimplemented("grow_filesystem"))
+	}
+
+	async fn mount_volume(&self, request: Request<MountVolumeRequest>) -> Result<Response<MountVolumeResponse>, Status> {
+		self.mounted.lock().unwrap().insert(request.into_inner().volume_id);
+		Ok(Response::new(MountVolumeResponse { total_bytes: 0 }))
+	}
+
+	async fn unmount_volume(&self, request: Request<UnmountVolumeRequest>) -> Result<Response<UnmountVolumeResponse>, Status> {
+		self.mounted.lock().unwrap().remove(&request.into_inner().volume_id);
+		Ok(Response::new(UnmountVolumeResponse {}))
+	}
+
+	async fn copy_volume(&self, request: Request<CopyVolumeRequest>) -> Result<Response<CopyVolumeResponse>, Status> {
+		let request = request.into_inner();
+		for volume_id in [&request.from_volume_id, &request.to_volume_id] {
+			if !self.is_mounted(volume_id) {
+				return Err(Status::failed_precondition(format!("volume {} is not mounted", volume_id)));
+			}
+		}
+
+		let data = self.read(&request.from_volume_id);
+		self.write(&request.to_volume_id, &data);
+		Ok(Response::new(CopyVolumeResponse { used_bytes: data.len() as u64 }))
+	}
+}
+
+// A volume attached to an instance whose worker mounted it.
+struct Attached {
+	volumes: VolumeManager,
+	worker: FakeWorker,
+	worker_id: u64,
+	instance_id: String,
+	volume: Volume,
+}
+
+async fn attached(kind: ProviderKind, region: &str, plan: &str, image: &str) -> Attached {
+	let mut shared_config = shared_config(&fake_cloud());
+	let provider = provider(&mut shared_config, kind, "default");
+	let pool = database().await;
+	let volumes = VolumeManager::new(&mut shared_config, pool.clone());
+
+	let instance_id = create_instance(provider.as_ref(), region, plan, image).await;
+
+	let fake = FakeWorker::default();
+	let mut registered = worker("127.0.0.1");
+	registered.provider = kind;
+	registered.instance_id = Some(instance_id.clone());
+	registered.grpc_addr = fake.serve().await;
+	let worker_id = WorkerStore::new(pool).register(&registered, "credential", "token").await.unwrap().id;
+
+	let volume = volumes
+		.create(&CreateVolume {
+			provider: kind,
+			region: region.to_string(),
+			size_gb: 40,
+			label: "data".to_string(),
+			block_type: None,
+		})
+		.await
+		.unwrap();
+	let attach = AttachVolume {
+		instance_id: instance_id.clone(),
+		worker_id: Some(worker_id),
+		container: Some("postgres".to_string()),
+		live: true,
+	};
+	let volume = volumes.attach(kind, &volume.id, &attach).await.unwrap();
+	assert!(fake.is_mounted(&volume.id));
+
+	Attached {
+		volumes,
+		worker: fake,
+		worker_id,
+		instance_id,
+		volume,
+	}
+}
+
+// Snapshots a volume, restores the snapshot onto a new volume on the same instance and deletes it.
+async fn snapshot_and_restore(kind: ProviderKind, region: &str, plan: &str, image: &str) {
+	let Attached {
+		volumes,
+		worker,
+		worker_id,
+		instance_id,
+		volume,
+	} = attached(kind, region, plan, image).await;
+	worker.write(&volume.id, "first");
+
+	let snapshot = volumes.create_snapshot(kind, &volume.id, "nightly").await.unwrap();
+	assert_eq!(snapshot.volume_id, volume.id);
+	assert_eq!(snapshot.label, "nightly");
+	assert_eq!(snapshot.size_gb, 40);
+	assert_eq!(snapshot.state, SnapshotState::Available);
+	assert_eq!(worker.read(&snapshot.id), "first");
+	assert!(!worker.is_mounted(&snapshot.id));
+
+	// Snapshots are kept on volumes of their own, which aren't listed as volumes.
+	let listed = volumes.list(Some(kind)).await.unwrap();
+	assert!(listed.iter().any(|listed| listed.id == volume.id));
+	assert!(listed.iter().all(|listed| listed.id != snapshot.id));
+	assert!(volumes.create_snapshot(kind, &volume.id, "nightly").await.is_err());
+	assert!(volumes.create_snapshot(kind, &volume.id, "not a label").await.is_err());
+
+	worker.write(&volume.id, "second");
+	let restore = RestoreSnapshot {
+		instance_id: instance_id.clone(),
+		worker_id: None,
+		container: Some("postgres-restored".to_string()),
+		size_gb: None,
+		label: String::new(),
+	};
+	let restored = volumes.restore_snapshot(kind, &volume.id, &snapshot.id, &restore).await.unwrap();
+	assert_eq!(restored.label, "restore-nightly");
+	assert_eq!(restored.state, VolumeState::Attached);
+	assert_eq!(restored.instance_id.as_deref(), Some(instance_id.as_str()));
+	assert_eq!(restored.worker_id, Some(worker_id));
+	assert_eq!(restored.container.as_deref(), Some("postgres-restored"));
+	assert_eq!(worker.read(&restored.id), "first");
+	assert!(worker.is_mounted(&restored.id));
+	assert!(!worker.is_mounted(&snapshot.id));
+
+	volumes.delete_snapshot(kind, &volume.id, &snapshot.id).await.unwrap();
+	assert!(volumes.list_snapshots(kind, &volume.id).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres database in TEST_DATABASE_URL"]
+async fn vultr_volumes_snapshot_and_restore() {
+	snapshot_and_restore(ProviderKind::Vultr, "ewr", "vhf-1c-1gb", "1743").await;
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres database in TEST_DATABASE_URL"]
+async fn hetzner_volumes_snapshot_and_restore() {
+	snapshot_and_restore(ProviderKind::Hetzner, "fsn1", "cx21", "ubuntu-22.04").await;
+}
+
+// Runs a policy as it comes due and checks it keeps only as many snapshots as it retains.
+async fn policy_snapshots_and_prunes(kind: ProviderKind, region: &str, plan: &str, image: &str) {
+	let Attached { volumes, volume, .. } = attached(kind, region, plan, image).await;
+
+	// Policies left by earlier runs may name volumes of this fake cloud.
+	for policy in volumes.snapshot_policies().await.unwrap() {
+		volumes.delete_snapshot_policy(policy.id).await.unwrap();
+	}
+	let policy = volumes
+		.create_snapshot_policy(&NewSnapshotPolicy {
+			provider: kind,
+			volume_id: volume.id.clone(),
+			interval_secs: 3600,
+			retention: 1,
+			enabled: true,
+		})
+		.await
+		.unwrap();
+
+	let now = Utc::now();
+	volumes.run_due_snapshot_policies(now).await;
+	volumes.run_due_snapshot_policies(now + Duration::minutes(1)).await;
+	volumes.run_due_snapshot_policies(now + Duration::hours(1)).await;
+
+	let runs = volumes.snapshot_runs(Some(policy.id)).await.unwrap();
+	assert_eq!(runs.len(), 2);
+	assert!(runs.iter().all(|run| run.succeeded), "{:?}", runs);
+	assert_eq!(runs[0].pruned, 1);
+	assert_eq!(runs[1].pruned, 0);
+
+	let snapshots = volumes.list_snapshots(kind, &volume.id).await.unwrap();
+	assert_eq!(snapshots.len(), 1);
+	assert_eq!(snapshots[0].label, policy.label(now + Duration::hours(1)));
+	assert_eq!(runs[0].snapshot_id.as_deref(), Some(snapshots[0].id.as_str()));
+
+	volumes.delete_snapshot_policy(policy.id).await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres database in TEST_DATABASE_URL"]
+async fn vultr_snapshot_policies_snapshot_and_prune() {
+	policy_snapshots_and_prunes(ProviderKind::Vultr, "ewr", "vhf-1c-1gb", "1743").await;
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres database in TEST_DATABASE_URL"]
+async fn hetzner_snapshot_policies_snapshot_and_prune() {
+	policy_snapshots_and_prunes(ProviderKind::Hetzner, "fsn1", "cx21", "ubuntu-22.04").await;
+}
@@ -0,0 +1,237 @@
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+use models::models::volume::VolumeState;
+use serde_json::{json, Value};
+
+use super::workers::worker;
+use super::{database, fake_cloud, shared_config};
+use crate::shared_config::SharedConfig;
+use crate::providers::hetzner::provider::Hetzner;
+use crate::providers::provider::{CloudProvider, InstanceSpec};
+use crate::providers::vultr::provider::Vultr;
+use crate::providers::wait::WaitOptions;
+use crate::volumes::volumes::{AttachVolume, CreateVolume, VolumeManager};
+
+// A client for the fake cloud's Vultr or Hetzner API, authenticating as `account`.
+pub(super) fn provider(shared_config: &mut SharedConfig, kind: ProviderKind, account: &str) -> Box<dyn CloudProvider> {
+	match kind {
+		ProviderKind::Vultr => {
+			let vultr = shared_config.config.vultr();
+			Box::new(
+				Vultr::new(shared_config.clients.vultr().clone(), vultr.api_key)
+					.base_url(vultr.base_url.unwrap())
+					.account(account.to_string()),
+			)
+		}
+		_ => {
+			let hetzner = shared_config.config.hetzner();
+			Box::new(
+				Hetzner::new(shared_config.clients.hetzner().clone(), hetzner.api_key)
+					.base_url(hetzner.base_url.unwrap())
+					.account(account.to_string()),
+			)
+		}
+	}
+}
+
+pub(super) async fn create_instance(provider: &dyn CloudProvider, region: &str, plan: &str, image: &str) -> String {
+	let spec = InstanceSpec {
+		region: region.to_string(),
+		plan: Some(plan.to_string()),
+		image: Some(image.to_string()),
+		..InstanceSpec::default()
+	};
+	let instance = provider.create(&spec).await.unwrap();
+
+	provider.wait_until_created(&instance, &WaitOptions::default()).await.unwrap().id
+}
+
+// Creates a volume and an instance in `region`, attaches the one to the other and detaches it
+// again, checking what the manager reports and stores at each step.
+async fn attach_and_detach(kind: ProviderKind, region: &str, plan: &str, image: &str) {
+	let mut shared_config = shared_config(&fake_cloud());
+	let provider = provider(&mut shared_config, kind, "default");
+	let volumes = VolumeManager::new(&mut shared_config, database().await);
+
+	let instance_id = create_instance(provider.as_ref(), region, plan, image).await;
+
+	let volume = volumes
+		.create(&CreateVolume {
+			provider: kind,
+			region: region.to_string(),
+			size_gb: 40,
+			label: "data".to_string(),
+			block_type: None,
+		})
+		.await
+		.unwrap();
+	assert_eq!(volume.state, VolumeState::Available);
+	assert_eq!(volume.instance_id, None);
+
+	let attached = volumes
+		.attach(
+			kind,
+			&volume.id,
+			&AttachVolume {
+				instance_id: instance_id.clone(),
+				worker_id: None,
+				container: Some("postgres".to_string()),
+				live: true,
+			},
+		)
+		.await
+		.unwrap();
+	assert_eq!(attached.state, VolumeState::Attached);
+	assert_eq!(attached.instance_id.as_deref(), Some(instance_id.as_str()));
+	assert_eq!(attached.container.as_deref(), Some("postgres"));
+
+	// The stored volume keeps the container, which the provider doesn't know about.
+	let stored = volumes.get(kind, &volume.id).await.unwrap();
+	assert_eq!(stored.instance_id.as_deref(), Some(instance_id.as_str()));
+	assert_eq!(stored.container.as_deref(), Some("postgres"));
+
+	let detached = volumes.detach(kind, &volume.id).await.unwrap();
+	assert_eq!(detached.state, VolumeState::Available);
+	assert_eq!(detached.instance_id, None);
+	assert_eq!(detached.container, None);
+
+	let stored = volumes.get(kind, &volume.id).await.unwrap();
+	assert_eq!(stored.state, VolumeState::Available);
+	assert_eq!(stored.instance_id, None);
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres database in TEST_DATABASE_URL"]
+async fn vultr_volumes_attach_and_detach() {
+	attach_and_detach(ProviderKind::Vultr, "ewr", "vhf-1c-1gb", "1743").await;
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres database in TEST_DATABASE_URL"]
+async fn hetzner_volumes_attach_and_detach() {
+	attach_and_detach(ProviderKind::Hetzner, "fsn1", "cx21", "ubuntu-22.04").await;
+}
+
+// Only the worker of the instance a volume is attached to may report its usage.
+async fn usage_from_the_owner_only(kind: ProviderKind, region: &str, plan: &str, image: &str) {
+	let mut shared_config = shared_config(&fake_cloud());
+	let provider = provider(&mut shared_config, kind, "default");
+	let volumes = VolumeManager::new(&mut shared_config, database().await);
+
+	let instance_id = create_instance(provider.as_ref(), region, plan, image).await;
+	let volume = volumes
+		.create(&CreateVolume {
+			provider: kind,
+			region: region.to_string(),
+			size_gb: 40,
+			label: "data".to_string(),
+			block_type: None,
+		})
+		.await
+		.unwrap();
+
+	// Ids no registered worker has, so attaching doesn't try to reach a worker to mount the volume.
+	let mut owner = worker("192.0.2.10");
+	owner.id = 1_000_010;
+	owner.provider = kind;
+	owner.instance_id = Some(instance_id.clone());
+	let mut elsewhere = owner.clone();
+	elsewhere.id = 1_000_011;
+	elsewhere.instance_id = Some("elsewhere".to_string());
+	let mut impostor = owner.clone();
+	impostor.id = 1_000_012;
+
+	// Detached volumes belong to no worker.
+	assert!(volumes.record_usage(&owner, &volume.id, 1 << 30).await.is_err());
+
+	let attach = AttachVolume {
+		instance_id,
+		worker_id: Some(owner.id),
+		container: None,
+		live: true,
+	};
+	volumes.attach(kind, &volume.id, &attach).await.unwrap();
+
+	assert!(volumes.record_usage(&elsewhere, &volume.id, 1 << 30).await.is_err());
+	assert!(volumes.record_usage(&impostor, &volume.id, 1 << 30).await.is_err());
+	assert_eq!(volumes.get(kind, &volume.id).await.unwrap().used, 0);
+
+	volumes.record_usage(&owner, &volume.id, 5 << 30).await.unwrap();
+	let stored = volumes.get(kind, &volume.id).await.unwrap();
+	assert_eq!(stored.used, 5);
+	assert_eq!(stored.worker_id, Some(owner.id));
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres database in TEST_DATABASE_URL"]
+async fn vultr_volume_usage_is_only_taken_from_its_worker() {
+	usage_from_the_owner_only(ProviderKind::Vultr, "ewr", "vhf-1c-1gb", "1743").await;
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres database in TEST_DATABASE_URL"]
+async fn hetzner_volume_usage_is_only_taken_from_its_worker() {
+	usage_from_the_owner_only(ProviderKind::Hetzner, "fsn1", "cx21", "ubuntu-22.04").await;
+}
+
+// Volumes of accounts other than the default one are detached through the account's own client.
+async fn detach_through_the_provider(kind: ProviderKind, region: &str, plan: &str, image: &str) {
+	let url = fake_cloud();
+	let mut shared_config = shared_config(&url);
+	let provider = provider(&mut shared_config, kind, "staging");
+	let instance_id = create_instance(provider.as_ref(), region, plan, image).await;
+
+	let client = reqwest::Client::new();
+	match kind {
+		ProviderKind::Vultr => {
+			let block: Value = client
+				.post(format!("{}/v2/blocks", url))
+				.bearer_auth("vultr-token")
+				.json(&json!({ "region": region, "size_gb": 40 }))
+				.send()
+				.await
+				.unwrap()
+				.json()
+				.await
+				.unwrap();
+			client
+				.post(format!("{}/v2/blocks/{}/attach", url, block["block"]["id"].as_str().unwrap()))
+				.bearer_auth("vultr-token")
+				.json(&json!({ "instance_id": instance_id }))
+				.send()
+				.await
+				.unwrap()
+				.error_for_status()
+				.unwrap();
+		}
+		_ => {
+			client
+				.post(format!("{}/v1/volumes", url))
+				.bearer_auth("hetzner-token")
+				.json(&json!({ "name": "data", "size": 40, "server": instance_id.parse::<u64>().unwrap() }))
+				.send()
+				.await
+				.unwrap()
+				.error_for_status()
+				.unwrap();
+		}
+	}
+
+	let volumes = provider.volumes().await.unwrap();
+	let volume = volumes.iter().find(|volume| volume.attached_to.as_deref() == Some(instance_id.as_str())).unwrap();
+	assert_eq!(volume.account, "staging");
+
+	provider.detach_volume(&volume.id).await.unwrap();
+
+	let volumes = provider.volumes().await.unwrap();
+	assert!(volumes.iter().all(|volume| volume.attached_to.is_none()));
+}
+
+#[tokio::test]
+async fn vultr_volumes_of_other_accounts_detach() {
+	detach_through_the_provider(ProviderKind::Vultr, "ewr", "vhf-1c-1gb", "1743").await;
+}
+
+#[tokio::test]
+async fn hetzner_volumes_of_other_accounts_detach() {
+	detach_through_the_provider(ProviderKind::Hetzner, "fsn1", "cx21", "ubuntu-22.04").await;
+}
@@ -0,0 +1,98 @@
+use chrono::Utc;
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+use models::models::instance::Instance;
+use models::models::instance_state::InstanceState;
+use models::models::metrics::Metrics;
+use models::models::network::Network;
+use models::models::worker::Worker;
+
+use super::database;
+use crate::workers::store::WorkerStore;
+
+// A worker for a fresh instance, so runs against the same database don't collide.
+pub(super) fn worker(primary_ipv4: &str) -> Worker {
+	let now = Utc::now();
+
+	Worker {
+		id: 0,
+		instance_id: Some(format!("instance-{}", now.timestamp_nanos_opt().unwrap())),
+		network: Network {
+			primary_ipv4: primary_ipv4.to_string(),
+			primary_ipv6: String::new(),
+		},
+		provider: ProviderKind::Vultr,
+		account: "default".to_string(),
+		region: "ewr".to_string(),
+		grpc_addr: format!("{}:50051", primary_ipv4),
+		instance: Instance {
+			provider: "vultr".to_string(),
+			region: "ewr".to_string(),
+			vcpu: 1,
+			memory: 1024,
+			boot_volume: None,
+		},
+		metrics: Metrics {
+			cpu: 0.0,
+			memory: 0.0,
+			disk: 0.0,
+			network: 0.0,
+			workload: 0.0,
+			time: now,
+		},
+		state: InstanceState::Running,
+		volumes: Vec::new(),
+		last_updated: now,
+		last_health_check: None,
+	}
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres database in TEST_DATABASE_URL"]
+async fn registering_again_keeps_the_record_of_the_instance_and_its_drain() {
+	let store = WorkerStore::new(database().await);
+	let first = worker("192.0.2.1");
+
+	let registered = store.register(&first, "first", "token-1").await.unwrap();
+	let mut drained = registered.clone();
+	drained.state = InstanceState::Draining;
+	store.save(&drained).await.unwrap();
+
+	// Same instance from another address, e.g. after a restart with a new public IP.
+	let again = store
+		.register(
+			&Worker {
+				network: Network {
+					primary_ipv4: "192.0.2.2".to_string(),
+					primary_ipv6: String::new(),
+				},
+				..first.clone()
+			},
+			"second",
+			"token-2",
+		)
+		.await
+		.unwrap();
+
+	assert_eq!(again.id, registered.id);
+	assert_eq!(again.state, InstanceState::Draining);
+	assert_eq!(again.network.primary_ipv4, "192.0.2.2");
+	assert_eq!(store.get(registered.id).await.unwrap().unwrap().state, InstanceState::Draining);
+	assert_eq!(store.service_token(registered.id).await.unwrap().as_deref(), Some("token-2"));
+
+	// Another instance from the first address is another worker.
+	let other = store.register(&worker("192.0.2.1"), "third", "token-3").await.unwrap();
+	assert_ne!(other.id, registered.id);
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres database in TEST_DATABASE_URL"]
+async fn heartbeats_need_the_credential_of_the_worker() {
+	let store = WorkerStore::new(database().await);
+	let registered = store.register(&worker("192.0.2.3"), "mine", "token").await.unwrap();
+
+	let forged = store.heartbeat(registered.id, "theirs", None, &[], Utc::now()).await.unwrap();
+	assert!(forged.is_none());
+
+	let beat = store.heartbeat(registered.id, "mine", None, &["vol-1".to_string()], Utc::now()).await.unwrap();
+	assert_eq!(beat.unwrap().volumes, vec!["vol-1".to_string()]);
+}
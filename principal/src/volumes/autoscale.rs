@@ -0,0 +1,128 @@
+use chrono::{DateTime, Duration, Utc};
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+use models::models::volume::Volume;
+use serde::{Deserialize, Serialize};
+
+// Grows a volume by `step_gb`, up to `max_gb`, once its worker reports it at least
+// `threshold_percent` full.
+#[derive(Debug, Clone, Serialize)]
+pub struct AutoscalePolicy {
+	pub id: i64,
+	pub provider: ProviderKind,
+	pub volume_id: String,
+	pub threshold_percent: u32,
+	pub step_gb: u64,
+	pub max_gb: u64,
+	pub enabled: bool,
+	pub last_scaled_at: Option<DateTime<Utc>>,
+}
+
+impl AutoscalePolicy {
+	// The size to grow the volume to, if it crossed the threshold and is not at the maximum yet.
+	pub fn target_size(&self, volume: &Volume) -> Option<u64> {
+		if !self.enabled || volume.total == 0 || volume.used * 100 < u64::from(self.threshold_percent) * volume.total {
+			return None;
+		}
+
+		let target = (volume.total + self.step_gb).min(self.max_gb);
+		(target > volume.total).then_some(target)
+	}
+
+	// Whether the volume was grown less than `cooldown` before `now`.
+	pub fn is_cooling_down(&self, now: DateTime<Utc>, cooldown: Duration) -> bool {
+		self.last_scaled_at
+			.is_some_and(|last_scaled_at| now - last_scaled_at < cooldown)
+	}
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewAutoscalePolicy {
+	pub provider: ProviderKind,
+	pub volume_id: String,
+	pub threshold_percent: u32,
+	pub step_gb: u64,
+	pub max_gb: u64,
+	#[serde(default = "default_enabled")]
+	pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+	true
+}
+
+#[cfg(test)]
+mod tests {
+	use models::models::volume::{VolumeState, VolumeTier, VolumeType};
+
+	use super::*;
+
+	fn policy() -> AutoscalePolicy {
+		AutoscalePolicy {
+			id: 1,
+			provider: ProviderKind::Vultr,
+			volume_id: "block".to_string(),
+			threshold_percent: 80,
+			step_gb: 10,
+			max_gb: 100,
+			enabled: true,
+			last_scaled_at: None,
+		}
+	}
+
+	fn volume(used: u64, total: u64) -> Volume {
+		Volume {
+			id: "block".to_string(),
+			provider: ProviderKind::Vultr,
+			region: "ewr".to_string(),
+			label: String::new(),
+			used,
+			total,
+			r#type: VolumeType::NVME,
+			tier: VolumeTier::HighPerformance,
+			state: VolumeState::Attached,
+			instance_id: None,
+			worker_id: None,
+			container: None,
+			device: None,
+		}
+	}
+
+	#[test]
+	fn target_size_grows_by_a_step_from_the_threshold_on() {
+		assert_eq!(policy().target_size(&volume(39, 50)), None);
+		assert_eq!(policy().target_size(&volume(40, 50)), Some(60));
+		assert_eq!(policy().target_size(&volume(50, 50)), Some(60));
+	}
+
+	#[test]
+	fn target_size_stops_at_the_maximum() {
+		assert_eq!(policy().target_size(&volume(90, 95)), Some(100));
+		assert_eq!(policy().target_size(&volume(100, 100)), None);
+		assert_eq!(policy().target_size(&volume(120, 120)), None);
+	}
+
+	#[test]
+	fn target_size_skips_disabled_policies_and_unreported_volumes() {
+		let disabled = AutoscalePolicy {
+			enabled: false,
+			..policy()
+		};
+
+		assert_eq!(disabled.target_size(&volume(50, 50)), None);
+		assert_eq!(policy().target_size(&volume(0, 0)), None);
+	}
+
+	#[test]
+	fn is_cooling_down_until_the_cooldown_passed() {
+		let now = Utc::now();
+		let scaled = |minutes| AutoscalePolicy {
+			last_scaled_at: Some(now - Duration::minutes(minutes)),
+			..policy()
+		};
+
+		assert!(!policy().is_cooling_down(now, Duration::minutes(10)));
+		assert!(scaled(5).is_cooling_down(now, Duration::minutes(10)));
+		assert!(!scaled(10).is_cooling_down(now, Duration::minutes(10)));
+		assert!(!scaled(5).is_cooling_down(now, Duration::zero()));
+	}
+}
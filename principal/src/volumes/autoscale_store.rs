@@ -0,0 +1,108 @@
+use chrono::{DateTime, TimeZone, Utc};
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+use sqlx::postgres::PgPool;
+use sqlx::FromRow;
+
+use crate::volumes::autoscale::{AutoscalePolicy, NewAutoscalePolicy};
+
+const POLICY_COLUMNS: &str = "id::BIGINT AS id, provider, volume_id, threshold_percent, step_gb, max_gb, enabled, \
+	EXTRACT(EPOCH FROM last_scaled_at)::BIGINT AS last_scaled_at";
+
+#[derive(FromRow)]
+struct PolicyRow {
+	id: i64,
+	provider: String,
+	volume_id: String,
+	threshold_percent: i32,
+	step_gb: i64,
+	max_gb: i64,
+	enabled: bool,
+	last_scaled_at: Option<i64>,
+}
+
+impl TryFrom<PolicyRow> for AutoscalePolicy {
+	type Error = sqlx::Error;
+
+	fn try_from(row: PolicyRow) -> Result<Self, Self::Error> {
+		Ok(AutoscalePolicy {
+			id: row.id,
+			provider: row
+				.provider
+				.parse::<ProviderKind>()
+				.map_err(|e| sqlx::Error::Decode(e.into()))?,
+			volume_id: row.volume_id,
+			threshold_percent: row.threshold_percent.max(0) as u32,
+			step_gb: row.step_gb.max(0) as u64,
+			max_gb: row.max_gb.max(0) as u64,
+			enabled: row.enabled,
+			last_scaled_at: row
+				.last_scaled_at
+				.map(|at| Utc.timestamp_opt(at, 0).single().unwrap_or_else(Utc::now)),
+		})
+	}
+}
+
+// Volume autoscaling policies, at most one per volume.
+#[derive(Debug, Clone)]
+pub struct AutoscaleStore {
+	pool: PgPool,
+}
+
+impl AutoscaleStore {
+	pub fn new(pool: PgPool) -> Self {
+		Self { pool }
+	}
+
+	pub async fn list(&self) -> Result<Vec<AutoscalePolicy>, sqlx::Error> {
+		sqlx::query_as::<_, PolicyRow>(&format!("SELECT {} FROM VolumeAutoscalePolicies ORDER BY id", POLICY_COLUMNS))
+			.fetch_all(&self.pool)
+			.await?
+			.into_iter()
+			.map(AutoscalePolicy::try_from)
+			.collect()
+	}
+
+	// Creates the volume's policy or replaces the one it has.
+	pub async fn save(&self, policy: &NewAutoscalePolicy) -> Result<AutoscalePolicy, sqlx::Error> {
+		let row = sqlx::query_as::<_, PolicyRow>(&format!(
+			r#"
+			INSERT INTO VolumeAutoscalePolicies (provider, volume_id, threshold_percent, step_gb, max_gb, enabled)
+			VALUES ($1, $2, $3, $4, $5, $6)
+			ON CONFLICT (provider, volume_id) DO UPDATE
+			SET threshold_percent = $3, step_gb = $4, max_gb = $5, enabled = $6
+			RETURNING {}
+			"#,
+			POLICY_COLUMNS
+		))
+		.bind(policy.provider.code())
+		.bind(&policy.volume_id)
+		.bind(policy.threshold_percent as i32)
+		.bind(policy.step_gb as i64)
+		.bind(policy.max_gb as i64)
+		.bind(policy.enabled)
+		.fetch_one(&self.pool)
+		.await?;
+
+		AutoscalePolicy::try_from(row)
+	}
+
+	// Returns whether the policy existed.
+	pub async fn delete(&self, id: i64) -> Result<bool, sqlx::Error> {
+		let result = sqlx::query("DELETE FROM VolumeAutoscalePolicies WHERE id = $1")
+			.bind(id)
+			.execute(&self.pool)
+			.await?;
+
+		Ok(result.rows_affected() > 0)
+	}
+
+	pub async fn scaled(&self, id: i64, at: DateTime<Utc>) -> Result<(), sqlx::Error> {
+		sqlx::query("UPDATE VolumeAutoscalePolicies SET last_scaled_at = to_timestamp($2) WHERE id = $1")
+			.bind(id)
+			.bind(at.timestamp())
+			.execute(&self.pool)
+			.await?;
+
+		Ok(())
+	}
+}
@@ -1 +1,6 @@
-pub mod volumes;
\ No newline at end of file
+pub mod autoscale;
+pub mod autoscale_store;
+pub mod snapshot_store;
+pub mod snapshots;
+pub mod store;
+pub mod volumes;
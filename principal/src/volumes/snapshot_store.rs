@@ -0,0 +1,187 @@
+use chrono::{DateTime, TimeZone, Utc};
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+use sqlx::postgres::PgPool;
+use sqlx::FromRow;
+
+use crate::volumes::snapshots::{NewSnapshotPolicy, SnapshotPolicy, SnapshotRun};
+
+// Most runs returned by `runs`.
+const MAX_RUNS: i64 = 1000;
+
+fn timestamp(seconds: i64) -> DateTime<Utc> {
+	Utc.timestamp_opt(seconds, 0).single().unwrap_or_else(Utc::now)
+}
+
+#[derive(FromRow)]
+struct PolicyRow {
+	id: i64,
+	provider: String,
+	volume_id: String,
+	interval_secs: i64,
+	retention: i32,
+	enabled: bool,
+	last_run_at: Option<i64>,
+}
+
+impl TryFrom<PolicyRow> for SnapshotPolicy {
+	type Error = sqlx::Error;
+
+	fn try_from(row: PolicyRow) -> Result<Self, Self::Error> {
+		Ok(SnapshotPolicy {
+			id: row.id,
+			provider: row
+				.provider
+				.parse::<ProviderKind>()
+				.map_err(|e| sqlx::Error::Decode(e.into()))?,
+			volume_id: row.volume_id,
+			interval_secs: row.interval_secs.max(0) as u64,
+			retention: row.retention.max(0) as u32,
+			enabled: row.enabled,
+			last_run_at: row.last_run_at.map(timestamp),
+		})
+	}
+}
+
+#[derive(FromRow)]
+struct RunRow {
+	id: i64,
+	policy_id: i64,
+	provider: String,
+	volume_id: String,
+	snapshot_id: Option<String>,
+	succeeded: bool,
+	error: Option<String>,
+	pruned: i32,
+	started_at: i64,
+	finished_at: i64,
+}
+
+impl From<RunRow> for SnapshotRun {
+	fn from(row: RunRow) -> Self {
+		SnapshotRun {
+			id: row.id,
+			policy_id: row.policy_id,
+			provider: row.provider,
+			volume_id: row.volume_id,
+			snapshot_id: row.snapshot_id,
+			succeeded: row.succeeded,
+			error: row.error,
+			pruned: row.pruned.max(0) as u32,
+			started_at: timestamp(row.started_at),
+			finished_at: timestamp(row.finished_at),
+		}
+	}
+}
+
+// Snapshot policies, and every run of them in `SnapshotRuns` as a record of the backups taken.
+#[derive(Debug, Clone)]
+pub struct SnapshotStore {
+	pool: PgPool,
+}
+
+impl SnapshotStore {
+	pub fn new(pool: PgPool) -> Self {
+		Self { pool }
+	}
+
+	pub async fn policies(&self) -> Result<Vec<SnapshotPolicy>, sqlx::Error> {
+		sqlx::query_as::<_, PolicyRow>(
+			r#"
+			SELECT id::BIGINT AS id, provider, volume_id, interval_secs, retention, enabled,
+				EXTRACT(EPOCH FROM last_run_at)::BIGINT AS last_run_at
+			FROM SnapshotPolicies
+			ORDER BY id
+			"#,
+		)
+		.fetch_all(&self.pool)
+		.await?
+		.into_iter()
+		.map(SnapshotPolicy::try_from)
+		.collect()
+	}
+
+	pub async fn create_policy(&self, policy: &NewSnapshotPolicy) -> Result<SnapshotPolicy, sqlx::Error> {
+		let row = sqlx::query_as::<_, PolicyRow>(
+			r#"
+			INSERT INTO SnapshotPolicies (provider, volume_id, interval_secs, retention, enabled)
+			VALUES ($1, $2, $3, $4, $5)
+			RETURNING id::BIGINT AS id, provider, volume_id, interval_secs, retention, enabled,
+				EXTRACT(EPOCH FROM last_run_at)::BIGINT AS last_run_at
+			"#,
+		)
+		.bind(policy.provider.code())
+		.bind(&policy.volume_id)
+		.bind(policy.interval_secs as i64)
+		.bind(policy.retention as i32)
+		.bind(policy.enabled)
+		.fetch_one(&self.pool)
+		.await?;
+
+		SnapshotPolicy::try_from(row)
+	}
+
+	// Returns whether the policy existed.
+	pub async fn delete_policy(&self, id: i64) -> Result<bool, sqlx::Error> {
+		let result = sqlx::query("DELETE FROM SnapshotPolicies WHERE id = $1")
+			.bind(id)
+			.execute(&self.pool)
+			.await?;
+
+		Ok(result.rows_affected() > 0)
+	}
+
+	// Records the run, and when the policy last ran if it succeeded so a failed run is retried.
+	pub async fn record_run(&self, run: &SnapshotRun) -> Result<(), sqlx::Error> {
+		let mut tx = self.pool.begin().await?;
+
+		sqlx::query(
+			r#"
+			INSERT INTO SnapshotRuns
+				(policy_id, provider, volume_id, snapshot_id, succeeded, error, pruned, started_at, finished_at)
+			VALUES ($1, $2, $3, $4, $5, $6, $7, to_timestamp($8), to_timestamp($9))
+			"#,
+		)
+		.bind(run.policy_id)
+		.bind(&run.provider)
+		.bind(&run.volume_id)
+		.bind(&run.snapshot_id)
+		.bind(run.succeeded)
+		.bind(&run.error)
+		.bind(run.pruned as i32)
+		.bind(run.started_at.timestamp())
+		.bind(run.finished_at.timestamp())
+		.execute(&mut tx)
+		.await?;
+
+		if run.succeeded {
+			sqlx::query("UPDATE SnapshotPolicies SET last_run_at = to_timestamp($2) WHERE id = $1")
+				.bind(run.policy_id)
+				.bind(run.started_at.timestamp())
+				.execute(&mut tx)
+				.await?;
+		}
+
+		tx.commit().await
+	}
+
+	// Newest first, optionally for a single policy.
+	pub async fn runs(&self, policy_id: Option<i64>) -> Result<Vec<SnapshotRun>, sqlx::Error> {
+		let rows = sqlx::query_as::<_, RunRow>(
+			r#"
+			SELECT id::BIGINT AS id, policy_id, provider, volume_id, snapshot_id, succeeded, error, pruned,
+				EXTRACT(EPOCH FROM started_at)::BIGINT AS started_at,
+				EXTRACT(EPOCH FROM finished_at)::BIGINT AS finished_at
+			FROM SnapshotRuns
+			WHERE $1::BIGINT IS NULL OR policy_id = $1
+			ORDER BY id DESC
+			LIMIT $2
+			"#,
+		)
+		.bind(policy_id)
+		.bind(MAX_RUNS)
+		.fetch_all(&self.pool)
+		.await?;
+
+		Ok(rows.into_iter().map(SnapshotRun::from).collect())
+	}
+}
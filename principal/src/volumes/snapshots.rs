@@ -0,0 +1,220 @@
+use chrono::{DateTime, Utc};
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+use models::models::volume::Volume;
+use serde::{Deserialize, Serialize};
+
+use crate::providers::error::ProviderError;
+
+// Neither provider can snapshot a volume, so a snapshot is a copy of the volume on a volume of its
+// own, labelled `snapshot-{volume id}-{label}` to tell it apart from other volumes.
+const SNAPSHOT_LABEL_PREFIX: &str = "snapshot-";
+// Labels end up in volume names, which Hetzner limits to 64 characters.
+const MAX_LABEL_LEN: usize = 32;
+
+// Snapshots taken by a policy are labelled `policy-{id}-{timestamp}`, so retention only ever
+// prunes the policy's own snapshots.
+const POLICY_LABEL_PREFIX: &str = "policy-";
+
+// A copy of a volume, identified by its provider and the id of the volume it is kept on.
+#[derive(Debug, Clone, Serialize)]
+pub struct Snapshot {
+	pub id: String,
+	pub provider: ProviderKind,
+	pub volume_id: String,
+	pub label: String,
+	pub region: String,
+	pub size_gb: u64,
+	pub state: SnapshotState,
+	pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum SnapshotState {
+	// Attached to an instance while it is taken or restored.
+	Copying,
+	Available,
+}
+
+impl Snapshot {
+	// The snapshot of `volume_id` kept on `volume`, if that is what `volume` holds.
+	pub fn of(volume_id: &str, volume: Volume, created_at: Option<DateTime<Utc>>) -> Option<Snapshot> {
+		let label = volume.label.strip_prefix(&volume_label(volume_id, ""))?.to_string();
+
+		Some(Snapshot {
+			id: volume.id,
+			provider: volume.provider,
+			volume_id: volume_id.to_string(),
+			label,
+			region: volume.region,
+			size_gb: volume.total,
+			state: match volume.instance_id {
+				Some(_) => SnapshotState::Copying,
+				None => SnapshotState::Available,
+			},
+			created_at,
+		})
+	}
+}
+
+// Whether the volume holds a snapshot rather than data of its own.
+pub fn is_snapshot(volume: &Volume) -> bool {
+	volume.label.starts_with(SNAPSHOT_LABEL_PREFIX)
+}
+
+// Label of the volume a snapshot of `volume_id` is kept on.
+pub fn volume_label(volume_id: &str, label: &str) -> String {
+	format!("{}{}-{}", SNAPSHOT_LABEL_PREFIX, volume_id, label)
+}
+
+// Labels must be valid in both providers' volume names.
+pub fn check_label(label: &str) -> Result<(), ProviderError> {
+	let valid = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.';
+	if label.is_empty() || label.len() > MAX_LABEL_LEN || !label.chars().all(valid) {
+		return Err(ProviderError::Invalid(format!(
+			"snapshot labels are 1 to {} letters, digits, '-', '_' or '.', not {:?}",
+			MAX_LABEL_LEN, label
+		)));
+	}
+
+	Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateSnapshot {
+	// Defaults to the time it is taken.
+	#[serde(default)]
+	pub label: String,
+}
+
+// Restores a snapshot into a new volume attached to the instance, which needs a worker to copy the
+// snapshot onto the volume. The volume is in the snapshot's region and by default of its size.
+#[derive(Debug, Deserialize)]
+pub struct RestoreSnapshot {
+	pub instance_id: String,
+	// Looked up from the instance when not given.
+	#[serde(default)]
+	pub worker_id: Option<u64>,
+	#[serde(default)]
+	pub container: Option<String>,
+	#[serde(default)]
+	pub size_gb: Option<u64>,
+	#[serde(default)]
+	pub label: String,
+}
+
+// Snapshots a volume every `interval_secs` and keeps the newest `retention` of them.
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotPolicy {
+	pub id: i64,
+	pub provider: ProviderKind,
+	pub volume_id: String,
+	pub interval_secs: u64,
+	pub retention: u32,
+	pub enabled: bool,
+	pub last_run_at: Option<DateTime<Utc>>,
+}
+
+impl SnapshotPolicy {
+	pub fn label_prefix(&self) -> String {
+		format!("{}{}-", POLICY_LABEL_PREFIX, self.id)
+	}
+
+	pub fn label(&self, at: DateTime<Utc>) -> String {
+		format!("{}{}", self.label_prefix(), at.format("%Y%m%d%H%M%S"))
+	}
+
+	pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+		self.enabled
+			&& self.last_run_at.is_none_or(|last_run_at| {
+				now - last_run_at >= chrono::Duration::seconds(self.interval_secs as i64)
+			})
+	}
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewSnapshotPolicy {
+	pub provider: ProviderKind,
+	pub volume_id: String,
+	pub interval_secs: u64,
+	pub retention: u32,
+	#[serde(default = "default_enabled")]
+	pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+	true
+}
+
+// One scheduled run of a policy: the snapshot it took, or why it failed, and how many old
+// snapshots it pruned.
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotRun {
+	pub id: i64,
+	pub policy_id: i64,
+	pub provider: String,
+	pub volume_id: String,
+	pub snapshot_id: Option<String>,
+	pub succeeded: bool,
+	pub error: Option<String>,
+	pub pruned: u32,
+	pub started_at: DateTime<Utc>,
+	pub finished_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+	use models::models::volume::{VolumeState, VolumeTier, VolumeType};
+
+	use super::*;
+
+	fn volume(label: &str, instance_id: Option<&str>) -> Volume {
+		Volume {
+			id: "copy".to_string(),
+			provider: ProviderKind::Hetzner,
+			region: "fsn1".to_string(),
+			label: label.to_string(),
+			used: 0,
+			total: 40,
+			r#type: VolumeType::SATA,
+			tier: VolumeTier::HighPerformance,
+			state: VolumeState::Available,
+			instance_id: instance_id.map(str::to_string),
+			worker_id: None,
+			container: None,
+			device: None,
+		}
+	}
+
+	#[test]
+	fn snapshots_are_told_apart_by_the_volume_they_were_taken_of() {
+		let label = volume_label("12", "nightly");
+		assert!(is_snapshot(&volume(&label, None)));
+		assert!(!is_snapshot(&volume("data", None)));
+
+		let snapshot = Snapshot::of("12", volume(&label, None), None).unwrap();
+		assert_eq!(snapshot.id, "copy");
+		assert_eq!(snapshot.volume_id, "12");
+		assert_eq!(snapshot.label, "nightly");
+		assert_eq!(snapshot.size_gb, 40);
+		assert_eq!(snapshot.state, SnapshotState::Available);
+
+		assert!(Snapshot::of("1", volume(&label, None), None).is_none());
+		assert!(Snapshot::of("12", volume("data", None), None).is_none());
+	}
+
+	#[test]
+	fn snapshots_attached_to_an_instance_are_being_copied() {
+		let snapshot = Snapshot::of("12", volume(&volume_label("12", "nightly"), Some("7")), None).unwrap();
+		assert_eq!(snapshot.state, SnapshotState::Copying);
+	}
+
+	#[test]
+	fn labels_must_fit_volume_names() {
+		assert!(check_label("policy-3-20261018120000").is_ok());
+		assert!(check_label("before_upgrade.v2").is_ok());
+		assert!(check_label("").is_err());
+		assert!(check_label("not a label").is_err());
+		assert!(check_label("../data").is_err());
+		assert!(check_label(&"a".repeat(33)).is_err());
+	}
+}
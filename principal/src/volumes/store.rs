@@ -0,0 +1,78 @@
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+use models::models::volume::Volume;
+use sqlx::postgres::PgPool;
+use sqlx::FromRow;
+
+// Volumes are stored as JSON next to the columns needed to look them up, like workers.
+#[derive(Debug, Clone)]
+pub struct VolumeStore {
+	pool: PgPool,
+}
+
+#[derive(FromRow)]
+struct VolumeRow {
+	volume: String,
+}
+
+impl TryFrom<VolumeRow> for Volume {
+	type Error = sqlx::Error;
+
+	fn try_from(row: VolumeRow) -> Result<Self, Self::Error> {
+		serde_json::from_str(&row.volume).map_err(|e| sqlx::Error::Decode(Box::new(e)))
+	}
+}
+
+impl VolumeStore {
+	pub fn new(pool: PgPool) -> Self {
+		Self { pool }
+	}
+
+	// Volumes that still exist, optionally of one provider only.
+	pub async fn list(&self, provider: Option<ProviderKind>) -> Result<Vec<Volume>, sqlx::Error> {
+		sqlx::query_as::<_, VolumeRow>(
+			r#"
+			SELECT volume FROM Volumes
+			WHERE state <> 'Deleted' AND ($1::TEXT IS NULL OR provider = $1)
+			ORDER BY provider, volume_id
+			"#,
+		)
+		.bind(provider.map(|provider| provider.code()))
+		.fetch_all(&self.pool)
+		.await?
+		.into_iter()
+		.map(Volume::try_from)
+		.collect()
+	}
+
+	pub async fn get(&self, provider: ProviderKind, volume_id: &str) -> Result<Option<Volume>, sqlx::Error> {
+		sqlx::query_as::<_, VolumeRow>("SELECT volume FROM Volumes WHERE provider = $1 AND volume_id = $2")
+			.bind(provider.code())
+			.bind(volume_id)
+			.fetch_optional(&self.pool)
+			.await?
+			.map(Volume::try_from)
+			.transpose()
+	}
+
+	pub async fn save(&self, volume: &Volume) -> Result<(), sqlx::Error> {
+		sqlx::query(
+			r#"
+			INSERT INTO Volumes (provider, volume_id, state, instance_id, worker_id, container, volume, updated_at)
+			VALUES ($1, $2, $3, $4, $5, $6, $7, now())
+			ON CONFLICT (provider, volume_id) DO UPDATE
+			SET state = $3, instance_id = $4, worker_id = $5, container = $6, volume = $7, updated_at = now()
+			"#,
+		)
+		.bind(volume.provider.code())
+		.bind(&volume.id)
+		.bind(format!("{:?}", volume.state))
+		.bind(&volume.instance_id)
+		.bind(volume.worker_id.map(|id| id as i64))
+		.bind(&volume.container)
+		.bind(serde_json::to_string(volume).unwrap_or_default())
+		.execute(&self.pool)
+		.await?;
+
+		Ok(())
+	}
+}
@@ -1,343 +1,1091 @@
-use std::error::Error;
-use std::collections::HashMap;
+use std::cmp::Reverse;
 use std::sync::Arc;
+use std::time::Duration;
 
-use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE, AUTHORIZATION};
-use serde::{Serialize, Deserialize};
-use dotenv_codegen::dotenv;
+use chrono::{DateTime, Utc};
 
-const VULTR_API_KEY: &str = dotenv!("VULTR_API_KEY");
-const HETZNER_API_KEY: &str = dotenv!("HETZNER_API_KEY");
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+use models::models::region::Region;
+use models::models::volume::{Volume, VolumeState, VolumeTier, VolumeType};
+use models::models::worker::Worker;
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::postgres::PgPool;
+use tokio::time::sleep;
+use tonic::Status;
+
+use crate::config::config::DEFAULT_ACCOUNT;
+use crate::costs::tracker::CostTracker;
+use crate::docker::{CopyVolumeRequest, GrowFilesystemRequest, MountVolumeRequest, UnmountVolumeRequest};
+use crate::manager::manager::ManagerError;
+use crate::providers::error::ProviderError;
+use crate::providers::hetzner::action::{wait_for_action, Action, ActionResponse};
+use crate::providers::hetzner::pages as hetzner_pages;
+use crate::providers::hetzner::provider::HETZNER_API_URL;
+use crate::providers::http::HttpClient;
+use crate::providers::provider::parse_timestamp;
+use crate::providers::vultr::pages as vultr_pages;
+use crate::providers::vultr::provider::VULTR_API_URL;
+use crate::providers::vultr::status::wait_for_block;
+use crate::providers::wait::WaitOptions;
+use crate::shared_config::SharedConfig;
+use crate::volumes::autoscale::{AutoscalePolicy, NewAutoscalePolicy};
+use crate::volumes::autoscale_store::AutoscaleStore;
+use crate::volumes::snapshot_store::SnapshotStore;
+use crate::volumes::snapshots::{
+    self, NewSnapshotPolicy, RestoreSnapshot, Snapshot, SnapshotPolicy, SnapshotRun, SnapshotState,
+};
+use crate::volumes::store::VolumeStore;
+use crate::workers::client::{self, WorkerClient};
+use crate::workers::store::WorkerStore;
+
+const BYTES_PER_GB: u64 = 1024 * 1024 * 1024;
+
+// NVMe backed; `storage_opt` is the cheaper HDD backed block storage.
+const VULTR_BLOCK_TYPE: &str = "high_perf";
 
 #[derive(Debug, Clone)]
 pub struct VolumeManager {
-    client: Arc<reqwest::Client>,
+    vultr_client: HttpClient,
+    hetzner_client: HttpClient,
+    vultr_key: String,
+    vultr_url: String,
+    hetzner_key: String,
+    hetzner_url: String,
+    costs: Arc<CostTracker>,
+    store: VolumeStore,
+    workers: WorkerStore,
+    snapshot_store: SnapshotStore,
+    snapshot_check_interval: Duration,
+    autoscale_store: AutoscaleStore,
+    autoscale_check_interval: Duration,
+    autoscale_cooldown: chrono::Duration,
 }
 
-/* Vultr Structs/Impls */
-#[derive(Debug, Serialize, Deserialize)]
-pub struct VultrVolumeConfig {
-    region: String,
-    size_gb: i32,
-    label: String,
-    block_type: String,
+#[derive(Debug, Deserialize)]
+pub struct CreateVolume {
+    pub provider: ProviderKind,
+    // Canonical region, area or provider region code.
+    pub region: String,
+    pub size_gb: u64,
+    #[serde(default)]
+    pub label: String,
+    // Vultr only: `high_perf` (default) or `storage_opt`.
+    #[serde(default)]
+    pub block_type: Option<String>,
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct VultrVolumeAttachmentConfig {
-    instance_id: String,
-    live: bool,
+#[derive(Debug, Deserialize)]
+pub struct AttachVolume {
+    pub instance_id: String,
+    // Looked up from the instance when not given.
+    #[serde(default)]
+    pub worker_id: Option<u64>,
+    #[serde(default)]
+    pub container: Option<String>,
+    // Vultr only: attach without restarting the instance.
+    #[serde(default = "default_live")]
+    pub live: bool,
 }
 
-impl VultrVolumeAttachmentConfig {
-    pub fn new(instance_id: &str, live: bool) -> Self {
-        Self {
-            instance_id: instance_id.to_string(),
-            live,            
-        }
-    }
-}
-
-#[derive(Serialize, Deserialize)]
-pub struct VultrVolumeDetachConfig {
-    live: bool,
+fn default_live() -> bool {
+    true
 }
 
-impl VultrVolumeDetachConfig {
-    pub fn new(live: bool) -> Self {
-        Self {
-            live,
-        }
-    }
+/* Vultr */
+#[derive(Deserialize)]
+struct VultrBlockResponse {
+    block: VultrBlock,
 }
 
 #[derive(Deserialize)]
-struct VultrBlockResponse {
+struct VultrBlock {
     id: String,
+    region: String,
+    size_gb: u64,
+    #[serde(default)]
+    label: String,
+    #[serde(default)]
+    block_type: String,
+    // Empty when detached.
+    #[serde(default)]
+    attached_to_instance: String,
+    // The block shows up as `/dev/disk/by-id/virtio-{mount_id}` on the instance.
+    #[serde(default)]
+    mount_id: String,
+    status: String,
+    #[serde(default)]
     date_created: String,
-    size_gb: i32,
 }
 
-#[derive(Deserialize, Serialize)]
-pub struct VultrBlock {
-    #[serde(skip)]
-    id: String,
-    date_created: String,
-    size_gb: i32,
+impl From<VultrBlock> for Volume {
+    fn from(block: VultrBlock) -> Self {
+        let instance_id = Some(block.attached_to_instance).filter(|id| !id.is_empty());
+        let device = Some(block.mount_id)
+            .filter(|mount_id| !mount_id.is_empty())
+            .map(|mount_id| format!("/dev/disk/by-id/virtio-{}", mount_id));
+
+        Volume {
+            id: block.id,
+            provider: ProviderKind::Vultr,
+            region: block.region,
+            label: block.label,
+            used: 0,
+            total: block.size_gb,
+            r#type: match block.block_type.as_str() {
+                "storage_opt" => VolumeType::HDD,
+                _ => VolumeType::NVME,
+            },
+            tier: VolumeTier::HighPerformance,
+            state: match (block.status.as_str(), &instance_id) {
+                ("pending", _) => VolumeState::Creating,
+                (_, Some(_)) => VolumeState::Attached,
+                _ => VolumeState::Available,
+            },
+            instance_id,
+            worker_id: None,
+            container: None,
+            device,
+        }
+    }
 }
 
+/* Hetzner */
 #[derive(Deserialize)]
-struct VultrBlocksResponse {
-    blocks: Vec<VultrBlock>,
+struct HetznerVolumeResponse {
+    volume: HetznerVolume,
+    // Only set when the volume is created.
+    action: Option<Action>,
 }
 
-/* Hetzner Structs/Impls */
-#[derive(Debug, Serialize, Deserialize)]
-pub struct HetznerVolumeConfig {
-    automount: bool,
-    format: String,
-    labels: HashMap<String, String>,
-    location: String,
+#[derive(Deserialize)]
+struct HetznerLocation {
     name: String,
-    size: i32,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct HetznerVolumeResizeConfig {
-    size: i32,
+#[derive(Deserialize)]
+struct HetznerVolume {
+    id: u64,
+    name: String,
+    size: u64,
+    server: Option<u64>,
+    location: HetznerLocation,
+    #[serde(default)]
+    linux_device: Option<String>,
+    status: String,
+    #[serde(default)]
+    created: String,
 }
 
-impl HetznerVolumeResizeConfig {
-    pub fn new(size: i32) -> Self {
-        Self {
-            size,
-        }
-    }
-}
-#[derive(Debug, Serialize, Deserialize)]
-pub struct HetznerVolumeAttachmentConfig {
-    automount: bool,
-    server: i32,
-}
+impl From<HetznerVolume> for Volume {
+    fn from(volume: HetznerVolume) -> Self {
+        let instance_id = volume.server.map(|server| server.to_string());
 
-impl HetznerVolumeAttachmentConfig {
-    pub fn new(server: i32, automount: bool) -> Self {
-        Self {
-            server,
-            automount
+        Volume {
+            id: volume.id.to_string(),
+            provider: ProviderKind::Hetzner,
+            region: volume.location.name,
+            label: volume.name,
+            used: 0,
+            total: volume.size,
+            r#type: VolumeType::SATA,
+            tier: VolumeTier::HighPerformance,
+            state: match (volume.status.as_str(), &instance_id) {
+                ("creating", _) => VolumeState::Creating,
+                (_, Some(_)) => VolumeState::Attached,
+                _ => VolumeState::Available,
+            },
+            instance_id,
+            worker_id: None,
+            container: None,
+            device: volume.linux_device,
         }
     }
 }
 
-#[derive(Deserialize, Serialize)]
-pub struct HetznerVolume {
-    id: i32,
-    name: String,
-    size: i32,
-    created: String,
+fn hetzner_id(id: &str) -> Result<u64, ProviderError> {
+    id.parse().map_err(|_| ProviderError::Invalid(format!("invalid Hetzner id: {}", id)))
 }
 
-#[derive(Deserialize)]
-struct HetznerVolumeResponse {
-    volume: HetznerVolume,
+// Vultr block type of a volume, so its snapshots are kept on the same kind of storage.
+fn block_type(volume: &Volume) -> &'static str {
+    match volume.r#type {
+        VolumeType::HDD => "storage_opt",
+        _ => VULTR_BLOCK_TYPE,
+    }
 }
 
-#[derive(Deserialize)]
-struct HetznerVolumesResponse {
-    volumes: Vec<HetznerVolume>,
+// A worker failing a call the operation needed fails it like an unavailable provider would.
+fn worker_error(worker_id: u64, status: Status) -> ManagerError {
+    ProviderError::Transient(format!("worker {}: {}", worker_id, status.message())).into()
 }
 
-#[derive(Deserialize, Serialize)]
-enum Provider {
-    Hetzner,
-    Vultr,
-}
+// The provider's view of a volume, with the owner and usage recorded for it as long as it is
+// still attached to the same instance.
+fn merge(mut volume: Volume, stored: Option<&Volume>) -> Volume {
+    if let Some(stored) = stored.filter(|stored| stored.instance_id == volume.instance_id) {
+        volume.worker_id = stored.worker_id;
+        volume.container = stored.container.clone();
+        volume.used = stored.used;
+    }
 
-#[derive(Serialize)]
-pub struct Volume {
-    id: String,
-    provider: Provider,
+    volume
 }
 
 impl VolumeManager {
-    pub fn volume() -> Self {
+    // Uses the shared provider clients, so volume calls count against the same rate limits.
+    pub fn new(shared_config: &mut SharedConfig, pool: PgPool) -> Self {
+        let vultr = shared_config.config.vultr();
+        let hetzner = shared_config.config.hetzner();
+
         VolumeManager {
-            client: Arc::new(reqwest::Client::new()),
+            vultr_client: shared_config.clients.vultr().clone(),
+            hetzner_client: shared_config.clients.hetzner().clone(),
+            vultr_key: vultr.api_key.clone(),
+            vultr_url: vultr.url(VULTR_API_URL),
+            hetzner_key: hetzner.api_key.clone(),
+            hetzner_url: hetzner.url(HETZNER_API_URL),
+            costs: Arc::clone(&shared_config.costs),
+            store: VolumeStore::new(pool.clone()),
+            workers: WorkerStore::new(pool.clone()),
+            snapshot_store: SnapshotStore::new(pool.clone()),
+            snapshot_check_interval: Duration::from_secs(shared_config.config.volumes.snapshot_check_interval_secs),
+            autoscale_store: AutoscaleStore::new(pool),
+            autoscale_check_interval: Duration::from_secs(shared_config.config.volumes.autoscale_check_interval_secs),
+            autoscale_cooldown: chrono::Duration::seconds(shared_config.config.volumes.autoscale_cooldown_secs as i64),
         }
     }
 
-    fn vultr_headers(&self) -> HeaderMap {
-        let mut headers = HeaderMap::new();
-        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-        headers.insert(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", VULTR_API_KEY)).unwrap());
-        headers
-    }
+    // Providers with block storage and an API key for the default account.
+    fn configured(&self) -> Vec<ProviderKind> {
+        let mut providers = Vec::new();
+        if !self.vultr_key.is_empty() {
+            providers.push(ProviderKind::Vultr);
+        }
+        if !self.hetzner_key.is_empty() {
+            providers.push(ProviderKind::Hetzner);
+        }
 
-    fn hetzner_headers(&self) -> HeaderMap {
-        let mut headers = HeaderMap::new();
-        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-        headers.insert(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", HETZNER_API_KEY)).unwrap());
-        headers
+        providers
     }
 
-    pub async fn get_all_volumes_hetzner(&self) -> Result<Vec<HetznerVolume>, Box<dyn Error>> {
-        let response = self.client.get("https://api.hetzner.cloud/v1/volumes")
-            .headers(self.hetzner_headers())
-            .send()
-            .await?;
+    pub fn supports(&self, provider: ProviderKind) -> bool {
+        self.configured().contains(&provider)
+    }
 
-        if !response.status().is_success() {
-            return Err(format!("Failed to get all volumes: {}", response.text().await?).into());
+    fn supported(&self, provider: ProviderKind) -> Result<(), ManagerError> {
+        if self.supports(provider) {
+            Ok(())
+        } else {
+            Err(ProviderError::Invalid(format!("volumes on {} are not supported", provider)).into())
         }
+    }
+
+    // Lists the providers' volumes and records them; volumes that are gone are marked deleted.
+    pub async fn list(&self, provider: Option<ProviderKind>) -> Result<Vec<Volume>, ManagerError> {
+        let providers = match provider {
+            Some(provider) => {
+                self.supported(provider)?;
+                vec![provider]
+            }
+            None => self.configured(),
+        };
+
+        let mut volumes = Vec::new();
+        for provider in providers {
+            let listed: Vec<Volume> = self
+                .list_on(provider)
+                .await?
+                .into_iter()
+                .map(|(volume, _)| volume)
+                .filter(|volume| !snapshots::is_snapshot(volume))
+                .collect();
+            let stored = self.store.list(Some(provider)).await?;
 
-        let volumes_response: HetznerVolumesResponse = response.json().await?;
+            for volume in stored.iter().filter(|stored| !listed.iter().any(|volume| volume.id == stored.id)) {
+                let mut volume = volume.clone();
+                volume.state = VolumeState::Deleted;
+                self.store.save(&volume).await?;
+            }
 
-        for volume in &volumes_response.volumes {
-            println!("Volume ID: {}", volume.id);
-            println!("Volume size: {} GB", volume.size);
-            println!("Volume created at: {}", volume.created);
+            for volume in listed {
+                let previous = stored.iter().find(|stored| stored.id == volume.id);
+                let volume = merge(volume, previous);
+                self.store.save(&volume).await?;
+                volumes.push(volume);
+            }
         }
 
-        Ok(volumes_response.volumes)
+        Ok(volumes)
     }
 
-    pub async fn get_all_volumes_on_vultr(&self) -> Result<Vec<VultrBlock>, Box<dyn Error>> {
-        let response = self.client.get("https://api.vultr.com/v2/blocks")
-            .headers(self.vultr_headers())
-            .send()
-            .await?;
+    pub async fn get(&self, provider: ProviderKind, volume_id: &str) -> Result<Volume, ManagerError> {
+        self.supported(provider)?;
+
+        let volume = self.fetch(provider, volume_id).await?;
+        let volume = merge(volume, self.store.get(provider, volume_id).await?.as_ref());
+        self.store.save(&volume).await?;
+
+        Ok(volume)
+    }
+
+    pub async fn create(&self, request: &CreateVolume) -> Result<Volume, ManagerError> {
+        self.supported(request.provider)?;
+
+        let region = Region::resolve(request.provider, &request.region)
+            .map(str::to_string)
+            .unwrap_or_else(|| request.region.clone());
+        self.costs.check_volume(request.provider, DEFAULT_ACCOUNT, &region, request.size_gb)?;
+
+        let block_type = request.block_type.as_deref().unwrap_or(VULTR_BLOCK_TYPE);
+        let volume = self.create_on(request.provider, &region, request.size_gb, &request.label, block_type).await?;
+
+        self.store.save(&volume).await?;
+        println!("Created {} GB volume {} on {} in {}", volume.total, volume.id, volume.provider, volume.region);
 
-        if !response.status().is_success() {
-            return Err(format!("Failed to get volumes: {}", response.text().await?).into());
+        Ok(volume)
+    }
+
+    // Creates a volume without recording it; `block_type` only applies to Vultr.
+    async fn create_on(
+        &self,
+        provider: ProviderKind,
+        region: &str,
+        size_gb: u64,
+        label: &str,
+        block_type: &str,
+    ) -> Result<Volume, ManagerError> {
+        let volume = match provider {
+            ProviderKind::Vultr => {
+                let block = self
+                    .vultr_client
+                    .post(format!("{}/blocks", self.vultr_url))
+                    .bearer_auth(&self.vultr_key)
+                    .json(&json!({
+                        "region": region,
+                        "size_gb": size_gb,
+                        "label": label,
+                        "block_type": block_type,
+                    }))
+                    .send()
+                    .await?
+                    .json::<VultrBlockResponse>()
+                    .await?
+                    .block;
+                wait_for_block(&self.vultr_client, &self.vultr_url, &self.vultr_key, &block.id, &WaitOptions::default()).await?;
+
+                self.fetch(ProviderKind::Vultr, &block.id).await?
+            }
+            _ => {
+                let response = self
+                    .hetzner_client
+                    .post(format!("{}/volumes", self.hetzner_url))
+                    .bearer_auth(&self.hetzner_key)
+                    .json(&json!({
+                        "location": region,
+                        "size": size_gb,
+                        "name": label,
+                        "automount": false,
+                        "format": "ext4",
+                    }))
+                    .send()
+                    .await?
+                    .json::<HetznerVolumeResponse>()
+                    .await?;
+                if let Some(action) = &response.action {
+                    self.wait_for_hetzner_action(action.id).await?;
+                }
+
+                self.fetch(ProviderKind::Hetzner, &response.volume.id.to_string()).await?
+            }
+        };
+
+        Ok(volume)
+    }
+
+    // Attaches the volume to an instance, records the worker and container it is for and has the
+    // worker mount it under its volumes directory. A failed mount is only logged, as the volume
+    // itself was attached.
+    pub async fn attach(&self, provider: ProviderKind, volume_id: &str, request: &AttachVolume) -> Result<Volume, ManagerError> {
+        self.supported(provider)?;
+        self.attach_on(provider, volume_id, &request.instance_id, request.live).await?;
+
+        let worker_id = match request.worker_id {
+            Some(worker_id) => Some(worker_id),
+            None => self.worker_for(provider, &request.instance_id).await?,
+        };
+
+        let mut volume = self.fetch(provider, volume_id).await?;
+        volume.used = self.store.get(provider, volume_id).await?.map_or(0, |stored| stored.used);
+        volume.state = VolumeState::Attached;
+        volume.instance_id = Some(request.instance_id.clone());
+        volume.worker_id = worker_id;
+        volume.container = request.container.clone();
+        self.store.save(&volume).await?;
+
+        if let (Some(worker_id), Some(device)) = (volume.worker_id, &volume.device) {
+            if let Err(e) = self.mount(worker_id, volume_id, device).await {
+                println!("Failed to mount volume {} on worker {}: {}", volume_id, worker_id, e);
+            }
         }
 
-        let volumes_response: VultrBlocksResponse = response.json().await?;
+        Ok(volume)
+    }
 
-        for volume in &volumes_response.blocks {
-            println!("Volume ID: {}", volume.id);
-            println!("Volume size: {} GB", volume.size_gb);
-            println!("Volume created at: {}", volume.date_created);
+    async fn attach_on(&self, provider: ProviderKind, volume_id: &str, instance_id: &str, live: bool) -> Result<(), ManagerError> {
+        match provider {
+            ProviderKind::Vultr => {
+                self.vultr_client
+                    .post(format!("{}/blocks/{}/attach", self.vultr_url, volume_id))
+                    .bearer_auth(&self.vultr_key)
+                    .json(&json!({ "instance_id": instance_id, "live": live }))
+                    .send()
+                    .await?;
+            }
+            _ => {
+                let server = hetzner_id(instance_id)?;
+                self.hetzner_action(volume_id, "attach", json!({ "server": server, "automount": false })).await?;
+            }
         }
 
-        Ok(volumes_response.blocks)
+        Ok(())
     }
 
-    pub async fn create_volume_on_vultr(&self, volume_config: VultrVolumeConfig)  -> Result<Volume, Box<dyn Error>> {
-        let response = self.client.post("https://api.vultr.com/v2/blocks")
-            .headers(self.vultr_headers())
-            .json(&volume_config)
-            .send()
-            .await?;
-        
-        if !response.status().is_success() {
-            return Err("Failed to create volume".into());
+    // Has the worker unmount the volume first; when that fails, e.g. because the worker is gone
+    // with its instance, the volume is detached anyway.
+    pub async fn detach(&self, provider: ProviderKind, volume_id: &str) -> Result<Volume, ManagerError> {
+        self.supported(provider)?;
+
+        if let Some(worker_id) = self.store.get(provider, volume_id).await?.and_then(|stored| stored.worker_id) {
+            if let Err(e) = self.unmount(worker_id, volume_id).await {
+                println!("Failed to unmount volume {} on worker {}: {}", volume_id, worker_id, e);
+            }
         }
 
-        let volume_response: VultrBlockResponse = response.json().await?;
-        
-        println!("Created a new volume on Vultr with ID: {}", volume_response.id);
-        println!("Volume size: {} GB", volume_response.size_gb);
-        println!("Volume created at: {}", volume_response.date_created);
+        self.detach_on(provider, volume_id, false).await?;
 
-        Ok(Volume {
-            id: volume_response.id,
-            provider: Provider::Vultr,
-        })
+        let mut volume = self.fetch(provider, volume_id).await?;
+        volume.state = VolumeState::Available;
+        volume.instance_id = None;
+        volume.worker_id = None;
+        volume.container = None;
+        self.store.save(&volume).await?;
+
+        Ok(volume)
     }
 
-    pub async fn attach_volume_on_vultr(&self, volume_id: &str, config: VultrVolumeAttachmentConfig) -> Result<(), Box<dyn Error>> {
-        let response = self.client.post(&format!("https://api.vultr.com/v2/blocks/{}/attach", volume_id))
-            .headers(self.vultr_headers())
-            .json(&config)
-            .send()
-            .await?;
-        
-        if !response.status().is_success() {
-            return Err(format!("Failed to attach volume {}: {}", volume_id, response.text().await?).into());
+    // Vultr only: `live` detaches without restarting the instance.
+    async fn detach_on(&self, provider: ProviderKind, volume_id: &str, live: bool) -> Result<(), ManagerError> {
+        match provider {
+            ProviderKind::Vultr => {
+                self.vultr_client
+                    .post(format!("{}/blocks/{}/detach", self.vultr_url, volume_id))
+                    .bearer_auth(&self.vultr_key)
+                    .json(&json!({ "live": live }))
+                    .send()
+                    .await?;
+            }
+            _ => self.hetzner_action(volume_id, "detach", json!({})).await?,
         }
 
         Ok(())
     }
 
-    pub async fn resize_volume_on_vultr(&self, block_id: &str, new_label: &str, new_size_gb: i32) -> Result<(), Box<dyn Error>> {
-        let resize_config = serde_json::json!({
-            "label": new_label,
-            "size_gb": new_size_gb,
-        });
+    // Volumes can only grow. The filesystem is grown along if the volume belongs to a worker; a
+    // failure there is only logged, as the volume itself was resized.
+    pub async fn resize(&self, provider: ProviderKind, volume_id: &str, size_gb: u64) -> Result<Volume, ManagerError> {
+        let current = self.get(provider, volume_id).await?;
+        if size_gb <= current.total {
+            return Err(ProviderError::Invalid(format!(
+                "volume {} is {} GB and can only grow, not to {} GB",
+                volume_id, current.total, size_gb
+            ))
+            .into());
+        }
+        self.costs.check_volume(provider, DEFAULT_ACCOUNT, &current.region, size_gb - current.total)?;
 
-        let response = self.client.patch(&format!("https://api.vultr.com/v2/blocks/{}", block_id))
-            .headers(self.vultr_headers())
-            .json(&resize_config)
-            .send()
-            .await?;
+        match provider {
+            ProviderKind::Vultr => {
+                self.vultr_client
+                    .patch(format!("{}/blocks/{}", self.vultr_url, volume_id))
+                    .bearer_auth(&self.vultr_key)
+                    .json(&json!({ "size_gb": size_gb }))
+                    .send()
+                    .await?;
+                wait_for_block(&self.vultr_client, &self.vultr_url, &self.vultr_key, volume_id, &WaitOptions::default()).await?;
+            }
+            _ => self.hetzner_action(volume_id, "resize", json!({ "size": size_gb })).await?,
+        }
+        println!("Resized volume {} on {} from {} to {} GB", volume_id, provider, current.total, size_gb);
 
-        if !response.status().is_success() {
-            return Err(format!("Failed to resize volume {}: {}", block_id, response.text().await?).into());
+        let volume = self.get(provider, volume_id).await?;
+        if let Some(worker_id) = volume.worker_id {
+            if let Err(e) = self.grow_filesystem(worker_id, volume_id).await {
+                println!("Failed to grow the filesystem of volume {} on worker {}: {}", volume_id, worker_id, e);
+            }
         }
 
-        println!("Resized volume with ID: {}", block_id);
+        Ok(volume)
+    }
+
+    // Asks the worker's `DockerService` to grow the filesystem to the new size of the volume.
+    async fn grow_filesystem(&self, worker_id: u64, volume_id: &str) -> Result<(), Status> {
+        let total_bytes = self
+            .worker_client(worker_id)
+            .await?
+            .grow_filesystem(GrowFilesystemRequest { volume_id: volume_id.to_string() })
+            .await?
+            .into_inner()
+            .total_bytes;
 
+        println!("Grew the filesystem of volume {} on worker {} to {} bytes", volume_id, worker_id, total_bytes);
         Ok(())
     }
 
-    pub async fn detach_volume_on_vultr(&self, volume_id: &str, config: VultrVolumeDetachConfig) -> Result<(), Box<dyn Error>> {
-        let response = self.client.post(&format!("https://api.vultr.com/v2/blocks/{}/detach", volume_id))
-            .headers(self.vultr_headers())
-            .json(&config)
-            .send()
+    // Asks the worker's `DockerService` to mount the volume at `/mnt/volumes/{volume_id}`, where
+    // it reports usage from and grows filesystems.
+    async fn mount(&self, worker_id: u64, volume_id: &str, device: &str) -> Result<(), Status> {
+        let total_bytes = self
+            .worker_client(worker_id)
+            .await?
+            .mount_volume(MountVolumeRequest {
+                volume_id: volume_id.to_string(),
+                device: device.to_string(),
+            })
+            .await?
+            .into_inner()
+            .total_bytes;
+
+        println!("Mounted volume {} on worker {} with {} bytes", volume_id, worker_id, total_bytes);
+        Ok(())
+    }
+
+    async fn unmount(&self, worker_id: u64, volume_id: &str) -> Result<(), Status> {
+        self.worker_client(worker_id)
+            .await?
+            .unmount_volume(UnmountVolumeRequest { volume_id: volume_id.to_string() })
             .await?;
 
-        if !response.status().is_success() {
-            return Err(format!("Failed to detach volume {}: {}", volume_id, response.text().await?).into());
+        println!("Unmounted volume {} on worker {}", volume_id, worker_id);
+        Ok(())
+    }
+
+    async fn worker_client(&self, worker_id: u64) -> Result<WorkerClient, Status> {
+        let worker = self
+            .workers
+            .get(worker_id)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .ok_or_else(|| Status::not_found(format!("unknown worker {}", worker_id)))?;
+
+        client::connect(&self.workers, &worker).await
+    }
+
+    // Records the usage a worker reported for the volumes mounted on it. Volumes not known yet
+    // are picked up by the next listing; reports for volumes attached to another instance or
+    // recorded for another worker are refused.
+    pub async fn record_usage(&self, worker: &Worker, volume_id: &str, used_bytes: u64) -> Result<(), ManagerError> {
+        let mut volume = match self.store.get(worker.provider, volume_id).await? {
+            Some(volume) if volume.state != VolumeState::Deleted => volume,
+            _ => return Ok(()),
+        };
+        let attached = volume.instance_id.is_some() && volume.instance_id == worker.instance_id;
+        if !attached || volume.worker_id.is_some_and(|worker_id| worker_id != worker.id) {
+            return Err(ProviderError::Invalid(format!(
+                "worker {} reported usage of volume {}, which is not attached to it",
+                worker.id, volume_id
+            ))
+            .into());
+        }
+
+        volume.used = used_bytes.div_ceil(BYTES_PER_GB);
+        volume.worker_id = Some(worker.id);
+        self.store.save(&volume).await?;
+
+        Ok(())
+    }
+
+    pub async fn autoscale_policies(&self) -> Result<Vec<AutoscalePolicy>, ManagerError> {
+        Ok(self.autoscale_store.list().await?)
+    }
+
+    // Sets the volume's autoscaling policy, replacing the one it had.
+    pub async fn save_autoscale_policy(&self, policy: &NewAutoscalePolicy) -> Result<AutoscalePolicy, ManagerError> {
+        if policy.threshold_percent == 0 || policy.threshold_percent > 100 || policy.step_gb == 0 {
+            return Err(ProviderError::Invalid(
+                "threshold_percent must be between 1 and 100 and step_gb positive".to_string(),
+            )
+            .into());
         }
+        let volume = self.get(policy.provider, &policy.volume_id).await?;
+        if policy.max_gb <= volume.total {
+            return Err(ProviderError::Invalid(format!(
+                "max_gb must be more than the {} GB volume {} has",
+                volume.total, volume.id
+            ))
+            .into());
+        }
+
+        Ok(self.autoscale_store.save(policy).await?)
+    }
+
+    // Returns whether the policy existed.
+    pub async fn delete_autoscale_policy(&self, id: i64) -> Result<bool, ManagerError> {
+        Ok(self.autoscale_store.delete(id).await?)
+    }
+
+    // Grows volumes whose reported usage crossed their policy's threshold.
+    pub async fn run_autoscaling(&self) {
+        loop {
+            match self.autoscale_store.list().await {
+                Ok(policies) => {
+                    for policy in policies.iter().filter(|policy| policy.enabled) {
+                        if let Err(e) = self.autoscale(policy).await {
+                            println!("Failed to autoscale volume {} on {}: {}", policy.volume_id, policy.provider, e);
+                        }
+                    }
+                }
+                Err(e) => println!("Failed to load autoscaling policies: {}", e),
+            }
+
+            sleep(self.autoscale_check_interval).await;
+        }
+    }
+
+    async fn autoscale(&self, policy: &AutoscalePolicy) -> Result<(), ManagerError> {
+        if policy.is_cooling_down(Utc::now(), self.autoscale_cooldown) {
+            return Ok(());
+        }
+        let volume = match self.store.get(policy.provider, &policy.volume_id).await? {
+            Some(volume) if volume.state != VolumeState::Deleted => volume,
+            _ => return Ok(()),
+        };
+        let target = match policy.target_size(&volume) {
+            Some(target) => target,
+            None => return Ok(()),
+        };
+
+        println!(
+            "Volume {} on {} is {} of {} GB full, growing it to {} GB",
+            volume.id, volume.provider, volume.used, volume.total, target
+        );
+        self.resize(policy.provider, &policy.volume_id, target).await?;
+        self.autoscale_store.scaled(policy.id, Utc::now()).await?;
 
         Ok(())
     }
 
-    pub async fn create_volume_on_hetzner(&self, volume_config: HetznerVolumeConfig) -> Result<Volume, Box<dyn Error>> {
-        let response = self.client.post("https://api.hetzner.cloud/v1/volumes")
-            .headers(self.hetzner_headers())
-            .json(&volume_config)
-            .send()
-            .await?;
+    // Deletes a detached volume; its last owner stays on record.
+    pub async fn delete(&self, provider: ProviderKind, volume_id: &str) -> Result<(), ManagerError> {
+        self.supported(provider)?;
+        self.delete_on(provider, volume_id).await?;
 
-        if !response.status().is_success() {
-            return Err(format!("Failed to create volume: {}", response.text().await?).into());
+        if let Some(mut volume) = self.store.get(provider, volume_id).await? {
+            volume.state = VolumeState::Deleted;
+            self.store.save(&volume).await?;
         }
 
-        let volume_response: HetznerVolumeResponse = response.json().await?;
+        println!("Deleted volume {} on {}", volume_id, provider);
+        Ok(())
+    }
 
-        println!("Created a new volume with ID: {}", volume_response.volume.id);
-        println!("Volume name: {}", volume_response.volume.name);
-        println!("Volume size: {} GB", volume_response.volume.size);
-        println!("Volume created at: {}", volume_response.volume.created);
+    async fn delete_on(&self, provider: ProviderKind, volume_id: &str) -> Result<(), ManagerError> {
+        let url = match provider {
+            ProviderKind::Vultr => format!("{}/blocks/{}", self.vultr_url, volume_id),
+            _ => format!("{}/volumes/{}", self.hetzner_url, volume_id),
+        };
+        let (client, key) = self.client(provider);
+        client.delete(url).bearer_auth(key).send().await?;
 
-        Ok(Volume {
-            id: volume_response.volume.id.to_string(),
-            provider: Provider::Hetzner
-        })
+        Ok(())
     }
 
-    pub async fn attach_volume_on_hetzner(&self, volume_id: &str, config: HetznerVolumeAttachmentConfig) -> Result<HetznerVolume, Box<dyn std::error::Error>> {
-        let response = self.client.post(&format!("https://api.hetzner.cloud/v1/volumes/{}/actions/attach", volume_id))
-            .headers(self.hetzner_headers())
-            .json(&config)
-            .send()
+    // Snapshots of the volume, newest first.
+    pub async fn list_snapshots(&self, provider: ProviderKind, volume_id: &str) -> Result<Vec<Snapshot>, ManagerError> {
+        self.supported(provider)?;
+
+        let mut snapshots: Vec<Snapshot> = self
+            .list_on(provider)
+            .await?
+            .into_iter()
+            .filter_map(|(volume, created_at)| Snapshot::of(volume_id, volume, created_at))
+            .collect();
+        snapshots.sort_by_key(|snapshot| Reverse((snapshot.created_at, snapshot.label.clone())));
+
+        Ok(snapshots)
+    }
+
+    // Copies the volume onto a new volume of the same size next to it, through the worker it is
+    // mounted on. The copy is attached to the volume's instance only while it is taken.
+    pub async fn create_snapshot(&self, provider: ProviderKind, volume_id: &str, label: &str) -> Result<Snapshot, ManagerError> {
+        let label = match label.is_empty() {
+            true => Utc::now().format("%Y%m%d%H%M%S").to_string(),
+            false => label.to_string(),
+        };
+        snapshots::check_label(&label)?;
+
+        let volume = self.get(provider, volume_id).await?;
+        let (instance_id, worker_id) = match (&volume.instance_id, volume.worker_id) {
+            (Some(instance_id), Some(worker_id)) => (instance_id.clone(), worker_id),
+            _ => {
+                return Err(ProviderError::Invalid(format!(
+                    "volume {} must be attached to a worker to be snapshotted",
+                    volume_id
+                ))
+                .into())
+            }
+        };
+        if self.list_snapshots(provider, volume_id).await?.iter().any(|snapshot| snapshot.label == label) {
+            return Err(ProviderError::Invalid(format!("volume {} already has a snapshot labelled {}", volume_id, label)).into());
+        }
+        self.costs.check_volume(provider, DEFAULT_ACCOUNT, &volume.region, volume.total)?;
+
+        let copy = self
+            .create_on(provider, &volume.region, volume.total, &snapshots::volume_label(volume_id, &label), block_type(&volume))
             .await?;
+        if let Err(e) = self.copy_with(provider, &copy.id, &instance_id, worker_id, volume_id, &copy.id).await {
+            if let Err(e) = self.delete_on(provider, &copy.id).await {
+                println!("Failed to delete volume {} of a failed snapshot: {}", copy.id, e);
+            }
+            return Err(e);
+        }
+
+        let snapshot = self
+            .list_snapshots(provider, volume_id)
+            .await?
+            .into_iter()
+            .find(|snapshot| snapshot.id == copy.id)
+            .ok_or_else(|| ProviderError::NotFound(format!("snapshot {} of volume {}", copy.id, volume_id)))?;
+
+        println!("Created snapshot {} of volume {} on {}", snapshot.id, volume_id, provider);
+        Ok(snapshot)
+    }
+
+    pub async fn delete_snapshot(&self, provider: ProviderKind, volume_id: &str, snapshot_id: &str) -> Result<(), ManagerError> {
+        let snapshot = self.available_snapshot(provider, volume_id, snapshot_id).await?;
+        self.delete_on(provider, &snapshot.id).await?;
 
-        if !response.status().is_success() {
-            return Err(format!("Failed to attach volume: {}: {}", volume_id, response.text().await?).into());
+        println!("Deleted snapshot {} of volume {} on {}", snapshot_id, volume_id, provider);
+        Ok(())
+    }
+
+    // Restores a snapshot into a new volume attached to the instance, which is mounted and
+    // recorded like any attached volume. The new volume is deleted again if the copy fails.
+    pub async fn restore_snapshot(
+        &self,
+        provider: ProviderKind,
+        volume_id: &str,
+        snapshot_id: &str,
+        request: &RestoreSnapshot,
+    ) -> Result<Volume, ManagerError> {
+        let snapshot = self.available_snapshot(provider, volume_id, snapshot_id).await?;
+
+        let size_gb = request.size_gb.unwrap_or(snapshot.size_gb);
+        if size_gb < snapshot.size_gb {
+            return Err(ProviderError::Invalid(format!(
+                "snapshot {} needs at least {} GB, not {} GB",
+                snapshot.id, snapshot.size_gb, size_gb
+            ))
+            .into());
+        }
+        let worker_id = match request.worker_id {
+            Some(worker_id) => worker_id,
+            None => self.worker_for(provider, &request.instance_id).await?.ok_or_else(|| {
+                ProviderError::Invalid(format!("no worker registered on instance {} to restore onto", request.instance_id))
+            })?,
+        };
+
+        let label = match request.label.is_empty() {
+            true => format!("restore-{}", snapshot.label),
+            false => request.label.clone(),
+        };
+        let create = CreateVolume {
+            provider,
+            region: snapshot.region.clone(),
+            size_gb,
+            label,
+            block_type: None,
+        };
+        let volume = self.create(&create).await?;
+
+        let attach = AttachVolume {
+            instance_id: request.instance_id.clone(),
+            worker_id: Some(worker_id),
+            container: request.container.clone(),
+            live: true,
+        };
+        let restored = match self.attach(provider, &volume.id, &attach).await {
+            Ok(_) => {
+                self.copy_with(provider, &snapshot.id, &request.instance_id, worker_id, &snapshot.id, &volume.id)
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = restored {
+            if let Err(e) = self.unmount(worker_id, &volume.id).await {
+                println!("Failed to unmount volume {} after a failed restore: {}", volume.id, e);
+            }
+            if let Err(e) = self.detach_on(provider, &volume.id, true).await {
+                println!("Failed to detach volume {} after a failed restore: {}", volume.id, e);
+            }
+            if let Err(e) = self.delete(provider, &volume.id).await {
+                println!("Failed to delete volume {} after a failed restore: {}", volume.id, e);
+            }
+            return Err(e);
         }
 
-        let volume_response: HetznerVolumeResponse = response.json().await?;
+        println!("Restored snapshot {} of volume {} into volume {} on {}", snapshot.id, volume_id, volume.id, provider);
+        self.get(provider, &volume.id).await
+    }
+
+    // A snapshot of the volume that is not being taken or restored from right now.
+    async fn available_snapshot(&self, provider: ProviderKind, volume_id: &str, snapshot_id: &str) -> Result<Snapshot, ManagerError> {
+        let snapshot = self
+            .list_snapshots(provider, volume_id)
+            .await?
+            .into_iter()
+            .find(|snapshot| snapshot.id == snapshot_id)
+            .ok_or_else(|| ProviderError::NotFound(format!("snapshot {} of volume {}", snapshot_id, volume_id)))?;
+        if snapshot.state != SnapshotState::Available {
+            return Err(ProviderError::Invalid(format!("snapshot {} is being copied", snapshot_id)).into());
+        }
 
-        Ok(volume_response.volume)
+        Ok(snapshot)
     }
 
-    pub async fn resize_volume_on_hetzner(&self, volume_id: &str, config: HetznerVolumeResizeConfig) -> Result<(), Box<dyn Error>> {
-        let response = self.client.post(&format!("https://api.hetzner.cloud/v1/volumes/{}/actions/resize", volume_id))
-            .headers(self.hetzner_headers())
-            .json(&config)
-            .send()
-            .await?;
-        
-        if !response.status().is_success() {
-            return Err(format!("Failed to resize volume {}: {}", volume_id, response.text().await?).into());
+    // Attaches the snapshot's volume to the instance and has its worker mount it and copy between
+    // `from` and `to`, one of which is the snapshot and the other a volume the worker has mounted.
+    // The snapshot is unmounted and detached again whether or not the copy succeeded.
+    async fn copy_with(
+        &self,
+        provider: ProviderKind,
+        snapshot_id: &str,
+        instance_id: &str,
+        worker_id: u64,
+        from: &str,
+        to: &str,
+    ) -> Result<(), ManagerError> {
+        self.attach_on(provider, snapshot_id, instance_id, true).await?;
+        let copied = self.mount_and_copy(provider, snapshot_id, worker_id, from, to).await;
+
+        if let Err(e) = self.unmount(worker_id, snapshot_id).await {
+            println!("Failed to unmount snapshot {} on worker {}: {}", snapshot_id, worker_id, e);
         }
+        self.detach_on(provider, snapshot_id, true).await?;
+
+        copied
+    }
+
+    async fn mount_and_copy(&self, provider: ProviderKind, snapshot_id: &str, worker_id: u64, from: &str, to: &str) -> Result<(), ManagerError> {
+        let device = self
+            .fetch(provider, snapshot_id)
+            .await?
+            .device
+            .ok_or_else(|| ProviderError::Invalid(format!("snapshot {} has no device to mount", snapshot_id)))?;
+        self.mount(worker_id, snapshot_id, &device).await.map_err(|e| worker_error(worker_id, e))?;
 
+        let used_bytes = self
+            .worker_client(worker_id)
+            .await
+            .map_err(|e| worker_error(worker_id, e))?
+            .copy_volume(CopyVolumeRequest {
+                from_volume_id: from.to_string(),
+                to_volume_id: to.to_string(),
+            })
+            .await
+            .map_err(|e| worker_error(worker_id, e))?
+            .into_inner()
+            .used_bytes;
+
+        println!("Copied volume {} to {} on worker {}: {} bytes", from, to, worker_id, used_bytes);
         Ok(())
     }
 
-    pub async fn detach_volume_hetzner(&self, volume_id: &str) -> Result<(), Box<dyn Error>> {
-        let response = self.client.post(&format!("https://api.hetzner.cloud/v1/volumes/{}/actions/detach", volume_id))
-            .headers(self.hetzner_headers())
+    pub async fn snapshot_policies(&self) -> Result<Vec<SnapshotPolicy>, ManagerError> {
+        Ok(self.snapshot_store.policies().await?)
+    }
+
+    pub async fn create_snapshot_policy(&self, policy: &NewSnapshotPolicy) -> Result<SnapshotPolicy, ManagerError> {
+        if policy.interval_secs == 0 || policy.retention == 0 {
+            return Err(ProviderError::Invalid("interval_secs and retention must be positive".to_string()).into());
+        }
+        // Only policies for volumes that exist.
+        self.get(policy.provider, &policy.volume_id).await?;
+
+        Ok(self.snapshot_store.create_policy(policy).await?)
+    }
+
+    // Returns whether the policy existed; the snapshots it took are kept.
+    pub async fn delete_snapshot_policy(&self, id: i64) -> Result<bool, ManagerError> {
+        Ok(self.snapshot_store.delete_policy(id).await?)
+    }
+
+    pub async fn snapshot_runs(&self, policy_id: Option<i64>) -> Result<Vec<SnapshotRun>, ManagerError> {
+        Ok(self.snapshot_store.runs(policy_id).await?)
+    }
+
+    // Runs the snapshot policies as they come due.
+    pub async fn run_snapshot_policies(&self) {
+        loop {
+            self.run_due_snapshot_policies(Utc::now()).await;
+            sleep(self.snapshot_check_interval).await;
+        }
+    }
+
+    pub async fn run_due_snapshot_policies(&self, now: DateTime<Utc>) {
+        match self.snapshot_store.policies().await {
+            Ok(policies) => {
+                for policy in policies.iter().filter(|policy| policy.is_due(now)) {
+                    self.run_snapshot_policy(policy, now).await;
+                }
+            }
+            Err(e) => println!("Failed to load snapshot policies: {}", e),
+        }
+    }
+
+    // Takes a snapshot, prunes the ones past the policy's retention and records the run either way.
+    async fn run_snapshot_policy(&self, policy: &SnapshotPolicy, started_at: DateTime<Utc>) {
+        let result = self.snapshot_and_prune(policy, started_at).await;
+
+        let run = SnapshotRun {
+            id: 0,
+            policy_id: policy.id,
+            provider: policy.provider.code().to_string(),
+            volume_id: policy.volume_id.clone(),
+            snapshot_id: result.as_ref().ok().map(|(snapshot_id, _)| snapshot_id.clone()),
+            succeeded: result.is_ok(),
+            error: result.as_ref().err().map(|e| e.to_string()),
+            pruned: result.as_ref().map_or(0, |(_, pruned)| *pruned),
+            started_at,
+            finished_at: Utc::now(),
+        };
+        if let Some(e) = &run.error {
+            println!("Snapshot policy {} failed for volume {}: {}", policy.id, policy.volume_id, e);
+        }
+
+        if let Err(e) = self.snapshot_store.record_run(&run).await {
+            println!("Failed to record run of snapshot policy {}: {}", policy.id, e);
+        }
+    }
+
+    // Returns the new snapshot's id and how many old ones were deleted.
+    async fn snapshot_and_prune(&self, policy: &SnapshotPolicy, at: DateTime<Utc>) -> Result<(String, u32), ManagerError> {
+        let snapshot = self.create_snapshot(policy.provider, &policy.volume_id, &policy.label(at)).await?;
+
+        // Newest first; snapshots the provider gave no creation time sort last, so they are pruned first.
+        let prefix = policy.label_prefix();
+        let taken: Vec<Snapshot> = self
+            .list_snapshots(policy.provider, &policy.volume_id)
+            .await?
+            .into_iter()
+            .filter(|snapshot| snapshot.label.starts_with(&prefix))
+            .collect();
+
+        let mut pruned = 0;
+        for old in taken.iter().skip(policy.retention as usize) {
+            self.delete_snapshot(policy.provider, &policy.volume_id, &old.id).await?;
+            pruned += 1;
+        }
+
+        Ok((snapshot.id, pruned))
+    }
+
+    fn client(&self, provider: ProviderKind) -> (&HttpClient, &str) {
+        match provider {
+            ProviderKind::Vultr => (&self.vultr_client, &self.vultr_key),
+            _ => (&self.hetzner_client, &self.hetzner_key),
+        }
+    }
+
+    // The provider's volumes, with when each was created.
+    async fn list_on(&self, provider: ProviderKind) -> Result<Vec<(Volume, Option<DateTime<Utc>>)>, ManagerError> {
+        let volumes = match provider {
+            ProviderKind::Vultr => vultr_pages::list_all::<VultrBlock>(
+                &self.vultr_client,
+                &format!("{}/blocks", self.vultr_url),
+                &self.vultr_key,
+                "blocks",
+            )
+            .await?
+            .into_iter()
+            .map(|block| {
+                let created_at = parse_timestamp(&block.date_created);
+                (Volume::from(block), created_at)
+            })
+            .collect(),
+            _ => hetzner_pages::list_all::<HetznerVolume>(
+                &self.hetzner_client,
+                &format!("{}/volumes", self.hetzner_url),
+                &self.hetzner_key,
+                "volumes",
+            )
+            .await?
+            .into_iter()
+            .map(|volume| {
+                let created_at = parse_timestamp(&volume.created);
+                (Volume::from(volume), created_at)
+            })
+            .collect(),
+        };
+
+        Ok(volumes)
+    }
+
+    async fn fetch(&self, provider: ProviderKind, volume_id: &str) -> Result<Volume, ManagerError> {
+        let volume = match provider {
+            ProviderKind::Vultr => self
+                .vultr_client
+                .get(format!("{}/blocks/{}", self.vultr_url, volume_id))
+                .bearer_auth(&self.vultr_key)
+                .send()
+                .await?
+                .json::<VultrBlockResponse>()
+                .await?
+                .block
+                .into(),
+            _ => self
+                .hetzner_client
+                .get(format!("{}/volumes/{}", self.hetzner_url, volume_id))
+                .bearer_auth(&self.hetzner_key)
+                .send()
+                .await?
+                .json::<HetznerVolumeResponse>()
+                .await?
+                .volume
+                .into(),
+        };
+
+        Ok(volume)
+    }
+
+    // The worker running on the instance, if it registered.
+    async fn worker_for(&self, provider: ProviderKind, instance_id: &str) -> Result<Option<u64>, ManagerError> {
+        let workers = self.workers.list().await?;
+
+        Ok(workers
+            .iter()
+            .find(|worker| worker.provider == provider && worker.instance_id.as_deref() == Some(instance_id))
+            .map(|worker| worker.id))
+    }
+
+    async fn hetzner_action(&self, volume_id: &str, action: &str, body: serde_json::Value) -> Result<(), ManagerError> {
+        let response = self
+            .hetzner_client
+            .post(format!("{}/volumes/{}/actions/{}", self.hetzner_url, volume_id, action))
+            .bearer_auth(&self.hetzner_key)
+            .json(&body)
             .send()
+            .await?
+            .json::<ActionResponse>()
             .await?;
 
-        if !response.status().is_success() {
-            return Err(format!("Failed to detach volume {}: {}", volume_id, response.text().await?).into());
-        }
+        self.wait_for_hetzner_action(response.action.id).await
+    }
+
+    // Hetzner volume calls return once the change is queued; this waits for it to be applied.
+    async fn wait_for_hetzner_action(&self, action_id: u64) -> Result<(), ManagerError> {
+        wait_for_action(&self.hetzner_client, &self.hetzner_url, &self.hetzner_key, action_id, &WaitOptions::default()).await?;
 
         Ok(())
     }
-}
\ No newline at end of file
+}
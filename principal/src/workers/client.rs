@@ -0,0 +1,44 @@
+use models::models::worker::Worker;
+use tonic::metadata::{Ascii, MetadataValue};
+use tonic::service::interceptor::InterceptedService;
+use tonic::service::Interceptor;
+use tonic::transport::Channel;
+use tonic::{Request, Status};
+
+use crate::docker::docker_service_client::DockerServiceClient;
+use crate::workers::store::WorkerStore;
+
+pub type WorkerClient = DockerServiceClient<InterceptedService<Channel, ServiceToken>>;
+
+// Sends the service token the worker was issued when it registered with every call; its
+// `DockerService` refuses calls without it.
+#[derive(Clone)]
+pub struct ServiceToken(MetadataValue<Ascii>);
+
+impl Interceptor for ServiceToken {
+	fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+		request.metadata_mut().insert("authorization", self.0.clone());
+		Ok(request)
+	}
+}
+
+// Connects to the worker's `DockerService` on its registered address.
+pub async fn connect(workers: &WorkerStore, worker: &Worker) -> Result<WorkerClient, Status> {
+	let token = workers
+		.service_token(worker.id)
+		.await
+		.map_err(|e| Status::internal(e.to_string()))?
+		.filter(|token| !token.is_empty())
+		.ok_or_else(|| Status::failed_precondition(format!("worker {} has no service token", worker.id)))?;
+	let token = format!("Bearer {}", token)
+		.parse()
+		.map_err(|_| Status::internal(format!("invalid service token for worker {}", worker.id)))?;
+
+	let channel = Channel::from_shared(format!("http://{}", worker.grpc_addr))
+		.map_err(|e| Status::invalid_argument(e.to_string()))?
+		.connect()
+		.await
+		.map_err(|e| Status::unavailable(e.to_string()))?;
+
+	Ok(DockerServiceClient::with_interceptor(channel, ServiceToken(token)))
+}
@@ -0,0 +1,45 @@
+use models::models::instance_state::InstanceState;
+use models::models::worker::Worker;
+use tonic::Status;
+
+use crate::docker::{ListContainersRequest, StopContainerRequest};
+use crate::workers::client;
+use crate::workers::store::WorkerStore;
+
+// The registered worker running on an instance, matched on instance id or public address.
+pub fn find<'a>(workers: &'a [Worker], instance_id: &str, main_ip: Option<&str>) -> Option<&'a Worker> {
+	workers.iter().find(|worker| {
+		worker.instance_id.as_deref() == Some(instance_id)
+			|| (main_ip.is_some() && Some(worker.network.primary_ipv4.as_str()) == main_ip)
+	})
+}
+
+// How busy an instance's worker is when picking instances to remove: running containers, with
+// CPU as the tie-breaker. Draining workers are already on their way out and rank lowest.
+pub fn load(worker: Option<&Worker>) -> f64 {
+	match worker {
+		Some(worker) if worker.state == InstanceState::Draining => -1.0,
+		Some(worker) => worker.metrics.workload + worker.metrics.cpu / 100.0,
+		None => 0.0,
+	}
+}
+
+// Asks the worker's `DockerService` to stop everything it runs, returns how many containers
+// were stopped.
+pub async fn stop_containers(workers: &WorkerStore, worker: &Worker) -> Result<usize, Status> {
+	let mut client = client::connect(workers, worker).await?;
+
+	let names = client
+		.list_containers(ListContainersRequest {})
+		.await?
+		.into_inner()
+		.names;
+
+	for name in &names {
+		client
+			.stop_container(StopContainerRequest { name: name.clone() })
+			.await?;
+	}
+
+	Ok(names.len())
+}
@@ -0,0 +1,4 @@
+pub mod client;
+pub mod drain;
+pub mod registry;
+pub mod store;
@@ -0,0 +1,261 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{TimeZone, Utc};
+use models::models::cloud_provider::CloudProvider as ProviderKind;
+use models::models::instance::Instance;
+use models::models::instance_state::InstanceState;
+use models::models::metrics::Metrics;
+use models::models::network::Network;
+use models::models::worker::Worker;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use tokio::time::sleep;
+use tonic::{Request, Response, Status};
+
+use crate::config::config::{WorkersConfig, DEFAULT_ACCOUNT};
+use crate::manager::manager::Manager;
+use crate::regions::resolve::canonical;
+use crate::worker_registry::worker_registry_server::WorkerRegistry;
+use crate::worker_registry::{
+	self, HeartbeatRequest, HeartbeatResponse, RegisterRequest, RegisterResponse,
+};
+
+// gRPC service workers register with at boot and then send heartbeats to.
+pub struct Registry {
+	manager: Arc<Manager>,
+	config: WorkersConfig,
+	join_token: String,
+}
+
+impl Registry {
+	pub fn new(manager: Arc<Manager>, config: WorkersConfig, join_token: String) -> Self {
+		Self {
+			manager,
+			config,
+			join_token,
+		}
+	}
+
+	fn authorize(&self, join_token: &str) -> Result<(), Status> {
+		if self.join_token.is_empty() {
+			return Err(Status::unavailable(
+				"worker registration is disabled without bootstrap.join_token",
+			));
+		}
+		if !tokens_match(join_token, &self.join_token) {
+			return Err(Status::unauthenticated("invalid join token"));
+		}
+
+		Ok(())
+	}
+
+	// Marks workers that missed their heartbeats as `Unknown`, once per heartbeat interval.
+	pub async fn sweep(&self) {
+		let interval = Duration::from_secs(self.config.heartbeat_interval_secs.into());
+		let timeout = chrono::Duration::seconds(
+			(self.config.heartbeat_interval_secs * self.config.missed_heartbeats).into(),
+		);
+
+		loop {
+			sleep(interval).await;
+
+			let workers = match self.manager.worker_store().list().await {
+				Ok(workers) => workers,
+				Err(e) => {
+					println!("Failed to list workers: {}", e);
+					continue;
+				}
+			};
+
+			for mut worker in workers {
+				let alive = matches!(worker.state, InstanceState::Starting | InstanceState::Running);
+				if !alive || Utc::now() - worker.last_updated < timeout {
+					continue;
+				}
+
+				println!(
+					"Worker {} missed {} heartbeats, marking it unknown",
+					worker.id, self.config.missed_heartbeats
+				);
+				worker.state = InstanceState::Unknown;
+				if let Err(e) = self.manager.worker_store().save(&worker).await {
+					println!("Failed to update worker {}: {}", worker.id, e);
+				}
+			}
+		}
+	}
+}
+
+// Takes as long wherever the tokens differ, so the join token can't be guessed a byte at a time
+// from response times; only its length shows.
+pub fn tokens_match(given: &str, expected: &str) -> bool {
+	let (given, expected) = (given.as_bytes(), expected.as_bytes());
+
+	given.len() == expected.len() && given.iter().zip(expected).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+// A new random credential and the hash the store keeps in its place.
+fn new_credential() -> (String, String) {
+	let credential = new_token();
+	let hash = credential_hash(&credential);
+
+	(credential, hash)
+}
+
+fn new_token() -> String {
+	hex(&rand::thread_rng().gen::<[u8; 32]>())
+}
+
+fn credential_hash(credential: &str) -> String {
+	hex(&Sha256::digest(credential.as_bytes()))
+}
+
+fn hex(bytes: &[u8]) -> String {
+	bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+impl From<worker_registry::Metrics> for Metrics {
+	fn from(metrics: worker_registry::Metrics) -> Self {
+		Metrics {
+			cpu: metrics.cpu,
+			memory: metrics.memory,
+			disk: metrics.disk,
+			network: metrics.network,
+			workload: metrics.workload,
+			time: Utc
+				.timestamp_opt(metrics.time, 0)
+				.single()
+				.unwrap_or_else(Utc::now),
+		}
+	}
+}
+
+fn internal(e: sqlx::Error) -> Status {
+	Status::internal(e.to_string())
+}
+
+#[tonic::async_trait]
+impl WorkerRegistry for Registry {
+	async fn register(
+		&self,
+		request: Request<RegisterRequest>,
+	) -> Result<Response<RegisterResponse>, Status> {
+		let remote_addr = request.remote_addr();
+		let request = request.into_inner();
+		self.authorize(&request.join_token)?;
+
+		let provider = request
+			.provider
+			.parse::<ProviderKind>()
+			.map_err(Status::invalid_argument)?;
+		let instance_id = request
+			.instance_id
+			.filter(|id| !id.is_empty())
+			.ok_or_else(|| Status::invalid_argument("instance_id is required"))?;
+
+		// Workers that don't know their public address get the one they connected from.
+		let network = match request.network {
+			Some(network) if !network.primary_ipv4.is_empty() => Network {
+				primary_ipv4: network.primary_ipv4,
+				primary_ipv6: network.primary_ipv6.unwrap_or_default(),
+			},
+			_ => Network {
+				primary_ipv4: remote_addr
+					.map(|addr| addr.ip().to_string())
+					.ok_or_else(|| Status::invalid_argument("network is required"))?,
+				primary_ipv6: String::new(),
+			},
+		};
+
+		let now = Utc::now();
+		let worker = Worker {
+			id: 0,
+			instance_id: Some(instance_id),
+			grpc_addr: format!("{}:{}", network.primary_ipv4, request.grpc_port),
+			network,
+			provider,
+			account: if request.account.is_empty() {
+				DEFAULT_ACCOUNT.to_string()
+			} else {
+				request.account
+			},
+			region: canonical(provider, &request.region),
+			instance: Instance {
+				provider: provider.code().to_string(),
+				region: request.region,
+				vcpu: request.vcpu,
+				memory: request.memory,
+				boot_volume: None,
+			},
+			metrics: Metrics {
+				cpu: 0.0,
+				memory: 0.0,
+				disk: 0.0,
+				network: 0.0,
+				workload: 0.0,
+				time: now,
+			},
+			state: InstanceState::Running,
+			volumes: Vec::new(),
+			last_updated: now,
+			last_health_check: None,
+		};
+
+		let (credential, credential_hash) = new_credential();
+		let service_token = new_token();
+		let worker = self
+			.manager
+			.worker_store()
+			.register(&worker, &credential_hash, &service_token)
+			.await
+			.map_err(internal)?;
+		println!(
+			"Registered worker {} in {} region {}",
+			worker.id, worker.provider, worker.region
+		);
+
+		Ok(Response::new(RegisterResponse {
+			worker_id: worker.id,
+			heartbeat_interval_secs: self.config.heartbeat_interval_secs,
+			credential,
+			service_token,
+		}))
+	}
+
+	async fn heartbeat(
+		&self,
+		request: Request<HeartbeatRequest>,
+	) -> Result<Response<HeartbeatResponse>, Status> {
+		let request = request.into_inner();
+
+		let metrics = request.metrics.map(Metrics::from);
+		let volumes: Vec<String> = request.volumes.iter().map(|usage| usage.volume_id.clone()).collect();
+		let worker = self
+			.manager
+			.worker_store()
+			.heartbeat(
+				request.worker_id,
+				&credential_hash(&request.credential),
+				metrics.as_ref(),
+				&volumes,
+				Utc::now(),
+			)
+			.await
+			.map_err(internal)?
+			.ok_or_else(|| Status::not_found("unknown worker, register again"))?;
+
+		for usage in &request.volumes {
+			if let Err(e) = self
+				.manager
+				.volumes()
+				.record_usage(&worker, &usage.volume_id, usage.used_bytes)
+				.await
+			{
+				println!("Failed to record usage of volume {}: {}", usage.volume_id, e);
+			}
+		}
+
+		Ok(Response::new(HeartbeatResponse {}))
+	}
+}
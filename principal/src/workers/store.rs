@@ -0,0 +1,149 @@
+use chrono::{DateTime, Utc};
+use models::models::metrics::Metrics;
+use models::models::worker::Worker;
+use sqlx::postgres::PgPool;
+use sqlx::FromRow;
+
+// Workers are stored as JSON next to the columns needed to look them up.
+#[derive(Debug, Clone)]
+pub struct WorkerStore {
+	pool: PgPool,
+}
+
+#[derive(FromRow)]
+struct WorkerRow {
+	id: i64,
+	worker: String,
+}
+
+impl TryFrom<WorkerRow> for Worker {
+	type Error = sqlx::Error;
+
+	fn try_from(row: WorkerRow) -> Result<Self, Self::Error> {
+		let mut worker: Worker =
+			serde_json::from_str(&row.worker).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+		worker.id = row.id as u64;
+
+		Ok(worker)
+	}
+}
+
+impl WorkerStore {
+	pub fn new(pool: PgPool) -> Self {
+		Self { pool }
+	}
+
+	pub async fn list(&self) -> Result<Vec<Worker>, sqlx::Error> {
+		sqlx::query_as::<_, WorkerRow>("SELECT id::BIGINT AS id, worker FROM Workers ORDER BY id")
+			.fetch_all(&self.pool)
+			.await?
+			.into_iter()
+			.map(Worker::try_from)
+			.collect()
+	}
+
+	pub async fn get(&self, id: u64) -> Result<Option<Worker>, sqlx::Error> {
+		sqlx::query_as::<_, WorkerRow>("SELECT id::BIGINT AS id, worker FROM Workers WHERE id = $1")
+			.bind(id as i64)
+			.fetch_optional(&self.pool)
+			.await?
+			.map(Worker::try_from)
+			.transpose()
+	}
+
+	// A worker that registers again for the same instance keeps its id and gets the new
+	// credential and service token, but not its state back if it was drained or removed in the
+	// meantime.
+	pub async fn register(
+		&self,
+		worker: &Worker,
+		credential_hash: &str,
+		service_token: &str,
+	) -> Result<Worker, sqlx::Error> {
+		sqlx::query_as::<_, WorkerRow>(
+			r#"
+			INSERT INTO Workers (
+				primary_ipv4, provider, account, region, instance_id, credential_hash, state, worker, service_token
+			)
+			VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+			ON CONFLICT (provider, instance_id) DO UPDATE SET
+				primary_ipv4 = $1, account = $3, region = $4, credential_hash = $6, service_token = $9,
+				state = CASE WHEN Workers.state IN ('Draining', 'Terminated') THEN Workers.state ELSE $7 END,
+				worker = CASE
+					WHEN Workers.state IN ('Draining', 'Terminated')
+					THEN ($8::jsonb || jsonb_build_object('state', Workers.state))::TEXT
+					ELSE $8
+				END
+			RETURNING id::BIGINT AS id, worker
+			"#,
+		)
+		.bind(&worker.network.primary_ipv4)
+		.bind(worker.provider.code())
+		.bind(&worker.account)
+		.bind(&worker.region)
+		.bind(&worker.instance_id)
+		.bind(credential_hash)
+		.bind(format!("{:?}", worker.state))
+		.bind(serde_json::to_string(worker).unwrap_or_default())
+		.bind(service_token)
+		.fetch_one(&self.pool)
+		.await
+		.and_then(Worker::try_from)
+	}
+
+	// The token the worker's `DockerService` expects, kept out of the JSON record so listing
+	// workers doesn't show it.
+	pub async fn service_token(&self, id: u64) -> Result<Option<String>, sqlx::Error> {
+		sqlx::query_scalar("SELECT service_token FROM Workers WHERE id = $1")
+			.bind(id as i64)
+			.fetch_optional(&self.pool)
+			.await
+	}
+
+	pub async fn save(&self, worker: &Worker) -> Result<(), sqlx::Error> {
+		sqlx::query("UPDATE Workers SET state = $2, worker = $3 WHERE id = $1")
+			.bind(worker.id as i64)
+			.bind(format!("{:?}", worker.state))
+			.bind(serde_json::to_string(worker).unwrap_or_default())
+			.execute(&self.pool)
+			.await?;
+
+		Ok(())
+	}
+
+	// Updates only what a heartbeat reports, in place, so it can't undo a state change made in
+	// the meantime, e.g. a drain. A worker that was starting or had gone quiet is running again.
+	// `None` if the worker is unknown or the credential isn't its own.
+	pub async fn heartbeat(
+		&self,
+		id: u64,
+		credential_hash: &str,
+		metrics: Option<&Metrics>,
+		volumes: &[String],
+		at: DateTime<Utc>,
+	) -> Result<Option<Worker>, sqlx::Error> {
+		sqlx::query_as::<_, WorkerRow>(
+			r#"
+			UPDATE Workers SET
+				state = CASE WHEN state IN ('Starting', 'Unknown') THEN 'Running' ELSE state END,
+				worker = (worker::jsonb || jsonb_build_object(
+					'metrics', COALESCE($2::jsonb, worker::jsonb -> 'metrics'),
+					'volumes', $3::jsonb,
+					'last_updated', $4::jsonb,
+					'state', CASE WHEN state IN ('Starting', 'Unknown') THEN 'Running' ELSE worker::jsonb ->> 'state' END
+				))::TEXT
+			WHERE id = $1 AND credential_hash = $5
+			RETURNING id::BIGINT AS id, worker
+			"#,
+		)
+		.bind(id as i64)
+		.bind(metrics.map(|metrics| serde_json::to_string(metrics).unwrap_or_default()))
+		.bind(serde_json::to_string(volumes).unwrap_or_default())
+		.bind(serde_json::to_string(&at).unwrap_or_default())
+		.bind(credential_hash)
+		.fetch_optional(&self.pool)
+		.await?
+		.map(Worker::try_from)
+		.transpose()
+	}
+}
@@ -1,33 +1,27 @@
 #!/bin/bash
 
 DATABASE_URL="db_url"
+SCHEMA="$(dirname "$0")/manager_schema.sql"
 
 SQL_COMMANDS=$(cat <<SQL
-CREATE TABLE IF NOT EXISTS Providers (
-    id SERIAL PRIMARY KEY,
-    provider TEXT NOT NULL,
-    region TEXT NOT NULL,
-    instance_count INT NOT NULL,
-    UNIQUE(provider, region)
-);
+INSERT INTO Providers (provider, region, instance_count, min_count, max_count, plan, image, labels)
+VALUES ('vultr', 'lax', 1, 0, 5, 'vhf-1c-1gb', '1743', '{role=worker}')
+ON CONFLICT DO NOTHING;
 
-INSERT INTO Providers (provider, region, instance_count)
-VALUES ('vultr', 'us-west', 1)
-ON CONFLICT (provider, region) DO NOTHING;
+INSERT INTO Providers (provider, region, instance_count, min_count, max_count, plan, image, labels)
+VALUES ('vultr', 'ewr', 1, 0, 5, 'vhf-1c-1gb', '1743', '{role=worker}')
+ON CONFLICT DO NOTHING;
 
-INSERT INTO Providers (provider, region, instance_count)
-VALUES ('vultr', 'us-east', 1)
-ON CONFLICT (provider, region) DO NOTHING;
+INSERT INTO Providers (provider, region, instance_count, min_count, max_count, plan, image, labels)
+VALUES ('hetzner', 'fsn1', 1, 0, 5, 'cx21', 'ubuntu-22.04', '{role=worker}')
+ON CONFLICT DO NOTHING;
 
-INSERT INTO Providers (provider, region, instance_count)
-VALUES ('hetzner', 'eu-west', 1)
-ON CONFLICT (provider, region) DO NOTHING;
-
-INSERT INTO Providers (provider, region, instance_count)
-VALUES ('hetzner', 'eu-central', 1)
-ON CONFLICT (provider, region) DO NOTHING;
+INSERT INTO Providers (provider, region, instance_count, min_count, max_count, plan, image, labels)
+VALUES ('hetzner', 'hel1', 1, 0, 5, 'cx21', 'ubuntu-22.04', '{role=worker}')
+ON CONFLICT DO NOTHING;
 SQL
 )
 
-# Execute the SQL commands using the psql tool
+# Create or migrate the tables, then seed the example rules using the psql tool
+psql "$DATABASE_URL" -f "$SCHEMA"
 echo "$SQL_COMMANDS" | psql "$DATABASE_URL"
\ No newline at end of file
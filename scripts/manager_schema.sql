@@ -0,0 +1,139 @@
+-- Tables of the principal, applied by manager_data.sh and the principal's tests.
+CREATE TABLE IF NOT EXISTS Providers (
+    id SERIAL PRIMARY KEY,
+    provider TEXT NOT NULL,
+    account TEXT NOT NULL DEFAULT 'default',
+    region TEXT NOT NULL,
+    instance_count INT NOT NULL,
+    min_count INT,
+    max_count INT,
+    plan TEXT,
+    image TEXT,
+    ssh_keys TEXT[] NOT NULL DEFAULT '{}',
+    user_data TEXT,
+    labels TEXT[] NOT NULL DEFAULT '{}'
+);
+
+-- Tables created before provider accounts existed: every rule targets the default account.
+ALTER TABLE Providers ADD COLUMN IF NOT EXISTS account TEXT NOT NULL DEFAULT 'default';
+ALTER TABLE Providers DROP CONSTRAINT IF EXISTS providers_provider_region_key;
+CREATE UNIQUE INDEX IF NOT EXISTS providers_provider_account_region_key ON Providers (provider, account, region);
+
+-- Tables created before rules carried what to boot.
+ALTER TABLE Providers ADD COLUMN IF NOT EXISTS min_count INT;
+ALTER TABLE Providers ADD COLUMN IF NOT EXISTS max_count INT;
+ALTER TABLE Providers ADD COLUMN IF NOT EXISTS plan TEXT;
+ALTER TABLE Providers ADD COLUMN IF NOT EXISTS image TEXT;
+ALTER TABLE Providers ADD COLUMN IF NOT EXISTS ssh_keys TEXT[] NOT NULL DEFAULT '{}';
+ALTER TABLE Providers ADD COLUMN IF NOT EXISTS user_data TEXT;
+ALTER TABLE Providers ADD COLUMN IF NOT EXISTS labels TEXT[] NOT NULL DEFAULT '{}';
+
+-- GPU pools share their region with the rule for regular instances.
+ALTER TABLE Providers ADD COLUMN IF NOT EXISTS gpu_model TEXT;
+ALTER TABLE Providers ADD COLUMN IF NOT EXISTS gpu_vram_gb INT;
+ALTER TABLE Providers DROP CONSTRAINT IF EXISTS providers_provider_account_region_key;
+DROP INDEX IF EXISTS providers_provider_account_region_key;
+CREATE UNIQUE INDEX IF NOT EXISTS providers_provider_account_region_gpu_key
+    ON Providers (provider, account, region, COALESCE(gpu_model, ''));
+
+-- Every change made through the principal's /rules API; the manager reloads its rules
+-- when the latest id changes.
+CREATE TABLE IF NOT EXISTS RuleHistory (
+    id BIGSERIAL PRIMARY KEY,
+    rule_id BIGINT NOT NULL,
+    action TEXT NOT NULL,
+    rule TEXT NOT NULL,
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Registered workers, one per instance; the full record is kept as JSON in `worker`. Only the
+-- hash of the credential each worker authenticates its heartbeats with is stored; the service
+-- token the principal presents to the worker's own gRPC services is kept as is.
+CREATE TABLE IF NOT EXISTS Workers (
+    id BIGSERIAL PRIMARY KEY,
+    primary_ipv4 TEXT NOT NULL,
+    provider TEXT NOT NULL,
+    account TEXT NOT NULL,
+    region TEXT NOT NULL,
+    instance_id TEXT,
+    credential_hash TEXT NOT NULL DEFAULT '',
+    service_token TEXT NOT NULL DEFAULT '',
+    state TEXT NOT NULL,
+    worker TEXT NOT NULL
+);
+
+-- Tables created while workers were keyed by their address and shared the join token.
+ALTER TABLE Workers ADD COLUMN IF NOT EXISTS instance_id TEXT;
+ALTER TABLE Workers ADD COLUMN IF NOT EXISTS credential_hash TEXT NOT NULL DEFAULT '';
+ALTER TABLE Workers ADD COLUMN IF NOT EXISTS service_token TEXT NOT NULL DEFAULT '';
+ALTER TABLE Workers DROP CONSTRAINT IF EXISTS workers_primary_ipv4_key;
+CREATE UNIQUE INDEX IF NOT EXISTS workers_provider_instance_id_key ON Workers (provider, instance_id);
+
+-- Instance lifecycle transitions and anomalies (stuck starting, unexpected termination).
+CREATE TABLE IF NOT EXISTS InstanceEvents (
+    id BIGSERIAL PRIMARY KEY,
+    instance_id TEXT NOT NULL,
+    provider TEXT NOT NULL,
+    account TEXT NOT NULL,
+    region TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    from_state TEXT,
+    to_state TEXT NOT NULL,
+    at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS instance_events_instance_id ON InstanceEvents (instance_id);
+
+-- Block storage volumes and the worker and container each one belongs to.
+CREATE TABLE IF NOT EXISTS Volumes (
+    id BIGSERIAL PRIMARY KEY,
+    provider TEXT NOT NULL,
+    volume_id TEXT NOT NULL,
+    state TEXT NOT NULL,
+    instance_id TEXT,
+    worker_id BIGINT,
+    container TEXT,
+    volume TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (provider, volume_id)
+);
+
+-- Scheduled volume snapshots; each policy keeps its newest `retention` snapshots.
+CREATE TABLE IF NOT EXISTS SnapshotPolicies (
+    id BIGSERIAL PRIMARY KEY,
+    provider TEXT NOT NULL,
+    volume_id TEXT NOT NULL,
+    interval_secs BIGINT NOT NULL,
+    retention INT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    last_run_at TIMESTAMPTZ
+);
+
+-- Every run of a snapshot policy, failed ones included.
+CREATE TABLE IF NOT EXISTS SnapshotRuns (
+    id BIGSERIAL PRIMARY KEY,
+    policy_id BIGINT NOT NULL,
+    provider TEXT NOT NULL,
+    volume_id TEXT NOT NULL,
+    snapshot_id TEXT,
+    succeeded BOOLEAN NOT NULL,
+    error TEXT,
+    pruned INT NOT NULL DEFAULT 0,
+    started_at TIMESTAMPTZ NOT NULL,
+    finished_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS snapshot_runs_policy_id ON SnapshotRuns (policy_id);
+
+-- Grows a volume by step_gb up to max_gb once its worker reports it threshold_percent full.
+CREATE TABLE IF NOT EXISTS VolumeAutoscalePolicies (
+    id BIGSERIAL PRIMARY KEY,
+    provider TEXT NOT NULL,
+    volume_id TEXT NOT NULL,
+    threshold_percent INT NOT NULL,
+    step_gb BIGINT NOT NULL,
+    max_gb BIGINT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    last_scaled_at TIMESTAMPTZ,
+    UNIQUE (provider, volume_id)
+);
@@ -3,11 +3,9 @@ use serde::Deserialize;
 use hmac::{Hmac, Mac};
 use sha2::Sha256;
 use reqwest::Client;
-use dotenv_codegen::dotenv;
 
 type HmacSha256 = Hmac<Sha256>;
 
-const WEBHOOK_SECRET: &str = dotenv!("GITHUB_WEBHOOK_SECRET");
 const BUILDER_ENDPOINT: &str = "http://localhost:8084/build";
 
 #[derive(Debug, Deserialize)]
@@ -59,8 +57,19 @@ pub async fn handle_request(req: Request<Body>) -> Result<Response<Body>, hyper:
         match (req.method(), req.uri().path()) {
             (&Method::POST, "/webhook") => {
                 let whole_body = hyper::body::to_bytes(req.into_body()).await?;
-                
-                let mut mac = HmacSha256::new_from_slice(WEBHOOK_SECRET.as_bytes()).expect("Invalid HMAC key");
+
+                // Read at runtime so the secret can be rotated without a rebuild.
+                let webhook_secret = match std::env::var("GITHUB_WEBHOOK_SECRET") {
+                    Ok(secret) if !secret.is_empty() => secret,
+                    _ => {
+                        return Ok(Response::builder()
+                            .status(StatusCode::INTERNAL_SERVER_ERROR)
+                            .body(Body::from("Webhook secret is not configured"))
+                            .unwrap());
+                    }
+                };
+
+                let mut mac = HmacSha256::new_from_slice(webhook_secret.as_bytes()).expect("Invalid HMAC key");
     
                 mac.update(&whole_body);
                 let result = mac.finalize();
@@ -0,0 +1,767 @@
+use std::collections::HashMap;
+use std::time::Instant;
+
+use hyper::http::StatusCode;
+use hyper::{Body, Method, Response};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::pagination::numbered_page;
+use crate::response::json;
+use crate::state::{ipv4, timestamp, Cloud, FakeRequest};
+
+// (code, city, country, network zone)
+const LOCATIONS: &[(&str, &str, &str, &str)] = &[
+	("fsn1", "Falkenstein", "DE", "eu-central"),
+	("nbg1", "Nuremberg", "DE", "eu-central"),
+	("hel1", "Helsinki", "FI", "eu-central"),
+	("ash", "Ashburn, VA", "US", "us-east"),
+	("hil", "Hillsboro, OR", "US", "us-west"),
+];
+
+// (name, cores, memory in GB, disk in GB, cpu type, architecture)
+const SERVER_TYPES: &[(&str, u32, f32, u32, &str, &str)] = &[
+	("cx11", 1, 2.0, 20, "shared", "x86"),
+	("cpx11", 2, 2.0, 40, "shared", "x86"),
+	("cx21", 2, 4.0, 40, "shared", "x86"),
+	("cpx21", 3, 4.0, 80, "shared", "x86"),
+	("cx31", 2, 8.0, 80, "shared", "x86"),
+	("cpx31", 4, 8.0, 160, "shared", "x86"),
+	("cx41", 4, 16.0, 160, "shared", "x86"),
+	("cpx41", 8, 16.0, 240, "shared", "x86"),
+	("cx51", 8, 32.0, 240, "shared", "x86"),
+	("cpx51", 16, 32.0, 360, "shared", "x86"),
+	("cax11", 2, 4.0, 40, "shared", "arm"),
+	("cax21", 4, 8.0, 80, "shared", "arm"),
+	("cax31", 8, 16.0, 160, "shared", "arm"),
+	("cax41", 16, 32.0, 320, "shared", "arm"),
+	("ccx12", 2, 8.0, 80, "dedicated", "x86"),
+	("ccx22", 4, 16.0, 160, "dedicated", "x86"),
+	("ccx32", 8, 32.0, 240, "dedicated", "x86"),
+	("ccx42", 16, 64.0, 360, "dedicated", "x86"),
+	("ccx52", 32, 128.0, 600, "dedicated", "x86"),
+	("ccx62", 48, 192.0, 960, "dedicated", "x86"),
+];
+
+#[derive(Default)]
+pub struct Store {
+	servers: Vec<Server>,
+	volumes: Vec<Volume>,
+	actions: Vec<Action>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct Server {
+	id: u64,
+	name: String,
+	status: String,
+	created: String,
+	public_net: Value,
+	private_net: Vec<Value>,
+	server_type: Value,
+	datacenter: Value,
+	image: Value,
+	iso: Option<Value>,
+	rescue_enabled: bool,
+	locked: bool,
+	backup_window: Option<String>,
+	outgoing_traffic: u64,
+	ingoing_traffic: u64,
+	included_traffic: u64,
+	protection: Value,
+	labels: HashMap<String, String>,
+	volumes: Vec<u64>,
+	load_balancers: Vec<u64>,
+	primary_disk_size: u32,
+	placement_group: Option<Value>,
+	#[serde(skip)]
+	user_data: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct Volume {
+	id: u64,
+	name: String,
+	server: Option<u64>,
+	location: Value,
+	size: u32,
+	linux_device: String,
+	protection: Value,
+	labels: HashMap<String, String>,
+	status: String,
+	created: String,
+	format: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct Action {
+	id: u64,
+	command: String,
+	status: String,
+	progress: u8,
+	started: String,
+	finished: Option<String>,
+	resources: Vec<Value>,
+	error: Option<Value>,
+	#[serde(skip)]
+	ready_at: Instant,
+	// Server status to apply once the action finishes.
+	#[serde(skip)]
+	server_status: Option<(u64, &'static str)>,
+}
+
+#[derive(Deserialize)]
+struct CreateServer {
+	name: Option<String>,
+	server_type: Option<String>,
+	image: Option<String>,
+	location: Option<String>,
+	labels: Option<HashMap<String, String>>,
+	user_data: Option<String>,
+	start_after_create: Option<bool>,
+	volumes: Option<Vec<u64>>,
+}
+
+#[derive(Deserialize)]
+struct CreateVolume {
+	name: Option<String>,
+	size: Option<u32>,
+	location: Option<String>,
+	server: Option<u64>,
+	format: Option<String>,
+	labels: Option<HashMap<String, String>>,
+}
+
+#[derive(Deserialize)]
+struct AttachVolume {
+	server: u64,
+}
+
+#[derive(Deserialize)]
+struct ResizeVolume {
+	size: u32,
+}
+
+#[derive(Deserialize)]
+struct Traffic {
+	#[serde(default)]
+	incoming_bytes: u64,
+	#[serde(default)]
+	outgoing_bytes: u64,
+}
+
+// Hetzner errors look like `{"error": {"code": "not_found", "message": "..."}}`.
+fn error(status: StatusCode, code: &str, message: impl ToString) -> Response<Body> {
+	json(
+		status,
+		&json!({ "error": { "code": code, "message": message.to_string(), "details": {} } }),
+	)
+}
+
+fn invalid_input(message: impl ToString) -> Response<Body> {
+	error(StatusCode::BAD_REQUEST, "invalid_input", message)
+}
+
+fn not_found(kind: &str, id: u64) -> Response<Body> {
+	error(
+		StatusCode::NOT_FOUND,
+		"not_found",
+		format!("{} with ID {} not found", kind, id),
+	)
+}
+
+fn parse_id(id: &str) -> Result<u64, Response<Body>> {
+	id.parse()
+		.map_err(|_| invalid_input(format!("invalid ID: {}", id)))
+}
+
+fn location(code: &str) -> Option<Value> {
+	LOCATIONS
+		.iter()
+		.enumerate()
+		.find(|(_, (name, ..))| *name == code)
+		.map(|(index, (name, city, country, network_zone))| {
+			json!({
+				"id": index + 1,
+				"name": name,
+				"description": format!("{} DC Park 1", city),
+				"country": country,
+				"city": city,
+				"latitude": 0.0,
+				"longitude": 0.0,
+				"network_zone": network_zone,
+			})
+		})
+}
+
+// Same net price in every location, in euros: 1.50 a core and 1.00 a GB of memory a month, plus 19% VAT
+// gross. Hetzner sends amounts as decimal strings.
+fn prices(cores: u32, memory: f32) -> Vec<Value> {
+	let monthly = 1.5 * cores as f64 + memory as f64;
+	let hourly = monthly / 730.0;
+
+	LOCATIONS
+		.iter()
+		.map(|(location, ..)| {
+			json!({
+				"location": location,
+				"price_hourly": { "net": format!("{:.10}", hourly), "gross": format!("{:.10}", hourly * 1.19) },
+				"price_monthly": { "net": format!("{:.10}", monthly), "gross": format!("{:.10}", monthly * 1.19) },
+			})
+		})
+		.collect()
+}
+
+fn server_type(name: &str) -> Option<(Value, u32)> {
+	server_types()
+		.into_iter()
+		.find(|(server_type, _)| server_type["name"] == name)
+}
+
+fn server_types() -> Vec<(Value, u32)> {
+	SERVER_TYPES
+		.iter()
+		.enumerate()
+		.map(
+			|(index, (name, cores, memory, disk, cpu_type, architecture))| {
+				(
+					json!({
+						"id": index + 1,
+						"name": name,
+						"description": name.to_uppercase(),
+						"cores": cores,
+						"memory": memory,
+						"disk": disk,
+						"deprecated": false,
+						"prices": prices(*cores, *memory),
+						"storage_type": "local",
+						"cpu_type": cpu_type,
+						"architecture": architecture,
+					}),
+					*disk,
+				)
+			},
+		)
+		.collect()
+}
+
+impl Store {
+	// Finishes actions whose delay has passed and applies their server status.
+	fn tick(&mut self) {
+		let now = Instant::now();
+
+		for action in self.actions.iter_mut() {
+			if action.status != "running" || action.ready_at > now {
+				continue;
+			}
+
+			action.status = "success".to_string();
+			action.progress = 100;
+			action.finished = Some(timestamp());
+
+			if let Some((server_id, status)) = action.server_status.take() {
+				if let Some(server) = self
+					.servers
+					.iter_mut()
+					.find(|server| server.id == server_id)
+				{
+					server.status = status.to_string();
+				}
+			}
+		}
+	}
+
+	fn server(&mut self, id: u64) -> Option<&mut Server> {
+		self.servers.iter_mut().find(|server| server.id == id)
+	}
+
+	fn volume(&mut self, id: u64) -> Option<&mut Volume> {
+		self.volumes.iter_mut().find(|volume| volume.id == id)
+	}
+}
+
+fn action(
+	cloud: &mut Cloud,
+	command: &str,
+	resources: &[(u64, &str)],
+	server_status: Option<(u64, &'static str)>,
+) -> Action {
+	let action = Action {
+		id: cloud.next_id(),
+		command: command.to_string(),
+		status: "running".to_string(),
+		progress: 0,
+		started: timestamp(),
+		finished: None,
+		resources: resources
+			.iter()
+			.map(|(id, kind)| json!({ "id": id, "type": kind }))
+			.collect(),
+		error: None,
+		ready_at: cloud.ready_at(),
+		server_status,
+	};
+
+	cloud.hetzner.actions.push(action);
+	cloud.hetzner.tick();
+
+	cloud.hetzner.actions.last().unwrap().clone()
+}
+
+pub fn handle(req: &FakeRequest, cloud: &mut Cloud) -> Response<Body> {
+	cloud.hetzner.tick();
+
+	let segments = req.segments();
+
+	let result = match (&req.method, segments.as_slice()) {
+		(&Method::GET, ["server_types"]) => list_server_types(req),
+
+		(&Method::GET, ["servers"]) => list_servers(req, cloud),
+		(&Method::POST, ["servers"]) => create_server(req, cloud),
+		(&Method::GET, ["servers", id]) => parse_id(id).and_then(|id| {
+			cloud
+				.hetzner
+				.server(id)
+				.map(|server| json(StatusCode::OK, &json!({ "server": server })))
+				.ok_or_else(|| not_found("server", id))
+		}),
+		(&Method::DELETE, ["servers", id]) => parse_id(id).and_then(|id| delete_server(cloud, id)),
+		(&Method::POST, ["servers", id, "actions", command]) => {
+			parse_id(id).and_then(|id| server_action(cloud, id, command))
+		}
+
+		(&Method::GET, ["volumes"]) => list_volumes(req, cloud),
+		(&Method::POST, ["volumes"]) => create_volume(req, cloud),
+		(&Method::GET, ["volumes", id]) => parse_id(id).and_then(|id| {
+			cloud
+				.hetzner
+				.volume(id)
+				.map(|volume| json(StatusCode::OK, &json!({ "volume": volume })))
+				.ok_or_else(|| not_found("volume", id))
+		}),
+		(&Method::DELETE, ["volumes", id]) => parse_id(id).and_then(|id| delete_volume(cloud, id)),
+		(&Method::POST, ["volumes", id, "actions", command]) => {
+			parse_id(id).and_then(|id| volume_action(req, cloud, id, command))
+		}
+
+		(&Method::GET, ["actions", id]) => parse_id(id).and_then(|id| {
+			cloud
+				.hetzner
+				.actions
+				.iter()
+				.find(|action| action.id == id)
+				.map(|action| json(StatusCode::OK, &json!({ "action": action })))
+				.ok_or_else(|| not_found("action", id))
+		}),
+
+		_ => Err(error(
+			StatusCode::NOT_FOUND,
+			"not_found",
+			"Invalid API route",
+		)),
+	};
+
+	result.unwrap_or_else(|response| response)
+}
+
+// Test hook: adds traffic to a server so bandwidth reports are non-zero.
+pub fn add_traffic(req: &FakeRequest, cloud: &mut Cloud, id: &str) -> Response<Body> {
+	let id = match parse_id(id) {
+		Ok(id) => id,
+		Err(response) => return response,
+	};
+	let traffic: Traffic = match req.json() {
+		Ok(traffic) => traffic,
+		Err(e) => return invalid_input(e),
+	};
+
+	match cloud.hetzner.server(id) {
+		Some(server) => {
+			server.ingoing_traffic += traffic.incoming_bytes;
+			server.outgoing_traffic += traffic.outgoing_bytes;
+			json(StatusCode::OK, &json!({ "server": server }))
+		}
+		None => not_found("server", id),
+	}
+}
+
+// Test hook: Hetzner never returns user data, so tests read it back here.
+pub fn user_data(cloud: &mut Cloud, id: &str) -> Response<Body> {
+	let id = match parse_id(id) {
+		Ok(id) => id,
+		Err(response) => return response,
+	};
+
+	match cloud.hetzner.server(id) {
+		Some(server) => json(StatusCode::OK, &json!({ "user_data": server.user_data })),
+		None => not_found("server", id),
+	}
+}
+
+fn list_server_types(req: &FakeRequest) -> Result<Response<Body>, Response<Body>> {
+	let server_types: Vec<Value> = server_types()
+		.into_iter()
+		.map(|(server_type, _)| server_type)
+		.collect();
+
+	let (server_types, meta) = numbered_page(&server_types, &req.query).map_err(invalid_input)?;
+
+	Ok(json(
+		StatusCode::OK,
+		&json!({ "server_types": server_types, "meta": meta }),
+	))
+}
+
+fn list_servers(req: &FakeRequest, cloud: &mut Cloud) -> Result<Response<Body>, Response<Body>> {
+	let servers: Vec<Server> = cloud
+		.hetzner
+		.servers
+		.iter()
+		.filter(|server| {
+			req.query
+				.get("name")
+				.is_none_or(|name| &server.name == name)
+		})
+		.cloned()
+		.collect();
+
+	let (servers, meta) = numbered_page(&servers, &req.query).map_err(invalid_input)?;
+
+	Ok(json(
+		StatusCode::OK,
+		&json!({ "servers": servers, "meta": meta }),
+	))
+}
+
+fn create_server(req: &FakeRequest, cloud: &mut Cloud) -> Result<Response<Body>, Response<Body>> {
+	let body: CreateServer = req.json().map_err(invalid_input)?;
+
+	let name = body
+		.name
+		.filter(|name| !name.is_empty())
+		.ok_or_else(|| invalid_input("name is required"))?;
+	if cloud
+		.hetzner
+		.servers
+		.iter()
+		.any(|server| server.name == name)
+	{
+		return Err(error(
+			StatusCode::CONFLICT,
+			"uniqueness_error",
+			format!("server name {} is already used", name),
+		));
+	}
+
+	let (server_type, disk) = body
+		.server_type
+		.as_deref()
+		.and_then(server_type)
+		.ok_or_else(|| invalid_input("server_type is missing or unknown"))?;
+	let image = body
+		.image
+		.filter(|image| !image.is_empty())
+		.ok_or_else(|| invalid_input("image is required"))?;
+	let location_code = body.location.unwrap_or_else(|| "fsn1".to_string());
+	let location = location(&location_code)
+		.ok_or_else(|| invalid_input(format!("unknown location: {}", location_code)))?;
+
+	let volumes = body.volumes.unwrap_or_default();
+	for volume in &volumes {
+		if cloud.hetzner.volume(*volume).is_none() {
+			return Err(not_found("volume", *volume));
+		}
+	}
+
+	let id = cloud.next_id();
+	let server = Server {
+		id,
+		name,
+		status: "initializing".to_string(),
+		created: timestamp(),
+		public_net: json!({
+			"ipv4": { "id": id, "ip": ipv4(id), "blocked": false, "dns_ptr": [] },
+			"ipv6": null,
+			"floating_ips": [],
+			"firewalls": [],
+		}),
+		private_net: Vec::new(),
+		server_type,
+		datacenter: json!({
+			"id": location["id"],
+			"name": format!("{}-dc14", location_code),
+			"description": format!("{} DC 14", location_code),
+			"location": location,
+		}),
+		image: json!({
+			"id": 1,
+			"type": "system",
+			"status": "available",
+			"name": image,
+			"description": image,
+			"image_size": null,
+			"disk_size": 5.0,
+			"created": "2022-04-21T13:32:38+00:00",
+			"created_from": null,
+			"bound_to": null,
+			"os_flavor": image.split('-').next().unwrap_or_default(),
+			"os_version": image.split('-').nth(1),
+			"rapid_deploy": true,
+			"protection": { "delete": false },
+			"deprecated": null,
+			"deleted": null,
+			"labels": {},
+			"architecture": "x86",
+		}),
+		iso: None,
+		rescue_enabled: false,
+		locked: false,
+		backup_window: None,
+		outgoing_traffic: 0,
+		ingoing_traffic: 0,
+		included_traffic: 21_990_232_555_520,
+		protection: json!({ "delete": false, "rebuild": false }),
+		labels: body.labels.unwrap_or_default(),
+		volumes: volumes.clone(),
+		load_balancers: Vec::new(),
+		primary_disk_size: disk,
+		placement_group: None,
+		user_data: body.user_data,
+	};
+	cloud.hetzner.servers.push(server);
+
+	for volume in &volumes {
+		cloud.hetzner.volume(*volume).unwrap().server = Some(id);
+	}
+
+	let final_status = if body.start_after_create.unwrap_or(true) {
+		"running"
+	} else {
+		"off"
+	};
+	let action = action(
+		cloud,
+		"create_server",
+		&[(id, "server")],
+		Some((id, final_status)),
+	);
+
+	Ok(json(
+		StatusCode::CREATED,
+		&json!({
+			"server": cloud.hetzner.server(id),
+			"action": action,
+			"next_actions": [],
+			"root_password": "fake-password",
+		}),
+	))
+}
+
+fn delete_server(cloud: &mut Cloud, id: u64) -> Result<Response<Body>, Response<Body>> {
+	if cloud.hetzner.server(id).is_none() {
+		return Err(not_found("server", id));
+	}
+
+	cloud.hetzner.servers.retain(|server| server.id != id);
+	for volume in cloud.hetzner.volumes.iter_mut() {
+		if volume.server == Some(id) {
+			volume.server = None;
+		}
+	}
+
+	let action = action(cloud, "delete_server", &[(id, "server")], None);
+
+	Ok(json(StatusCode::OK, &json!({ "action": action })))
+}
+
+fn server_action(
+	cloud: &mut Cloud,
+	id: u64,
+	command: &str,
+) -> Result<Response<Body>, Response<Body>> {
+	let (transition, status) = match command {
+		"poweron" => ("starting", "running"),
+		"poweroff" | "shutdown" => ("stopping", "off"),
+		"reboot" | "reset" => ("starting", "running"),
+		_ => {
+			return Err(error(
+				StatusCode::NOT_FOUND,
+				"not_found",
+				"Invalid API route",
+			))
+		}
+	};
+
+	let server = cloud
+		.hetzner
+		.server(id)
+		.ok_or_else(|| not_found("server", id))?;
+	server.status = transition.to_string();
+
+	let command = match command {
+		"poweron" => "start_server",
+		"poweroff" => "stop_server",
+		"shutdown" => "shutdown_server",
+		"reboot" => "reboot_server",
+		_ => "reset_server",
+	};
+	let action = action(cloud, command, &[(id, "server")], Some((id, status)));
+
+	Ok(json(StatusCode::CREATED, &json!({ "action": action })))
+}
+
+fn list_volumes(req: &FakeRequest, cloud: &mut Cloud) -> Result<Response<Body>, Response<Body>> {
+	let (volumes, meta) =
+		numbered_page(&cloud.hetzner.volumes, &req.query).map_err(invalid_input)?;
+
+	Ok(json(
+		StatusCode::OK,
+		&json!({ "volumes": volumes, "meta": meta }),
+	))
+}
+
+fn create_volume(req: &FakeRequest, cloud: &mut Cloud) -> Result<Response<Body>, Response<Body>> {
+	let body: CreateVolume = req.json().map_err(invalid_input)?;
+
+	let name = body
+		.name
+		.filter(|name| !name.is_empty())
+		.ok_or_else(|| invalid_input("name is required"))?;
+	let size = body
+		.size
+		.filter(|size| (10..=10_240).contains(size))
+		.ok_or_else(|| invalid_input("size must be between 10 and 10240"))?;
+
+	// Volumes live next to their server when one is given.
+	let location_code = match (body.server, body.location) {
+		(Some(server), _) => {
+			let server = cloud
+				.hetzner
+				.server(server)
+				.ok_or_else(|| not_found("server", server))?;
+			server.datacenter["location"]["name"]
+				.as_str()
+				.unwrap_or_default()
+				.to_string()
+		}
+		(None, Some(location)) => location,
+		(None, None) => return Err(invalid_input("either server or location is required")),
+	};
+	let location = location(&location_code)
+		.ok_or_else(|| invalid_input(format!("unknown location: {}", location_code)))?;
+
+	let id = cloud.next_id();
+	let volume = Volume {
+		id,
+		name,
+		server: body.server,
+		location,
+		size,
+		linux_device: format!("/dev/disk/by-id/scsi-0HC_Volume_{}", id),
+		protection: json!({ "delete": false }),
+		labels: body.labels.unwrap_or_default(),
+		status: "available".to_string(),
+		created: timestamp(),
+		format: body.format,
+	};
+	cloud.hetzner.volumes.push(volume.clone());
+
+	if let Some(server) = body.server {
+		cloud.hetzner.server(server).unwrap().volumes.push(id);
+	}
+
+	let action = action(cloud, "create_volume", &[(id, "volume")], None);
+
+	Ok(json(
+		StatusCode::CREATED,
+		&json!({ "volume": volume, "action": action, "next_actions": [] }),
+	))
+}
+
+fn delete_volume(cloud: &mut Cloud, id: u64) -> Result<Response<Body>, Response<Body>> {
+	let volume = cloud
+		.hetzner
+		.volume(id)
+		.ok_or_else(|| not_found("volume", id))?;
+	if volume.server.is_some() {
+		return Err(error(
+			StatusCode::LOCKED,
+			"locked",
+			"volume is attached to a server",
+		));
+	}
+
+	cloud.hetzner.volumes.retain(|volume| volume.id != id);
+
+	Ok(Response::builder()
+		.status(StatusCode::NO_CONTENT)
+		.body(Body::empty())
+		.unwrap())
+}
+
+fn volume_action(
+	req: &FakeRequest,
+	cloud: &mut Cloud,
+	id: u64,
+	command: &str,
+) -> Result<Response<Body>, Response<Body>> {
+	let volume = cloud
+		.hetzner
+		.volume(id)
+		.ok_or_else(|| not_found("volume", id))?;
+	let attached_to = volume.server;
+	let size = volume.size;
+
+	let resources = match command {
+		"attach" => {
+			let body: AttachVolume = req.json().map_err(invalid_input)?;
+			if attached_to.is_some() {
+				return Err(error(
+					StatusCode::CONFLICT,
+					"conflict",
+					"volume is already attached to a server",
+				));
+			}
+
+			let server = cloud
+				.hetzner
+				.server(body.server)
+				.ok_or_else(|| not_found("server", body.server))?;
+			server.volumes.push(id);
+			cloud.hetzner.volume(id).unwrap().server = Some(body.server);
+
+			vec![(id, "volume"), (body.server, "server")]
+		}
+		"detach" => {
+			let server_id =
+				attached_to.ok_or_else(|| invalid_input("volume is not attached to a server"))?;
+			if let Some(server) = cloud.hetzner.server(server_id) {
+				server.volumes.retain(|volume| *volume != id);
+			}
+			cloud.hetzner.volume(id).unwrap().server = None;
+
+			vec![(id, "volume"), (server_id, "server")]
+		}
+		"resize" => {
+			let body: ResizeVolume = req.json().map_err(invalid_input)?;
+			// Volumes can only grow.
+			if body.size <= size {
+				return Err(invalid_input("size must be larger than the current size"));
+			}
+			cloud.hetzner.volume(id).unwrap().size = body.size;
+
+			vec![(id, "volume")]
+		}
+		_ => {
+			return Err(error(
+				StatusCode::NOT_FOUND,
+				"not_found",
+				"Invalid API route",
+			))
+		}
+	};
+
+	let action = action(cloud, &format!("{}_volume", command), &resources, None);
+
+	Ok(json(StatusCode::CREATED, &json!({ "action": action })))
+}
@@ -0,0 +1,154 @@
+// Handlers return error responses as `Err` so they can bail out early with `?`.
+#![allow(clippy::result_large_err)]
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use hyper::header::AUTHORIZATION;
+use hyper::http::StatusCode;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server};
+use serde_json::json;
+
+use response::no_content;
+use state::{Cloud, FakeRequest};
+
+mod hetzner;
+mod pagination;
+mod response;
+mod state;
+mod vultr;
+
+// In-memory stand-in for the parts of the Vultr v2 (`/v2`) and Hetzner v1 (`/v1`) APIs
+// the principal uses, served on `listener` until the returned future is dropped. Point
+// VULTR_API_URL and HETZNER_API_URL at it, e.g. `http://127.0.0.1:8090/v2` and
+// `http://127.0.0.1:8090/v1`.
+//
+// Instances stay pending and actions stay running for `action_delay`. With a `token`, requests
+// must use it as their bearer token; otherwise any token is accepted.
+//
+// `/fake/*` routes are test hooks and need no token:
+//   POST /fake/reset                                drop all state
+//   POST /fake/vultr/instances/{id}/traffic         add {incoming_bytes, outgoing_bytes}
+//   POST /fake/hetzner/servers/{id}/traffic         add {incoming_bytes, outgoing_bytes}
+//   GET  /fake/hetzner/servers/{id}/user-data       read back the submitted user data
+pub async fn serve(
+	listener: TcpListener,
+	action_delay: Duration,
+	token: Option<String>,
+) -> Result<(), hyper::Error> {
+	let cloud = Arc::new(Mutex::new(Cloud::new(action_delay)));
+
+	let make_svc = make_service_fn(move |_conn| {
+		let cloud = Arc::clone(&cloud);
+		let token = token.clone();
+		async move {
+			Ok::<_, hyper::Error>(service_fn(move |req| {
+				handle_request(req, Arc::clone(&cloud), token.clone())
+			}))
+		}
+	});
+
+	Server::from_tcp(listener)?.serve(make_svc).await
+}
+
+async fn handle_request(
+	req: Request<Body>,
+	cloud: Arc<Mutex<Cloud>>,
+	token: Option<String>,
+) -> Result<Response<Body>, Infallible> {
+	let authorized = match req
+		.headers()
+		.get(AUTHORIZATION)
+		.and_then(|h| h.to_str().ok())
+	{
+		Some(header) => match (header.strip_prefix("Bearer "), &token) {
+			(Some(given), Some(token)) => given == token,
+			(Some(given), None) => !given.is_empty(),
+			(None, _) => false,
+		},
+		None => false,
+	};
+
+	let method = req.method().clone();
+	let mut segments: Vec<String> = req
+		.uri()
+		.path()
+		.split('/')
+		.filter(|segment| !segment.is_empty())
+		.map(String::from)
+		.collect();
+	let query: HashMap<String, String> = req
+		.uri()
+		.query()
+		.map(|query| {
+			form_urlencoded::parse(query.as_bytes())
+				.into_owned()
+				.collect()
+		})
+		.unwrap_or_default();
+
+	let body = match hyper::body::to_bytes(req.into_body()).await {
+		Ok(body) => body,
+		Err(e) => {
+			return Ok(response::json(
+				StatusCode::BAD_REQUEST,
+				&json!({ "error": e.to_string() }),
+			))
+		}
+	};
+
+	let api = if segments.is_empty() {
+		String::new()
+	} else {
+		segments.remove(0)
+	};
+	let req = FakeRequest {
+		method,
+		segments,
+		query,
+		body,
+	};
+
+	// Vultr lists its plans without a token.
+	let public = req.method == Method::GET && api == "v2" && req.segments() == ["plans"];
+	if api != "fake" && !public && !authorized {
+		return Ok(response::json(
+			StatusCode::UNAUTHORIZED,
+			&json!({ "error": "Invalid API token" }),
+		));
+	}
+
+	let mut cloud = cloud.lock().unwrap();
+
+	let response = match api.as_str() {
+		"v2" => vultr::handle(&req, &mut cloud),
+		"v1" => hetzner::handle(&req, &mut cloud),
+		"fake" => match (&req.method, req.segments().as_slice()) {
+			(&Method::POST, ["reset"]) => {
+				cloud.reset();
+				no_content()
+			}
+			(&Method::POST, ["vultr", "instances", id, "traffic"]) => {
+				vultr::add_traffic(&req, &mut cloud, id)
+			}
+			(&Method::POST, ["hetzner", "servers", id, "traffic"]) => {
+				hetzner::add_traffic(&req, &mut cloud, id)
+			}
+			(&Method::GET, ["hetzner", "servers", id, "user-data"]) => {
+				hetzner::user_data(&mut cloud, id)
+			}
+			_ => not_found(),
+		},
+		_ => not_found(),
+	};
+
+	Ok(response)
+}
+
+fn not_found() -> Response<Body> {
+	response::json(StatusCode::NOT_FOUND, &json!({ "error": "Not found" }))
+}
@@ -0,0 +1,29 @@
+use std::env;
+use std::net::{SocketAddr, TcpListener};
+use std::time::Duration;
+
+// Runs the fake cloud on FAKE_CLOUD_ADDR; see `fake_cloud::serve` for the routes.
+#[tokio::main]
+async fn main() {
+	let addr: SocketAddr = env::var("FAKE_CLOUD_ADDR")
+		.unwrap_or_else(|_| "127.0.0.1:8090".to_string())
+		.parse()
+		.expect("FAKE_CLOUD_ADDR must be a socket address");
+
+	// Instances stay pending and actions stay running for this long.
+	let action_delay = env::var("FAKE_CLOUD_ACTION_DELAY_MS")
+		.ok()
+		.and_then(|delay| delay.parse().ok())
+		.map(Duration::from_millis)
+		.unwrap_or_default();
+
+	// When set, requests must use this bearer token; otherwise any token is accepted.
+	let token = env::var("FAKE_CLOUD_TOKEN").ok();
+
+	let listener = TcpListener::bind(addr).expect("FAKE_CLOUD_ADDR must be free");
+	println!("Fake cloud listening on {}", addr);
+
+	if let Err(e) = fake_cloud::serve(listener, action_delay, token).await {
+		eprintln!("Server error: {}", e);
+	}
+}
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+
+// Vultr v2 style: `per_page` plus an opaque `cursor`, answered with `meta.links.next`.
+// Our cursors are plain offsets, which clients must treat as opaque anyway.
+pub fn cursor_page<T: Clone>(
+	items: &[T],
+	query: &HashMap<String, String>,
+) -> Result<(Vec<T>, Value), String> {
+	let per_page = parse(query, "per_page", 100)?.clamp(1, 500);
+	let offset = match query.get("cursor").filter(|cursor| !cursor.is_empty()) {
+		Some(cursor) => cursor
+			.parse::<usize>()
+			.map_err(|_| format!("Invalid cursor: {}", cursor))?,
+		None => 0,
+	};
+
+	let end = (offset + per_page).min(items.len());
+	let page = items.get(offset..end).unwrap_or_default().to_vec();
+
+	let next = if end < items.len() {
+		end.to_string()
+	} else {
+		String::new()
+	};
+	let prev = if offset > 0 {
+		offset.saturating_sub(per_page).to_string()
+	} else {
+		String::new()
+	};
+
+	Ok((
+		page,
+		json!({
+			"total": items.len(),
+			"links": { "next": next, "prev": prev },
+		}),
+	))
+}
+
+// Hetzner v1 style: `page` and `per_page`, answered with `meta.pagination`.
+pub fn numbered_page<T: Clone>(
+	items: &[T],
+	query: &HashMap<String, String>,
+) -> Result<(Vec<T>, Value), String> {
+	let per_page = parse(query, "per_page", 25)?.clamp(1, 50);
+	let page = parse(query, "page", 1)?.max(1);
+	let last_page = items.len().div_ceil(per_page).max(1);
+
+	let start = (page - 1) * per_page;
+	let end = (start + per_page).min(items.len());
+	let entries = items.get(start..end).unwrap_or_default().to_vec();
+
+	Ok((
+		entries,
+		json!({
+			"pagination": {
+				"page": page,
+				"per_page": per_page,
+				"previous_page": if page > 1 { Some(page - 1) } else { None },
+				"next_page": if page < last_page { Some(page + 1) } else { None },
+				"last_page": last_page,
+				"total_entries": items.len(),
+			}
+		}),
+	))
+}
+
+fn parse(query: &HashMap<String, String>, key: &str, default: usize) -> Result<usize, String> {
+	match query.get(key) {
+		Some(value) => value
+			.parse()
+			.map_err(|_| format!("{} must be a positive integer", key)),
+		None => Ok(default),
+	}
+}
@@ -0,0 +1,19 @@
+use hyper::header::CONTENT_TYPE;
+use hyper::http::StatusCode;
+use hyper::{Body, Response};
+use serde::Serialize;
+
+pub fn json<T: Serialize>(status: StatusCode, body: &T) -> Response<Body> {
+	Response::builder()
+		.status(status)
+		.header(CONTENT_TYPE, "application/json")
+		.body(Body::from(serde_json::to_vec(body).unwrap()))
+		.unwrap()
+}
+
+pub fn no_content() -> Response<Body> {
+	Response::builder()
+		.status(StatusCode::NO_CONTENT)
+		.body(Body::empty())
+		.unwrap()
+}
@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use hyper::body::Bytes;
+use hyper::Method;
+use serde::de::DeserializeOwned;
+
+use crate::{hetzner, vultr};
+
+// Everything both fake APIs know about, behind a single lock.
+pub struct Cloud {
+	pub vultr: vultr::Store,
+	pub hetzner: hetzner::Store,
+	// How long instances take to provision and actions take to finish.
+	pub action_delay: Duration,
+	next_id: u64,
+}
+
+impl Cloud {
+	pub fn new(action_delay: Duration) -> Self {
+		Cloud {
+			vultr: vultr::Store::default(),
+			hetzner: hetzner::Store::default(),
+			action_delay,
+			next_id: 1,
+		}
+	}
+
+	pub fn next_id(&mut self) -> u64 {
+		let id = self.next_id;
+		self.next_id += 1;
+		id
+	}
+
+	pub fn ready_at(&self) -> Instant {
+		Instant::now() + self.action_delay
+	}
+
+	pub fn reset(&mut self) {
+		*self = Cloud::new(self.action_delay);
+	}
+}
+
+// A request with its body already read, so handlers can run while holding the lock.
+pub struct FakeRequest {
+	pub method: Method,
+	pub segments: Vec<String>,
+	pub query: HashMap<String, String>,
+	pub body: Bytes,
+}
+
+impl FakeRequest {
+	pub fn json<T: DeserializeOwned>(&self) -> Result<T, String> {
+		if self.body.is_empty() {
+			return serde_json::from_slice(b"{}").map_err(|e| e.to_string());
+		}
+
+		serde_json::from_slice(&self.body).map_err(|e| e.to_string())
+	}
+
+	pub fn segments(&self) -> Vec<&str> {
+		self.segments.iter().map(String::as_str).collect()
+	}
+}
+
+// Fake IPv4 addresses are derived from resource ids so they stay stable.
+pub fn ipv4(id: u64) -> String {
+	format!(
+		"10.{}.{}.{}",
+		(id >> 16) & 0xff,
+		(id >> 8) & 0xff,
+		id & 0xff
+	)
+}
+
+pub fn timestamp() -> String {
+	chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false)
+}
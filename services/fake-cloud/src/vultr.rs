@@ -0,0 +1,622 @@
+use std::collections::HashMap;
+use std::time::Instant;
+
+use chrono::Utc;
+use hyper::http::StatusCode;
+use hyper::{Body, Method, Response};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::pagination::cursor_page;
+use crate::response::{json, no_content};
+use crate::state::{ipv4, timestamp, Cloud, FakeRequest};
+
+const REGIONS: &[&str] = &[
+	"ams", "atl", "blr", "bom", "cdg", "del", "dfw", "ewr", "fra", "hnl", "icn", "itm", "jnb",
+	"lax", "lhr", "mad", "mel", "mex", "mia", "nrt", "ord", "sao", "scl", "sea", "sgp", "sjc",
+	"sto", "syd", "tlv", "waw", "yto",
+];
+
+// (id, monthly cost in USD) of the compute plans `/plans` lists; their specs follow from the id.
+const PLANS: &[(&str, f64)] = &[
+	("vc2-1c-1gb", 5.0),
+	("vc2-1c-2gb", 10.0),
+	("vc2-2c-4gb", 20.0),
+	("vc2-4c-8gb", 40.0),
+	("vhf-1c-1gb", 6.0),
+	("vhf-2c-4gb", 24.0),
+	("vhf-4c-16gb", 96.0),
+];
+
+// (id, vcpu, ram in MB, disk in GB, monthly cost in USD, GPU type, VRAM in GB) of the `vcg` plans.
+const GPU_PLANS: &[(&str, u32, u32, u32, f64, &str, u32)] = &[
+	("vcg-a16-2c-8g-2vram", 2, 8192, 50, 43.0, "NVIDIA_A16", 2),
+	("vcg-a100-1c-6g-4vram", 1, 6144, 70, 90.0, "NVIDIA_A100", 4),
+	(
+		"vcg-a100-12c-120g-80vram",
+		12,
+		122880,
+		1400,
+		1750.0,
+		"NVIDIA_A100",
+		80,
+	),
+];
+
+#[derive(Default)]
+pub struct Store {
+	instances: Vec<Instance>,
+	blocks: Vec<Block>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct Instance {
+	id: String,
+	os: String,
+	ram: u32,
+	disk: u32,
+	main_ip: String,
+	vcpu_count: u32,
+	region: String,
+	plan: String,
+	date_created: String,
+	status: String,
+	allowed_bandwidth: u32,
+	netmask_v4: String,
+	gateway_v4: String,
+	power_status: String,
+	server_status: String,
+	v6_network: String,
+	v6_main_ip: String,
+	v6_network_size: u32,
+	label: String,
+	internal_ip: String,
+	kvm: String,
+	hostname: String,
+	tag: String,
+	tags: Vec<String>,
+	os_id: u32,
+	app_id: u32,
+	image_id: String,
+	firewall_group_id: String,
+	features: Vec<String>,
+	user_scheme: String,
+	#[serde(skip)]
+	user_data: Option<String>,
+	#[serde(skip)]
+	ready_at: Instant,
+	#[serde(skip)]
+	traffic: Traffic,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Traffic {
+	#[serde(default)]
+	incoming_bytes: u64,
+	#[serde(default)]
+	outgoing_bytes: u64,
+}
+
+#[derive(Clone, Serialize)]
+pub struct Block {
+	id: String,
+	date_created: String,
+	cost: f64,
+	status: String,
+	size_gb: u32,
+	region: String,
+	attached_to_instance: String,
+	label: String,
+	mount_id: String,
+	block_type: String,
+}
+
+#[derive(Deserialize)]
+struct CreateInstance {
+	region: Option<String>,
+	plan: Option<String>,
+	os_id: Option<u32>,
+	app_id: Option<u32>,
+	image_id: Option<String>,
+	label: Option<String>,
+	hostname: Option<String>,
+	tag: Option<String>,
+	tags: Option<Vec<String>>,
+	user_data: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct InstanceIds {
+	instance_ids: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct CreateBlock {
+	region: Option<String>,
+	size_gb: Option<u32>,
+	label: Option<String>,
+	block_type: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct UpdateBlock {
+	label: Option<String>,
+	size_gb: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct AttachBlock {
+	instance_id: String,
+}
+
+// Vultr errors look like `{"error": "...", "status": 404}`.
+fn error(status: StatusCode, message: impl ToString) -> Response<Body> {
+	json(
+		status,
+		&json!({ "error": message.to_string(), "status": status.as_u16() }),
+	)
+}
+
+fn not_found(kind: &str, id: &str) -> Response<Body> {
+	error(StatusCode::NOT_FOUND, format!("{} {} not found", kind, id))
+}
+
+// Vultr ids are UUIDs; ours encode the counter so they are unique and readable.
+fn uuid(id: u64) -> String {
+	format!("{:08x}-0000-4000-8000-{:012x}", id, id)
+}
+
+// Plan codes look like `vc2-1c-1gb` or `vhf-2c-4gb`; anything else gets the smallest spec.
+fn plan_spec(plan: &str) -> (u32, u32, u32) {
+	let mut vcpu = 1;
+	let mut ram = 1024;
+
+	for part in plan.split('-') {
+		if let Some(cores) = part.strip_suffix('c').and_then(|c| c.parse().ok()) {
+			vcpu = cores;
+		} else if let Some(gb) = part
+			.strip_suffix("gb")
+			.and_then(|gb| gb.parse::<u32>().ok())
+		{
+			ram = gb * 1024;
+		}
+	}
+
+	(vcpu, ram, 25 * vcpu)
+}
+
+impl Store {
+	// Finishes provisioning for instances whose delay has passed.
+	fn tick(&mut self) {
+		let now = Instant::now();
+
+		for instance in self.instances.iter_mut() {
+			if instance.status == "pending" && instance.ready_at <= now {
+				instance.status = "active".to_string();
+				instance.server_status = "ok".to_string();
+				instance.power_status = "running".to_string();
+			}
+		}
+	}
+
+	fn instance(&mut self, id: &str) -> Option<&mut Instance> {
+		self.instances.iter_mut().find(|instance| instance.id == id)
+	}
+
+	fn block(&mut self, id: &str) -> Option<&mut Block> {
+		self.blocks.iter_mut().find(|block| block.id == id)
+	}
+}
+
+pub fn handle(req: &FakeRequest, cloud: &mut Cloud) -> Response<Body> {
+	cloud.vultr.tick();
+
+	let segments = req.segments();
+
+	match (&req.method, segments.as_slice()) {
+		(&Method::GET, ["plans"]) => list_plans(req),
+
+		(&Method::GET, ["instances"]) => list_instances(req, cloud),
+		(&Method::POST, ["instances"]) => create_instance(req, cloud),
+		(&Method::POST, ["instances", action @ ("start" | "halt" | "reboot")]) => {
+			instances_action(req, cloud, action)
+		}
+		(&Method::GET, ["instances", id]) => match cloud.vultr.instance(id) {
+			Some(instance) => json(StatusCode::OK, &json!({ "instance": instance })),
+			None => not_found("Instance", id),
+		},
+		(&Method::DELETE, ["instances", id]) => delete_instance(cloud, id),
+		(&Method::POST, ["instances", id, action @ ("start" | "halt" | "reboot")]) => {
+			instance_action(cloud, id, action)
+		}
+		(&Method::GET, ["instances", id, "bandwidth"]) => bandwidth(cloud, id),
+		(&Method::GET, ["instances", id, "user-data"]) => match cloud.vultr.instance(id) {
+			Some(instance) => json(
+				StatusCode::OK,
+				&json!({ "user_data": { "data": instance.user_data.clone().unwrap_or_default() } }),
+			),
+			None => not_found("Instance", id),
+		},
+
+		(&Method::GET, ["blocks"]) => list_blocks(req, cloud),
+		(&Method::POST, ["blocks"]) => create_block(req, cloud),
+		(&Method::GET, ["blocks", id]) => match cloud.vultr.block(id) {
+			Some(block) => json(StatusCode::OK, &json!({ "block": block })),
+			None => not_found("Block", id),
+		},
+		(&Method::PATCH, ["blocks", id]) => update_block(req, cloud, id),
+		(&Method::DELETE, ["blocks", id]) => delete_block(cloud, id),
+		(&Method::POST, ["blocks", id, "attach"]) => attach_block(req, cloud, id),
+		(&Method::POST, ["blocks", id, "detach"]) => detach_block(cloud, id),
+
+		_ => error(StatusCode::NOT_FOUND, "Invalid API route"),
+	}
+}
+
+// Test hook: adds traffic to an instance so bandwidth reports are non-zero.
+pub fn add_traffic(req: &FakeRequest, cloud: &mut Cloud, id: &str) -> Response<Body> {
+	let traffic: Traffic = match req.json() {
+		Ok(traffic) => traffic,
+		Err(e) => return error(StatusCode::BAD_REQUEST, e),
+	};
+
+	match cloud.vultr.instance(id) {
+		Some(instance) => {
+			instance.traffic.incoming_bytes += traffic.incoming_bytes;
+			instance.traffic.outgoing_bytes += traffic.outgoing_bytes;
+			no_content()
+		}
+		None => not_found("Instance", id),
+	}
+}
+
+// Every plan is offered in every region, with the hourly cost left out as Vultr does for most.
+fn list_plans(req: &FakeRequest) -> Response<Body> {
+	let compute = PLANS.iter().map(|(id, monthly_cost)| {
+		let (vcpu_count, ram, disk) = plan_spec(id);
+		json!({
+			"id": id,
+			"vcpu_count": vcpu_count,
+			"ram": ram,
+			"disk": disk,
+			"disk_count": 1,
+			"bandwidth": 1024,
+			"monthly_cost": monthly_cost,
+			"type": id.split('-').next().unwrap_or_default(),
+			"locations": REGIONS,
+		})
+	});
+	let gpu = GPU_PLANS.iter().map(
+		|(id, vcpu_count, ram, disk, monthly_cost, gpu_type, gpu_vram_gb)| {
+			json!({
+				"id": id,
+				"vcpu_count": vcpu_count,
+				"ram": ram,
+				"disk": disk,
+				"disk_count": 1,
+				"bandwidth": 1024,
+				"monthly_cost": monthly_cost,
+				"type": "vcg",
+				"locations": REGIONS,
+				"gpu_vram_gb": gpu_vram_gb,
+				"gpu_type": gpu_type,
+			})
+		},
+	);
+
+	let plans: Vec<Value> = compute
+		.chain(gpu)
+		.filter(|plan| {
+			req.query
+				.get("type")
+				.is_none_or(|kind| kind == "all" || plan["type"] == kind.as_str())
+		})
+		.collect();
+
+	match cursor_page(&plans, &req.query) {
+		Ok((plans, meta)) => json(StatusCode::OK, &json!({ "plans": plans, "meta": meta })),
+		Err(e) => error(StatusCode::BAD_REQUEST, e),
+	}
+}
+
+fn list_instances(req: &FakeRequest, cloud: &mut Cloud) -> Response<Body> {
+	let instances: Vec<Instance> = cloud
+		.vultr
+		.instances
+		.iter()
+		.filter(|instance| {
+			req.query
+				.get("region")
+				.is_none_or(|region| &instance.region == region)
+		})
+		.filter(|instance| {
+			req.query
+				.get("label")
+				.is_none_or(|label| &instance.label == label)
+		})
+		.cloned()
+		.collect();
+
+	match cursor_page(&instances, &req.query) {
+		Ok((instances, meta)) => json(
+			StatusCode::OK,
+			&json!({ "instances": instances, "meta": meta }),
+		),
+		Err(e) => error(StatusCode::BAD_REQUEST, e),
+	}
+}
+
+fn create_instance(req: &FakeRequest, cloud: &mut Cloud) -> Response<Body> {
+	let body: CreateInstance = match req.json() {
+		Ok(body) => body,
+		Err(e) => return error(StatusCode::BAD_REQUEST, e),
+	};
+
+	let region = match body.region {
+		Some(region) if REGIONS.contains(&region.as_str()) => region,
+		Some(region) => {
+			return error(
+				StatusCode::BAD_REQUEST,
+				format!("Invalid region: {}", region),
+			)
+		}
+		None => return error(StatusCode::BAD_REQUEST, "region is required"),
+	};
+	let plan = match body.plan.filter(|plan| !plan.is_empty()) {
+		Some(plan) => plan,
+		None => return error(StatusCode::BAD_REQUEST, "plan is required"),
+	};
+
+	let id = cloud.next_id();
+	let (vcpu_count, ram, disk) = plan_spec(&plan);
+	let label = body.label.unwrap_or_default();
+
+	let instance = Instance {
+		id: uuid(id),
+		os: "Ubuntu 22.04 LTS x64".to_string(),
+		ram,
+		disk,
+		main_ip: ipv4(id),
+		vcpu_count,
+		region,
+		plan,
+		date_created: timestamp(),
+		status: "pending".to_string(),
+		allowed_bandwidth: 1000,
+		netmask_v4: "255.255.254.0".to_string(),
+		gateway_v4: "10.0.0.1".to_string(),
+		power_status: "stopped".to_string(),
+		server_status: "installingbooting".to_string(),
+		v6_network: String::new(),
+		v6_main_ip: String::new(),
+		v6_network_size: 0,
+		hostname: body.hostname.unwrap_or_else(|| label.clone()),
+		label,
+		internal_ip: String::new(),
+		kvm: String::new(),
+		tag: body.tag.unwrap_or_default(),
+		tags: body.tags.unwrap_or_default(),
+		os_id: body.os_id.unwrap_or(1743),
+		app_id: body.app_id.unwrap_or(0),
+		image_id: body.image_id.unwrap_or_default(),
+		firewall_group_id: String::new(),
+		features: Vec::new(),
+		user_scheme: "root".to_string(),
+		user_data: body.user_data,
+		ready_at: cloud.ready_at(),
+		traffic: Traffic::default(),
+	};
+
+	cloud.vultr.instances.push(instance.clone());
+	cloud.vultr.tick();
+
+	// The root password is only ever returned from the create call.
+	let mut instance = serde_json::to_value(cloud.vultr.instance(&instance.id).unwrap()).unwrap();
+	instance["default_password"] = json!("fake-password");
+
+	json(StatusCode::ACCEPTED, &json!({ "instance": instance }))
+}
+
+fn power(instance: &mut Instance, action: &str) {
+	instance.power_status = match action {
+		"halt" => "stopped".to_string(),
+		_ => "running".to_string(),
+	};
+}
+
+fn instances_action(req: &FakeRequest, cloud: &mut Cloud, action: &str) -> Response<Body> {
+	let body: InstanceIds = match req.json() {
+		Ok(body) => body,
+		Err(e) => return error(StatusCode::BAD_REQUEST, e),
+	};
+
+	if let Some(id) = body
+		.instance_ids
+		.iter()
+		.find(|id| cloud.vultr.instance(id).is_none())
+	{
+		return not_found("Instance", id);
+	}
+
+	for id in &body.instance_ids {
+		power(cloud.vultr.instance(id).unwrap(), action);
+	}
+
+	no_content()
+}
+
+fn instance_action(cloud: &mut Cloud, id: &str, action: &str) -> Response<Body> {
+	match cloud.vultr.instance(id) {
+		Some(instance) => {
+			power(instance, action);
+			no_content()
+		}
+		None => not_found("Instance", id),
+	}
+}
+
+fn delete_instance(cloud: &mut Cloud, id: &str) -> Response<Body> {
+	if cloud.vultr.instance(id).is_none() {
+		return not_found("Instance", id);
+	}
+
+	cloud.vultr.instances.retain(|instance| instance.id != id);
+	for block in cloud.vultr.blocks.iter_mut() {
+		if block.attached_to_instance == id {
+			block.attached_to_instance = String::new();
+		}
+	}
+
+	no_content()
+}
+
+// All traffic is reported against today's date.
+fn bandwidth(cloud: &mut Cloud, id: &str) -> Response<Body> {
+	match cloud.vultr.instance(id) {
+		Some(instance) => {
+			let mut bandwidth = HashMap::new();
+			bandwidth.insert(
+				Utc::now().format("%Y-%m-%d").to_string(),
+				instance.traffic.clone(),
+			);
+
+			json(StatusCode::OK, &json!({ "bandwidth": bandwidth }))
+		}
+		None => not_found("Instance", id),
+	}
+}
+
+fn list_blocks(req: &FakeRequest, cloud: &mut Cloud) -> Response<Body> {
+	match cursor_page(&cloud.vultr.blocks, &req.query) {
+		Ok((blocks, meta)) => json(StatusCode::OK, &json!({ "blocks": blocks, "meta": meta })),
+		Err(e) => error(StatusCode::BAD_REQUEST, e),
+	}
+}
+
+fn create_block(req: &FakeRequest, cloud: &mut Cloud) -> Response<Body> {
+	let body: CreateBlock = match req.json() {
+		Ok(body) => body,
+		Err(e) => return error(StatusCode::BAD_REQUEST, e),
+	};
+
+	let region = match body.region {
+		Some(region) if REGIONS.contains(&region.as_str()) => region,
+		Some(region) => {
+			return error(
+				StatusCode::BAD_REQUEST,
+				format!("Invalid region: {}", region),
+			)
+		}
+		None => return error(StatusCode::BAD_REQUEST, "region is required"),
+	};
+	let size_gb = match body.size_gb {
+		Some(size_gb) if (10..=40_000).contains(&size_gb) => size_gb,
+		_ => {
+			return error(
+				StatusCode::BAD_REQUEST,
+				"size_gb must be between 10 and 40000",
+			)
+		}
+	};
+
+	let id = cloud.next_id();
+	let block = Block {
+		mount_id: format!("{}-{:x}", region, id),
+		id: uuid(id),
+		date_created: timestamp(),
+		cost: size_gb as f64 * 0.1,
+		status: "active".to_string(),
+		size_gb,
+		region,
+		attached_to_instance: String::new(),
+		label: body.label.unwrap_or_default(),
+		block_type: body.block_type.unwrap_or_else(|| "high_perf".to_string()),
+	};
+
+	cloud.vultr.blocks.push(block.clone());
+
+	json(StatusCode::ACCEPTED, &json!({ "block": block }))
+}
+
+fn update_block(req: &FakeRequest, cloud: &mut Cloud, id: &str) -> Response<Body> {
+	let body: UpdateBlock = match req.json() {
+		Ok(body) => body,
+		Err(e) => return error(StatusCode::BAD_REQUEST, e),
+	};
+
+	let block = match cloud.vultr.block(id) {
+		Some(block) => block,
+		None => return not_found("Block", id),
+	};
+
+	if let Some(size_gb) = body.size_gb {
+		// Blocks can only grow.
+		if size_gb < block.size_gb {
+			return error(
+				StatusCode::BAD_REQUEST,
+				"size_gb cannot be smaller than the current size",
+			);
+		}
+		block.size_gb = size_gb;
+		block.cost = size_gb as f64 * 0.1;
+	}
+	if let Some(label) = body.label {
+		block.label = label;
+	}
+
+	no_content()
+}
+
+fn delete_block(cloud: &mut Cloud, id: &str) -> Response<Body> {
+	match cloud.vultr.block(id) {
+		Some(block) if !block.attached_to_instance.is_empty() => error(
+			StatusCode::BAD_REQUEST,
+			"Block is attached to an instance; detach it first",
+		),
+		Some(_) => {
+			cloud.vultr.blocks.retain(|block| block.id != id);
+			no_content()
+		}
+		None => not_found("Block", id),
+	}
+}
+
+fn attach_block(req: &FakeRequest, cloud: &mut Cloud, id: &str) -> Response<Body> {
+	let body: AttachBlock = match req.json() {
+		Ok(body) => body,
+		Err(e) => return error(StatusCode::BAD_REQUEST, e),
+	};
+
+	let region = match cloud.vultr.instance(&body.instance_id) {
+		Some(instance) => instance.region.clone(),
+		None => return not_found("Instance", &body.instance_id),
+	};
+
+	match cloud.vultr.block(id) {
+		Some(block) if !block.attached_to_instance.is_empty() => {
+			error(StatusCode::BAD_REQUEST, "Block is already attached")
+		}
+		Some(block) if block.region != region => error(
+			StatusCode::BAD_REQUEST,
+			"Block and instance must be in the same region",
+		),
+		Some(block) => {
+			block.attached_to_instance = body.instance_id;
+			no_content()
+		}
+		None => not_found("Block", id),
+	}
+}
+
+fn detach_block(cloud: &mut Cloud, id: &str) -> Response<Body> {
+	match cloud.vultr.block(id) {
+		Some(block) if block.attached_to_instance.is_empty() => {
+			error(StatusCode::BAD_REQUEST, "Block is not attached")
+		}
+		Some(block) => {
+			block.attached_to_instance = String::new();
+			no_content()
+		}
+		None => not_found("Block", id),
+	}
+}
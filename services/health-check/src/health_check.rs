@@ -1,4 +1,3 @@
-use dotenv_codegen::dotenv;
 use models::models::health_check::{HealthCheck, HealthCheckType, HttpMethod};
 use models::models::network::Network;
 use redis::aio::Connection;
@@ -7,6 +6,7 @@ use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use reqwest::{Client, Error};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::env;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::Mutex;
@@ -39,12 +39,13 @@ pub async fn schedule_health_checks(
 	tasks_map: Arc<Mutex<HashMap<String, HealthCheckTask>>>,
 ) -> Vec<String> {
 	let mut tasks = vec![];
+	let region = env::var("REGION").expect("REGION must be set");
 
 	for config in configs {
 		let connection = Arc::clone(&connection);
 		let worker_clone = worker.clone();
 		let config_clone = config.clone();
-		let region = dotenv!("REGION");
+		let region = region.clone();
 		let uuid = Uuid::new_v4();
 
 		let key = format!(
@@ -69,7 +70,7 @@ pub async fn schedule_health_checks(
 					project_id,
 					worker: &worker_clone,
 					config: &config_clone,
-					region,
+					region: &region,
 				})
 				.await
 				{
@@ -1,12 +1,18 @@
 use std::collections::HashMap;
+use std::env;
 
-use dotenv_codegen::dotenv;
 use futures::stream::StreamExt;
 use redis::aio::Connection;
-use redis::{AsyncCommands, RedisResult};
+use redis::{AsyncCommands, ErrorKind, RedisError, RedisResult};
 
 pub async fn connection() -> RedisResult<Connection> {
-	let client = redis::Client::open(dotenv!("MASTER_REDIS_CONNECTION_URL"))?;
+	let url = env::var("MASTER_REDIS_CONNECTION_URL").map_err(|_| {
+		RedisError::from((
+			ErrorKind::InvalidClientConfig,
+			"MASTER_REDIS_CONNECTION_URL is not set",
+		))
+	})?;
+	let client = redis::Client::open(url)?;
 
 	let connection = client.get_async_connection().await?;
 
@@ -1,7 +1,9 @@
 use colored::Colorize;
-use dotenv_codegen::dotenv;
+use dotenv::dotenv;
+use models::models::region::Region;
 use redis::AsyncCommands;
 use std::convert::TryInto;
+use std::env;
 use std::net::IpAddr;
 use std::str::FromStr;
 use std::sync::Arc;
@@ -26,9 +28,23 @@ async fn calculate_round_trip(destination_ip: IpAddr) -> u64 {
 	(total_rtt / 5).try_into().unwrap()
 }
 
+// Ping times are keyed by canonical region id, so they line up with rules and workers; any
+// provider's code for the region is accepted too.
+fn canonical_region(code: &str) -> String {
+	match Region::lookup(code) {
+		Some(region) => region.id.to_string(),
+		None => {
+			println!("Unknown region {}, using it as is", code);
+			code.to_string()
+		}
+	}
+}
+
 #[tokio::main]
 async fn main() {
-	let origin_region = dotenv!("REGION");
+	dotenv().ok();
+
+	let origin_region = canonical_region(&env::var("REGION").expect("REGION must be set"));
 
 	let mut connection = db::connection().await.unwrap();
 
@@ -62,12 +78,13 @@ async fn main() {
 
 			for (destination_region, ip) in ping_map_copy.iter() {
 				let destination_ip = IpAddr::from_str(ip).unwrap();
+				let destination_region = canonical_region(destination_region);
 
 				// Calculate the round trip time
 				let rtt = calculate_round_trip(destination_ip).await;
 
 				// Store the round trip time in Redis
-				db::store_ping(origin_region, destination_region, rtt)
+				db::store_ping(&origin_region, &destination_region, rtt)
 					.await
 					.unwrap();
 
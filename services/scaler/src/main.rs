@@ -9,11 +9,10 @@ use shiplift::{Docker, ContainerListOptions, ContainerOptions};
 
 use warp::{Filter, reject};
 use warp::http::Response;
-use dotenv_codegen::dotenv;
+use dotenv::dotenv;
 
-const VULTR_API_KEY: &str = dotenv!("VULTR_API_KEY");
-
-const PROMETHEUS_ADDR: &str = dotenv!("PROMETHEUS_ADDR");
+// Used when PROMETHEUS_ADDR is not set at runtime.
+const DEFAULT_PROMETHEUS_ADDR: &str = "http://localhost:9090";
 
 const CONTAINER_THRESHOLD: usize = 2; // Number of containers to create when scaling
 const DESCALE_CPU_THRESHOLD: f64 = 30.0;
@@ -177,7 +176,9 @@ async fn handle_rejection(err: warp::Rejection) -> Result<impl warp::Reply, warp
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-	let _prometheus_addr = env::var("PROMETHEUS_ADDR").unwrap_or_else(|_| PROMETHEUS_ADDR.to_string());
+	dotenv().ok();
+
+	let _prometheus_addr = env::var("PROMETHEUS_ADDR").unwrap_or_else(|_| DEFAULT_PROMETHEUS_ADDR.to_string());
 	let prometheus_metrics = warp::path("metrics").and_then(|| async {
 		let encoder = TextEncoder::new();
 		let metrics_families = prometheus::gather();
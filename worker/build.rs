@@ -5,6 +5,7 @@ fn main() {
 	let proto_files = vec![
 		"./src/proto/container.proto",
 		"./src/proto/stats.proto",
+		"./src/proto/worker_registry.proto",
 	];
 
 	let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
@@ -0,0 +1,49 @@
+use std::env;
+use std::sync::{Arc, RwLock};
+
+use tonic::{Request, Status};
+
+// The token the principal presents as `authorization: Bearer <token>` on every call to the
+// `DockerService`. The principal issues a new one each time the worker registers; a standalone
+// worker takes it from `WORKER_SERVICE_TOKEN`. Without one every call is refused.
+#[derive(Clone, Default)]
+pub struct ServiceToken(Arc<RwLock<Option<String>>>);
+
+impl ServiceToken {
+	pub fn from_env() -> Self {
+		let token = env::var("WORKER_SERVICE_TOKEN").ok().filter(|token| !token.is_empty());
+
+		ServiceToken(Arc::new(RwLock::new(token)))
+	}
+
+	pub fn set(&self, token: String) {
+		*self.0.write().unwrap() = Some(token).filter(|token| !token.is_empty());
+	}
+
+	// Interceptor for the `DockerService` server.
+	pub fn check(&self, request: Request<()>) -> Result<Request<()>, Status> {
+		let expected = self.0.read().unwrap();
+		let expected = expected
+			.as_deref()
+			.ok_or_else(|| Status::unavailable("not registered with a principal yet"))?;
+
+		let given = request
+			.metadata()
+			.get("authorization")
+			.and_then(|value| value.to_str().ok())
+			.and_then(|value| value.strip_prefix("Bearer "))
+			.unwrap_or_default();
+		if !tokens_match(given, expected) {
+			return Err(Status::unauthenticated("missing or invalid service token"));
+		}
+
+		Ok(request)
+	}
+}
+
+// Takes as long wherever the tokens differ, so the token can't be guessed a byte at a time.
+fn tokens_match(given: &str, expected: &str) -> bool {
+	let (given, expected) = (given.as_bytes(), expected.as_bytes());
+
+	given.len() == expected.len() && given.iter().zip(expected).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
@@ -1,51 +1,41 @@
-use bollard::container::{Config, CreateContainerOptions, RemoveContainerOptions};
+use bollard::container::{Config, CreateContainerOptions, ListContainersOptions, RemoveContainerOptions};
 use bollard::Docker;
 
 use std::collections::HashMap;
 use tonic::{Request, Response, Status};
 
-mod docker {
-	include!("../docker.rs");
-}
-
-use docker::{
-	CreatePodResponse, DeleteContainerRequest, Pod, StartContainerRequest, StopContainerRequest,
+use crate::docker::docker_service_server::DockerService;
+use crate::docker::{
+	CopyVolumeRequest, CopyVolumeResponse, CreatePodResponse, DeleteContainerRequest, DeleteContainerResponse, GrowFilesystemRequest,
+	GrowFilesystemResponse, ListContainersRequest, ListContainersResponse, MountVolumeRequest, MountVolumeResponse,
+	Pod, StartContainerRequest, StartContainerResponse, StopContainerRequest, StopContainerResponse,
+	UnmountVolumeRequest, UnmountVolumeResponse,
 };
-
-#[tonic::async_trait]
-pub trait DockerService {
-	async fn create_container(
-		&self,
-		request: Request<Pod>,
-	) -> Result<Response<CreatePodResponse>, Status>;
-
-	async fn start_container(
-		&self,
-		request: Request<StartContainerRequest>,
-	) -> Result<Response<()>, Status>;
-
-	async fn stop_container(
-		&self,
-		request: Request<StopContainerRequest>,
-	) -> Result<Response<()>, Status>;
-
-	async fn delete_container(
-		&self,
-		request: Request<DeleteContainerRequest>,
-	) -> Result<Response<()>, Status>;
+use crate::volumes;
+
+// Handlers return the `Status` as is, so it is not worth boxing here.
+#[allow(clippy::result_large_err)]
+fn docker() -> Result<Docker, Status> {
+	Docker::connect_with_local_defaults().map_err(|err| {
+		eprintln!("Error connecting to Docker: {:?}", err);
+		Status::unavailable("Docker is not available")
+	})
 }
 
+// Serves the generated `DockerService`, which the principal calls on the worker's gRPC address.
+#[derive(Default)]
 pub struct MyDockerService {}
 
 #[tonic::async_trait]
 impl DockerService for MyDockerService {
-	async fn create_container(
-		&self,
-		request: Request<Pod>,
-	) -> Result<Response<CreatePodResponse>, Status> {
+	async fn create_pod(&self, request: Request<Pod>) -> Result<Response<CreatePodResponse>, Status> {
 		let request = request.into_inner();
+		let container = request
+			.containers
+			.first()
+			.ok_or_else(|| Status::invalid_argument("Pod has no containers"))?;
 
-		let docker = Docker::connect_with_local_defaults().unwrap();
+		let docker = docker()?;
 		let mut exposed_ports = HashMap::new();
 
 		for port in request.containers.iter().flat_map(|c| c.ports.iter()) {
@@ -53,29 +43,28 @@ impl DockerService for MyDockerService {
 		}
 
 		let config = Config {
-			image: Some(request.containers[0].image.clone()),
+			image: Some(container.image.clone()),
 			env: Some(
-				request.containers[0]
+				container
 					.env
 					.iter()
 					.map(|(k, v)| format!("{}={}", k, v))
 					.collect::<Vec<_>>(),
 			),
-			cmd: Some(request.containers[0].commands.clone()),
+			cmd: Some(container.commands.clone()),
 			exposed_ports: Some(exposed_ports),
 			..Default::default()
 		};
 
 		let options = Some(CreateContainerOptions {
-			name: request.containers[0].name.clone(),
+			name: container.name.clone(),
 			platform: Some("linux/amd64".to_owned()),
 		});
 
 		match docker.create_container(options, config).await {
 			Ok(container) => {
 				let message = format!("Created container with ID: {}", container.id);
-				let response = CreatePodResponse { message };
-				Ok(Response::new(response))
+				Ok(Response::new(CreatePodResponse { message }))
 			}
 			Err(err) => {
 				eprintln!("Error creating container: {:?}", err);
@@ -87,17 +76,18 @@ impl DockerService for MyDockerService {
 	async fn start_container(
 		&self,
 		request: Request<StartContainerRequest>,
-	) -> Result<Response<()>, Status> {
+	) -> Result<Response<StartContainerResponse>, Status> {
 		let request = request.into_inner();
 
-		let docker = Docker::connect_with_local_defaults().unwrap();
+		let docker = docker()?;
 
 		match docker
 			.start_container::<String>(&request.container_id, None)
 			.await
 		{
-			Ok(_) => Ok(Response::new(())),
-
+			Ok(_) => Ok(Response::new(StartContainerResponse {
+				message: format!("Started container {}", request.container_id),
+			})),
 			Err(err) => {
 				eprintln!("Error starting container: {:?}", err);
 				Err(Status::internal("Failed to start container"))
@@ -108,13 +98,15 @@ impl DockerService for MyDockerService {
 	async fn stop_container(
 		&self,
 		request: Request<StopContainerRequest>,
-	) -> Result<Response<()>, Status> {
+	) -> Result<Response<StopContainerResponse>, Status> {
 		let request = request.into_inner();
 
-		let docker = Docker::connect_with_local_defaults().unwrap();
+		let docker = docker()?;
 
 		match docker.stop_container(&request.name, None).await {
-			Ok(_) => Ok(Response::new(())),
+			Ok(_) => Ok(Response::new(StopContainerResponse {
+				message: format!("Stopped container {}", request.name),
+			})),
 			Err(err) => {
 				eprintln!("Error stopping container: {:?}", err);
 				Err(Status::internal("Failed to stop container"))
@@ -125,10 +117,10 @@ impl DockerService for MyDockerService {
 	async fn delete_container(
 		&self,
 		request: Request<DeleteContainerRequest>,
-	) -> Result<Response<()>, Status> {
+	) -> Result<Response<DeleteContainerResponse>, Status> {
 		let request = request.into_inner();
 
-		let docker = Docker::connect_with_local_defaults().unwrap();
+		let docker = docker()?;
 
 		let options = Some(RemoveContainerOptions {
 			force: true,
@@ -140,11 +132,105 @@ impl DockerService for MyDockerService {
 			.remove_container(&request.container_id, options)
 			.await
 		{
-			Ok(_) => Ok(Response::new(())),
+			Ok(_) => Ok(Response::new(DeleteContainerResponse {
+				message: format!("Deleted container {}", request.container_id),
+			})),
 			Err(err) => {
 				eprintln!("Error deleting container: {:?}", err);
 				Err(Status::internal("Failed to delete container"))
 			}
 		}
 	}
+
+	async fn list_containers(
+		&self,
+		_request: Request<ListContainersRequest>,
+	) -> Result<Response<ListContainersResponse>, Status> {
+		let docker = docker()?;
+
+		match docker
+			.list_containers(None::<ListContainersOptions<String>>)
+			.await
+		{
+			// Docker reports names with a leading slash.
+			Ok(containers) => Ok(Response::new(ListContainersResponse {
+				names: containers
+					.into_iter()
+					.filter_map(|container| container.names?.into_iter().next())
+					.map(|name| name.trim_start_matches('/').to_string())
+					.collect(),
+			})),
+			Err(err) => {
+				eprintln!("Error listing containers: {:?}", err);
+				Err(Status::internal("Failed to list containers"))
+			}
+		}
+	}
+
+	// Growing, unmounting and copying run tools that can take a while, so like mounting they run
+	// off the async workers.
+	async fn grow_filesystem(
+		&self,
+		request: Request<GrowFilesystemRequest>,
+	) -> Result<Response<GrowFilesystemResponse>, Status> {
+		let request = request.into_inner();
+
+		match tokio::task::spawn_blocking(move || volumes::grow(&request.volume_id)).await {
+			Ok(Ok(total_bytes)) => Ok(Response::new(GrowFilesystemResponse { total_bytes })),
+			Ok(Err(err)) => {
+				eprintln!("Error growing filesystem: {}", err);
+				Err(Status::failed_precondition(err))
+			}
+			Err(err) => Err(Status::internal(err.to_string())),
+		}
+	}
+
+	// Mounting waits for the device and may format it, so it runs off the async workers.
+	async fn mount_volume(
+		&self,
+		request: Request<MountVolumeRequest>,
+	) -> Result<Response<MountVolumeResponse>, Status> {
+		let request = request.into_inner();
+
+		match tokio::task::spawn_blocking(move || volumes::mount(&request.volume_id, &request.device)).await {
+			Ok(Ok(total_bytes)) => Ok(Response::new(MountVolumeResponse { total_bytes })),
+			Ok(Err(err)) => {
+				eprintln!("Error mounting volume: {}", err);
+				Err(Status::failed_precondition(err))
+			}
+			Err(err) => Err(Status::internal(err.to_string())),
+		}
+	}
+
+	async fn unmount_volume(
+		&self,
+		request: Request<UnmountVolumeRequest>,
+	) -> Result<Response<UnmountVolumeResponse>, Status> {
+		let request = request.into_inner();
+
+		match tokio::task::spawn_blocking(move || volumes::unmount(&request.volume_id)).await {
+			Ok(Ok(())) => Ok(Response::new(UnmountVolumeResponse {})),
+			Ok(Err(err)) => {
+				eprintln!("Error unmounting volume: {}", err);
+				Err(Status::failed_precondition(err))
+			}
+			Err(err) => Err(Status::internal(err.to_string())),
+		}
+	}
+
+	async fn copy_volume(
+		&self,
+		request: Request<CopyVolumeRequest>,
+	) -> Result<Response<CopyVolumeResponse>, Status> {
+		let request = request.into_inner();
+
+		match tokio::task::spawn_blocking(move || volumes::copy(&request.from_volume_id, &request.to_volume_id)).await {
+			Ok(Ok(used_bytes)) => Ok(Response::new(CopyVolumeResponse { used_bytes })),
+			Ok(Err(err)) => {
+				eprintln!("Error copying volume: {}", err);
+				Err(Status::failed_precondition(err))
+			}
+			Err(err) => Err(Status::internal(err.to_string())),
+		}
+	}
 }
@@ -63,6 +63,70 @@ pub struct DeleteContainerResponse {
     #[prost(string, tag = "1")]
     pub message: ::prost::alloc::string::String,
 }
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListContainersRequest {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListContainersResponse {
+    /// Names of the running containers.
+    #[prost(string, repeated, tag = "1")]
+    pub names: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GrowFilesystemRequest {
+    #[prost(string, tag = "1")]
+    pub volume_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GrowFilesystemResponse {
+    /// Size of the filesystem in bytes once grown.
+    #[prost(uint64, tag = "1")]
+    pub total_bytes: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MountVolumeRequest {
+    #[prost(string, tag = "1")]
+    pub volume_id: ::prost::alloc::string::String,
+    /// Block device the volume shows up as, formatted as ext4 if it has no filesystem yet.
+    #[prost(string, tag = "2")]
+    pub device: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MountVolumeResponse {
+    /// Size of the mounted filesystem in bytes.
+    #[prost(uint64, tag = "1")]
+    pub total_bytes: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UnmountVolumeRequest {
+    #[prost(string, tag = "1")]
+    pub volume_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UnmountVolumeResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CopyVolumeRequest {
+    /// Both volumes must be mounted on the worker.
+    #[prost(string, tag = "1")]
+    pub from_volume_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub to_volume_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CopyVolumeResponse {
+    /// Bytes used on the volume copied to.
+    #[prost(uint64, tag = "1")]
+    pub used_bytes: u64,
+}
 /// Generated client implementations.
 pub mod docker_service_client {
     #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
@@ -208,6 +272,105 @@ pub mod docker_service_client {
             );
             self.inner.unary(request.into_request(), path, codec).await
         }
+        /// Used by the principal to find what to stop when draining the worker.
+        pub async fn list_containers(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListContainersRequest>,
+        ) -> Result<tonic::Response<super::ListContainersResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/docker.DockerService/ListContainers",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// Used by the principal after resizing a volume mounted under the worker's volumes directory.
+        pub async fn grow_filesystem(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GrowFilesystemRequest>,
+        ) -> Result<tonic::Response<super::GrowFilesystemResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/docker.DockerService/GrowFilesystem",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// Used by the principal after attaching a volume to the worker's instance, and before detaching it.
+        pub async fn mount_volume(
+            &mut self,
+            request: impl tonic::IntoRequest<super::MountVolumeRequest>,
+        ) -> Result<tonic::Response<super::MountVolumeResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/docker.DockerService/MountVolume",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        pub async fn unmount_volume(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UnmountVolumeRequest>,
+        ) -> Result<tonic::Response<super::UnmountVolumeResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/docker.DockerService/UnmountVolume",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+        /// Used by the principal to take and restore volume snapshots, which are copies of the volume.
+        pub async fn copy_volume(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CopyVolumeRequest>,
+        ) -> Result<tonic::Response<super::CopyVolumeResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/docker.DockerService/CopyVolume",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -233,6 +396,30 @@ pub mod docker_service_server {
             &self,
             request: tonic::Request<super::DeleteContainerRequest>,
         ) -> Result<tonic::Response<super::DeleteContainerResponse>, tonic::Status>;
+        /// Used by the principal to find what to stop when draining the worker.
+        async fn list_containers(
+            &self,
+            request: tonic::Request<super::ListContainersRequest>,
+        ) -> Result<tonic::Response<super::ListContainersResponse>, tonic::Status>;
+        /// Used by the principal after resizing a volume mounted under the worker's volumes directory.
+        async fn grow_filesystem(
+            &self,
+            request: tonic::Request<super::GrowFilesystemRequest>,
+        ) -> Result<tonic::Response<super::GrowFilesystemResponse>, tonic::Status>;
+        /// Used by the principal after attaching a volume to the worker's instance, and before detaching it.
+        async fn mount_volume(
+            &self,
+            request: tonic::Request<super::MountVolumeRequest>,
+        ) -> Result<tonic::Response<super::MountVolumeResponse>, tonic::Status>;
+        async fn unmount_volume(
+            &self,
+            request: tonic::Request<super::UnmountVolumeRequest>,
+        ) -> Result<tonic::Response<super::UnmountVolumeResponse>, tonic::Status>;
+        /// Used by the principal to take and restore volume snapshots, which are copies of the volume.
+        async fn copy_volume(
+            &self,
+            request: tonic::Request<super::CopyVolumeRequest>,
+        ) -> Result<tonic::Response<super::CopyVolumeResponse>, tonic::Status>;
     }
     #[derive(Debug)]
     pub struct DockerServiceServer<T: DockerService> {
@@ -449,6 +636,204 @@ pub mod docker_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/docker.DockerService/ListContainers" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListContainersSvc<T: DockerService>(pub Arc<T>);
+                    impl<
+                        T: DockerService,
+                    > tonic::server::UnaryService<super::ListContainersRequest>
+                    for ListContainersSvc<T> {
+                        type Response = super::ListContainersResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListContainersRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).list_containers(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListContainersSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/docker.DockerService/GrowFilesystem" => {
+                    #[allow(non_camel_case_types)]
+                    struct GrowFilesystemSvc<T: DockerService>(pub Arc<T>);
+                    impl<
+                        T: DockerService,
+                    > tonic::server::UnaryService<super::GrowFilesystemRequest>
+                    for GrowFilesystemSvc<T> {
+                        type Response = super::GrowFilesystemResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GrowFilesystemRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).grow_filesystem(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GrowFilesystemSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/docker.DockerService/MountVolume" => {
+                    #[allow(non_camel_case_types)]
+                    struct MountVolumeSvc<T: DockerService>(pub Arc<T>);
+                    impl<
+                        T: DockerService,
+                    > tonic::server::UnaryService<super::MountVolumeRequest>
+                    for MountVolumeSvc<T> {
+                        type Response = super::MountVolumeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::MountVolumeRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).mount_volume(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = MountVolumeSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/docker.DockerService/UnmountVolume" => {
+                    #[allow(non_camel_case_types)]
+                    struct UnmountVolumeSvc<T: DockerService>(pub Arc<T>);
+                    impl<
+                        T: DockerService,
+                    > tonic::server::UnaryService<super::UnmountVolumeRequest>
+                    for UnmountVolumeSvc<T> {
+                        type Response = super::UnmountVolumeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UnmountVolumeRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move {
+                                (*inner).unmount_volume(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = UnmountVolumeSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/docker.DockerService/CopyVolume" => {
+                    #[allow(non_camel_case_types)]
+                    struct CopyVolumeSvc<T: DockerService>(pub Arc<T>);
+                    impl<
+                        T: DockerService,
+                    > tonic::server::UnaryService<super::CopyVolumeRequest>
+                    for CopyVolumeSvc<T> {
+                        type Response = super::CopyVolumeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CopyVolumeRequest>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).copy_volume(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CopyVolumeSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         Ok(
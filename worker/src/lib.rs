@@ -1,2 +1,3 @@
 pub mod container;
-pub mod docker;
\ No newline at end of file
+pub mod docker;
+pub mod volumes;
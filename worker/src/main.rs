@@ -1,20 +1,16 @@
-use std::sync::Mutex;
-use std::sync::Arc;
-use std::collections::HashMap;
+// gRPC interceptors have to return `Status` as `Err`, which is large; boxing it isn't an option.
+#![allow(clippy::result_large_err)]
 
 use tonic::transport::Server;
-use tonic::{Request, Response, Status};
-
-use prometheus::Counter;
-use bollard::Docker;
-use bollard::container::{Config, CreateContainerOptions};
 
+pub mod auth;
 pub mod container;
+pub mod registry;
+pub mod volumes;
 
+use auth::ServiceToken;
 use container::logic::MyDockerService;
-use container::stats::MyContainerStatsService;
 use docker::docker_service_server::DockerServiceServer;
-use stats::container_stats_service_server::ContainerStatsServiceServer;
 
 pub mod stats {
 	include!("stats.rs");
@@ -24,27 +20,32 @@ pub mod docker {
 	include!("docker.rs");
 }
 
-
-//#[derive(Default)]
-//pub struct MyDockerService {}
+pub mod worker_registry {
+	include!("worker_registry.rs");
+}
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-	let addr = "[::1]:50051".parse().unwrap();
-	let greeter = MyGreeter::default();
-
-	let docker = Docker::connect_with_local_defaults()?;
-	let create_options = CreateContainerOptions::default();
-	let container_config = Config::default();
-
-	//let docker_service = MyDockerService::default();
-	//let container_stats_service = MyContainerStatsService {};
+	// The principal reaches the worker on its primary address, not on loopback, so every call
+	// has to carry the service token.
+	let addr = format!("0.0.0.0:{}", registry::GRPC_PORT).parse().unwrap();
+	let service_token = ServiceToken::from_env();
+
+	// Without a principal address the worker runs standalone.
+	match registry::RegistryConfig::from_env() {
+		Some(config) => {
+			tokio::spawn(registry::run(config, service_token.clone()));
+		}
+		None => println!("PRINCIPAL_ADDR is not set, not registering with a principal"),
+	}
 
 	println!("Worker listening on {}", addr);
 
 	Server::builder()
-		//.add_service(DockerServiceServer::new(docker_service))
-		//.add_service(ContainerStatsServiceServer::new(container_stats_service))
+		.add_service(DockerServiceServer::with_interceptor(
+			MyDockerService::default(),
+			move |request| service_token.check(request),
+		))
 		.serve(addr)
 		.await?;
 
@@ -0,0 +1,246 @@
+use std::env;
+use std::fs;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use bollard::Docker;
+use tokio::time::sleep;
+use tonic::transport::Channel;
+use tonic::Code;
+
+use crate::auth::ServiceToken;
+use crate::worker_registry::worker_registry_client::WorkerRegistryClient;
+use crate::volumes;
+use crate::worker_registry::{HeartbeatRequest, Metrics, Network, RegisterRequest, VolumeUsage};
+
+// Port the worker's own gRPC services listen on, reported when registering.
+pub const GRPC_PORT: u32 = 50051;
+
+// Wait before retrying after the principal could not be reached.
+const RETRY_DELAY: Duration = Duration::from_secs(10);
+
+// What the worker was bootstrapped with, see `/etc/infralink/worker.env` written by cloud-init.
+pub struct RegistryConfig {
+	pub principal_addr: String,
+	pub join_token: String,
+	pub provider: String,
+	pub account: String,
+	pub region: String,
+	pub instance_id: Option<String>,
+	pub public_ipv4: Option<String>,
+}
+
+impl RegistryConfig {
+	// Returns `None` when the worker was started without a principal to register with.
+	pub fn from_env() -> Option<Self> {
+		let principal_addr = env::var("PRINCIPAL_ADDR").ok().filter(|addr| !addr.is_empty())?;
+
+		Some(RegistryConfig {
+			principal_addr,
+			join_token: env::var("JOIN_TOKEN").unwrap_or_default(),
+			provider: env::var("PROVIDER").unwrap_or_default(),
+			account: env::var("ACCOUNT").unwrap_or_default(),
+			region: env::var("REGION").unwrap_or_default(),
+			instance_id: env::var("INSTANCE_ID").ok(),
+			public_ipv4: env::var("WORKER_PUBLIC_IPV4").ok(),
+		})
+	}
+}
+
+// Registers with the principal and keeps sending heartbeats, registering again whenever the
+// principal no longer knows this worker. Each registration replaces the service token.
+pub async fn run(config: RegistryConfig, service_token: ServiceToken) {
+	let mut sampler = Sampler::default();
+
+	loop {
+		let mut client = match WorkerRegistryClient::connect(config.principal_addr.clone()).await {
+			Ok(client) => client,
+			Err(e) => {
+				println!("Failed to connect to principal {}: {}", config.principal_addr, e);
+				sleep(RETRY_DELAY).await;
+				continue;
+			}
+		};
+
+		let (worker_id, interval, credential) = match register(&mut client, &config, &service_token).await {
+			Ok(registered) => registered,
+			Err(e) => {
+				println!("Failed to register with principal: {}", e);
+				sleep(RETRY_DELAY).await;
+				continue;
+			}
+		};
+		println!("Registered with principal as worker {}", worker_id);
+
+		loop {
+			sleep(interval).await;
+
+			let request = HeartbeatRequest {
+				credential: credential.clone(),
+				worker_id,
+				metrics: Some(sampler.sample().await),
+				volumes: volume_usage(),
+			};
+
+			match client.heartbeat(request).await {
+				Ok(_) => {}
+				Err(status) if status.code() == Code::NotFound => {
+					println!("Principal no longer knows worker {}, registering again", worker_id);
+					break;
+				}
+				Err(status) => println!("Heartbeat failed: {}", status),
+			}
+		}
+	}
+}
+
+async fn register(
+	client: &mut WorkerRegistryClient<Channel>,
+	config: &RegistryConfig,
+	service_token: &ServiceToken,
+) -> Result<(u64, Duration, String), tonic::Status> {
+	let request = RegisterRequest {
+		join_token: config.join_token.clone(),
+		provider: config.provider.clone(),
+		account: config.account.clone(),
+		region: config.region.clone(),
+		instance_id: config.instance_id.clone(),
+		network: config.public_ipv4.clone().map(|primary_ipv4| Network {
+			primary_ipv4,
+			primary_ipv6: None,
+		}),
+		grpc_port: GRPC_PORT,
+		vcpu: std::thread::available_parallelism()
+			.map(|n| n.get() as u64)
+			.unwrap_or(1),
+		memory: meminfo("MemTotal").unwrap_or(0) / 1024,
+	};
+
+	let response = client.register(request).await?.into_inner();
+	service_token.set(response.service_token);
+
+	Ok((
+		response.worker_id,
+		Duration::from_secs(response.heartbeat_interval_secs.max(1).into()),
+		response.credential,
+	))
+}
+
+// Collects the metrics sent with each heartbeat. CPU, memory and disk are percentages, network
+// is bytes per second since the previous sample and workload the number of running containers.
+#[derive(Default)]
+struct Sampler {
+	network: Option<(Instant, u64)>,
+}
+
+impl Sampler {
+	async fn sample(&mut self) -> Metrics {
+		Metrics {
+			cpu: cpu_usage().unwrap_or(0.0),
+			memory: memory_usage().unwrap_or(0.0),
+			disk: disk_usage().unwrap_or(0.0),
+			network: self.network_rate().unwrap_or(0.0),
+			workload: running_containers().await.unwrap_or(0) as f64,
+			time: chrono::Utc::now().timestamp(),
+		}
+	}
+
+	fn network_rate(&mut self) -> Option<f64> {
+		let now = Instant::now();
+		let bytes = network_bytes()?;
+		let previous = self.network.replace((now, bytes))?;
+
+		let elapsed = now.duration_since(previous.0).as_secs_f64();
+		if elapsed == 0.0 {
+			return None;
+		}
+
+		Some(bytes.saturating_sub(previous.1) as f64 / elapsed)
+	}
+}
+
+// One minute load average relative to the number of CPUs.
+fn cpu_usage() -> Option<f64> {
+	let loadavg = fs::read_to_string("/proc/loadavg").ok()?;
+	let load: f64 = loadavg.split_whitespace().next()?.parse().ok()?;
+	let cpus = std::thread::available_parallelism().ok()?.get() as f64;
+
+	Some((load / cpus * 100.0).min(100.0))
+}
+
+// Value of a `/proc/meminfo` field in kB.
+fn meminfo(field: &str) -> Option<u64> {
+	let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+
+	meminfo
+		.lines()
+		.find_map(|line| line.strip_prefix(field)?.strip_prefix(':'))
+		.and_then(|value| value.split_whitespace().next())
+		.and_then(|value| value.parse().ok())
+}
+
+fn memory_usage() -> Option<f64> {
+	let total = meminfo("MemTotal")? as f64;
+	let available = meminfo("MemAvailable")? as f64;
+	if total == 0.0 {
+		return None;
+	}
+
+	Some((total - available) / total * 100.0)
+}
+
+// Usage of the root filesystem, from `df -Pk /`.
+fn disk_usage() -> Option<f64> {
+	let output = Command::new("df").args(["-Pk", "/"]).output().ok()?;
+	let output = String::from_utf8(output.stdout).ok()?;
+	let columns: Vec<&str> = output.lines().nth(1)?.split_whitespace().collect();
+
+	let total: f64 = columns.get(1)?.parse().ok()?;
+	let used: f64 = columns.get(2)?.parse().ok()?;
+	if total == 0.0 {
+		return None;
+	}
+
+	Some(used / total * 100.0)
+}
+
+fn volume_usage() -> Vec<VolumeUsage> {
+	volumes::usage()
+		.into_iter()
+		.map(|usage| VolumeUsage {
+			volume_id: usage.volume_id,
+			used_bytes: usage.used_bytes,
+			total_bytes: usage.total_bytes,
+		})
+		.collect()
+}
+
+// Bytes received and sent on all interfaces except loopback.
+fn network_bytes() -> Option<u64> {
+	let dev = fs::read_to_string("/proc/net/dev").ok()?;
+
+	Some(
+		dev.lines()
+			.skip(2)
+			.filter_map(|line| line.split_once(':'))
+			.filter(|(interface, _)| interface.trim() != "lo")
+			.map(|(_, counters)| {
+				let counters: Vec<u64> = counters
+					.split_whitespace()
+					.map(|counter| counter.parse().unwrap_or(0))
+					.collect();
+				counters.first().unwrap_or(&0) + counters.get(8).unwrap_or(&0)
+			})
+			.sum(),
+	)
+}
+
+async fn running_containers() -> Option<usize> {
+	let docker = Docker::connect_with_local_defaults().ok()?;
+
+	docker
+		.list_containers::<String>(None)
+		.await
+		.ok()
+		.map(|containers| containers.len())
+}
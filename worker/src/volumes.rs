@@ -0,0 +1,215 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread::sleep;
+use std::time::Duration;
+
+// Volumes are mounted at `{VOLUMES_DIR}/{volume id}`, so their usage can be told apart.
+pub const VOLUMES_DIR: &str = "/mnt/volumes";
+
+pub struct VolumeUsage {
+	pub volume_id: String,
+	pub used_bytes: u64,
+	pub total_bytes: u64,
+}
+
+// Usage of every volume mounted under `VOLUMES_DIR`.
+pub fn usage() -> Vec<VolumeUsage> {
+	let entries = match fs::read_dir(VOLUMES_DIR) {
+		Ok(entries) => entries,
+		Err(_) => return Vec::new(),
+	};
+
+	entries
+		.filter_map(|entry| entry.ok())
+		.filter_map(|entry| {
+			let volume_id = entry.file_name().into_string().ok()?;
+			let (used_bytes, total_bytes) = filesystem_usage(&entry.path())?;
+
+			Some(VolumeUsage {
+				volume_id,
+				used_bytes,
+				total_bytes,
+			})
+		})
+		.collect()
+}
+
+// Used and total bytes of the filesystem mounted at `path`, from `df -PB1`.
+fn filesystem_usage(path: &Path) -> Option<(u64, u64)> {
+	let output = Command::new("df").arg("-PB1").arg(path).output().ok()?;
+	let output = String::from_utf8(output.stdout).ok()?;
+	let columns: Vec<&str> = output.lines().nth(1)?.split_whitespace().collect();
+
+	// A directory nothing is mounted on reports the filesystem it sits on.
+	if Path::new(columns.get(5)?) != path {
+		return None;
+	}
+
+	Some((columns.get(2)?.parse().ok()?, columns.get(1)?.parse().ok()?))
+}
+
+// An attached device can take a moment to show up on the instance.
+const DEVICE_WAIT_ATTEMPTS: u32 = 30;
+const DEVICE_WAIT_INTERVAL: Duration = Duration::from_secs(1);
+
+// Where the volume is mounted; ids are single path components.
+fn mount_point(volume_id: &str) -> Result<PathBuf, String> {
+	if volume_id.is_empty() || volume_id.contains('/') || volume_id.starts_with('.') {
+		return Err(format!("invalid volume id: {}", volume_id));
+	}
+
+	Ok(Path::new(VOLUMES_DIR).join(volume_id))
+}
+
+// Block storage volumes show up under `/dev/disk/by-id`: `virtio-{mount id}` on Vultr,
+// `scsi-0HC_Volume_{id}` on Hetzner. Other devices, e.g. the boot disk, are never mounted or
+// formatted.
+const DEVICE_DIR: &str = "/dev/disk/by-id";
+const VOLUME_DEVICE_PREFIXES: [&str; 2] = ["virtio-", "scsi-0HC_Volume_"];
+
+fn check_device(device: &str) -> Result<(), String> {
+	let name = device
+		.strip_prefix(DEVICE_DIR)
+		.and_then(|name| name.strip_prefix('/'))
+		.filter(|name| !name.contains('/'))
+		.unwrap_or_default();
+
+	if !VOLUME_DEVICE_PREFIXES.iter().any(|prefix| name.len() > prefix.len() && name.starts_with(prefix)) {
+		return Err(format!("{} is not a block storage volume", device));
+	}
+
+	Ok(())
+}
+
+// Whether the device or any of its partitions is mounted somewhere, from `lsblk`.
+fn in_use(device: &str) -> Result<bool, String> {
+	let output = Command::new("lsblk")
+		.args(["-n", "-o", "MOUNTPOINT"])
+		.arg(device)
+		.output()
+		.map_err(|e| e.to_string())?;
+	if !output.status.success() {
+		return Err(format!("lsblk {} failed: {}", device, output.status));
+	}
+
+	Ok(String::from_utf8_lossy(&output.stdout).lines().any(|line| !line.trim().is_empty()))
+}
+
+// Source device and filesystem type of what is mounted at `path`, if anything.
+fn mounted(path: &Path) -> Result<Option<(String, String)>, String> {
+	let output = Command::new("findmnt")
+		.args(["-n", "-o", "SOURCE,FSTYPE", "--mountpoint"])
+		.arg(path)
+		.output()
+		.map_err(|e| e.to_string())?;
+	let output = String::from_utf8_lossy(&output.stdout).to_string();
+
+	let mut columns = output.split_whitespace();
+	Ok(match (columns.next(), columns.next()) {
+		(Some(source), Some(fstype)) => Some((source.to_string(), fstype.to_string())),
+		_ => None,
+	})
+}
+
+fn run(command: &mut Command) -> Result<(), String> {
+	let status = command.status().map_err(|e| e.to_string())?;
+	if !status.success() {
+		return Err(format!("{:?} failed: {}", command, status));
+	}
+
+	Ok(())
+}
+
+// Mounts an attached volume at `{VOLUMES_DIR}/{volume id}`, formatting it as ext4 first if it
+// has no filesystem yet; returns its size in bytes. Mounting a mounted volume does nothing, a
+// device mounted anywhere else is refused.
+pub fn mount(volume_id: &str, device: &str) -> Result<u64, String> {
+	let path = mount_point(volume_id)?;
+	check_device(device)?;
+
+	if mounted(&path)?.is_none() {
+		let mut attempts = 0;
+		while !Path::new(device).exists() {
+			attempts += 1;
+			if attempts >= DEVICE_WAIT_ATTEMPTS {
+				return Err(format!("device {} of volume {} did not show up", device, volume_id));
+			}
+			sleep(DEVICE_WAIT_INTERVAL);
+		}
+
+		if in_use(device)? {
+			return Err(format!("device {} of volume {} is already in use", device, volume_id));
+		}
+
+		// `blkid` prints nothing for a device without a filesystem.
+		let output = Command::new("blkid")
+			.args(["-o", "value", "-s", "TYPE"])
+			.arg(device)
+			.output()
+			.map_err(|e| e.to_string())?;
+		if output.stdout.iter().all(u8::is_ascii_whitespace) {
+			run(Command::new("mkfs.ext4").arg("-q").arg(device))?;
+		}
+
+		fs::create_dir_all(&path).map_err(|e| e.to_string())?;
+		run(Command::new("mount").arg(device).arg(&path))?;
+	}
+
+	filesystem_usage(&path)
+		.map(|(_, total_bytes)| total_bytes)
+		.ok_or_else(|| format!("volume {} is not mounted at {}", volume_id, path.display()))
+}
+
+// Unmounts the volume before it is detached; unmounting a volume that is not mounted does nothing.
+pub fn unmount(volume_id: &str) -> Result<(), String> {
+	let path = mount_point(volume_id)?;
+
+	if mounted(&path)?.is_some() {
+		run(Command::new("umount").arg(&path))?;
+	}
+
+	match fs::remove_dir(&path) {
+		Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+		_ => Ok(()),
+	}
+}
+
+// Grows the filesystem of a resized volume to fill it; returns its new size in bytes.
+pub fn grow(volume_id: &str) -> Result<u64, String> {
+	let path = mount_point(volume_id)?;
+	let (source, fstype) = mounted(&path)?
+		.ok_or_else(|| format!("volume {} is not mounted at {}", volume_id, path.display()))?;
+
+	// ext filesystems are grown through the device, XFS through the mount point.
+	match fstype.as_str() {
+		"ext2" | "ext3" | "ext4" => run(Command::new("resize2fs").arg(&source)),
+		"xfs" => run(Command::new("xfs_growfs").arg(&path)),
+		_ => return Err(format!("cannot grow the {} filesystem of volume {}", fstype, volume_id)),
+	}
+	.map_err(|e| format!("growing the filesystem of volume {} failed: {}", volume_id, e))?;
+
+	filesystem_usage(&path)
+		.map(|(_, total_bytes)| total_bytes)
+		.ok_or_else(|| format!("volume {} is not mounted at {}", volume_id, path.display()))
+}
+
+// Copies the files of one mounted volume onto another, keeping owners, modes and timestamps;
+// returns the bytes used on the volume copied to. The volume copied from stays in use, so the
+// copy is only as consistent as each file is when it is read.
+pub fn copy(from_volume_id: &str, to_volume_id: &str) -> Result<u64, String> {
+	let from = mount_point(from_volume_id)?;
+	let to = mount_point(to_volume_id)?;
+	for (volume_id, path) in [(from_volume_id, &from), (to_volume_id, &to)] {
+		if mounted(path)?.is_none() {
+			return Err(format!("volume {} is not mounted at {}", volume_id, path.display()));
+		}
+	}
+
+	run(Command::new("cp").arg("-a").arg(from.join(".")).arg(&to))
+		.map_err(|e| format!("copying volume {} to {} failed: {}", from_volume_id, to_volume_id, e))?;
+
+	filesystem_usage(&to)
+		.map(|(used_bytes, _)| used_bytes)
+		.ok_or_else(|| format!("volume {} is not mounted at {}", to_volume_id, to.display()))
+}